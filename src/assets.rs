@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+use crate::{hexagonal_column, map_layout};
+use crate::gameplay::enemy::EnemyKind;
+use crate::map::terrain::Terrain;
+use crate::gameplay::towers::{TowerRegistry, WALL_HEIGHT};
+use crate::state::global::GameState;
+use crate::ui::health_bars::BAR_SIZE;
+
+/// Fonts and textures used by the UI, tower models are taken from the `TowerRegistry`
+const PRELOADED_PATHS: [&str; 2] = [
+    "fonts/FiraSans-Bold.ttf",
+    "images/button-01.png",
+];
+
+/// Quits right away over a data file the game can't run without, like the tower definitions.
+/// Such paths are relative to the working directory, the message says which one that is
+pub fn quit_without_file(path: &str, error: impl std::fmt::Display) -> ! {
+    let directory = std::env::current_dir().map_or_else(|_| "an unknown directory".to_string(), |directory| directory.display().to_string());
+    error!("Can't start without {} (looked for below {}): {}", path, directory, error);
+    std::process::exit(1);
+}
+
+pub struct GameAssetsPlugin;
+
+impl Plugin for GameAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<PreloadedAssets>()
+            .add_startup_system(create_game_assets.in_base_set(StartupSet::PreStartup))
+            .add_system(start_preloading.in_schedule(OnEnter(GameState::AssetLoading)))
+            .add_system(check_preloading.in_set(OnUpdate(GameState::AssetLoading)))
+        ;
+    }
+}
+
+/// Meshes and materials shared by everything spawned during the game,
+/// so spawning doesn't add new assets every time
+#[derive(Resource)]
+pub struct GameAssets {
+    pub hex_mesh: Handle<Mesh>,
+    /// colored by the vertices of the merged grid
+    pub grid_material: Handle<StandardMaterial>,
+    pub enemy_meshes: HashMap<EnemyKind, Handle<Mesh>>,
+    pub enemy_materials: HashMap<EnemyKind, Handle<StandardMaterial>>,
+    pub bullet_mesh: Handle<Mesh>,
+    pub bullet_material: Handle<StandardMaterial>,
+    pub shockwave_mesh: Handle<Mesh>,
+    /// unit cube stretched into beams and lightning arcs
+    pub ray_mesh: Handle<Mesh>,
+    pub beam_material: Handle<StandardMaterial>,
+    pub lightning_material: Handle<StandardMaterial>,
+    pub shockwave_material: Handle<StandardMaterial>,
+    pub particle_mesh: Handle<Mesh>,
+    pub muzzle_flash_material: Handle<StandardMaterial>,
+    pub impact_material: Handle<StandardMaterial>,
+    pub range_material: Handle<StandardMaterial>,
+    pub aura_material: Handle<StandardMaterial>,
+    pub ghost_valid_material: Handle<StandardMaterial>,
+    pub ghost_invalid_material: Handle<StandardMaterial>,
+    pub health_bar_mesh: Handle<Mesh>,
+    pub health_bar_background_material: Handle<StandardMaterial>,
+    pub health_bar_fill_material: Handle<StandardMaterial>,
+    pub minimap_enemy_material: Handle<StandardMaterial>,
+    pub minimap_tower_material: Handle<StandardMaterial>,
+    pub minimap_frustum_material: Handle<StandardMaterial>,
+    pub terrain_materials: HashMap<Terrain, Handle<StandardMaterial>>,
+    pub highlighted_material: Handle<StandardMaterial>,
+    /// front of the trail of a route preview
+    pub route_head_material: Handle<StandardMaterial>,
+    pub fog_material: Handle<StandardMaterial>,
+    pub rain_material: Handle<StandardMaterial>,
+    pub wall_mesh: Handle<Mesh>,
+    pub wall_material: Handle<StandardMaterial>,
+    /// bubble around shielded enemies and bosses
+    pub shield_mesh: Handle<Mesh>,
+    pub shield_material: Handle<StandardMaterial>,
+    /// star above veteran towers
+    pub badge_mesh: Handle<Mesh>,
+    pub badge_material: Handle<StandardMaterial>,
+    pub selection_material: Handle<StandardMaterial>,
+    /// ring on the ground around selected towers and enemies
+    pub selection_ring_mesh: Handle<Mesh>,
+    pub selection_ring_material: Handle<StandardMaterial>,
+    pub valid_material: Handle<StandardMaterial>,
+    pub invalid_material: Handle<StandardMaterial>,
+    /// hexagonal line around hexes highlighted with a pattern, see `HighlightPalette`
+    pub highlight_outline_mesh: Handle<Mesh>,
+    pub highlight_dot_mesh: Handle<Mesh>,
+    /// one of the two bars of a cross
+    pub highlight_bar_mesh: Handle<Mesh>,
+    pub highlight_pattern_material: Handle<StandardMaterial>,
+}
+
+impl GameAssets {
+    pub fn enemy_mesh(&self, kind: EnemyKind) -> Handle<Mesh> {
+        self.enemy_meshes[&kind].clone()
+    }
+
+    pub fn enemy_material(&self, kind: EnemyKind) -> Handle<StandardMaterial> {
+        self.enemy_materials[&kind].clone()
+    }
+
+    pub fn terrain_material(&self, terrain: Terrain) -> Handle<StandardMaterial> {
+        self.terrain_materials[&terrain].clone()
+    }
+}
+
+/// Handles of everything loaded before the game starts, kept around so the assets stay loaded
+#[derive(Resource, Default)]
+pub struct PreloadedAssets {
+    handles: Vec<HandleUntyped>,
+}
+
+impl PreloadedAssets {
+    /// Share of the assets already loaded (0..1)
+    pub fn progress(&self, asset_server: &AssetServer) -> f32 {
+        if self.handles.is_empty() {
+            return 1.0;
+        }
+
+        let loaded = self.handles
+            .iter()
+            .filter(|handle| asset_server.get_load_state(handle.id()) == LoadState::Loaded)
+            .count();
+        loaded as f32 / self.handles.len() as f32
+    }
+}
+
+fn start_preloading(
+    asset_server: Res<AssetServer>,
+    registry: Res<TowerRegistry>,
+    mut preloaded: ResMut<PreloadedAssets>,
+) {
+    let tower_models = registry.towers
+        .iter()
+        .filter(|tower| !tower.is_wall())
+        .flat_map(|tower| {
+            std::iter::once(tower.model.clone())
+                .chain(tower.upgrades.iter().filter_map(|upgrade| upgrade.model.clone()))
+        });
+
+    preloaded.handles = PRELOADED_PATHS
+        .iter()
+        .map(|path| path.to_string())
+        .chain(tower_models)
+        .map(|path| asset_server.load_untyped(path))
+        .collect();
+}
+
+fn check_preloading(
+    asset_server: Res<AssetServer>,
+    preloaded: Res<PreloadedAssets>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    match asset_server.get_group_load_state(preloaded.handles.iter().map(|handle| handle.id())) {
+        LoadState::Loaded => next_state.set(GameState::MainMenu),
+        LoadState::Failed => {
+            warn!("Some assets failed to load, starting anyway");
+            next_state.set(GameState::MainMenu);
+        }
+        _ => {}
+    }
+}
+
+fn create_game_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let enemy_meshes = EnemyKind::ALL
+        .iter()
+        .map(|kind| {
+            let size = kind.stats().size;
+            let mesh = meshes.add(Mesh::from(shape::Capsule {
+                radius: size,
+                depth: size * 4.0,
+                ..default()
+            }));
+            (*kind, mesh)
+        })
+        .collect();
+
+    let enemy_materials = EnemyKind::ALL
+        .iter()
+        .map(|kind| (*kind, materials.add(kind.stats().color.into())))
+        .collect();
+
+    let terrain_materials = Terrain::ALL
+        .iter()
+        .map(|terrain| (*terrain, materials.add(terrain.color().into())))
+        .collect();
+
+    commands.insert_resource(GameAssets {
+        hex_mesh: meshes.add(hexagonal_column(&map_layout())),
+        grid_material: materials.add(Color::WHITE.into()),
+        enemy_meshes,
+        enemy_materials,
+        bullet_mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 0.05,
+            ..default()
+        })),
+        bullet_material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+        shockwave_mesh: meshes.add(Mesh::from(shape::Circle::new(1.0))),
+        shockwave_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 0.6, 0.2, 0.4),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        ray_mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        beam_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 0.3, 0.3, 0.8),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        lightning_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.75, 0.85, 1.0),
+            unlit: true,
+            ..default()
+        }),
+        particle_mesh: meshes.add(Mesh::from(shape::Cube { size: 0.03 })),
+        muzzle_flash_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.9, 0.5),
+            unlit: true,
+            ..default()
+        }),
+        impact_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.5, 0.15),
+            unlit: true,
+            ..default()
+        }),
+        range_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.4, 0.7, 1.0, 0.25),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        aura_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.5, 1.0, 0.5, 0.2),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        ghost_valid_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.3, 1.0, 0.3, 0.45),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+        ghost_invalid_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 0.25, 0.25, 0.45),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+        health_bar_mesh: meshes.add(Mesh::from(shape::Quad::new(BAR_SIZE))),
+        health_bar_background_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.15, 0.15, 0.15),
+            unlit: true,
+            ..default()
+        }),
+        health_bar_fill_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.2, 0.85, 0.2),
+            unlit: true,
+            ..default()
+        }),
+        minimap_enemy_material: materials.add(StandardMaterial {
+            base_color: Color::RED,
+            unlit: true,
+            ..default()
+        }),
+        minimap_tower_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.3, 0.6, 1.0),
+            unlit: true,
+            ..default()
+        }),
+        minimap_frustum_material: materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            unlit: true,
+            ..default()
+        }),
+        terrain_materials,
+        highlighted_material: materials.add(Color::YELLOW.into()),
+        route_head_material: materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            emissive: Color::YELLOW,
+            ..default()
+        }),
+        fog_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.12, 0.12, 0.15),
+            perceptual_roughness: 1.0,
+            ..default()
+        }),
+        wall_mesh: meshes.add(Mesh::from(shape::Box::new(4.5, WALL_HEIGHT, 4.5))),
+        wall_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.55, 0.52, 0.48),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
+        shield_mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 1.0,
+            ..default()
+        })),
+        shield_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.4, 0.7, 1.0, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        // four sectors and two stacks make a diamond
+        badge_mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 0.6,
+            sectors: 4,
+            stacks: 2,
+        })),
+        badge_material: materials.add(StandardMaterial {
+            base_color: Color::GOLD,
+            emissive: Color::rgb(0.4, 0.3, 0.0),
+            unlit: true,
+            ..default()
+        }),
+        rain_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.7, 0.8, 1.0, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        selection_material: materials.add(Color::AQUAMARINE.into()),
+        selection_ring_mesh: meshes.add(Mesh::from(shape::Torus {
+            radius: 0.25,
+            ring_radius: 0.015,
+            ..default()
+        })),
+        selection_ring_material: materials.add(StandardMaterial {
+            base_color: Color::AQUAMARINE,
+            unlit: true,
+            ..default()
+        }),
+        valid_material: materials.add(Color::GREEN.into()),
+        invalid_material: materials.add(Color::RED.into()),
+        // six segments turn the torus into a hexagon
+        highlight_outline_mesh: meshes.add(Mesh::from(shape::Torus {
+            radius: 0.22,
+            ring_radius: 0.015,
+            subdivisions_segments: 6,
+            subdivisions_sides: 4,
+        })),
+        highlight_dot_mesh: meshes.add(Mesh::from(shape::Circle::new(0.06))),
+        highlight_bar_mesh: meshes.add(Mesh::from(shape::Box::new(0.3, 0.01, 0.04))),
+        highlight_pattern_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.05, 0.05, 0.05),
+            unlit: true,
+            ..default()
+        }),
+    });
+}
@@ -0,0 +1,361 @@
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::RaycastPickCamera;
+use leafwing_input_manager::buttonlike::MouseWheelDirection;
+use leafwing_input_manager::prelude::*;
+
+use crate::Action;
+use crate::gameplay::enemy::EnemyTag;
+use crate::state::global::GameState;
+use crate::ui::control_groups::{ASSIGN_ACTIONS, DIGIT_KEYS, RECALL_ACTIONS};
+use crate::ui::player::{BUILD_SLOT_ACTIONS, SelectedBuilding};
+
+/// Closest the camera may get to its focus point
+const MIN_DISTANCE: f32 = 4.0;
+/// Farthest the camera may get from its focus point
+const MAX_DISTANCE: f32 = 25.0;
+/// Factor the distance changes with per scroll step
+const ZOOM_STEP: f32 = 0.9;
+/// Radians per second
+const ROTATION_SPEED: f32 = 1.5;
+/// The focus point can't leave a circle of this radius around the map center
+const MAX_FOCUS_DISTANCE: f32 = 8.0;
+/// Width (in pixels) of the screen border which pans the camera when hovered
+const EDGE_PAN_MARGIN: f32 = 10.0;
+/// How quickly the camera catches up with a followed entity
+const FOLLOW_SMOOTHING: f32 = 5.0;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CameraSettings>()
+            .init_resource::<CameraFocus>()
+            .add_startup_system(spawn_camera)
+            .add_systems(
+                (
+                    choose_camera_focus,
+                    camera_keyboard_controls.after(choose_camera_focus),
+                    camera_edge_panning
+                        .run_if(is_free_camera)
+                        .after(choose_camera_focus),
+                    follow_camera_focus.after(camera_keyboard_controls),
+                    apply_camera_transform
+                        .after(follow_camera_focus)
+                        .after(camera_edge_panning),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+#[derive(Component)]
+pub struct PlayerCamera;
+
+#[derive(Resource, Debug)]
+pub struct CameraSettings {
+    /// world units per second, the camera ignores the game speed
+    pub pan_speed: f32,
+    /// pan the camera when the cursor touches the border of the window
+    pub edge_panning: bool,
+    /// factor applied to the panning and rotation speed
+    pub sensitivity: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            pan_speed: 6.0,
+            edge_panning: true,
+            sensitivity: 1.0,
+        }
+    }
+}
+
+/// Entity the camera is following, the camera can be moved freely when there is none
+#[derive(Resource, Debug, Default)]
+pub struct CameraFocus {
+    pub target: Option<Entity>,
+}
+
+fn is_free_camera(focus: Res<CameraFocus>) -> bool {
+    focus.target.is_none()
+}
+
+/// Orbit of the camera around a point on the ground
+#[derive(Component, Debug)]
+pub struct CameraController {
+    pub focus: Vec3,
+    pub distance: f32,
+    /// rotation around the Y axis
+    pub yaw: f32,
+    /// angle between the ground and the camera
+    pub pitch: f32,
+}
+
+impl CameraController {
+    /// Rotation of the camera, looking at the focus point
+    fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(-self.pitch)
+    }
+
+    /// Moves the focus point relative to the camera orientation, x is right and y forward
+    pub fn pan(&mut self, direction: Vec2, amount: f32) {
+        let forward = Quat::from_rotation_y(self.yaw) * Vec3::NEG_Z;
+        let right = Quat::from_rotation_y(self.yaw) * Vec3::X;
+        self.focus += (right * direction.x + forward * direction.y) * amount;
+        self.focus = self.focus.clamp_length_max(MAX_FOCUS_DISTANCE);
+    }
+
+    /// Moves the focus point straight to `focus`, staying within the allowed area
+    pub fn jump_to(&mut self, focus: Vec3) {
+        self.focus = (focus * Vec3::new(1.0, 0.0, 1.0)).clamp_length_max(MAX_FOCUS_DISTANCE);
+    }
+
+    fn transform(&self) -> Transform {
+        let rotation = self.rotation();
+        Transform::from_translation(self.focus + rotation * Vec3::Z * self.distance)
+            .with_rotation(rotation)
+    }
+}
+
+impl Default for CameraController {
+    /// Same view as the former fixed camera at (-4.0, 8.5, 13.0) looking at (0.0, 0.0, 2.0)
+    fn default() -> Self {
+        let offset = Vec3::new(-4.0, 8.5, 11.0);
+        CameraController {
+            focus: Vec3::new(0.0, 0.0, 2.0),
+            distance: offset.length(),
+            yaw: offset.x.atan2(offset.z),
+            pitch: (offset.y / offset.length()).asin(),
+        }
+    }
+}
+
+/// Default bindings of the gameplay actions for keyboard, mouse and gamepad
+pub fn default_input_map() -> InputMap<Action> {
+    let mut input_map = InputMap::new([
+        (UserInput::from(KeyCode::W), Action::MoveForward),
+        (UserInput::from(KeyCode::Up), Action::MoveForward),
+        (UserInput::from(KeyCode::S), Action::MoveBack),
+        (UserInput::from(KeyCode::Down), Action::MoveBack),
+        (UserInput::from(KeyCode::A), Action::MoveLeft),
+        (UserInput::from(KeyCode::Left), Action::MoveLeft),
+        (UserInput::from(KeyCode::D), Action::MoveRight),
+        (UserInput::from(KeyCode::Right), Action::MoveRight),
+        (UserInput::from(KeyCode::Q), Action::RotateLeft),
+        (UserInput::from(KeyCode::E), Action::RotateRight),
+        (UserInput::from(MouseWheelDirection::Up), Action::ZoomIn),
+        (UserInput::from(MouseWheelDirection::Down), Action::ZoomOut),
+        (UserInput::from(KeyCode::F), Action::FollowTarget),
+        (UserInput::from(KeyCode::Escape), Action::FreeCamera),
+        (UserInput::from(KeyCode::H), Action::Jump),
+        (UserInput::from(KeyCode::Plus), Action::SpeedUp),
+        (UserInput::from(KeyCode::Equals), Action::SpeedUp),
+        (UserInput::from(KeyCode::Minus), Action::SpeedDown),
+        (UserInput::from(KeyCode::R), Action::UseAbility),
+        // the ability is left alone while a building is placed, see `use_ability_by_hotkey`
+        (UserInput::from(KeyCode::R), Action::RotateBuilding),
+        (UserInput::chord([KeyCode::LControl, KeyCode::Z]), Action::Undo),
+        (UserInput::chord([KeyCode::LControl, KeyCode::Y]), Action::Redo),
+        (UserInput::from(KeyCode::P), Action::TogglePathPreview),
+    ]);
+    // the chords win over the number key alone, they don't start a placement as well
+    for (((key, build), assign), recall) in DIGIT_KEYS.iter().zip(BUILD_SLOT_ACTIONS).zip(ASSIGN_ACTIONS).zip(RECALL_ACTIONS) {
+        input_map.insert(UserInput::from(*key), build);
+        input_map.insert(UserInput::chord([KeyCode::LControl, *key]), assign);
+        input_map.insert(UserInput::chord([KeyCode::RControl, *key]), assign);
+        input_map.insert(UserInput::chord([KeyCode::LShift, *key]), recall);
+        input_map.insert(UserInput::chord([KeyCode::RShift, *key]), recall);
+    }
+    // the D-pad is reserved for menu navigation, the left stick for the hex cursor
+    input_map.insert(DualAxis::right_stick(), Action::PanCamera);
+    input_map.insert(DualAxis::left_stick(), Action::MoveCursor);
+    input_map.insert_multiple([
+        (GamepadButtonType::LeftTrigger, Action::RotateLeft),
+        (GamepadButtonType::RightTrigger, Action::RotateRight),
+        (GamepadButtonType::RightTrigger2, Action::ZoomIn),
+        (GamepadButtonType::LeftTrigger2, Action::ZoomOut),
+        (GamepadButtonType::North, Action::FollowTarget),
+        (GamepadButtonType::West, Action::FreeCamera),
+        (GamepadButtonType::Select, Action::Jump),
+        (GamepadButtonType::RightThumb, Action::SpeedUp),
+        (GamepadButtonType::LeftThumb, Action::SpeedDown),
+    ]);
+    input_map
+}
+
+fn spawn_camera(mut commands: Commands) {
+    let controller = CameraController::default();
+
+    commands
+        .spawn((
+            Camera3dBundle {
+                transform: controller.transform(),
+                ..default()
+            },
+            RaycastPickCamera::default(),
+            PlayerCamera,
+            controller,
+            InputManagerBundle::<Action> {
+                action_state: ActionState::default(),
+                input_map: default_input_map(),
+            },
+        ));
+}
+
+/// Follows the selected building, or the enemy closest to the view when no building is selected
+pub fn choose_camera_focus(
+    mut focus: ResMut<CameraFocus>,
+    cameras: Query<(&CameraController, &ActionState<Action>)>,
+    selected: Option<Res<SelectedBuilding>>,
+    enemies: Query<(Entity, &GlobalTransform), With<EnemyTag>>,
+) {
+    for (controller, action_state) in &cameras {
+        if action_state.just_pressed(Action::FreeCamera) || action_state.just_pressed(Action::Jump) {
+            focus.target = None;
+        }
+
+        if action_state.just_pressed(Action::FollowTarget) {
+            focus.target = match &selected {
+                Some(selected) => Some(selected.0),
+                None => enemies
+                    .iter()
+                    .min_by(|(_, a), (_, b)| {
+                        a.translation().distance_squared(controller.focus)
+                            .total_cmp(&b.translation().distance_squared(controller.focus))
+                    })
+                    .map(|(e, _)| e),
+            };
+        }
+    }
+}
+
+fn follow_camera_focus(
+    mut focus: ResMut<CameraFocus>,
+    mut cameras: Query<&mut CameraController>,
+    targets: Query<&GlobalTransform>,
+    time: Res<Time>,
+) {
+    let Some(target) = focus.target else {
+        return;
+    };
+
+    // the followed entity died or got sold
+    let Ok(target_transform) = targets.get(target) else {
+        focus.target = None;
+        return;
+    };
+
+    let goal = target_transform.translation() * Vec3::new(1.0, 0.0, 1.0);
+    let t = 1.0 - (-FOLLOW_SMOOTHING * time.raw_delta_seconds()).exp();
+    for mut controller in &mut cameras {
+        controller.focus = controller.focus.lerp(goal, t);
+    }
+}
+
+fn camera_keyboard_controls(
+    mut q: Query<(&mut CameraController, &ActionState<Action>)>,
+    settings: Res<CameraSettings>,
+    focus: Res<CameraFocus>,
+    time: Res<Time>,
+    interactions: Query<&Interaction, With<Node>>,
+) {
+    // the mouse wheel scrolls the hovered UI, e.g. the build menu, instead of zooming
+    let over_ui = interactions.iter().any(|interaction| *interaction != Interaction::None);
+
+    for (mut controller, action_state) in &mut q {
+        let mut direction = Vec2::ZERO;
+        if action_state.pressed(Action::MoveForward) {
+            direction.y += 1.0;
+        }
+        if action_state.pressed(Action::MoveBack) {
+            direction.y -= 1.0;
+        }
+        if action_state.pressed(Action::MoveRight) {
+            direction.x += 1.0;
+        }
+        if action_state.pressed(Action::MoveLeft) {
+            direction.x -= 1.0;
+        }
+        let direction = match action_state.axis_pair(Action::PanCamera) {
+            Some(stick) if direction == Vec2::ZERO => stick.xy().clamp_length_max(1.0),
+            _ => direction.normalize_or_zero(),
+        };
+        // panning is up to the followed entity
+        if direction != Vec2::ZERO && focus.target.is_none() {
+            controller.pan(direction, settings.pan_speed * settings.sensitivity * time.raw_delta_seconds());
+        }
+
+        if action_state.pressed(Action::RotateLeft) {
+            controller.yaw -= ROTATION_SPEED * settings.sensitivity * time.raw_delta_seconds();
+        }
+        if action_state.pressed(Action::RotateRight) {
+            controller.yaw += ROTATION_SPEED * settings.sensitivity * time.raw_delta_seconds();
+        }
+
+        // jump back to the starting view
+        if action_state.just_pressed(Action::Jump) {
+            *controller = CameraController::default();
+        }
+
+        if over_ui {
+            continue;
+        }
+        if action_state.just_pressed(Action::ZoomIn) {
+            controller.distance = (controller.distance * ZOOM_STEP).max(MIN_DISTANCE);
+        }
+        if action_state.just_pressed(Action::ZoomOut) {
+            controller.distance = (controller.distance / ZOOM_STEP).min(MAX_DISTANCE);
+        }
+    }
+}
+
+fn camera_edge_panning(
+    mut q: Query<&mut CameraController>,
+    windows: Query<&Window>,
+    settings: Res<CameraSettings>,
+    time: Res<Time>,
+) {
+    if !settings.edge_panning {
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+
+    // the cursor position starts at the bottom left corner of the window
+    let mut direction = Vec2::ZERO;
+    if cursor.x < EDGE_PAN_MARGIN {
+        direction.x -= 1.0;
+    }
+    if cursor.x > window.width() - EDGE_PAN_MARGIN {
+        direction.x += 1.0;
+    }
+    if cursor.y < EDGE_PAN_MARGIN {
+        direction.y -= 1.0;
+    }
+    if cursor.y > window.height() - EDGE_PAN_MARGIN {
+        direction.y += 1.0;
+    }
+
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    for mut controller in &mut q {
+        controller.pan(direction.normalize(), settings.pan_speed * settings.sensitivity * time.raw_delta_seconds());
+    }
+}
+
+fn apply_camera_transform(
+    mut q: Query<(&CameraController, &mut Transform), Changed<CameraController>>,
+) {
+    for (controller, mut transform) in &mut q {
+        *transform = controller.transform();
+    }
+}
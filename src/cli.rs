@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use crate::StartNewGame;
+use crate::gameplay::difficulty::Difficulty;
+use crate::headless::{DEFAULT_WAVES, HeadlessOptions};
+use crate::map::format::{DEFAULT_MAP_PATH, MapFile};
+use crate::settings::DisplayMode;
+use crate::state::global::GameState;
+
+const USAGE: &str = "\
+Usage: game-with-bevy [options]
+
+  --map <path>           start a game on the map right away, e.g. assets/maps/default.ron
+  --difficulty <name>    easy, normal, hard or endless, for the games started from the command line
+  --seed <number>        seed of the combat rolls, the same seed rolls the same critical hits
+  --window <mode>        windowed, borderless or fullscreen, the settings are left as they are
+  --skip-menu            start a game right away, on the default map unless --map is given
+  --debug <flags>        comma separated: colliders (draws the physics shapes), diagnostics (logs frame times)
+  --headless             play without window and print the outcome, see --waves and --build-order
+  --waves <number>       waves the headless game plays
+  --build-order <path>   towers the headless game builds, e.g. assets/data/default.build_order.ron
+  --help                 show this text";
+
+/// How the game was started from the command line, everything left out works like a normal start
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LaunchOptions {
+    /// map (relative to the working directory) the game started right away is played on
+    pub map: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    /// seed of the `CombatRng` of every new game, a random one is picked when missing
+    pub seed: Option<u64>,
+    /// display mode replacing the one of the settings for this run
+    pub display_mode: Option<DisplayMode>,
+    /// starts a game instead of showing the main menu after loading
+    pub skip_menu: bool,
+    pub debug: DebugFlags,
+    /// runs without window, `None` for a normal game
+    pub headless: Option<HeadlessOptions>,
+}
+
+/// Developer aids turned on with `--debug`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DebugFlags {
+    /// draws the colliders of rapier
+    pub colliders: bool,
+    /// logs frame times to the console
+    pub diagnostics: bool,
+}
+
+impl LaunchOptions {
+    /// Options of the command line of this process, prints the usage and exits on `--help` and on mistakes
+    pub fn from_args() -> Self {
+        match LaunchOptions::parse(std::env::args().skip(1)) {
+            Ok(Some(options)) => options,
+            Ok(None) => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// `None` when the usage was asked for
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = LaunchOptions::default();
+        let mut headless = false;
+        let mut waves = None;
+        let mut build_order = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--map" => options.map = Some(value()?),
+                "--difficulty" => options.difficulty = Some(parse_difficulty(&value()?)?),
+                "--seed" => {
+                    let seed = value()?;
+                    options.seed = Some(seed.parse().map_err(|_| format!("the seed {} isn't a number", seed))?);
+                }
+                "--window" => options.display_mode = Some(parse_display_mode(&value()?)?),
+                "--skip-menu" => options.skip_menu = true,
+                "--debug" => {
+                    for flag in value()?.split(',') {
+                        match flag.trim() {
+                            "colliders" => options.debug.colliders = true,
+                            "diagnostics" => options.debug.diagnostics = true,
+                            flag => return Err(format!("there is no debug flag {}", flag)),
+                        }
+                    }
+                }
+                "--headless" => headless = true,
+                "--waves" => {
+                    let count = value()?;
+                    waves = Some(count.parse().map_err(|_| format!("the wave count {} isn't a number", count))?);
+                }
+                "--build-order" => build_order = Some(value()?),
+                "--help" | "-h" => return Ok(None),
+                arg => return Err(format!("unknown option {}", arg)),
+            }
+        }
+
+        // caught here, a game which can't start leaves a headless run waiting forever
+        if let Some(map) = &options.map {
+            MapFile::load(map).map_err(|e| format!("can't load the map {}: {}", map, e))?;
+        }
+        if headless {
+            options.headless = Some(HeadlessOptions {
+                waves: waves.unwrap_or(DEFAULT_WAVES),
+                build_order,
+            });
+        } else if waves.is_some() || build_order.is_some() {
+            return Err("--waves and --build-order only work with --headless".to_string());
+        }
+        // a map of its own or a headless game only make sense without the menu
+        options.skip_menu |= options.map.is_some() || headless;
+        Ok(Some(options))
+    }
+
+    /// Game started in place of the main menu
+    pub fn new_game(&self) -> StartNewGame {
+        StartNewGame {
+            map: self.map.clone().unwrap_or_else(|| DEFAULT_MAP_PATH.to_string()),
+            difficulty: self.difficulty.unwrap_or_default(),
+            level: None,
+        }
+    }
+}
+
+fn parse_difficulty(name: &str) -> Result<Difficulty, String> {
+    match name {
+        "easy" => Ok(Difficulty::Easy),
+        "normal" => Ok(Difficulty::Normal),
+        "hard" => Ok(Difficulty::Hard),
+        "endless" => Ok(Difficulty::Endless),
+        _ => Err(format!("there is no difficulty {}", name)),
+    }
+}
+
+fn parse_display_mode(name: &str) -> Result<DisplayMode, String> {
+    match name {
+        "windowed" => Ok(DisplayMode::Windowed),
+        "borderless" => Ok(DisplayMode::Borderless),
+        "fullscreen" => Ok(DisplayMode::Fullscreen),
+        _ => Err(format!("there is no window mode {}", name)),
+    }
+}
+
+/// Makes the options of the command line available to the other plugins,
+/// and starts the game right away with `--skip-menu`
+pub struct LaunchPlugin(pub LaunchOptions);
+
+impl Plugin for LaunchPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(self.0.clone())
+            .add_system(skip_main_menu.in_schedule(OnEnter(GameState::MainMenu)).run_if(skips_menu))
+        ;
+    }
+}
+
+fn skips_menu(options: Res<LaunchOptions>) -> bool {
+    options.skip_menu
+}
+
+/// Only the first time, leaving a game still leads to the main menu
+fn skip_main_menu(
+    options: Res<LaunchOptions>,
+    mut new_game_writer: EventWriter<StartNewGame>,
+    mut skipped: Local<bool>,
+) {
+    if *skipped {
+        return;
+    }
+    *skipped = true;
+
+    let new_game = options.new_game();
+    info!("Starting {} right away", new_game.map);
+    new_game_writer.send(new_game);
+}
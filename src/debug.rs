@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_mod_picking::PickableBundle;
+use bevy_mod_picking::event_listening::{Bubble, ListenedEvent, OnPointer};
+use bevy_mod_picking::events::Click;
+use bevy_mod_picking::prelude::RaycastPickTarget;
+use hexx::Hex;
+use hexx::algorithms::a_star;
+use rand::seq::IteratorRandom;
+
+use crate::{HexLocation, Map};
+use crate::assets::GameAssets;
+use crate::gameplay::enemy::EnemyKind;
+use crate::state::global::GameState;
+use crate::ui::highlight::{apply_hex_highlights, HexHighlights, HighlightKind};
+
+/// Markers spawned on random hexes to plan routes between
+const ROUTE_MARKERS: usize = 9;
+/// Time the trail of a route preview takes from one hex to the next
+const TRAIL_STEP: Duration = Duration::from_millis(60);
+
+/// Developer tools which have no place in a normal game, only built with the `debug-tools` feature
+pub struct DebugToolsPlugin;
+
+impl Plugin for DebugToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DebugTools>()
+            .init_resource::<RoutePlanner>()
+            .add_event::<RouteChosenEvent>()
+            .add_system(toggle_debug_tools)
+            .add_systems(
+                (
+                    // a new game or a loaded one replaces the map, which only marks it changed
+                    spawn_route_markers.run_if(resource_changed::<Map>()),
+                    listen_for_route_planning,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(
+                march_route_preview
+                    .run_if(resource_exists::<RoutePreview>())
+                    .before(apply_hex_highlights)
+            )
+        ;
+    }
+}
+
+/// Switches for the developer tools, F3 toggles them during the game
+#[derive(Resource, Debug)]
+pub struct DebugTools {
+    /// clicking two route markers draws the A* path between them
+    pub route_planner: bool,
+}
+
+impl Default for DebugTools {
+    fn default() -> Self {
+        DebugTools { route_planner: true }
+    }
+}
+
+/// Route markers clicked so far
+#[derive(Resource, Default)]
+struct RoutePlanner {
+    obj1: Option<Entity>,
+    obj2: Option<Entity>,
+}
+
+struct RouteChosenEvent;
+
+/// Route revealed hex by hex, its head moving along like a marching trail
+#[derive(Resource)]
+struct RoutePreview {
+    hexes: Vec<Hex>,
+    /// index of the hex the head of the trail is on
+    head: usize,
+    timer: Timer,
+}
+
+impl RoutePreview {
+    fn new(hexes: Vec<Hex>) -> Self {
+        RoutePreview {
+            hexes,
+            head: 0,
+            timer: Timer::new(TRAIL_STEP, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Capsule on a hex which can be picked as start or end of a planned route
+#[derive(Component)]
+struct RouteMarker;
+
+fn toggle_debug_tools(keys: Res<Input<KeyCode>>, mut tools: ResMut<DebugTools>) {
+    if keys.just_pressed(KeyCode::F3) {
+        tools.route_planner = !tools.route_planner;
+        info!("Route planner {}", if tools.route_planner { "enabled" } else { "disabled" });
+    }
+}
+
+fn spawn_route_markers(
+    mut commands: Commands,
+    map: Res<Map>,
+    assets: Res<GameAssets>,
+    mut highlights: ResMut<HexHighlights>,
+    mut planner: ResMut<RoutePlanner>,
+    markers: Query<(Entity, &HexLocation), With<RouteMarker>>,
+) {
+    // building changes the map as well, markers still standing on it keep their place
+    if !markers.is_empty() && markers.iter().all(|(_, location)| map.terrain.contains_key(&location.location)) {
+        // the highlights of a new map start out empty
+        for (_, location) in &markers {
+            highlights.highlight(location.location, HighlightKind::Route);
+        }
+        return;
+    }
+
+    for (entity, _) in &markers {
+        commands.entity(entity).despawn_recursive();
+    }
+    *planner = RoutePlanner::default();
+
+    let hexes: Vec<Hex> = map.terrain.keys().copied().choose_multiple(&mut rand::thread_rng(), ROUTE_MARKERS);
+    for hex in hexes {
+        highlights.highlight(hex, HighlightKind::Route);
+        commands
+            .spawn((
+                PbrBundle {
+                    mesh: assets.enemy_mesh(EnemyKind::Normal),
+                    material: assets.enemy_material(EnemyKind::Normal),
+                    transform: Transform::from_translation(map.surface_pos(hex) + Vec3::Y * 0.1),
+                    ..default()
+                },
+                HexLocation { location: hex },
+                RouteMarker,
+                PickableBundle::default(),
+                RaycastPickTarget::default(),
+                OnPointer::<Click>::run_callback(on_route_marker_clicked),
+            ));
+    }
+}
+
+fn on_route_marker_clicked(
+    In(event): In<ListenedEvent<Click>>,
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    tools: Res<DebugTools>,
+    mut planner: ResMut<RoutePlanner>,
+    mut planner_event_writer: EventWriter<RouteChosenEvent>,
+) -> Bubble {
+    // with the planner switched off the click goes on to whatever is below the marker
+    if !tools.route_planner {
+        return Bubble::Up;
+    }
+
+    commands.entity(event.target).insert(assets.highlighted_material.clone());
+
+    if planner.obj1.is_none() {
+        planner.obj1 = Some(event.target);
+    } else {
+        planner.obj2 = Some(event.target);
+        planner_event_writer.send(RouteChosenEvent);
+    }
+
+    Bubble::Burst
+}
+
+fn listen_for_route_planning(
+    mut commands: Commands,
+    mut planner: ResMut<RoutePlanner>,
+    mut events: EventReader<RouteChosenEvent>,
+    hex_query: Query<&HexLocation>,
+) {
+    for _ in events.iter() {
+        let (Some(start), Some(end)) = (planner.obj1, planner.obj2) else {
+            continue;
+        };
+        let (Ok(start_location), Ok(end_location)) = (hex_query.get(start), hex_query.get(end)) else {
+            continue;
+        };
+
+        let path = a_star(start_location.location, end_location.location, |_| Some(1));
+        if let Some(hex_fields) = path {
+            commands.insert_resource(RoutePreview::new(hex_fields));
+        }
+
+        planner.obj1 = None;
+        planner.obj2 = None;
+    }
+}
+
+fn march_route_preview(
+    mut commands: Commands,
+    mut highlights: ResMut<HexHighlights>,
+    mut preview: ResMut<RoutePreview>,
+    time: Res<Time>,
+) {
+    if preview.head == 0 {
+        // the trail replaces the previously planned route
+        highlights.clear(HighlightKind::Route);
+    }
+
+    preview.timer.tick(time.delta());
+    for _ in 0..preview.timer.times_finished_this_tick() {
+        // the hex left behind joins the highlighted trail
+        if let Some(hex) = preview.head.checked_sub(1).and_then(|i| preview.hexes.get(i)).copied() {
+            highlights.unhighlight(hex, HighlightKind::RouteHead);
+            highlights.highlight(hex, HighlightKind::Route);
+        }
+
+        let Some(hex) = preview.hexes.get(preview.head).copied() else {
+            commands.remove_resource::<RoutePreview>();
+            return;
+        };
+        highlights.highlight(hex, HighlightKind::RouteHead);
+        preview.head += 1;
+    }
+}
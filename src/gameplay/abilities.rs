@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::state::global::GameState;
+
+pub struct TowerAbilityPlugin;
+
+impl Plugin for TowerAbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<ActivateAbility>()
+            .add_systems(
+                (
+                    tick_abilities,
+                    activate_abilities.after(tick_abilities),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbilityKind {
+    /// doubles the fire rate while active
+    Overcharge,
+}
+
+impl AbilityKind {
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            AbilityKind::Overcharge => "ability.overcharge",
+        }
+    }
+}
+
+/// Active ability of a tower type, triggered by the player
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct TowerAbility {
+    pub kind: AbilityKind,
+    /// seconds the ability lasts
+    pub duration: f32,
+    /// seconds after activating before the ability can be used again
+    pub cooldown: f32,
+}
+
+/// Ability of a placed tower, with its running effect and cooldown
+#[derive(Component, Debug)]
+pub struct AbilityState {
+    pub ability: TowerAbility,
+    active: Option<Timer>,
+    cooldown: Option<Timer>,
+}
+
+impl AbilityState {
+    pub fn new(ability: TowerAbility) -> Self {
+        AbilityState {
+            ability,
+            active: None,
+            cooldown: None,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown.is_none()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Share of the cooldown still to go (0..1), 0 once the ability is ready
+    pub fn cooldown_left(&self) -> f32 {
+        self.cooldown.as_ref().map_or(0.0, |timer| timer.percent_left())
+    }
+
+    pub fn cooldown_remaining(&self) -> Duration {
+        self.cooldown.as_ref().map_or(Duration::ZERO, |timer| timer.remaining())
+    }
+
+    /// Factor on the fire rate of the tower
+    pub fn fire_rate_factor(&self) -> f32 {
+        match self.ability.kind {
+            AbilityKind::Overcharge if self.is_active() => 2.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Request to trigger the ability of the tower, ignored while it is cooling down
+pub struct ActivateAbility(pub Entity);
+
+fn tick_abilities(
+    mut abilities: Query<&mut AbilityState>,
+    time: Res<Time>,
+) {
+    for mut state in &mut abilities {
+        if state.active.as_mut().map_or(false, |timer| timer.tick(time.delta()).finished()) {
+            state.active = None;
+        }
+        if state.cooldown.as_mut().map_or(false, |timer| timer.tick(time.delta()).finished()) {
+            state.cooldown = None;
+        }
+    }
+}
+
+fn activate_abilities(
+    mut activate_reader: EventReader<ActivateAbility>,
+    mut abilities: Query<&mut AbilityState>,
+) {
+    for event in activate_reader.iter() {
+        let Ok(mut state) = abilities.get_mut(event.0) else {
+            continue;
+        };
+        if !state.is_ready() {
+            continue;
+        }
+
+        let ability = state.ability;
+        state.active = Some(Timer::from_seconds(ability.duration, TimerMode::Once));
+        state.cooldown = Some(Timer::from_seconds(ability.cooldown, TimerMode::Once));
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::gameplay::enemy::{EnemyTag, Health};
+use crate::gameplay::movement::{MovementSpeed, SpeedChange, SpeedSource};
+use crate::gameplay::spatial::SpatialIndex;
+use crate::state::global::GameState;
+
+pub struct AuraPlugin;
+
+impl Plugin for AuraPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(
+                (
+                    heal_auras,
+                    haste_auras,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// What a support enemy does to the enemies around it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuraKind {
+    /// restores `amount` health every `interval`
+    Heal { amount: f32, interval: Duration },
+    /// multiplies the walking speed
+    Haste { factor: f32 },
+}
+
+impl AuraKind {
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            AuraKind::Heal { .. } => "aura.heal",
+            AuraKind::Haste { .. } => "aura.haste",
+        }
+    }
+}
+
+/// Area around a support enemy affecting all enemies within `radius`, including itself
+#[derive(Component, Debug)]
+pub struct Aura {
+    pub kind: AuraKind,
+    pub radius: f32,
+    timer: Timer,
+}
+
+impl Aura {
+    pub fn new(kind: AuraKind, radius: f32) -> Self {
+        let interval = match kind {
+            AuraKind::Heal { interval, .. } => interval,
+            AuraKind::Haste { .. } => Duration::ZERO,
+        };
+        Aura {
+            kind,
+            radius,
+            timer: Timer::new(interval, TimerMode::Repeating),
+        }
+    }
+}
+
+fn heal_auras(
+    mut auras: Query<(&mut Aura, &Transform), With<EnemyTag>>,
+    mut enemies: Query<&mut Health, With<EnemyTag>>,
+    index: Res<SpatialIndex>,
+    time: Res<Time>,
+) {
+    for (mut aura, aura_transform) in &mut auras {
+        let AuraKind::Heal { amount, .. } = aura.kind else {
+            continue;
+        };
+        if !aura.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        for (e, _) in index.within(aura_transform.translation, aura.radius) {
+            let Ok(mut health) = enemies.get_mut(e) else {
+                continue;
+            };
+            // the dead stay dead
+            if health.current > 0.0 && health.current < health.max {
+                health.current = (health.current + amount).min(health.max);
+            }
+        }
+    }
+}
+
+/// Speeds up the enemies standing in haste auras, the strongest aura counts
+fn haste_auras(
+    auras: Query<(&Aura, &Transform), With<EnemyTag>>,
+    mut enemies: Query<(Entity, &mut MovementSpeed), With<EnemyTag>>,
+    index: Res<SpatialIndex>,
+) {
+    // strongest haste reaching each enemy
+    let mut factors: HashMap<Entity, f32> = HashMap::new();
+    for (aura, transform) in &auras {
+        let AuraKind::Haste { factor } = aura.kind else {
+            continue;
+        };
+        for (e, _) in index.within(transform.translation, aura.radius) {
+            let strongest = factors.entry(e).or_insert(factor);
+            *strongest = strongest.max(factor);
+        }
+    }
+
+    for (e, mut speed) in &mut enemies {
+        let current = speed.modifier(SpeedSource::Haste);
+        match factors.get(&e).copied() {
+            Some(factor) if current == Some(SpeedChange::Multiply(factor)) => {}
+            Some(factor) => speed.add(SpeedSource::Haste, SpeedChange::Multiply(factor)),
+            None if current.is_some() => speed.remove(SpeedSource::Haste),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use crate::gameplay::buildings::{HasAttack, TargetingRange};
+use crate::gameplay::combat::Armor;
+use crate::gameplay::enemy::{EnemyKind, EnemyStats, Health};
+use crate::gameplay::mods::ModPacks;
+use crate::gameplay::movement::MovementSpeed;
+use crate::gameplay::towers::{fire_interval, Tower, TowerDefinition, TowerLevel, TowerRegistry};
+use crate::gameplay::waves::Waves;
+use crate::gameplay::weather::WeatherForecast;
+
+/// Asset path of the file with the numbers the game is tuned with
+const BALANCE_PATH: &str = "data/game.balance.ron";
+
+/// Loads the balance file and applies every change of it to the running game,
+/// needs the `AssetServer` and goes after the `DefaultPlugins`
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Balance>()
+            .add_asset::<Balance>()
+            .init_asset_loader::<BalanceLoader>()
+            .add_startup_system(load_balance)
+            .add_system(apply_balance_file.run_if(resource_exists::<BalanceFile>()))
+            .add_system(apply_tower_balance.after(apply_balance_file).run_if(resource_changed::<Balance>()))
+            .add_system(apply_enemy_balance.after(apply_balance_file).run_if(resource_changed::<Balance>()))
+            .add_system(apply_timer_balance.after(apply_balance_file).run_if(resource_changed::<Balance>()))
+        ;
+    }
+}
+
+/// Numbers the game is tuned with, everything left out of the balance file keeps the value the game comes with
+#[derive(Resource, Deserialize, TypeUuid, Clone, Default, Debug)]
+#[uuid = "0f2b0a4e-7c1d-4d55-9a3b-5e8f6d1c2b90"]
+#[serde(default)]
+pub struct Balance {
+    pub economy: EconomyBalance,
+    pub timers: TimerBalance,
+    /// stats replacing the built-in ones of the enemy kinds
+    pub enemies: HashMap<EnemyKind, EnemyBalance>,
+    /// stats replacing the ones of `towers.ron`, by the id of the tower
+    pub towers: HashMap<String, TowerBalance>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EconomyBalance {
+    /// gold awarded for every killed enemy
+    pub kill_reward: u32,
+    /// gold awarded for killing a boss
+    pub boss_kill_reward: u32,
+    /// gold awarded per second of the wave break skipped by starting the next wave early
+    pub early_start_gold_per_second: f32,
+    /// lives given back by the repair ability of the player
+    pub repair_lives: u32,
+    /// share of the invested gold the player gets back when selling a building
+    pub sell_refund_ratio: f32,
+}
+
+impl Default for EconomyBalance {
+    fn default() -> Self {
+        EconomyBalance {
+            kill_reward: 10,
+            boss_kill_reward: 250,
+            early_start_gold_per_second: 2.0,
+            repair_lives: 5,
+            sell_refund_ratio: 0.7,
+        }
+    }
+}
+
+/// Durations in seconds
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TimerBalance {
+    /// time between two enemies of a wave, unless their group sets its own spacing
+    pub spawn_interval: f32,
+    /// time between clearing a wave and the next one starting
+    pub wave_break: f32,
+    /// time random weather lasts before the next change
+    pub weather_duration: f32,
+    /// time enemies stay frozen by the freeze ability of the player
+    pub freeze_duration: f32,
+}
+
+impl Default for TimerBalance {
+    fn default() -> Self {
+        TimerBalance {
+            spawn_interval: 0.9,
+            wave_break: 8.0,
+            weather_duration: 45.0,
+            freeze_duration: 3.0,
+        }
+    }
+}
+
+impl TimerBalance {
+    pub fn spawn_interval(&self) -> Duration {
+        Duration::from_secs_f32(self.spawn_interval)
+    }
+
+    pub fn wave_break(&self) -> Duration {
+        Duration::from_secs_f32(self.wave_break)
+    }
+
+    pub fn weather_duration(&self) -> Duration {
+        Duration::from_secs_f32(self.weather_duration)
+    }
+}
+
+/// Stats replacing the built-in ones of an enemy kind, the ones left out stay as they are
+#[derive(Deserialize, Clone, Copy, Default, Debug)]
+#[serde(default)]
+pub struct EnemyBalance {
+    /// health before the factors of the difficulty
+    pub health: Option<f32>,
+    pub speed: Option<f32>,
+    pub armor: Option<Armor>,
+}
+
+impl EnemyBalance {
+    pub fn validate(&self, kind: EnemyKind) -> Result<(), String> {
+        // the health of enemies on the grid is scaled by the share of the new one to the old one
+        if let Some(health) = self.health {
+            check_positive(&format!("the health of {:?}", kind), health)?;
+        }
+        if let Some(speed) = self.speed {
+            check_not_negative(&format!("the speed of {:?}", kind), speed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stats replacing the ones of a tower type, the ones left out stay as they are
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct TowerBalance {
+    pub cost: Option<u32>,
+    pub range: Option<f32>,
+    pub fire_rate: Option<f32>,
+    pub damage: Option<f32>,
+    /// replacements for the upgrade tiers, in their order
+    pub upgrades: Vec<TowerBalance>,
+}
+
+impl Balance {
+    /// Stats of an enemy kind with the balanced ones in place
+    pub fn enemy_stats(&self, kind: EnemyKind) -> EnemyStats {
+        let mut stats = kind.stats();
+        if let Some(balance) = self.enemies.get(&kind) {
+            stats.health = balance.health.unwrap_or(stats.health);
+            stats.speed = balance.speed.unwrap_or(stats.speed);
+            stats.armor = balance.armor.unwrap_or(stats.armor);
+        }
+        stats
+    }
+
+    /// Catches the values which would stop the game, like timers repeating every zero seconds
+    fn validate(&self) -> Result<(), String> {
+        check_positive("spawn_interval", self.timers.spawn_interval)?;
+        check_not_negative("wave_break", self.timers.wave_break)?;
+        check_positive("weather_duration", self.timers.weather_duration)?;
+        check_not_negative("freeze_duration", self.timers.freeze_duration)?;
+        for (kind, balance) in &self.enemies {
+            balance.validate(*kind)?;
+        }
+        for (id, balance) in &self.towers {
+            balance.validate(id)?;
+        }
+        Ok(())
+    }
+
+    /// Gold awarded for starting the next wave while `remaining` of the break is left
+    pub fn early_start_bonus(&self, remaining: Duration) -> u32 {
+        (remaining.as_secs_f32() * self.economy.early_start_gold_per_second).round() as u32
+    }
+}
+
+impl TowerBalance {
+    fn validate(&self, id: &str) -> Result<(), String> {
+        for balance in std::iter::once(self).chain(&self.upgrades) {
+            if let Some(fire_rate) = balance.fire_rate {
+                check_positive(&format!("the fire rate of the tower {}", id), fire_rate)?;
+            }
+            if let Some(range) = balance.range {
+                check_not_negative(&format!("the range of the tower {}", id), range)?;
+            }
+            if let Some(damage) = balance.damage {
+                check_not_negative(&format!("the damage of the tower {}", id), damage)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Values are replaced rather than scaled, applying the same balance again changes nothing
+    fn apply(&self, definition: &mut TowerDefinition) {
+        definition.cost = self.cost.unwrap_or(definition.cost);
+        definition.range = self.range.unwrap_or(definition.range);
+        definition.fire_rate = self.fire_rate.unwrap_or(definition.fire_rate);
+        definition.damage = self.damage.unwrap_or(definition.damage);
+        for (upgrade, balance) in definition.upgrades.iter_mut().zip(&self.upgrades) {
+            upgrade.cost = balance.cost.unwrap_or(upgrade.cost);
+            upgrade.range = balance.range.unwrap_or(upgrade.range);
+            upgrade.fire_rate = balance.fire_rate.unwrap_or(upgrade.fire_rate);
+            upgrade.damage = balance.damage.unwrap_or(upgrade.damage);
+        }
+    }
+}
+
+/// Rates, intervals and health end up as divisors or repeating timers, which need them above zero
+pub fn check_positive(name: &str, value: f32) -> Result<(), String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} has to be above zero, it is {}", name, value))
+    }
+}
+
+/// Durations, ranges and damage may be zero, `Duration` can't hold less
+pub fn check_not_negative(name: &str, value: f32) -> Result<(), String> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} can't be below zero, it is {}", name, value))
+    }
+}
+
+#[derive(Resource, Debug)]
+struct BalanceFile(Handle<Balance>);
+
+#[derive(Default)]
+struct BalanceLoader;
+
+impl AssetLoader for BalanceLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            // a broken edit keeps the balance applied before
+            let balance: Balance = ron::de::from_bytes(bytes)?;
+            balance.validate().map_err(bevy::asset::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(balance));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["balance.ron"]
+    }
+}
+
+fn load_balance(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BalanceFile(asset_server.load(BALANCE_PATH)));
+}
+
+/// Takes over the balance once the file is loaded, and again whenever it changes on disk
+fn apply_balance_file(
+    mut events: EventReader<AssetEvent<Balance>>,
+    files: Res<Assets<Balance>>,
+    balance_file: Res<BalanceFile>,
+    mods: Res<ModPacks>,
+    mut balance: ResMut<Balance>,
+) {
+    let loaded = events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => *handle == balance_file.0,
+        AssetEvent::Removed { .. } => false,
+    });
+    if !loaded {
+        return;
+    }
+
+    if let Some(file) = files.get(&balance_file.0) {
+        info!("Applying the balance of {}", BALANCE_PATH);
+        *balance = file.clone();
+        // enemies of mod packs win over the file
+        balance.enemies.extend(mods.enemies());
+    }
+}
+
+/// Rebuilds the tower types from the loaded ones and the balance, and updates the towers already standing
+fn apply_tower_balance(
+    balance: Res<Balance>,
+    mut registry: ResMut<TowerRegistry>,
+    mut towers: Query<(&Tower, &TowerLevel, &mut HasAttack, &mut TargetingRange)>,
+) {
+    // overrides removed from the file go back to the values the towers came with
+    registry.towers = registry.defaults.clone();
+    for definition in registry.towers.iter_mut() {
+        if let Some(tower_balance) = balance.towers.get(&definition.id) {
+            tower_balance.apply(definition);
+        }
+    }
+
+    for (tower, level, mut attack, mut range) in &mut towers {
+        let Some(definition) = registry.get(&tower.id) else {
+            continue;
+        };
+        let (tower_range, fire_rate, damage, _) = definition.stats_at(level.0);
+        attack.timer.set_duration(fire_interval(fire_rate));
+        attack.damage = damage;
+        range.0 = tower_range;
+    }
+}
+
+/// Changes the enemies already on the grid, their health keeps its share of the maximum
+fn apply_enemy_balance(
+    balance: Res<Balance>,
+    // what the enemies were spawned with, or last changed to
+    mut applied: Local<Balance>,
+    mut enemies: Query<(&EnemyKind, &mut Health, &mut MovementSpeed, &mut Armor)>,
+) {
+    for (kind, mut health, mut speed, mut armor) in &mut enemies {
+        let stats = balance.enemy_stats(*kind);
+        let factor = stats.health / applied.enemy_stats(*kind).health;
+        health.max *= factor;
+        health.current *= factor;
+        speed.base = stats.speed;
+        *armor = stats.armor;
+    }
+    *applied = balance.clone();
+}
+
+fn apply_timer_balance(
+    balance: Res<Balance>,
+    waves: Option<ResMut<Waves>>,
+    mut forecast: ResMut<WeatherForecast>,
+) {
+    if let Some(mut waves) = waves {
+        waves.set_timing(&balance.timers);
+    }
+    forecast.set_duration(balance.timers.weather_duration());
+}
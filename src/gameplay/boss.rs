@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{HexLocation, Map};
+use crate::assets::GameAssets;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::enemy::{EnemyKind, EnemyProgress, EnemyTag, Health, restore_enemy, WalkingPath};
+use crate::gameplay::movement::{MovementSpeed, SpeedChange, SpeedSource};
+use crate::gameplay::vfx::{SpawnVfx, VfxKind};
+use crate::gameplay::waves::Waves;
+use crate::state::global::GameState;
+
+/// Phases every boss goes through, highest health share first
+const BOSS_PHASES: [BossPhase; 3] = [
+    BossPhase { health_share: 0.75, action: PhaseAction::SpeedUp(1.4) },
+    BossPhase { health_share: 0.5, action: PhaseAction::SpawnMinions { kind: EnemyKind::Fast, count: 4 } },
+    BossPhase { health_share: 0.25, action: PhaseAction::Shield(Duration::from_secs(4)) },
+];
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(
+                (
+                    enter_boss_phases,
+                    expire_boss_shields,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// What a boss does once it drops below the health share of a phase
+#[derive(Clone, Copy, Debug)]
+pub enum PhaseAction {
+    /// multiplies the walking speed
+    SpeedUp(f32),
+    /// calls enemies to its side, they walk on from where the boss stands
+    SpawnMinions { kind: EnemyKind, count: u32 },
+    /// ignores all damage for a while
+    Shield(Duration),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BossPhase {
+    /// share (0..1) of the maximum health the boss enters the phase at
+    pub health_share: f32,
+    pub action: PhaseAction,
+}
+
+/// Enemy going through `BOSS_PHASES` while it loses health
+#[derive(Component, Debug)]
+pub struct Boss {
+    /// phases not entered yet
+    phases: Vec<BossPhase>,
+    pub speed_factor: f32,
+    shield: Option<Timer>,
+}
+
+/// Bubble around a boss while it ignores damage
+#[derive(Component)]
+struct BossShield;
+
+impl Boss {
+    /// Boss at the given share of its health, phases above it count as entered
+    pub fn new(health_share: f32) -> Self {
+        let mut boss = Boss {
+            phases: vec![],
+            speed_factor: 1.0,
+            shield: None,
+        };
+        for phase in BOSS_PHASES {
+            if phase.health_share < health_share {
+                boss.phases.push(phase);
+            } else if let PhaseAction::SpeedUp(factor) = phase.action {
+                // a restored boss keeps the pace it had
+                boss.speed_factor *= factor;
+            }
+        }
+        boss
+    }
+
+    pub fn is_shielded(&self) -> bool {
+        self.shield.is_some()
+    }
+
+    /// Time the shield of the boss stays up, `None` without one
+    pub fn shield_left(&self) -> Option<Duration> {
+        self.shield.as_ref().map(Timer::remaining)
+    }
+
+    /// Number of the current phase, starting at 1
+    pub fn phase(&self) -> usize {
+        BOSS_PHASES.len() - self.phases.len() + 1
+    }
+
+    pub fn phase_count(&self) -> usize {
+        BOSS_PHASES.len() + 1
+    }
+}
+
+fn enter_boss_phases(
+    mut commands: Commands,
+    mut bosses: Query<(Entity, &mut Boss, &mut MovementSpeed, &Health, &HexLocation, &WalkingPath, &Transform, &EnemyKind), (With<EnemyTag>, Changed<Health>)>,
+    mut vfx_writer: EventWriter<SpawnVfx>,
+    map: Res<Map>,
+    assets: Res<GameAssets>,
+    balance: Res<Balance>,
+    waves: Res<Waves>,
+    difficulty: Res<Difficulty>,
+) {
+    let health_factor = difficulty.health_factor(waves.round());
+
+    for (e, mut boss, mut speed, health, location, walking_path, transform, kind) in &mut bosses {
+        // the dead don't change phases anymore
+        if health.current <= 0.0 {
+            continue;
+        }
+        let share = health.current / health.max;
+
+        // a single big hit can skip through several phases at once
+        while boss.phases.first().map_or(false, |phase| share <= phase.health_share) {
+            let phase = boss.phases.remove(0);
+            vfx_writer.send(SpawnVfx { kind: VfxKind::Impact, position: transform.translation });
+
+            match phase.action {
+                PhaseAction::SpeedUp(factor) => {
+                    boss.speed_factor *= factor;
+                    speed.add(SpeedSource::Boss, SpeedChange::Multiply(boss.speed_factor));
+                }
+                PhaseAction::SpawnMinions { kind: minion, count } => {
+                    for _ in 0..count {
+                        let progress = EnemyProgress {
+                            location: location.location,
+                            ground_pos: Vec2::new(transform.translation.x, transform.translation.z),
+                            waypoints: walking_path.waypoints().to_vec(),
+                        };
+                        let health = balance.enemy_stats(minion).health * health_factor;
+                        restore_enemy(&mut commands, &map, &assets, &balance, minion, health, health_factor, progress);
+                    }
+                }
+                PhaseAction::Shield(duration) => {
+                    boss.shield = Some(Timer::new(duration, TimerMode::Once));
+                    spawn_shield_bubble(&mut commands, &assets, e, *kind);
+                }
+            }
+        }
+    }
+}
+
+/// Puts the shield of a boss back up for the time it had left in a saved game
+pub fn restore_boss_shield(commands: &mut Commands, assets: &GameAssets, boss: Entity, kind: EnemyKind, left: Duration) {
+    // the boss is only spawned once the commands are applied
+    commands.add(move |world: &mut World| {
+        if let Some(mut restored) = world.get_mut::<Boss>(boss) {
+            restored.shield = Some(Timer::new(left, TimerMode::Once));
+        }
+    });
+    spawn_shield_bubble(commands, assets, boss, kind);
+}
+
+fn spawn_shield_bubble(commands: &mut Commands, assets: &GameAssets, boss: Entity, kind: EnemyKind) {
+    let size = kind.stats().size * 2.5;
+    let bubble = commands
+        .spawn((
+            BossShield,
+            PbrBundle {
+                mesh: assets.shield_mesh.clone(),
+                material: assets.shield_material.clone(),
+                transform: Transform::from_scale(Vec3::splat(size)),
+                ..default()
+            },
+        ))
+        .id();
+    commands.entity(boss).add_child(bubble);
+}
+
+fn expire_boss_shields(
+    mut commands: Commands,
+    mut bosses: Query<(Entity, &mut Boss)>,
+    shields: Query<(Entity, &Parent), With<BossShield>>,
+    time: Res<Time>,
+) {
+    for (e, mut boss) in &mut bosses {
+        let Some(timer) = boss.shield.as_mut() else {
+            continue;
+        };
+        if !timer.tick(time.delta()).finished() {
+            continue;
+        }
+
+        boss.shield = None;
+        for (shield, parent) in &shields {
+            if parent.get() == e {
+                commands.entity(shield).despawn_recursive();
+            }
+        }
+    }
+}
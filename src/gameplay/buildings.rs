@@ -1,18 +1,82 @@
+use std::collections::HashSet;
 use std::time::Duration;
 use bevy::prelude::*;
-use bevy_rapier3d::prelude::{ActiveEvents, Collider, RigidBody};
+#[cfg(feature = "rapier")]
+use bevy_rapier3d::prelude::{ActiveEvents, Collider, CollisionGroups, RigidBody, Sensor};
+use serde::Deserialize;
+
+use crate::assets::GameAssets;
+use crate::gameplay::abilities::AbilityState;
+use crate::gameplay::auras::Aura;
+use crate::gameplay::combat::{CombatRng, CriticalHit, DamageType, EnemyHit, roll_critical};
+use crate::gameplay::effects::{OnHitEffects, StatusEffect};
+use crate::gameplay::enemy::{EnemyTag, Health, Velocity, WalkingPath};
+use crate::gameplay::fog::InFog;
+#[cfg(feature = "rapier")]
+use crate::gameplay::physics::projectile_groups;
+use crate::gameplay::research::{Research, TechRegistry};
+use crate::gameplay::spatial::SpatialIndex;
+use crate::gameplay::vfx::{SpawnVfx, VfxKind};
+use crate::gameplay::weather::Weather;
+use crate::state::global::GameState;
 
 pub struct BuildingPlugin;
 
 impl Plugin for BuildingPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_system(building_shooting)
-            .add_system(move_bullets)
+            .add_event::<AreaImpact>()
+            .add_event::<BulletSpent>()
+            .add_event::<DamageDealt>()
+            .init_resource::<ProjectilePool>()
+            .add_startup_system(setup_projectile_pool)
+            .add_systems(
+                (
+                    building_targeting,
+                    release_bullets.before(building_shooting),
+                    building_shooting.after(building_targeting),
+                    steer_homing_bullets.before(move_bullets),
+                    move_bullets,
+                    fly_mortar_shells,
+                    attach_beam_rays,
+                    aim_beam_rays.after(attach_beam_rays).after(building_targeting),
+                    fade_lightning_arcs,
+                    spawn_shockwaves,
+                    animate_shockwaves,
+                    award_veterancy_badges,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
         ;
     }
 }
 
+/// Scale applied to the tower models
+pub const BUILDING_SCALING: Vec3 = Vec3::splat(0.1);
+/// Number of bullets spawned up front, the pool grows beyond that when needed
+const PROJECTILE_POOL_SIZE: usize = 64;
+/// World units per second covered by bullets and mortar shells
+const PROJECTILE_SPEED: f32 = 3.0;
+/// Bullets hit enemies this close to their center
+pub const BULLET_RADIUS: f32 = 0.8;
+/// Peak height of a mortar arc per world unit between the tower and the impact
+const MORTAR_ARC: f32 = 0.5;
+/// Blast of mortars fired by towers without an area of effect of their own
+const MORTAR_AREA: AreaOfEffect = AreaOfEffect { radius: 0.6, falloff: 0.3, shockwave: true };
+/// Jumps of chain lightning fired by towers without a `LightningChain` of their own
+const DEFAULT_CHAIN: LightningChain = LightningChain { jumps: 3, jump_range: 0.8, falloff: 0.25 };
+/// Kills a tower needs for each veterancy rank
+pub const VETERANCY_KILLS: [u32; 3] = [10, 30, 75];
+/// Share of extra damage and range per veterancy rank
+const VETERANCY_BONUS: f32 = 0.05;
+/// Height of the veterancy badges above the origin of the tower, before the scaling of the buildings
+const BADGE_HEIGHT: f32 = 9.0;
+/// Distance between two badges, before the scaling of the buildings
+const BADGE_SPACING: f32 = 1.5;
+/// Time a lightning arc stays visible
+const LIGHTNING_ARC_LIFETIME: Duration = Duration::from_millis(150);
+/// Thickness of beams and lightning arcs
+const RAY_WIDTH: f32 = 0.03;
+
 #[derive(Component)]
 pub struct BuildingTag;
 
@@ -20,59 +84,746 @@ pub struct BuildingTag;
 pub struct HasAttack {
     /// How often to spawn a new bullet? (repeating timer)
     pub(crate) timer: Timer,
+    /// Damage of every bullet fired
+    pub(crate) damage: f32,
+}
+
+/// Radius (in world units) in which a building is able to pick up enemies
+#[derive(Component)]
+pub struct TargetingRange(pub f32);
+
+/// Decides which of the enemies in range a building is going to shoot at
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetingMode {
+    #[default]
+    Nearest,
+    /// the enemy which walked the furthest along its path
+    First,
+    /// the enemy which walked the least along its path
+    Last,
+    /// the enemy with the most health left
+    Strongest,
+    /// healers and banner carriers first, otherwise the nearest enemy
+    Support,
+}
+
+impl TargetingMode {
+    pub const ALL: [TargetingMode; 5] = [
+        TargetingMode::Nearest,
+        TargetingMode::First,
+        TargetingMode::Last,
+        TargetingMode::Strongest,
+        TargetingMode::Support,
+    ];
+
+    /// Mode following this one, wrapping around after the last
+    pub fn next(&self) -> TargetingMode {
+        let index = TargetingMode::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        TargetingMode::ALL[(index + 1) % TargetingMode::ALL.len()]
+    }
+
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            TargetingMode::Nearest => "targeting.nearest",
+            TargetingMode::First => "targeting.first",
+            TargetingMode::Last => "targeting.last",
+            TargetingMode::Strongest => "targeting.strongest",
+            TargetingMode::Support => "targeting.support",
+        }
+    }
+}
+
+/// Damage dealt and enemies killed by a building so far
+#[derive(Component, Default, Debug)]
+pub struct CombatRecord {
+    pub kills: u32,
+    pub damage_dealt: f32,
+}
+
+impl CombatRecord {
+    /// Veterancy rank earned through the kills, 0 for towers without enough of them
+    pub fn rank(&self) -> usize {
+        VETERANCY_KILLS.iter().filter(|kills| self.kills >= **kills).count()
+    }
+
+    /// Factor on the damage and the range of the tower
+    pub fn veterancy_factor(&self) -> f32 {
+        1.0 + self.rank() as f32 * VETERANCY_BONUS
+    }
+}
+
+/// Star above a tower, one per veterancy rank
+#[derive(Component)]
+struct VeterancyBadge;
+
+/// Sent for every hit of a building, `amount` leaves out damage beyond the remaining health
+pub struct DamageDealt {
+    pub building: Entity,
+    pub amount: f32,
+}
+
+/// Enemy the building is currently aiming at, updated every frame
+#[derive(Component, Default)]
+pub struct CurrentTarget(pub Option<Entity>);
+
+/// Kind of bullet a building fires
+#[derive(Component, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectileKind {
+    /// flies in a straight line towards the position of the target at the time of firing
+    #[default]
+    Straight,
+    /// steers towards the target every frame
+    Homing,
+    /// continuous ray hurting the target with every tick of the attack as long as the tower keeps it in sight
+    Beam,
+    /// shell lobbed in an arc onto the spot of the target, hurting every enemy around the impact
+    Mortar,
+    /// strikes the target right away and jumps on to the enemies next to it
+    ChainLightning,
+}
+
+/// How far the lightning of a `ProjectileKind::ChainLightning` tower spreads
+#[derive(Component, Deserialize, Clone, Copy, Debug)]
+pub struct LightningChain {
+    /// enemies struck after the target
+    pub jumps: u32,
+    /// distance the lightning jumps at most, in world units
+    pub jump_range: f32,
+    /// share (0..1) of the damage lost with every jump
+    #[serde(default)]
+    pub falloff: f32,
+}
+
+/// Shell of a mortar on its way to the impact, it doesn't collide with anything on the way
+#[derive(Component)]
+pub struct MortarShell {
+    start: Vec3,
+    end: Vec3,
+    flight: Timer,
+    damage: f32,
+    damage_type: DamageType,
+    fired_by: Entity,
+    area: AreaOfEffect,
+    effects: Vec<StatusEffect>,
+}
+
+/// Ray between a beam tower and its target, kept apart from the tower so it isn't scaled with it
+#[derive(Component)]
+struct BeamRay {
+    tower: Entity,
+}
+
+/// Flash between two enemies struck by chain lightning
+#[derive(Component)]
+pub struct LightningArc(Timer);
+
+/// Buildings with this component aim at where the target is going to be instead of where it is
+#[derive(Component)]
+pub struct LeadPrediction;
+
+/// Bullets with this component steer towards their target every frame
+#[derive(Component)]
+pub struct Homing {
+    pub target: Entity,
+    /// how fast the bullet is able to change its direction
+    pub turn_rate: f32,
+    pub lead: bool,
 }
 
 #[derive(Component)]
 pub struct Bullet {
     speed: f32,
+    direction: Vec3,
     pub(crate) life_timer: Timer,
 }
 
+/// Bullet which already hit an enemy, it passes through everything until it is back in the pool
+#[derive(Component)]
+pub struct SpentBullet;
+
+/// Deactivated bullet entities waiting to be fired again
+#[derive(Resource, Default)]
+pub struct ProjectilePool {
+    free: Vec<Entity>,
+}
+
+/// Sent when a bullet hit something or ran out of time, it gets returned to the pool
+pub struct BulletSpent(pub Entity);
+
+/// Building which fired the bullet, credited with the damage it deals
+#[derive(Component)]
+pub struct FiredBy(pub Entity);
+
+/// Amount of health a bullet removes from the enemy it hits
+#[derive(Component)]
+pub struct Damage(pub f32);
+
+/// Bullets with this component damage all enemies around the point of impact
+#[derive(Component, Deserialize, Clone, Copy, Debug)]
+pub struct AreaOfEffect {
+    pub radius: f32,
+    /// share of the damage lost towards the edge of the radius (0..1)
+    #[serde(default)]
+    pub falloff: f32,
+    /// show an expanding ring on impact
+    #[serde(default)]
+    pub shockwave: bool,
+}
+
+impl AreaOfEffect {
+    /// Factor applied to the damage of an enemy `distance` away from the impact
+    pub fn damage_factor(&self, distance: f32) -> f32 {
+        (1.0 - self.falloff * (distance / self.radius)).clamp(0.0, 1.0)
+    }
+}
+
+/// Sent when a projectile with a visible area of effect hit something
+pub struct AreaImpact {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+#[derive(Component)]
+struct Shockwave {
+    radius: f32,
+    timer: Timer,
+}
+
+/// Position where a projectile fired from `origin` meets a target moving with `velocity`,
+/// falls back to the current target position when the projectile is unable to catch up
+fn predict_intercept(origin: Vec3, speed: f32, target: Vec3, velocity: Vec3) -> Vec3 {
+    let to_target = target - origin;
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2.0 * to_target.dot(velocity);
+    let c = to_target.length_squared();
+
+    let time = if a.abs() < f32::EPSILON {
+        if b.abs() < f32::EPSILON {
+            return target;
+        }
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return target;
+        }
+
+        let root = discriminant.sqrt();
+        let t1 = (-b - root) / (2.0 * a);
+        let t2 = (-b + root) / (2.0 * a);
+        match (t1 > 0.0, t2 > 0.0) {
+            (true, true) => t1.min(t2),
+            (true, false) => t1,
+            (false, true) => t2,
+            (false, false) => return target,
+        }
+    };
+
+    if time <= 0.0 {
+        return target;
+    }
+
+    target + velocity * time
+}
+
+/// Distance between two positions ignoring their height
+fn ground_distance(a: Vec3, b: Vec3) -> f32 {
+    Vec2::new(a.x, a.z).distance(Vec2::new(b.x, b.z))
+}
+
+/// Point projectiles of a building start from
+fn muzzle(transform: &Transform) -> Vec3 {
+    Vec3::new(transform.translation.x, 0.3, transform.translation.z)
+}
+
+/// Transform stretching the ray mesh from `from` to `to`
+fn ray_transform(from: Vec3, to: Vec3) -> Transform {
+    let length = from.distance(to);
+    if length < f32::EPSILON {
+        return Transform::from_translation(from).with_scale(Vec3::ZERO);
+    }
+    Transform::from_translation((from + to) / 2.0)
+        .looking_at(to, Vec3::Y)
+        .with_scale(Vec3::new(RAY_WIDTH, RAY_WIDTH, length))
+}
+
+/// Enemies struck by chain lightning hitting `first`, in order, every jump goes to the nearest enemy not struck yet
+fn chain_targets(first: (Entity, Vec3), chain: &LightningChain, candidates: &[(Entity, Vec3)]) -> Vec<(Entity, Vec3)> {
+    let mut struck = vec![first];
+    for _ in 0..chain.jumps {
+        let (_, last) = struck[struck.len() - 1];
+        let next = candidates
+            .iter()
+            .filter(|(e, _)| struck.iter().all(|(other, _)| other != e))
+            .map(|(e, position)| (*e, *position, last.distance(*position)))
+            .filter(|(_, _, distance)| *distance <= chain.jump_range)
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+
+        match next {
+            Some((e, position, _)) => struck.push((e, position)),
+            None => break,
+        }
+    }
+    struck
+}
+
+fn building_targeting(
+    mut buildings: Query<(Entity, &Transform, &TargetingRange, &TargetingMode, &mut CurrentTarget), With<BuildingTag>>,
+    enemies: Query<(&Transform, &WalkingPath, &Health, Option<&Aura>), (With<EnemyTag>, Without<InFog>)>,
+    records: Query<&CombatRecord>,
+    index: Res<SpatialIndex>,
+    weather: Res<Weather>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+) {
+    let range_factor = weather.range_factor() * research.range_factor(&techs);
+    for (building, transform, range, mode, mut target) in &mut buildings {
+        let veterancy = records.get(building).map_or(1.0, |record| record.veterancy_factor());
+        let reach = range.0 * range_factor * veterancy;
+        let in_range = index
+            .within(transform.translation, reach)
+            .filter_map(|(e, _)| {
+                let (enemy_transform, path, health, aura) = enemies.get(e).ok()?;
+                Some((ground_distance(transform.translation, enemy_transform.translation), path.remaining(), health.current, aura.is_some(), e))
+            })
+            // the index holds the positions from the start of the frame
+            .filter(|(distance, _, _, _, _)| *distance <= reach);
+
+        let chosen = match mode {
+            TargetingMode::Nearest => in_range.min_by(|a, b| a.0.total_cmp(&b.0)),
+            TargetingMode::First => in_range.min_by_key(|candidate| candidate.1),
+            TargetingMode::Last => in_range.max_by_key(|candidate| candidate.1),
+            TargetingMode::Strongest => in_range.max_by(|a, b| a.2.total_cmp(&b.2)),
+            TargetingMode::Support => in_range.min_by(|a, b| b.3.cmp(&a.3).then(a.0.total_cmp(&b.0))),
+        };
+
+        target.0 = chosen.map(|(_, _, _, _, e)| e);
+    }
+}
+
+fn setup_projectile_pool(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut pool: ResMut<ProjectilePool>,
+) {
+    pool.free = (0..PROJECTILE_POOL_SIZE)
+        .map(|_| spawn_pooled_bullet(&mut commands, &assets))
+        .collect();
+}
+
+/// Spawns an inactive bullet, it only becomes visible once fired
+fn spawn_pooled_bullet(commands: &mut Commands, assets: &GameAssets) -> Entity {
+    commands
+        .spawn((
+            Name::from("Bullet"),
+            PbrBundle {
+                mesh: assets.bullet_mesh.clone(),
+                material: assets.bullet_material.clone(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .id()
+}
+
+fn release_bullets(
+    mut commands: Commands,
+    mut spent_reader: EventReader<BulletSpent>,
+    mut pool: ResMut<ProjectilePool>,
+    bullets: Query<(), With<Bullet>>,
+) {
+    // the same bullet may be reported more than once, e.g. hitting something in its last frame
+    let mut released = HashSet::new();
+
+    for event in spent_reader.iter() {
+        if !bullets.contains(event.0) || !released.insert(event.0) {
+            continue;
+        }
+
+        #[cfg(feature = "rapier")]
+        commands.entity(event.0).remove::<(Collider, Sensor, RigidBody, CollisionGroups, ActiveEvents)>();
+        commands.entity(event.0)
+            .remove::<(Bullet, SpentBullet, Damage, FiredBy, Homing, OnHitEffects, AreaOfEffect)>()
+            .insert(Visibility::Hidden);
+        pool.free.push(event.0);
+    }
+}
+
 fn building_shooting(
     mut commands: Commands,
-    mut q: Query<(&Transform, &mut HasAttack), With<BuildingTag>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut q: Query<(Entity, &Transform, &mut HasAttack, &CurrentTarget, &ProjectileKind, &DamageType, Option<&LeadPrediction>, Option<&OnHitEffects>, Option<&AreaOfEffect>, Option<&LightningChain>, Option<&AbilityState>), With<BuildingTag>>,
+    enemies: Query<(&Transform, &Velocity), With<EnemyTag>>,
+    jump_targets: Query<&Transform, (With<EnemyTag>, Without<InFog>)>,
+    index: Res<SpatialIndex>,
+    crits: Query<&CriticalHit>,
+    mut pool: ResMut<ProjectilePool>,
+    mut vfx_writer: EventWriter<SpawnVfx>,
+    mut hit_writer: EventWriter<EnemyHit>,
+    mut rng: ResMut<CombatRng>,
+    assets: Res<GameAssets>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
     time: Res<Time>,
 ) {
-    q.iter_mut().for_each(|(transform, mut attack)| {
-        attack.timer.tick(time.delta());
-
-        // if it finished, despawn the bomb
-        if attack.timer.finished() {
-            commands.spawn((
-                Name::from("Bullet"),
-                Bullet {
-                    speed: 0.01,
-                    life_timer: Timer::new(Duration::from_millis(11300), TimerMode::Once),
-                },
-                PbrBundle {
-                    mesh: meshes.add(Mesh::from(shape::UVSphere {
-                        radius: 0.05,
+    let researched_fire_rate = research.fire_rate_factor(&techs);
+    q.iter_mut().for_each(|(building, transform, mut attack, target, kind, damage_type, lead, on_hit, area, chain, ability)| {
+        let fire_rate_factor = ability.map_or(1.0, |ability| ability.fire_rate_factor()) * researched_fire_rate;
+        attack.timer.tick(time.delta().mul_f32(fire_rate_factor));
+
+        let Some((target_entity, (target_transform, target_velocity))) = target.0
+            .and_then(|e| enemies.get(e).ok().map(|enemy| (e, enemy))) else {
+            return;
+        };
+
+        if !attack.timer.finished() {
+            return;
+        }
+
+        let origin = muzzle(transform);
+        let effects = on_hit.map_or(vec![], |on_hit| on_hit.0.clone());
+        let aim = if lead.is_some() {
+            predict_intercept(origin, PROJECTILE_SPEED, target_transform.translation, target_velocity.0)
+        } else {
+            target_transform.translation
+        };
+
+        match kind {
+            ProjectileKind::Straight | ProjectileKind::Homing => {
+                let entity = pool.free
+                    .pop()
+                    .unwrap_or_else(|| spawn_pooled_bullet(&mut commands, &assets));
+                vfx_writer.send(SpawnVfx { kind: VfxKind::MuzzleFlash, position: origin });
+
+                let mut bullet = commands.entity(entity);
+                bullet.insert((
+                    Bullet {
+                        speed: PROJECTILE_SPEED,
+                        direction: (aim - origin).normalize_or_zero(),
+                        life_timer: Timer::new(Duration::from_millis(11300), TimerMode::Once),
+                    },
+                    Damage(attack.damage),
+                    *damage_type,
+                    FiredBy(building),
+                    Transform::from_translation(origin),
+                    Visibility::Visible,
+                ));
+
+                // moved by `move_bullets`, rapier only reports the enemies they touch
+                #[cfg(feature = "rapier")]
+                bullet.insert((
+                    Collider::ball(BULLET_RADIUS),
+                    Sensor,
+                    RigidBody::KinematicPositionBased,
+                    projectile_groups(),
+                    ActiveEvents::COLLISION_EVENTS,
+                ));
+
+                if !effects.is_empty() {
+                    bullet.insert(OnHitEffects(effects));
+                }
+
+                if let Some(area) = area {
+                    bullet.insert(*area);
+                }
+
+                if *kind == ProjectileKind::Homing {
+                    bullet.insert(Homing {
+                        target: target_entity,
+                        turn_rate: 6.0,
+                        lead: lead.is_some(),
+                    });
+                }
+            }
+            ProjectileKind::Beam => {
+                // the ray itself is kept on the target by `aim_beam_rays`
+                let critical = roll_critical(crits.get(building).ok(), &mut rng);
+                hit_writer.send(EnemyHit {
+                    target: target_entity,
+                    amount: attack.damage * critical.unwrap_or(1.0),
+                    damage_type: *damage_type,
+                    fired_by: Some(building),
+                    critical: critical.is_some(),
+                    effects,
+                });
+            }
+            ProjectileKind::Mortar => {
+                vfx_writer.send(SpawnVfx { kind: VfxKind::MuzzleFlash, position: origin });
+                let flight = (ground_distance(origin, aim) / PROJECTILE_SPEED).max(0.3);
+
+                commands.spawn((
+                    Name::from("Mortar shell"),
+                    MortarShell {
+                        start: origin,
+                        end: aim,
+                        flight: Timer::from_seconds(flight, TimerMode::Once),
+                        damage: attack.damage,
+                        damage_type: *damage_type,
+                        fired_by: building,
+                        area: area.copied().unwrap_or(MORTAR_AREA),
+                        effects,
+                    },
+                    PbrBundle {
+                        mesh: assets.bullet_mesh.clone(),
+                        material: assets.bullet_material.clone(),
+                        transform: Transform::from_translation(origin),
                         ..default()
-                    })),
-                    material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
-                    transform: Transform::from_xyz(transform.translation.x, 0.3, transform.translation.z),
-                    ..default()
-                },
-                Collider::ball(0.8),
-            ));
+                    },
+                ));
+            }
+            ProjectileKind::ChainLightning => {
+                let chain = chain.copied().unwrap_or(DEFAULT_CHAIN);
+                // one roll for the whole chain
+                let critical = roll_critical(crits.get(building).ok(), &mut rng);
+                // no chain reaches further than all of its jumps in a straight line
+                let reach = chain.jump_range * chain.jumps as f32;
+                let candidates: Vec<(Entity, Vec3)> = index
+                    .within(target_transform.translation, reach)
+                    .filter_map(|(e, _)| jump_targets.get(e).ok().map(|transform| (e, transform.translation)))
+                    .collect();
+
+                let mut from = origin;
+                let mut damage = attack.damage * critical.unwrap_or(1.0);
+                for (enemy, position) in chain_targets((target_entity, target_transform.translation), &chain, &candidates) {
+                    commands.spawn((
+                        Name::from("Lightning"),
+                        LightningArc(Timer::new(LIGHTNING_ARC_LIFETIME, TimerMode::Once)),
+                        PbrBundle {
+                            mesh: assets.ray_mesh.clone(),
+                            material: assets.lightning_material.clone(),
+                            transform: ray_transform(from, position),
+                            ..default()
+                        },
+                    ));
+                    hit_writer.send(EnemyHit {
+                        target: enemy,
+                        amount: damage,
+                        damage_type: *damage_type,
+                        fired_by: Some(building),
+                        critical: critical.is_some(),
+                        effects: effects.clone(),
+                    });
+
+                    from = position;
+                    damage *= 1.0 - chain.falloff.clamp(0.0, 1.0);
+                }
+            }
         }
     });
 }
 
+fn steer_homing_bullets(
+    mut bullets: Query<(&mut Bullet, &Transform, &Homing)>,
+    enemies: Query<(&Transform, &Velocity), With<EnemyTag>>,
+    time: Res<Time>,
+) {
+    for (mut bullet, transform, homing) in &mut bullets {
+        // once the target is gone the bullet just keeps flying straight
+        let Ok((target_transform, target_velocity)) = enemies.get(homing.target) else {
+            continue;
+        };
+
+        let aim = if homing.lead {
+            predict_intercept(transform.translation, bullet.speed, target_transform.translation, target_velocity.0)
+        } else {
+            target_transform.translation
+        };
+
+        let desired = (aim - transform.translation).normalize_or_zero();
+        let steering = (homing.turn_rate * time.delta_seconds()).min(1.0);
+        bullet.direction = bullet.direction.lerp(desired, steering).normalize_or_zero();
+    }
+}
+
 fn move_bullets(
-    mut commands: Commands,
+    mut spent_writer: EventWriter<BulletSpent>,
     mut q: Query<(&mut Bullet, &mut Transform, Entity)>,
     time: Res<Time>,
 ) {
     q.iter_mut().for_each(|(mut bullet, mut transform, e)| {
-        transform.translation.x += bullet.speed;
+        transform.translation += bullet.direction * bullet.speed * time.delta_seconds();
 
         bullet.life_timer.tick(time.delta());
 
         if bullet.life_timer.finished() {
-            commands.entity(e).despawn();
+            spent_writer.send(BulletSpent(e));
         }
     });
-}
\ No newline at end of file
+}
+
+/// Moves mortar shells along their arc, blasting every enemy around the spot they land on
+fn fly_mortar_shells(
+    mut commands: Commands,
+    mut shells: Query<(Entity, &mut MortarShell, &mut Transform)>,
+    enemies: Query<&Transform, (With<EnemyTag>, Without<MortarShell>)>,
+    index: Res<SpatialIndex>,
+    crits: Query<&CriticalHit>,
+    mut rng: ResMut<CombatRng>,
+    mut impact_writer: EventWriter<AreaImpact>,
+    mut vfx_writer: EventWriter<SpawnVfx>,
+    mut hit_writer: EventWriter<EnemyHit>,
+    time: Res<Time>,
+) {
+    for (e, mut shell, mut transform) in &mut shells {
+        shell.flight.tick(time.delta());
+
+        // parabola peaking halfway between the tower and the impact
+        let t = shell.flight.percent();
+        let height = ground_distance(shell.start, shell.end) * MORTAR_ARC;
+        transform.translation = shell.start.lerp(shell.end, t) + Vec3::Y * height * 4.0 * t * (1.0 - t);
+
+        if !shell.flight.finished() {
+            continue;
+        }
+        commands.entity(e).despawn_recursive();
+
+        vfx_writer.send(SpawnVfx { kind: VfxKind::Impact, position: shell.end });
+        if shell.area.shockwave {
+            impact_writer.send(AreaImpact { position: shell.end, radius: shell.area.radius });
+        }
+
+        let critical = roll_critical(crits.get(shell.fired_by).ok(), &mut rng);
+        let blasted = index
+            .within(shell.end, shell.area.radius)
+            .filter_map(|(e, _)| enemies.get(e).ok().map(|transform| (e, transform.translation)));
+        for (enemy, position) in blasted {
+            let distance = ground_distance(position, shell.end);
+            if distance > shell.area.radius {
+                continue;
+            }
+            hit_writer.send(EnemyHit {
+                target: enemy,
+                amount: shell.damage * shell.area.damage_factor(distance) * critical.unwrap_or(1.0),
+                damage_type: shell.damage_type,
+                fired_by: Some(shell.fired_by),
+                critical: critical.is_some(),
+                effects: shell.effects.clone(),
+            });
+        }
+    }
+}
+
+fn attach_beam_rays(
+    mut commands: Commands,
+    towers: Query<(Entity, &ProjectileKind), Added<ProjectileKind>>,
+    assets: Res<GameAssets>,
+) {
+    for (tower, kind) in &towers {
+        if *kind != ProjectileKind::Beam {
+            continue;
+        }
+        commands.spawn((
+            Name::from("Beam"),
+            BeamRay { tower },
+            PbrBundle {
+                mesh: assets.ray_mesh.clone(),
+                material: assets.beam_material.clone(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Keeps the rays of beam towers on their targets, they vanish together with the target or the tower
+fn aim_beam_rays(
+    mut commands: Commands,
+    mut rays: Query<(Entity, &BeamRay, &mut Transform, &mut Visibility)>,
+    towers: Query<(&Transform, &CurrentTarget), (With<BuildingTag>, Without<BeamRay>)>,
+    enemies: Query<&Transform, (With<EnemyTag>, Without<BeamRay>)>,
+) {
+    for (e, ray, mut transform, mut visibility) in &mut rays {
+        let Ok((tower_transform, target)) = towers.get(ray.tower) else {
+            commands.entity(e).despawn_recursive();
+            continue;
+        };
+
+        match target.0.and_then(|target| enemies.get(target).ok()) {
+            Some(enemy_transform) => {
+                *transform = ray_transform(muzzle(tower_transform), enemy_transform.translation);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn fade_lightning_arcs(
+    mut commands: Commands,
+    mut arcs: Query<(Entity, &mut LightningArc)>,
+    time: Res<Time>,
+) {
+    for (e, mut arc) in &mut arcs {
+        if arc.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+fn spawn_shockwaves(
+    mut commands: Commands,
+    mut impact_reader: EventReader<AreaImpact>,
+    assets: Res<GameAssets>,
+) {
+    for impact in impact_reader.iter() {
+        commands.spawn((
+            Name::from("Shockwave"),
+            Shockwave {
+                radius: impact.radius,
+                timer: Timer::new(Duration::from_millis(300), TimerMode::Once),
+            },
+            PbrBundle {
+                mesh: assets.shockwave_mesh.clone(),
+                material: assets.shockwave_material.clone(),
+                transform: Transform::from_xyz(impact.position.x, 0.05, impact.position.z)
+                    .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2))
+                    .with_scale(Vec3::ZERO),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn animate_shockwaves(
+    mut commands: Commands,
+    mut q: Query<(&mut Shockwave, &mut Transform, Entity)>,
+    time: Res<Time>,
+) {
+    for (mut shockwave, mut transform, e) in &mut q {
+        shockwave.timer.tick(time.delta());
+        transform.scale = Vec3::splat(shockwave.radius * shockwave.timer.percent());
+
+        if shockwave.timer.finished() {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+/// Adds a badge above a tower for every veterancy rank it reached
+fn award_veterancy_badges(
+    mut commands: Commands,
+    towers: Query<(Entity, &CombatRecord, Option<&Children>), Changed<CombatRecord>>,
+    badges: Query<(), With<VeterancyBadge>>,
+    assets: Res<GameAssets>,
+) {
+    for (tower, record, children) in &towers {
+        let shown = children.map_or(0, |children| children.iter().filter(|child| badges.contains(**child)).count());
+        let rank = record.rank();
+        for index in shown..rank {
+            // centered above the tower, growing to both sides
+            let offset = (index as f32 - (VETERANCY_KILLS.len() - 1) as f32 / 2.0) * BADGE_SPACING;
+            let badge = commands
+                .spawn((
+                    VeterancyBadge,
+                    PbrBundle {
+                        mesh: assets.badge_mesh.clone(),
+                        material: assets.badge_material.clone(),
+                        transform: Transform::from_xyz(offset, BADGE_HEIGHT, 0.0),
+                        ..default()
+                    },
+                ))
+                .id();
+            commands.entity(tower).add_child(badge);
+        }
+    }
+}
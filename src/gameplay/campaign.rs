@@ -0,0 +1,262 @@
+use std::fs;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{Map, StartNewGame};
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::enemy::{EnemyKind, SpawnEnemy};
+use crate::gameplay::save::LoadGame;
+use crate::gameplay::stats::GameStats;
+use crate::gameplay::towers::TowerDefinition;
+use crate::gameplay::waves::{WavePhase, Waves};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+
+/// File (relative to the working directory) containing the levels of the campaign
+const CAMPAIGN_PATH: &str = "assets/data/campaign.ron";
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<LevelMessage>()
+            .add_startup_system(load_campaign.in_base_set(StartupSet::PreStartup))
+            .add_system(choose_active_level)
+            .add_systems(
+                (
+                    // a new game brings new resources, the rules of the level go on top
+                    apply_level_rules.run_if(resource_exists::<ActiveLevel>()),
+                    run_level_script.after(apply_level_rules),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Ordered list of the levels, each one is opened by winning the one before
+#[derive(Resource, Debug)]
+pub struct Campaign {
+    pub levels: Vec<CampaignLevel>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CampaignLevel {
+    pub title: String,
+    /// path of the map file the level is played on
+    pub map: String,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// shown when the level starts
+    #[serde(default)]
+    pub intro: String,
+    #[serde(default)]
+    pub rules: Vec<LevelRule>,
+    #[serde(default)]
+    pub events: Vec<ScriptedEvent>,
+}
+
+/// Special rule holding for a whole level
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelRule {
+    /// walls can't be built
+    NoWalls,
+    /// towers can't be sold
+    NoSelling,
+    /// replaces the gold the difficulty starts with
+    StartingGold(u32),
+}
+
+/// Action of the level script, run once when its trigger fires
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScriptedEvent {
+    pub trigger: Trigger,
+    pub action: ScriptAction,
+}
+
+/// Condition checked during play, once met it stays met
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Trigger {
+    /// as soon as the level starts
+    Start,
+    /// the wave with the given number, counting from 1, started spawning
+    WaveStarted(usize),
+    /// the wave with the given number, counting from 1, has been beaten
+    WaveCleared(usize),
+    /// enemies killed in the level
+    Kills(u32),
+    /// the lives left dropped below the given number
+    LivesBelow(u32),
+    /// seconds of game time since the start of the level
+    Time(f32),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum ScriptAction {
+    /// text shown to the player
+    Message(String),
+    /// extra enemies spread over the spawn points, on top of the waves
+    Reinforcements(EnemyKind, u32),
+    Gold(u32),
+}
+
+/// Level of the campaign the running game plays, missing in games outside the campaign
+#[derive(Resource, Debug)]
+pub struct ActiveLevel {
+    pub index: usize,
+    pub level: CampaignLevel,
+    /// game time since the start of the level
+    elapsed: Duration,
+    /// which of the scripted events already ran, in the order of `level.events`
+    fired: Vec<bool>,
+    /// starting gold and intro are given once the game is running, replaying inserts a fresh level
+    rules_applied: bool,
+}
+
+impl ActiveLevel {
+    fn new(index: usize, level: CampaignLevel) -> Self {
+        ActiveLevel {
+            index,
+            fired: vec![false; level.events.len()],
+            level,
+            elapsed: Duration::ZERO,
+            rules_applied: false,
+        }
+    }
+
+    pub fn has_rule(&self, rule: LevelRule) -> bool {
+        self.level.rules.contains(&rule)
+    }
+}
+
+/// Whether the running game, campaign or not, has the rule
+pub fn level_has_rule(level: Option<&ActiveLevel>, rule: LevelRule) -> bool {
+    level.map_or(false, |level| level.has_rule(rule))
+}
+
+/// Whether the rules of the running game let the player build the tower
+pub fn allows_tower(level: Option<&ActiveLevel>, definition: &TowerDefinition) -> bool {
+    !(definition.is_wall() && level_has_rule(level, LevelRule::NoWalls))
+}
+
+/// Text of the intro or of a scripted message, shown in the level briefing
+pub struct LevelMessage(pub String);
+
+impl Campaign {
+    /// New game starting the level at `index`
+    pub fn start_level(&self, index: usize) -> Option<StartNewGame> {
+        self.levels.get(index).map(|level| StartNewGame {
+            map: level.map.clone(),
+            difficulty: level.difficulty,
+            level: Some(index),
+        })
+    }
+}
+
+/// A missing or broken campaign leaves the free games playable
+fn load_campaign(mut commands: Commands) {
+    let levels = match fs::read_to_string(CAMPAIGN_PATH).map_err(|e| e.to_string())
+        .and_then(|content| ron::from_str(&content).map_err(|e| e.to_string()))
+    {
+        Ok(levels) => levels,
+        Err(e) => {
+            warn!("Could not load the campaign {}, playing without one: {}", CAMPAIGN_PATH, e);
+            Vec::new()
+        }
+    };
+
+    commands.insert_resource(Campaign { levels });
+}
+
+/// Every new game either plays a level of the campaign or none
+fn choose_active_level(
+    mut commands: Commands,
+    mut new_game_reader: EventReader<StartNewGame>,
+    mut load_reader: EventReader<LoadGame>,
+    campaign: Res<Campaign>,
+) {
+    // saves don't know about the campaign, a loaded game is a free one
+    if load_reader.iter().count() > 0 {
+        commands.remove_resource::<ActiveLevel>();
+    }
+    let Some(new_game) = new_game_reader.iter().last() else {
+        return;
+    };
+
+    match new_game.level.and_then(|index| campaign.levels.get(index).map(|level| (index, level))) {
+        Some((index, level)) => commands.insert_resource(ActiveLevel::new(index, level.clone())),
+        None => commands.remove_resource::<ActiveLevel>(),
+    }
+}
+
+fn apply_level_rules(
+    mut level: ResMut<ActiveLevel>,
+    localization: Res<Localization>,
+    mut resources: ResMut<PlayerResources>,
+    mut message_writer: EventWriter<LevelMessage>,
+) {
+    if level.rules_applied {
+        return;
+    }
+    level.rules_applied = true;
+
+    for rule in &level.level.rules {
+        if let LevelRule::StartingGold(gold) = rule {
+            resources.gold = *gold;
+        }
+    }
+
+    if !level.level.intro.is_empty() {
+        message_writer.send(LevelMessage(format!("{}\n{}", localization.t(&level.level.title), localization.t(&level.level.intro))));
+    }
+}
+
+/// Checks the triggers of the scripted events not run yet and runs the ones met
+fn run_level_script(
+    time: Res<Time>,
+    level: Option<ResMut<ActiveLevel>>,
+    waves: Option<Res<Waves>>,
+    stats: Res<GameStats>,
+    map: Res<Map>,
+    localization: Res<Localization>,
+    mut resources: ResMut<PlayerResources>,
+    mut spawn_writer: EventWriter<SpawnEnemy>,
+    mut message_writer: EventWriter<LevelMessage>,
+) {
+    let (Some(mut level), Some(waves)) = (level, waves) else {
+        return;
+    };
+    level.elapsed += time.delta();
+
+    let started_waves = waves.current + matches!(waves.phase, WavePhase::Spawning | WavePhase::Fighting) as usize;
+    let is_met = |trigger: Trigger, elapsed: Duration| match trigger {
+        Trigger::Start => true,
+        Trigger::WaveStarted(wave) => started_waves >= wave,
+        // the wave counter moves on once a wave is beaten
+        Trigger::WaveCleared(wave) => waves.current >= wave,
+        Trigger::Kills(kills) => stats.kills >= kills,
+        Trigger::LivesBelow(lives) => resources.lives < lives,
+        Trigger::Time(seconds) => elapsed.as_secs_f32() >= seconds,
+    };
+
+    let elapsed = level.elapsed;
+    let due: Vec<usize> = (0..level.level.events.len())
+        .filter(|index| !level.fired[*index] && is_met(level.level.events[*index].trigger, elapsed))
+        .collect();
+
+    for index in due {
+        level.fired[index] = true;
+        match &level.level.events[index].action {
+            ScriptAction::Message(text) => message_writer.send(LevelMessage(localization.t(text).to_string())),
+            ScriptAction::Reinforcements(kind, count) => {
+                for spawn in map.spawn_points.iter().cycle().take(*count as usize) {
+                    spawn_writer.send(SpawnEnemy(*kind, *spawn));
+                }
+            }
+            ScriptAction::Gold(gold) => resources.earn(*gold),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::effects::StatusEffect;
+
+/// Share of the physical armor explosions blast through
+const EXPLOSIVE_ARMOR_PIERCING: f32 = 0.5;
+
+/// Kind of damage dealt by a tower and its bullets, enemies resist every kind differently
+#[derive(Component, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DamageType {
+    /// reduced by the physical armor of the enemy
+    #[default]
+    Physical,
+    /// ignores armor, but some enemies resist a share of it
+    Magic,
+    /// pierces half of the armor, enemies hard to hit by blasts resist a share of it
+    Explosive,
+}
+
+impl DamageType {
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            DamageType::Physical => "damage_type.physical",
+            DamageType::Magic => "damage_type.magic",
+            DamageType::Explosive => "damage_type.explosive",
+        }
+    }
+}
+
+/// Resistances of an enemy against the different damage types
+#[derive(Component, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Armor {
+    /// damage removed from every physical hit
+    pub physical: f32,
+    /// share (0..1) of magic damage ignored
+    pub magic: f32,
+    /// share (0..1) of explosive damage ignored
+    pub explosive: f32,
+}
+
+/// Health a hit of `amount` damage of the given type removes from an enemy with `armor`
+pub fn calculate_damage(amount: f32, damage_type: DamageType, armor: &Armor) -> f32 {
+    let damage = match damage_type {
+        DamageType::Physical => amount - armor.physical,
+        DamageType::Magic => amount * (1.0 - armor.magic.clamp(0.0, 1.0)),
+        DamageType::Explosive => {
+            amount * (1.0 - armor.explosive.clamp(0.0, 1.0)) - armor.physical * (1.0 - EXPLOSIVE_ARMOR_PIERCING)
+        }
+    };
+    damage.max(0.0)
+}
+
+/// Chance of a tower to deal multiplied damage with a hit
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CriticalHit {
+    /// share (0..1) of the hits that are critical
+    pub chance: f32,
+    pub multiplier: f32,
+}
+
+/// Multiplier of an attack by a tower with the given crit stats, `None` if the attack is no crit
+pub fn roll_critical(crit: Option<&CriticalHit>, rng: &mut CombatRng) -> Option<f32> {
+    crit.filter(|crit| rng.chance(crit.chance)).map(|crit| crit.multiplier)
+}
+
+/// Request to hit an enemy, shields and armor are taken into account when applying it
+pub struct EnemyHit {
+    pub target: Entity,
+    /// damage before armor, with falloff and crits already applied
+    pub amount: f32,
+    pub damage_type: DamageType,
+    /// building credited with the damage
+    pub fired_by: Option<Entity>,
+    pub critical: bool,
+    /// applied to the enemy unless the hit is blocked
+    pub effects: Vec<StatusEffect>,
+}
+
+/// Sent for every critical hit, with the damage it dealt
+pub struct CriticalHitDealt {
+    pub position: Vec3,
+    pub amount: f32,
+}
+
+/// Seed and number of rolls so far, enough to put the `CombatRng` back into the same state
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CombatRngState {
+    pub seed: u64,
+    pub rolls: u64,
+}
+
+/// Source of all random rolls of the game, like hits, spawn points and weather, seeded so a game plays out the same again from a save
+#[derive(Resource)]
+pub struct CombatRng {
+    state: CombatRngState,
+    rng: StdRng,
+}
+
+impl Default for CombatRng {
+    fn default() -> Self {
+        CombatRng::new(rand::random())
+    }
+}
+
+impl CombatRng {
+    pub fn new(seed: u64) -> Self {
+        CombatRng {
+            state: CombatRngState { seed, rolls: 0 },
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Continues where the rng of a saved game left off by repeating all of its rolls
+    pub fn restore(state: CombatRngState) -> Self {
+        let mut rng = CombatRng::new(state.seed);
+        for _ in 0..state.rolls {
+            rng.roll();
+        }
+        rng
+    }
+
+    pub fn state(&self) -> CombatRngState {
+        self.state
+    }
+
+    /// Random number in 0..1
+    pub fn roll(&mut self) -> f32 {
+        self.state.rolls += 1;
+        self.rng.gen()
+    }
+
+    /// Whether something with the given chance (0..1) happens
+    pub fn chance(&mut self, chance: f32) -> bool {
+        self.roll() < chance
+    }
+
+    /// Random index below `len`, which must be above zero
+    pub fn index(&mut self, len: usize) -> usize {
+        ((self.roll() * len as f32) as usize).min(len - 1)
+    }
+
+    /// Index picked by its share of all weights, `None` while no weight is above zero
+    pub fn choose_weighted(&mut self, weights: &[u32]) -> Option<usize> {
+        let total: u32 = weights.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let mut left = self.roll() * total as f32;
+        weights.iter()
+            .position(|weight| {
+                left -= *weight as f32;
+                left < 0.0
+            })
+            // rounding can leave a bit over after the last weight
+            .or_else(|| weights.iter().rposition(|weight| *weight > 0))
+    }
+}
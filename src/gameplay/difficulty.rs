@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Enemy health grows by this share of the base health with every round of an endless game
+const ENDLESS_HEALTH_GROWTH: f32 = 0.5;
+/// Enemy counts grow by this share with every round of an endless game
+const ENDLESS_COUNT_GROWTH: f32 = 0.25;
+
+/// Chosen when starting a new game, scales the enemies and the resources of the player
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// normal enemies, but the waves of the map start over with stronger enemies after the last one
+    Endless,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Endless,
+    ];
+
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+            Difficulty::Endless => "difficulty.endless",
+        }
+    }
+
+    pub fn is_endless(&self) -> bool {
+        *self == Difficulty::Endless
+    }
+
+    pub fn starting_gold(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 200,
+            Difficulty::Normal | Difficulty::Endless => 150,
+            Difficulty::Hard => 100,
+        }
+    }
+
+    pub fn lives(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 30,
+            Difficulty::Normal | Difficulty::Endless => 20,
+            Difficulty::Hard => 10,
+        }
+    }
+
+    /// Factor applied to the health of enemies spawned in the given round, rounds only count up in endless games
+    pub fn health_factor(&self, round: usize) -> f32 {
+        let base = match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal | Difficulty::Endless => 1.0,
+            Difficulty::Hard => 1.4,
+        };
+        base * (1.0 + ENDLESS_HEALTH_GROWTH * round as f32)
+    }
+
+    /// Factor applied to the movement speed of all enemies
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.9,
+            Difficulty::Normal | Difficulty::Endless => 1.0,
+            Difficulty::Hard => 1.15,
+        }
+    }
+
+    /// Factor applied to the number of enemies of every wave group in the given round
+    pub fn count_factor(&self, round: usize) -> f32 {
+        let base = match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal | Difficulty::Endless => 1.0,
+            Difficulty::Hard => 1.25,
+        };
+        base * (1.0 + ENDLESS_COUNT_GROWTH * round as f32)
+    }
+}
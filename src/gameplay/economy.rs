@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use crate::gameplay::balance::Balance;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::enemy::{EnemyArrivedAtEnd, EnemyDied, EnemyKind};
+use crate::gameplay::player_abilities::RepairBase;
+use crate::state::global::GameState;
+
+pub struct EconomyPlugin;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Difficulty>()
+            .init_resource::<PlayerResources>()
+            .add_systems(
+                (
+                    reward_kills,
+                    lose_lives,
+                    repair_base,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct PlayerResources {
+    pub gold: u32,
+    /// enemies that may still reach the exit before the game is lost
+    pub lives: u32,
+    /// all gold earned during the game
+    pub total_earned: u32,
+    /// all gold spent during the game
+    pub total_spent: u32,
+}
+
+impl Default for PlayerResources {
+    fn default() -> Self {
+        PlayerResources::new(Difficulty::default())
+    }
+}
+
+impl PlayerResources {
+    pub fn new(difficulty: Difficulty) -> Self {
+        PlayerResources {
+            gold: difficulty.starting_gold(),
+            lives: difficulty.lives(),
+            total_earned: 0,
+            total_spent: 0,
+        }
+    }
+
+    pub fn can_afford(&self, cost: u32) -> bool {
+        self.gold >= cost
+    }
+
+    /// Removes `cost` from the balance, returns false (and keeps the balance) if the player can't afford it
+    pub fn try_spend(&mut self, cost: u32) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+
+        self.gold -= cost;
+        self.total_spent += cost;
+        true
+    }
+
+    pub fn earn(&mut self, amount: u32) {
+        self.gold += amount;
+        self.total_earned += amount;
+    }
+
+    /// Gives back gold of an undone purchase, it no longer counts as spent
+    pub fn refund(&mut self, amount: u32) {
+        self.gold += amount;
+        self.total_spent = self.total_spent.saturating_sub(amount);
+    }
+}
+
+fn reward_kills(
+    mut died_reader: EventReader<EnemyDied>,
+    mut resources: ResMut<PlayerResources>,
+    balance: Res<Balance>,
+    // the killed enemy stays around for its death animation
+    kinds: Query<&EnemyKind>,
+) {
+    for event in died_reader.iter() {
+        let reward = match kinds.get(event.0) {
+            Ok(EnemyKind::Boss) => balance.economy.boss_kill_reward,
+            _ => balance.economy.kill_reward,
+        };
+        resources.earn(reward);
+    }
+}
+
+fn lose_lives(
+    mut arrived_reader: EventReader<EnemyArrivedAtEnd>,
+    mut resources: ResMut<PlayerResources>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let arrived = arrived_reader.iter().count() as u32;
+    if arrived == 0 {
+        return;
+    }
+
+    resources.lives = resources.lives.saturating_sub(arrived);
+    if resources.lives == 0 {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Gives back lives, never more than the player started with
+fn repair_base(
+    mut repair_reader: EventReader<RepairBase>,
+    mut resources: ResMut<PlayerResources>,
+    difficulty: Res<Difficulty>,
+    balance: Res<Balance>,
+) {
+    for _ in repair_reader.iter() {
+        resources.lives = (resources.lives + balance.economy.repair_lives).min(difficulty.lives());
+    }
+}
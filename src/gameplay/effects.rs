@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::HexLocation;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::boss::Boss;
+use crate::gameplay::enemy::{EnemyTag, Health};
+use crate::gameplay::movement::{MovementSpeed, SpeedChange, SpeedSource};
+use crate::gameplay::player_abilities::FreezeArea;
+use crate::state::global::GameState;
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<ApplyStatusEffect>()
+            .add_systems(
+                (
+                    freeze_areas.before(apply_status_effects),
+                    apply_status_effects,
+                    tick_status_effects.after(apply_status_effects),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EffectKind {
+    /// reduces the movement speed by `magnitude` (0..1)
+    Slow,
+    /// deals `magnitude` damage every tick, hitting again refreshes the duration
+    Burn,
+    /// deals `magnitude` damage every tick, every hit adds another stack
+    Poison,
+    /// stops the movement completely
+    Stun,
+}
+
+impl EffectKind {
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            EffectKind::Slow => "effect.slow",
+            EffectKind::Burn => "effect.burn",
+            EffectKind::Poison => "effect.poison",
+            EffectKind::Stun => "effect.stun",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct StatusEffect {
+    pub kind: EffectKind,
+    /// seconds the effect lasts
+    pub duration: f32,
+    /// seconds between two ticks of damage over time effects
+    #[serde(default = "default_tick_rate")]
+    pub tick_rate: f32,
+    pub magnitude: f32,
+}
+
+fn default_tick_rate() -> f32 {
+    1.0
+}
+
+/// Effect currently affecting an enemy
+#[derive(Debug)]
+pub struct ActiveEffect {
+    pub effect: StatusEffect,
+    remaining: Timer,
+    tick: Timer,
+}
+
+impl ActiveEffect {
+    fn new(effect: StatusEffect) -> Self {
+        ActiveEffect {
+            remaining: Timer::new(Duration::from_secs_f32(effect.duration), TimerMode::Once),
+            tick: Timer::new(Duration::from_secs_f32(effect.tick_rate), TimerMode::Repeating),
+            effect,
+        }
+    }
+}
+
+/// All effects currently active on an enemy
+#[derive(Component, Default, Debug)]
+pub struct StatusEffects {
+    pub active: Vec<ActiveEffect>,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, effect: StatusEffect) {
+        if effect.kind != EffectKind::Poison {
+            self.active.retain(|active| active.effect.kind != effect.kind);
+        }
+        self.active.push(ActiveEffect::new(effect));
+    }
+
+    pub fn has(&self, kind: EffectKind) -> bool {
+        self.active.iter().any(|active| active.effect.kind == kind)
+    }
+}
+
+/// Effects applied to the enemy hit by a projectile, also carried by the buildings firing them
+#[derive(Component, Clone, Default, Debug)]
+pub struct OnHitEffects(pub Vec<StatusEffect>);
+
+/// Request to apply an effect to an enemy
+pub struct ApplyStatusEffect {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+/// Stuns the enemies hit by a freeze cast by the player
+fn freeze_areas(
+    mut freeze_reader: EventReader<FreezeArea>,
+    mut effect_writer: EventWriter<ApplyStatusEffect>,
+    enemies: Query<(Entity, &HexLocation), With<EnemyTag>>,
+    balance: Res<Balance>,
+) {
+    for freeze in freeze_reader.iter() {
+        for (target, location) in &enemies {
+            if location.location.distance_to(freeze.hex) <= freeze.radius as i32 {
+                effect_writer.send(ApplyStatusEffect {
+                    target,
+                    effect: StatusEffect {
+                        kind: EffectKind::Stun,
+                        duration: balance.timers.freeze_duration,
+                        tick_rate: default_tick_rate(),
+                        magnitude: 0.0,
+                    },
+                });
+            }
+        }
+    }
+}
+
+fn apply_status_effects(
+    mut effect_reader: EventReader<ApplyStatusEffect>,
+    mut enemies: Query<(&mut StatusEffects, &mut MovementSpeed), With<EnemyTag>>,
+) {
+    for event in effect_reader.iter() {
+        let Ok((mut effects, mut speed)) = enemies.get_mut(event.target) else {
+            continue;
+        };
+        effects.apply(event.effect.clone());
+
+        // the speed modifier runs out together with the effect
+        let duration = Duration::from_secs_f32(event.effect.duration);
+        match event.effect.kind {
+            EffectKind::Slow => {
+                let factor = 1.0 - event.effect.magnitude.clamp(0.0, 1.0);
+                speed.add_timed(SpeedSource::Slow, SpeedChange::Multiply(factor), duration);
+            }
+            EffectKind::Stun => speed.add_timed(SpeedSource::Stun, SpeedChange::Multiply(0.0), duration),
+            EffectKind::Burn | EffectKind::Poison => {}
+        }
+    }
+}
+
+fn tick_status_effects(
+    mut enemies: Query<(&mut StatusEffects, &mut Health, Option<&Boss>), With<EnemyTag>>,
+    time: Res<Time>,
+) {
+    for (mut effects, mut health, boss) in &mut enemies {
+        if effects.active.is_empty() {
+            continue;
+        }
+
+        let mut damage = 0.0;
+        for active in effects.active.iter_mut() {
+            active.remaining.tick(time.delta());
+            active.tick.tick(time.delta());
+
+            if matches!(active.effect.kind, EffectKind::Burn | EffectKind::Poison) {
+                damage += active.effect.magnitude * active.tick.times_finished_this_tick() as f32;
+            }
+        }
+
+        effects.active.retain(|active| !active.remaining.finished());
+
+        if damage > 0.0 && !boss.map_or(false, |boss| boss.is_shielded()) {
+            health.current -= damage;
+        }
+    }
+}
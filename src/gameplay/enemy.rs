@@ -1,27 +1,96 @@
-use std::ops::{Add, Mul};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 use bevy::app::App;
 use bevy::core::Name;
 use bevy::prelude::*;
 use bevy::utils::default;
-use bevy_rapier3d::prelude::{ActiveEvents, Collider, CollisionEvent, Friction, GravityScale, RigidBody, Sensor};
+use bevy_mod_picking::PickableBundle;
+use bevy_mod_picking::prelude::RaycastPickTarget;
+#[cfg(feature = "rapier")]
+use bevy_rapier3d::prelude::{ActiveEvents, Collider, CollisionGroups, GravityScale, RigidBody};
 use hexx::algorithms::a_star;
 use hexx::Hex;
+use serde::{Deserialize, Serialize};
 
-use crate::{HexLocation, Map};
+use crate::{GridChanged, HexLocation, Map};
+use crate::assets::GameAssets;
+use crate::gameplay::auras::{Aura, AuraKind};
+use crate::gameplay::balance::Balance;
+use crate::gameplay::boss::Boss;
+use crate::gameplay::buildings::{AreaImpact, AreaOfEffect, Bullet, BulletSpent, CombatRecord, Damage, DamageDealt, FiredBy, SpentBullet};
+use crate::gameplay::combat::{Armor, calculate_damage, CombatRng, CriticalHit, CriticalHitDealt, DamageType, EnemyHit, roll_critical};
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::effects::{ApplyStatusEffect, OnHitEffects, StatusEffects};
+use crate::gameplay::movement::{MovementSpeed, SpeedChange, SpeedSource};
+#[cfg(feature = "rapier")]
+use crate::gameplay::physics::enemy_groups;
+use crate::gameplay::physics::{BulletTouched, HitDetection};
+use crate::gameplay::player_abilities::MeteorStrike;
+use crate::gameplay::research::{Research, TechRegistry};
+use crate::gameplay::spatial::SpatialIndex;
+use crate::gameplay::vfx::{SpawnVfx, VfxKind};
+use crate::gameplay::waves::Waves;
+use crate::gameplay::weather::Weather;
+use crate::map::flow_field::{FlowFields, PathfindingMode};
+use crate::map::path_cache::PathCache;
+use crate::map::route::Routes;
+use crate::state::global::GameState;
+
+/// World units per second an enemy with a speed of 1 walks
+const SPEED_SCALE: f32 = 0.4;
+/// Enemies closer than this push each other away
+const SEPARATION_RADIUS: f32 = 0.15;
+/// How hard overlapping enemies push each other
+const SEPARATION_STRENGTH: f32 = 4.0;
+/// Share of the push lost per second, enemies drift back onto their path once there's space
+const SEPARATION_RELAXATION: f32 = 0.5;
+/// Enemies never leave their path further than this
+const MAX_SEPARATION: f32 = 0.12;
+/// Time a killed enemy takes to topple over and shrink away
+const DEATH_ANIMATION: Duration = Duration::from_millis(500);
+/// Damage a meteor strike deals to every enemy it hits, regardless of armor
+const METEOR_DAMAGE: f32 = 150.0;
 
 pub struct EnemyPlugin;
 
-pub struct EnemyArrivedAtEnd(Entity);
+pub struct EnemyArrivedAtEnd(pub Entity);
+
+/// Request to spawn a new enemy of the given kind on the given spawn point
+pub struct SpawnEnemy(pub EnemyKind, pub Hex);
+
+/// Sent once an enemy has been killed, right before its death animation starts
+pub struct EnemyDied(pub Entity);
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_event::<EnemyArrivedAtEnd>()
-            .add_startup_system(spawn_initial_enemy.in_base_set(StartupSet::PostStartup))
-            .add_system(enemy_walking)
-            .add_system(handle_enemy_events)
-            .add_system(collision_event_handler)
+            .add_event::<EnemyDied>()
+            .add_event::<SpawnEnemy>()
+            .add_event::<CriticalHitDealt>()
+            .add_event::<EnemyHit>()
+            .init_resource::<CombatRng>()
+            .add_systems(
+                (
+                    bump_map_version
+                        .before(spawn_requested_enemies)
+                        .before(repath_enemies),
+                    spawn_requested_enemies,
+                    enemy_walking,
+                    repath_enemies.before(enemy_walking),
+                    separate_enemies.before(enemy_walking),
+                    // enemies standing at the exit would otherwise arrive again before being despawned
+                    handle_enemy_events.after(enemy_walking),
+                    bullet_hits.after(HitDetection),
+                    apply_enemy_hits.after(bullet_hits),
+                    meteor_strikes,
+                    enemy_death.after(apply_enemy_hits).after(meteor_strikes),
+                    split_dying_enemies.after(enemy_death),
+                    break_shields.after(apply_enemy_hits),
+                    animate_dying_enemies,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
         ;
     }
 }
@@ -29,169 +98,977 @@ impl Plugin for EnemyPlugin {
 #[derive(Component)]
 pub struct EnemyTag;
 
+/// Archetype of an enemy, decides about its stats and looks
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EnemyKind {
+    Normal,
+    Fast,
+    Armored,
+    /// flies over buildings instead of walking around them
+    Flying,
+    Boss,
+    /// heals the enemies around it every now and then
+    Healer,
+    /// carries a banner speeding up the enemies around it
+    Banner,
+    /// splits into bloblings when killed
+    Blob,
+    Blobling,
+    /// carries a shield taking the first hits
+    Warden,
+}
+
+impl EnemyKind {
+    pub const ALL: [EnemyKind; 10] = [
+        EnemyKind::Normal,
+        EnemyKind::Fast,
+        EnemyKind::Armored,
+        EnemyKind::Flying,
+        EnemyKind::Boss,
+        EnemyKind::Healer,
+        EnemyKind::Banner,
+        EnemyKind::Blob,
+        EnemyKind::Blobling,
+        EnemyKind::Warden,
+    ];
+
+    /// Aura of the support enemies
+    pub fn aura(&self) -> Option<Aura> {
+        match self {
+            EnemyKind::Healer => Some(Aura::new(AuraKind::Heal { amount: 15.0, interval: Duration::from_secs(2) }, 0.9)),
+            EnemyKind::Banner => Some(Aura::new(AuraKind::Haste { factor: 1.3 }, 0.9)),
+            _ => None,
+        }
+    }
+
+    /// Number of hits the shield of the enemy blocks
+    pub fn shield_charges(&self) -> Option<u32> {
+        match self {
+            EnemyKind::Warden => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Enemies taking over the way to the exit once this one is killed
+    pub fn splits(&self) -> Option<SplitsOnDeath> {
+        match self {
+            EnemyKind::Blob => Some(SplitsOnDeath { child_kind: EnemyKind::Blobling, count: 3 }),
+            _ => None,
+        }
+    }
+}
+
+/// Blocks the next `charges` bullet hits completely, the health is only touched once it is broken
+#[derive(Component, Debug)]
+pub struct Shield {
+    pub charges: u32,
+}
+
+/// Bubble around an enemy showing its `Shield`
+#[derive(Component)]
+struct ShieldBubble;
+
+/// Spawns `count` enemies of `child_kind` where the enemy died, they walk on along its remaining path
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SplitsOnDeath {
+    pub child_kind: EnemyKind,
+    pub count: u32,
+}
+
+/// How an enemy gets from one waypoint to the next
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Movement {
+    /// walks around buildings
+    Ground,
+    /// ignores buildings
+    Flying,
+}
+
+impl Movement {
+    /// Distance between the ground and the center of the enemy
+    pub fn hover_height(&self) -> f32 {
+        match self {
+            Movement::Ground => 0.1,
+            Movement::Flying => 0.6,
+        }
+    }
+}
+
+pub struct EnemyStats {
+    /// key of the name shown to the player, see `Localization`
+    pub name: &'static str,
+    pub health: f32,
+    /// factor applied to the distance to the next hex to get the movement per second
+    pub speed: f32,
+    /// resistances against the damage of the towers
+    pub armor: Armor,
+    /// radius of the capsule mesh
+    pub size: f32,
+    pub collider_radius: f32,
+    pub color: Color,
+    pub movement: Movement,
+}
+
+impl EnemyKind {
+    pub fn stats(&self) -> EnemyStats {
+        match self {
+            EnemyKind::Normal => EnemyStats {
+                name: "enemy.grunt",
+                health: 100.0,
+                speed: 1.1,
+                armor: Armor::default(),
+                size: 0.1,
+                collider_radius: 0.5,
+                color: Color::rgb(0.8, 0.7, 0.6),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Fast => EnemyStats {
+                name: "enemy.runner",
+                health: 60.0,
+                speed: 2.0,
+                armor: Armor { physical: 0.0, magic: 0.4, explosive: 0.5 },
+                size: 0.08,
+                collider_radius: 0.4,
+                color: Color::rgb(0.9, 0.6, 0.2),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Armored => EnemyStats {
+                name: "enemy.brute",
+                health: 250.0,
+                speed: 0.8,
+                armor: Armor { physical: 5.0, magic: 0.0, explosive: 0.0 },
+                size: 0.13,
+                collider_radius: 0.6,
+                color: Color::rgb(0.4, 0.4, 0.45),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Flying => EnemyStats {
+                name: "enemy.flyer",
+                health: 80.0,
+                speed: 1.4,
+                armor: Armor { physical: 0.0, magic: 0.0, explosive: 0.75 },
+                size: 0.1,
+                collider_radius: 0.5,
+                color: Color::rgb(0.5, 0.7, 0.95),
+                movement: Movement::Flying,
+            },
+            EnemyKind::Boss => EnemyStats {
+                name: "enemy.boss",
+                health: 1500.0,
+                speed: 0.6,
+                armor: Armor { physical: 10.0, magic: 0.3, explosive: 0.2 },
+                size: 0.2,
+                collider_radius: 0.9,
+                color: Color::rgb(0.6, 0.1, 0.1),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Healer => EnemyStats {
+                name: "enemy.mender",
+                health: 90.0,
+                speed: 1.0,
+                armor: Armor { physical: 0.0, magic: 0.2, explosive: 0.0 },
+                size: 0.1,
+                collider_radius: 0.5,
+                color: Color::rgb(0.3, 0.8, 0.4),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Banner => EnemyStats {
+                name: "enemy.standard_bearer",
+                health: 120.0,
+                speed: 0.9,
+                armor: Armor { physical: 2.0, magic: 0.0, explosive: 0.0 },
+                size: 0.11,
+                collider_radius: 0.5,
+                color: Color::rgb(0.6, 0.3, 0.8),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Blob => EnemyStats {
+                name: "enemy.blob",
+                health: 160.0,
+                speed: 0.9,
+                armor: Armor { physical: 0.0, magic: 0.0, explosive: 0.3 },
+                size: 0.14,
+                collider_radius: 0.6,
+                color: Color::rgb(0.4, 0.75, 0.7),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Blobling => EnemyStats {
+                name: "enemy.blobling",
+                health: 40.0,
+                speed: 1.3,
+                armor: Armor::default(),
+                size: 0.06,
+                collider_radius: 0.35,
+                color: Color::rgb(0.5, 0.85, 0.8),
+                movement: Movement::Ground,
+            },
+            EnemyKind::Warden => EnemyStats {
+                name: "enemy.warden",
+                health: 140.0,
+                speed: 0.9,
+                armor: Armor { physical: 2.0, magic: 0.1, explosive: 0.0 },
+                size: 0.11,
+                collider_radius: 0.5,
+                color: Color::rgb(0.75, 0.75, 0.5),
+                movement: Movement::Ground,
+            },
+        }
+    }
+}
+
+/// Killed enemy playing its death animation, it is no enemy for the rest of the game anymore
+#[derive(Component)]
+pub struct Dying {
+    timer: Timer,
+    start_scale: Vec3,
+}
+
+#[derive(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health { current: max, max }
+    }
+}
+
 #[derive(Component)]
 pub struct WalkingPath {
     path: Vec<Hex>,
     next_location: Hex,
+    /// waypoints the enemy still has to pass, used to find a new path when the grid changes
+    waypoints: Vec<Hex>,
+    /// where the enemy started walking towards `next_location`
+    segment_start: Vec3,
+    /// share of the way from `segment_start` to `next_location` already covered
+    progress: f32,
+}
+
+/// Sideways push away from the `WalkingPath` keeping enemies sharing a path from overlapping
+#[derive(Component, Default)]
+pub struct Separation(Vec3);
+
+/// Current movement of the enemy along its `WalkingPath` in world units per second
+#[derive(Component, Default)]
+pub struct Velocity(pub Vec3);
+
+impl WalkingPath {
+    /// Waypoints the enemy still has to pass
+    pub fn waypoints(&self) -> &[Hex] {
+        &self.waypoints
+    }
+
+    fn hex_before(&self, hex: Hex) -> Option<Hex> {
+        let position = self.path.iter().position(|h| *h == hex)?;
+        position.checked_sub(1).map(|i| self.path[i])
+    }
+
+    fn hex_after(&self, hex: Hex) -> Option<Hex> {
+        let position = self.path.iter().position(|h| *h == hex)?;
+        self.path.get(position + 1).copied()
+    }
+
+    /// Part of the path from the hex the enemy stands on to the end
+    fn path_from(&self, location: Hex) -> Option<&[Hex]> {
+        let position = self.path.iter().position(|hex| *hex == location)?;
+        Some(&self.path[position..])
+    }
+
+    /// Number of hexes left until the enemy reaches the end of its path
+    pub fn remaining(&self) -> usize {
+        let position = self.path
+            .iter()
+            .position(|hex| *hex == self.next_location)
+            .unwrap_or(0);
+        self.path.len() - position
+    }
+}
+
+/// Cost of entering `hex`, ground movement avoids occupied hexes and `blocked`
+pub fn step_cost(map: &Map, hex: Hex, movement: Movement, blocked: Option<Hex>) -> Option<u32> {
+    match movement {
+        Movement::Flying => map.entities.contains_key(&hex).then_some(1),
+        Movement::Ground if Some(hex) == blocked => None,
+        Movement::Ground => map.path_cost(hex),
+    }
+}
+
+/// A* path from `start` to `goal`, ground movement avoids occupied hexes and `blocked`
+pub fn segment_path(map: &Map, start: Hex, goal: Hex, movement: Movement, blocked: Option<Hex>) -> Option<Vec<Hex>> {
+    a_star(start, goal, |h| step_cost(map, h, movement, blocked))
+}
+
+/// Stitches together paths between all `waypoints`, ground movement avoids occupied hexes and `blocked`.
+/// Returns `None` if one of the waypoints can't be reached.
+pub fn compute_route(map: &Map, waypoints: &[Hex], movement: Movement, blocked: Option<Hex>) -> Option<Vec<Hex>> {
+    let mut full_path: Vec<Hex> = vec![];
+
+    for segment in waypoints.windows(2) {
+        let path = segment_path(map, segment[0], segment[1], movement, blocked)?;
+
+        // the start of a segment is the end of the previous one
+        let skip = if full_path.is_empty() { 0 } else { 1 };
+        full_path.extend(path.into_iter().skip(skip));
+    }
+
+    Some(full_path)
+}
+
+/// Routes of ground enemies from every spawn point to the exit, `None` if one of them is cut off
+fn ground_routes(map: &Map, blocked: Option<Hex>) -> Option<Vec<Vec<Hex>>> {
+    map.spawn_points
+        .iter()
+        .map(|spawn| compute_route(map, &map.route_from(*spawn), Movement::Ground, blocked))
+        .collect()
+}
+
+/// All hexes ground enemies walk over on their way to the exit on the current grid
+pub fn enemy_route(map: &Map) -> Option<Vec<Hex>> {
+    ground_routes(map, None).map(|routes| routes.concat())
+}
+
+/// Whether enemies would still find a way to the exit with a building placed on `hex`
+pub fn route_stays_open(map: &Map, hex: Hex) -> bool {
+    ground_routes(map, Some(hex)).is_some()
+}
+
+/// Outdates all cached paths, enemies sharing them have to walk around the new buildings
+pub fn bump_map_version(
+    mut grid_changed: EventReader<GridChanged>,
+    mut map: ResMut<Map>,
+    mut path_cache: ResMut<PathCache>,
+) {
+    if grid_changed.is_empty() {
+        return;
+    }
+    grid_changed.clear();
+    map.version += 1;
+    path_cache.evict_outdated(map.version);
 }
 
-fn spawn_initial_enemy(
+fn spawn_requested_enemies(
+    mut spawn_reader: EventReader<SpawnEnemy>,
     mut commands: Commands,
     map: Res<Map>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    routes: Res<Routes>,
+    mut path_cache: ResMut<PathCache>,
+    assets: Res<GameAssets>,
+    balance: Res<Balance>,
+    waves: Res<Waves>,
+    difficulty: Res<Difficulty>,
 ) {
-    spawn_enemy(
-        &mut commands,
-        &map,
-        &mut meshes,
-        &mut materials
-    );
+    let health_factor = difficulty.health_factor(waves.round());
+    for event in spawn_reader.iter() {
+        spawn_enemy(
+            &mut commands,
+            &map,
+            &routes,
+            &mut path_cache,
+            &assets,
+            &balance,
+            event.0,
+            event.1,
+            health_factor,
+        );
+    }
 }
 
 fn enemy_walking(
     mut event_writer: EventWriter<EnemyArrivedAtEnd>,
-    mut enemies: Query<(&mut Transform, &mut WalkingPath, &mut HexLocation, Entity), (With<EnemyTag>)>,
+    mut enemies: Query<(&mut Transform, &mut WalkingPath, &mut HexLocation, &mut Velocity, &Separation, &EnemyKind, &MovementSpeed, Entity), (With<EnemyTag>)>,
     time: Res<Time>,
     map: Res<Map>,
+    mode: Res<PathfindingMode>,
+    difficulty: Res<Difficulty>,
+    weather: Res<Weather>,
+    mut flow_fields: ResMut<FlowFields>,
 ) {
-    for (mut transform, mut walking_path, mut location, e) in &mut enemies {
-        let mut current_pos = transform.translation;
+    let delta = time.delta_seconds();
+    if delta <= 0.0 {
+        return;
+    }
 
+    for (mut transform, mut walking_path, mut location, mut velocity, separation, kind, movement_speed, e) in &mut enemies {
+        let stats = kind.stats();
         let next_location = walking_path.next_location;
-        let future_pos = map.layout.hex_to_world_pos(next_location);
 
-        let movement_vec = Vec3::new(
-            future_pos.x - current_pos.x,
-            0.0,
-            future_pos.y - current_pos.z,
-        );
+        if location.location == next_location {
+            velocity.0 = Vec3::ZERO;
+            event_writer.send(EnemyArrivedAtEnd(e));
+            continue;
+        }
 
-        if approximate_pos(movement_vec) == Vec3::ZERO {
+        // heading for the ground of the hexes interpolates the height between the tiles
+        let hover = Vec3::Y * stats.movement.hover_height();
+        let start = walking_path.segment_start;
+        let end = map.surface_pos(next_location) + hover;
+        let before = walking_path.hex_before(location.location).map_or(start, |hex| map.surface_pos(hex) + hover);
+        let after = walking_path.hex_after(next_location).map_or(end, |hex| map.surface_pos(hex) + hover);
 
-            if location.location == next_location {
-                event_writer.send(EnemyArrivedAtEnd(e));
-            } else {
-                location.location = next_location;
-                let mut updated_next_location: Option<Hex> = None;
+        let length = start.distance(end).max(f32::EPSILON);
+        let speed = movement_speed.resolved() * SPEED_SCALE * difficulty.speed_factor() * weather.speed_factor();
+        walking_path.progress += speed * delta / length;
 
-                walking_path.path.windows(2).for_each(|two| {
-                    let h1 = two.first().unwrap();
-                    let h2 = two.last().unwrap();
+        let previous_pos = transform.translation;
+        let path_pos = if walking_path.progress >= 1.0 {
+            walking_path.segment_start = end;
+            walking_path.progress = 0.0;
 
-                    if next_location == *h1 {
-                        updated_next_location = Some(*h2);
-                    }
-                });
-
-                if let Some(next_location) = updated_next_location {
-                    walking_path.next_location = next_location;
-                }
+            location.location = next_location;
+            if walking_path.waypoints.first() == Some(&next_location) {
+                walking_path.waypoints.remove(0);
             }
 
+            if *mode == PathfindingMode::FlowField {
+                follow_flow_field(&map, &mut flow_fields, &mut walking_path, stats.movement);
+            } else if let Some(next) = walking_path.hex_after(next_location) {
+                walking_path.next_location = next;
+            }
+            end
         } else {
-            transform.translation = current_pos.add(movement_vec.mul(time.delta_seconds() * 1.1));
+            catmull_rom(before, start, end, after, walking_path.progress)
+        };
+        transform.translation = path_pos + separation.0;
+
+        velocity.0 = (transform.translation - previous_pos) / delta;
+
+        let heading = Vec3::new(velocity.0.x, 0.0, velocity.0.z);
+        if heading.length_squared() > f32::EPSILON {
+            transform.rotation = Quat::from_rotation_y(f32::atan2(-heading.x, -heading.z));
+        }
+    }
+}
+
+/// Pushes enemies near each other apart, only looking at the hexes around each enemy
+fn separate_enemies(
+    mut enemies: Query<(Entity, &Transform, &HexLocation, &mut Separation), With<EnemyTag>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    let mut by_hex: HashMap<Hex, Vec<(Entity, Vec3)>> = HashMap::new();
+    for (entity, transform, location, _) in &enemies {
+        by_hex.entry(location.location).or_default().push((entity, transform.translation));
+    }
+
+    for (entity, transform, location, mut separation) in &mut enemies {
+        let mut push = Vec3::ZERO;
+
+        let nearby = std::iter::once(location.location)
+            .chain(location.location.all_neighbors())
+            .filter_map(|hex| by_hex.get(&hex))
+            .flatten();
+        for (other, position) in nearby {
+            if *other == entity {
+                continue;
+            }
+            let away = (transform.translation - *position) * Vec3::new(1.0, 0.0, 1.0);
+            let distance = away.length();
+            if distance >= SEPARATION_RADIUS {
+                continue;
+            }
+            // enemies on the exact same spot split up in a direction picked by their id
+            let direction = if distance > f32::EPSILON {
+                away / distance
+            } else {
+                Quat::from_rotation_y(entity.index() as f32) * Vec3::X
+            };
+            push += direction * (SEPARATION_RADIUS - distance);
+        }
+
+        let relaxed = separation.0 * (1.0 - (SEPARATION_RELAXATION * delta).min(1.0));
+        separation.0 = (relaxed + push * SEPARATION_STRENGTH * delta).clamp_length_max(MAX_SEPARATION);
+    }
+}
+
+/// Point at `t` (0..1) on the Catmull-Rom spline between `p1` and `p2`
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Steps onto the neighbor the field of the next waypoint points to, the path is only kept for targeting
+fn follow_flow_field(map: &Map, flow_fields: &mut FlowFields, walking_path: &mut WalkingPath, movement: Movement) {
+    let current = walking_path.next_location;
+    let Some(goal) = walking_path.waypoints.first().copied() else {
+        return;
+    };
+
+    let field = flow_fields.field(map, goal, movement);
+    let Some(next) = field.next_hex(current) else {
+        // cut off from the goal, stay until the grid changes
+        return;
+    };
+    walking_path.next_location = next;
+
+    let mut waypoints = vec![current];
+    waypoints.extend(walking_path.waypoints.iter().copied());
+    if let Some(path) = flow_fields.route(map, &waypoints, movement) {
+        walking_path.path = path;
+    }
+}
+
+fn repath_enemies(
+    mut grid_changed: EventReader<GridChanged>,
+    mut map: ResMut<Map>,
+    mut path_cache: ResMut<PathCache>,
+    mode: Res<PathfindingMode>,
+    mut enemies: Query<(&mut WalkingPath, &EnemyKind), With<EnemyTag>>,
+) {
+    if grid_changed.is_empty() {
+        return;
+    }
+    grid_changed.clear();
+
+    for (mut walking_path, kind) in &mut enemies {
+        let movement = kind.stats().movement;
+        // enemies following flow fields pick up the new fields on their next step
+        if movement == Movement::Flying || *mode == PathfindingMode::FlowField {
+            continue;
+        }
+
+        let mut waypoints = vec![walking_path.next_location];
+        waypoints.extend(walking_path.waypoints.iter().copied());
+        waypoints.dedup();
+
+        // enemies without a way around keep walking their old path
+        if let Some(path) = path_cache.route(&map, &waypoints, movement) {
+            walking_path.path = path;
         }
     }
+
+    update_enemy_route(&mut map);
 }
 
-fn approximate_pos(input: Vec3) -> Vec3 {
-    return Vec3::new(
-        (input.x * 7.0).trunc(),
-        (input.y * 7.0).trunc(),
-        (input.z * 7.0).trunc(),
-    );
+/// Recomputes the route new ground enemies will take, the path preview shows it on demand
+pub fn update_enemy_route(map: &mut Map) {
+    if let Some(route) = enemy_route(map) {
+        map.enemy_path = route.into_iter().collect();
+    }
 }
 
 fn handle_enemy_events(
     mut walking_er: EventReader<EnemyArrivedAtEnd>,
     mut commands: Commands,
-    map: Res<Map>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     for event in &mut walking_er {
         let enemy_entity = event.0;
-        commands.entity(enemy_entity).despawn();
-
-        spawn_enemy(
-            &mut commands,
-            &map,
-            &mut meshes,
-            &mut materials
-        );
+        commands.entity(enemy_entity).despawn_recursive();
     }
 }
 
 fn spawn_enemy(
-    mut commands: &mut Commands,
-    map: &Res<Map>,
-    mut meshes: &mut ResMut<Assets<Mesh>>,
-    mut materials: &mut ResMut<Assets<StandardMaterial>>,
+    commands: &mut Commands,
+    map: &Map,
+    routes: &Routes,
+    path_cache: &mut PathCache,
+    assets: &GameAssets,
+    balance: &Balance,
+    kind: EnemyKind,
+    spawn: Hex,
+    health_factor: f32,
 ) {
-    let initial_hex_field = Hex { x: 0, y: -13 };
-    let world_pos = map.layout.hex_to_world_pos(initial_hex_field);
-    let mut full_path: Vec<Hex> = vec![];
+    let stats = balance.enemy_stats(kind);
+    let Some(route) = routes.starting_at(spawn) else {
+        warn!("No route starts at the spawn point {:?}", spawn);
+        return;
+    };
+    let waypoints = route.waypoints[1..].to_vec();
 
-    let pos_1 = Hex { x: 5, y: -7 };
-    let pos_2 = Hex { x: 0, y: 0 };
-    let pos_3 = Hex { x: -9, y: 13 };
+    let full_path = route.path(map, path_cache, stats.movement)
+        .expect("placement validation keeps the route open");
 
-    let path = a_star(initial_hex_field, pos_1, |h| Some(1));
-    if let Some(hex_fields) = path {
-        hex_fields.iter().for_each(|pos| {
-            commands.entity(*map.entities.get(pos).unwrap()).insert(map.highlighted_material.clone());
-            full_path.push(*pos);
-        })
-    }
+    let progress = EnemyProgress {
+        location: spawn,
+        ground_pos: map.layout.hex_to_world_pos(spawn),
+        waypoints,
+    };
+    spawn_enemy_on_path(commands, map, assets, balance, kind, Health::new(stats.health * health_factor), progress, full_path);
+}
 
-    let path = a_star(pos_1, pos_2, |h| Some(1));
-    if let Some(hex_fields) = path {
-        hex_fields.iter().for_each(|pos| {
-            commands.entity(*map.entities.get(pos).unwrap()).insert(map.highlighted_material.clone());
-            full_path.push(*pos);
-        })
-    }
+/// How far an enemy got on its way to the exit
+pub struct EnemyProgress {
+    /// hex the enemy stands on
+    pub location: Hex,
+    /// position on the ground plane, x and z in world space
+    pub ground_pos: Vec2,
+    /// waypoints the enemy still has to pass
+    pub waypoints: Vec<Hex>,
+}
 
-    let path = a_star(pos_2, pos_3, |h| Some(1));
-    if let Some(hex_fields) = path {
-        hex_fields.iter().for_each(|pos| {
-            commands.entity(*map.entities.get(pos).unwrap()).insert(map.highlighted_material.clone());
-            full_path.push(*pos);
-        })
-    }
+/// Puts an enemy back on the grid, it continues towards its waypoints from where it stood
+pub fn restore_enemy(
+    commands: &mut Commands,
+    map: &Map,
+    assets: &GameAssets,
+    balance: &Balance,
+    kind: EnemyKind,
+    current_health: f32,
+    health_factor: f32,
+    progress: EnemyProgress,
+) -> Entity {
+    let stats = balance.enemy_stats(kind);
+
+    let mut route = vec![progress.location];
+    route.extend(progress.waypoints.iter().copied());
+    route.dedup();
 
-    let first_field = *full_path.get(1).unwrap();
+    // the saved grid might not have a way around anymore, cut straight through in that case
+    let path = compute_route(map, &route, stats.movement, None)
+        .or_else(|| compute_route(map, &route, Movement::Flying, None))
+        .unwrap_or_else(|| vec![progress.location]);
 
-    commands.spawn((
-        Name::from("Enemy"),
+    let mut health = Health::new(stats.health * health_factor);
+    health.current = current_health;
+
+    spawn_enemy_on_path(commands, map, assets, balance, kind, health, progress, path)
+}
+
+fn spawn_enemy_on_path(
+    commands: &mut Commands,
+    map: &Map,
+    assets: &GameAssets,
+    balance: &Balance,
+    kind: EnemyKind,
+    health: Health,
+    progress: EnemyProgress,
+    path: Vec<Hex>,
+) -> Entity {
+    let stats = balance.enemy_stats(kind);
+    let height = map.elevation(progress.location) + stats.movement.hover_height();
+    let EnemyProgress { location, ground_pos, waypoints } = progress;
+    let next_location = path.get(1).copied().unwrap_or(location);
+    let position = Vec3::new(ground_pos.x, height, ground_pos.y);
+    let health_share = health.current / health.max;
+
+    let enemy = commands.spawn((
+        Name::from(stats.name),
         EnemyTag,
-        HexLocation { location: initial_hex_field },
+        kind,
+        health,
+        stats.armor,
+        StatusEffects::default(),
+        MovementSpeed::new(stats.speed),
+        Velocity::default(),
+        Separation::default(),
+        HexLocation { location },
         WalkingPath {
-            path: full_path,
-            next_location: first_field,
+            path,
+            next_location,
+            waypoints,
+            segment_start: position,
+            progress: 0.0,
         },
         PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Capsule {
-                radius: 0.1,
-                depth: 0.4,
-                ..default()
-            })),
-            material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
-            transform: Transform::from_xyz(world_pos.x, 0.1, world_pos.y),
+            mesh: assets.enemy_mesh(kind),
+            material: assets.enemy_material(kind),
+            transform: Transform::from_translation(position),
             ..default()
         },
-        Collider::ball(0.5),
+        // hovering shows the enemy tooltip
+        (PickableBundle::default(), RaycastPickTarget::default()),
+    )).id();
+
+    // hit by projectiles, moved along the path by `enemy_walking`
+    #[cfg(feature = "rapier")]
+    commands.entity(enemy).insert((
+        Collider::ball(stats.collider_radius),
+        enemy_groups(),
         RigidBody::Dynamic,
         GravityScale(0.0),
-        ActiveEvents::COLLISION_EVENTS
+        ActiveEvents::COLLISION_EVENTS,
     ));
+
+    if let Some(aura) = kind.aura() {
+        // disc on the ground marking the reach of the aura
+        let disc = commands
+            .spawn(PbrBundle {
+                mesh: assets.shockwave_mesh.clone(),
+                material: assets.aura_material.clone(),
+                transform: Transform::from_xyz(0.0, 0.02 - stats.movement.hover_height(), 0.0)
+                    .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2))
+                    .with_scale(Vec3::new(aura.radius, aura.radius, 1.0)),
+                ..default()
+            })
+            .id();
+        commands.entity(enemy).insert(aura).add_child(disc);
+    }
+
+    if kind == EnemyKind::Boss {
+        let boss = Boss::new(health_share);
+        let mut speed = MovementSpeed::new(stats.speed);
+        speed.add(SpeedSource::Boss, SpeedChange::Multiply(boss.speed_factor));
+        commands.entity(enemy).insert((boss, speed));
+    }
+    if let Some(splits) = kind.splits() {
+        commands.entity(enemy).insert(splits);
+    }
+    if let Some(charges) = kind.shield_charges() {
+        let bubble = commands
+            .spawn((
+                ShieldBubble,
+                PbrBundle {
+                    mesh: assets.shield_mesh.clone(),
+                    material: assets.shield_material.clone(),
+                    transform: Transform::from_scale(Vec3::splat(stats.size * 2.5)),
+                    ..default()
+                },
+            ))
+            .id();
+        commands.entity(enemy).insert(Shield { charges }).add_child(bubble);
+    }
+    enemy
+}
+
+/// Damages the enemies touched by bullets, each bullet only hits once
+fn bullet_hits(
+    mut commands: Commands,
+    mut touch_reader: EventReader<BulletTouched>,
+    mut impact_writer: EventWriter<AreaImpact>,
+    mut spent_writer: EventWriter<BulletSpent>,
+    mut vfx_writer: EventWriter<SpawnVfx>,
+    mut hit_writer: EventWriter<EnemyHit>,
+    bullets: Query<(&Damage, &DamageType, &Transform, Option<&FiredBy>, Option<&OnHitEffects>, Option<&AreaOfEffect>), (With<Bullet>, Without<SpentBullet>)>,
+    enemies: Query<(&Transform, Entity), With<EnemyTag>>,
+    index: Res<SpatialIndex>,
+    crits: Query<&CriticalHit>,
+    mut rng: ResMut<CombatRng>,
+) {
+    // a bullet touching several enemies at once only hits the first one
+    let mut spent_bullets = HashSet::new();
+
+    for &BulletTouched { bullet, enemy } in touch_reader.iter() {
+        let Ok((damage, damage_type, bullet_transform, fired_by, on_hit, area)) = bullets.get(bullet) else {
+            continue;
+        };
+        if !enemies.contains(enemy) || !spent_bullets.insert(bullet) {
+            continue;
+        }
+        // still flying until it is back in the pool, where it must not hit anything else
+        commands.entity(bullet).insert(SpentBullet);
+        spent_writer.send(BulletSpent(bullet));
+        vfx_writer.send(SpawnVfx { kind: VfxKind::Impact, position: bullet_transform.translation });
+
+        // one roll per bullet, a critical blast hits every enemy around harder
+        let critical = roll_critical(fired_by.and_then(|f| crits.get(f.0).ok()), &mut rng);
+        let multiplier = critical.unwrap_or(1.0);
+
+        let hits: Vec<(Entity, f32)> = match area {
+            Some(area) => {
+                let impact = bullet_transform.translation;
+                if area.shockwave {
+                    impact_writer.send(AreaImpact { position: impact, radius: area.radius });
+                }
+
+                index
+                    .within(impact, area.radius)
+                    .filter_map(|(e, _)| enemies.get(e).ok())
+                    .filter_map(|(transform, e)| {
+                        let distance = transform.translation.distance(impact);
+                        (distance <= area.radius).then(|| (e, area.damage_factor(distance)))
+                    })
+                    .collect()
+            }
+            None => vec![(enemy, 1.0)],
+        };
+
+        for (target, factor) in hits {
+            hit_writer.send(EnemyHit {
+                target,
+                amount: damage.0 * factor * multiplier,
+                damage_type: *damage_type,
+                fired_by: fired_by.map(|f| f.0),
+                critical: critical.is_some(),
+                effects: on_hit.map_or(vec![], |on_hit| on_hit.0.clone()),
+            });
+        }
+    }
+}
+
+/// Takes the hits of all towers off the health of the enemies, unless a shield blocks them
+fn apply_enemy_hits(
+    mut hit_reader: EventReader<EnemyHit>,
+    mut effect_writer: EventWriter<ApplyStatusEffect>,
+    mut damage_writer: EventWriter<DamageDealt>,
+    mut crit_writer: EventWriter<CriticalHitDealt>,
+    mut enemies: Query<(&Transform, &Armor, &mut Health, Option<&Boss>, Option<&mut Shield>), With<EnemyTag>>,
+    mut records: Query<&mut CombatRecord>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+) {
+    // all hits come from towers
+    let damage_factor = research.damage_factor(&techs);
+    for event in hit_reader.iter() {
+        let Ok((enemy_transform, armor, mut health, boss, shield)) = enemies.get_mut(event.target) else {
+            continue;
+        };
+        // a shielded boss shrugs off the hit and its effects
+        if boss.map_or(false, |boss| boss.is_shielded()) {
+            continue;
+        }
+        // so does a shield, losing a charge
+        if let Some(mut shield) = shield.filter(|shield| shield.charges > 0) {
+            shield.charges -= 1;
+            continue;
+        }
+
+        let veterancy = event.fired_by
+            .and_then(|building| records.get(building).ok())
+            .map_or(1.0, |record| record.veterancy_factor());
+        let hit = calculate_damage(event.amount * damage_factor * veterancy, event.damage_type, armor);
+        if event.critical {
+            crit_writer.send(CriticalHitDealt { position: enemy_transform.translation, amount: hit });
+        }
+        // damage beyond the remaining health doesn't count for the tower
+        let dealt = hit.min(health.current.max(0.0));
+        let killed = health.current > 0.0 && health.current <= hit;
+        health.current -= hit;
+
+        if let Some(building) = event.fired_by {
+            damage_writer.send(DamageDealt { building, amount: dealt });
+        }
+        if let Some(mut record) = event.fired_by.and_then(|building| records.get_mut(building).ok()) {
+            record.damage_dealt += dealt;
+            if killed {
+                record.kills += 1;
+            }
+        }
+
+        for effect in &event.effects {
+            effect_writer.send(ApplyStatusEffect { target: event.target, effect: effect.clone() });
+        }
+    }
+}
+
+fn meteor_strikes(
+    mut meteor_reader: EventReader<MeteorStrike>,
+    mut impact_writer: EventWriter<AreaImpact>,
+    mut vfx_writer: EventWriter<SpawnVfx>,
+    mut enemies: Query<(&HexLocation, &mut Health, Option<&Boss>), With<EnemyTag>>,
+    map: Res<Map>,
+) {
+    for strike in meteor_reader.iter() {
+        let position = map.surface_pos(strike.hex);
+        // out to the centers of the outermost ring, plus half a hex
+        let radius = (strike.radius as f32 + 0.5) * 3f32.sqrt() * map.layout.hex_size.x;
+        impact_writer.send(AreaImpact { position, radius });
+        vfx_writer.send(SpawnVfx { kind: VfxKind::Impact, position });
+
+        for (location, mut health, boss) in &mut enemies {
+            let shielded = boss.map_or(false, |boss| boss.is_shielded());
+            if !shielded && location.location.distance_to(strike.hex) <= strike.radius as i32 {
+                health.current -= METEOR_DAMAGE;
+            }
+        }
+    }
 }
 
-fn collision_event_handler(mut event_reader: EventReader<CollisionEvent>) {
-    event_reader.iter().for_each(|e| {
-        if CollisionEvent::Started(e1, e2, _) = *e {
-            //
+fn enemy_death(
+    mut commands: Commands,
+    mut event_writer: EventWriter<EnemyDied>,
+    mut vfx_writer: EventWriter<SpawnVfx>,
+    enemies: Query<(&Health, &Transform, &EnemyKind, Entity), (With<EnemyTag>, Changed<Health>)>,
+) {
+    for (health, transform, kind, e) in &enemies {
+        if health.current <= 0.0 {
+            event_writer.send(EnemyDied(e));
+            vfx_writer.send(SpawnVfx { kind: VfxKind::Death(*kind), position: transform.translation });
+
+            // without the tag towers, bullets and waves ignore the enemy while it fades out
+            #[cfg(feature = "rapier")]
+            commands.entity(e).remove::<(Collider, RigidBody, CollisionGroups)>();
+            commands.entity(e)
+                .remove::<EnemyTag>()
+                .insert(Dying {
+                    timer: Timer::new(DEATH_ANIMATION, TimerMode::Once),
+                    start_scale: transform.scale,
+                })
+                // the health bar
+                .despawn_descendants();
         }
-    })
+    }
+}
+
+/// Removes shields without charges left, together with their bubble
+fn break_shields(
+    mut commands: Commands,
+    shields: Query<(Entity, &Shield), Changed<Shield>>,
+    bubbles: Query<(Entity, &Parent), With<ShieldBubble>>,
+) {
+    for (e, shield) in &shields {
+        if shield.charges > 0 {
+            continue;
+        }
+
+        commands.entity(e).remove::<Shield>();
+        for (bubble, parent) in &bubbles {
+            if parent.get() == e {
+                commands.entity(bubble).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Spawns the children of killed enemies splitting on death, they take over the rest of the parent's path
+fn split_dying_enemies(
+    mut commands: Commands,
+    mut died_reader: EventReader<EnemyDied>,
+    // the killed enemy is still around for its death animation
+    parents: Query<(&SplitsOnDeath, &HexLocation, &WalkingPath, &Transform)>,
+    map: Res<Map>,
+    assets: Res<GameAssets>,
+    balance: Res<Balance>,
+    waves: Res<Waves>,
+    difficulty: Res<Difficulty>,
+) {
+    let health_factor = difficulty.health_factor(waves.round());
+
+    for event in died_reader.iter() {
+        let Ok((splits, location, walking_path, transform)) = parents.get(event.0) else {
+            continue;
+        };
+        let stats = balance.enemy_stats(splits.child_kind);
+
+        for _ in 0..splits.count {
+            let progress = EnemyProgress {
+                location: location.location,
+                ground_pos: Vec2::new(transform.translation.x, transform.translation.z),
+                waypoints: walking_path.waypoints.clone(),
+            };
+            let health = Health::new(stats.health * health_factor);
+
+            match walking_path.path_from(location.location) {
+                Some(path) => spawn_enemy_on_path(&mut commands, &map, &assets, &balance, splits.child_kind, health, progress, path.to_vec()),
+                // the parent got pushed off its path, find a new one from where it fell
+                None => restore_enemy(&mut commands, &map, &assets, &balance, splits.child_kind, health.current, health_factor, progress),
+            }
+        }
+    }
+}
+
+fn animate_dying_enemies(
+    mut commands: Commands,
+    mut enemies: Query<(&mut Dying, &mut Transform, Entity)>,
+    time: Res<Time>,
+) {
+    for (mut dying, mut transform, e) in &mut enemies {
+        dying.timer.tick(time.delta());
+        if dying.timer.finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+
+        // eased out, the enemy drops quickly and slowly fades away
+        let t = dying.timer.percent();
+        let eased = 1.0 - (1.0 - t) * (1.0 - t);
+        transform.rotation = Quat::from_rotation_x(eased * std::f32::consts::FRAC_PI_2);
+        transform.scale = dying.start_scale * (1.0 - eased);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use hexx::Hex;
+
+use crate::{HexLocation, Map};
+use crate::gameplay::buildings::{BuildingTag, TargetingRange};
+use crate::gameplay::enemy::EnemyTag;
+use crate::state::global::GameState;
+use crate::ui::highlight::{HexHighlights, HighlightKind};
+
+/// Hexes around the exits the player always sees
+const BASE_VISION_RADIUS: u32 = 3;
+/// Towers see this far beyond their range, so enemies show up before they are shot at
+const TOWER_VISION_MARGIN: f32 = 0.5;
+
+pub struct FogOfWarPlugin;
+
+impl Plugin for FogOfWarPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<FogOfWar>()
+            .add_systems(
+                (
+                    update_vision,
+                    hide_fogged_enemies.after(update_vision),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Shroud over the hexes no tower and no exit can see, enabled per map
+#[derive(Resource, Default, Debug)]
+pub struct FogOfWar {
+    pub enabled: bool,
+    visible: HashSet<Hex>,
+}
+
+impl FogOfWar {
+    pub fn new(enabled: bool) -> Self {
+        FogOfWar { enabled, visible: HashSet::new() }
+    }
+
+    pub fn is_visible(&self, hex: Hex) -> bool {
+        !self.enabled || self.visible.contains(&hex)
+    }
+}
+
+/// Enemy walking through the fog, towers can't see or target it
+#[derive(Component)]
+pub struct InFog;
+
+/// Recomputes the visible hexes whenever towers are built, upgraded or sold, and for the fog of a new map
+fn update_vision(
+    mut fog: ResMut<FogOfWar>,
+    map: Res<Map>,
+    mut highlights: ResMut<HexHighlights>,
+    towers: Query<(&Transform, &TargetingRange), With<BuildingTag>>,
+    changed_towers: Query<(), (With<BuildingTag>, Changed<TargetingRange>)>,
+    mut removed_towers: RemovedComponents<BuildingTag>,
+) {
+    let towers_changed = !changed_towers.is_empty() || removed_towers.iter().count() > 0;
+    // every new game replaces the fog, which counts as a change rather than an addition
+    if !fog.enabled || !(towers_changed || fog.is_changed()) {
+        return;
+    }
+
+    let vision: Vec<(Vec2, f32)> = towers
+        .iter()
+        .map(|(transform, range)| (Vec2::new(transform.translation.x, transform.translation.z), range.0 + TOWER_VISION_MARGIN))
+        .collect();
+
+    let visible: HashSet<Hex> = map.terrain
+        .keys()
+        .copied()
+        .filter(|hex| {
+            let near_base = map.exit_points.iter().any(|exit| exit.distance_to(*hex) <= BASE_VISION_RADIUS as i32);
+            let pos = map.layout.hex_to_world_pos(*hex);
+            near_base || vision.iter().any(|(center, radius)| center.distance(pos) <= *radius)
+        })
+        .collect();
+
+    for hex in map.terrain.keys() {
+        if visible.contains(hex) {
+            highlights.unhighlight(*hex, HighlightKind::Fog);
+        } else {
+            highlights.highlight(*hex, HighlightKind::Fog);
+        }
+    }
+    fog.visible = visible;
+}
+
+fn hide_fogged_enemies(
+    mut commands: Commands,
+    fog: Res<FogOfWar>,
+    mut enemies: Query<(Entity, &HexLocation, &mut Visibility, Option<&InFog>), With<EnemyTag>>,
+) {
+    if !fog.enabled {
+        return;
+    }
+
+    for (e, location, mut visibility, in_fog) in &mut enemies {
+        let visible = fog.is_visible(location.location);
+        if visible && in_fog.is_some() {
+            commands.entity(e).remove::<InFog>();
+            *visibility = Visibility::Inherited;
+        } else if !visible && in_fog.is_none() {
+            commands.entity(e).insert(InFog);
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use hexx::Hex;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{Action, GridChanged, HexLocation, Map};
+use crate::gameplay::balance::Balance;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::enemy::route_stays_open;
+use crate::gameplay::towers::{insert_tower, Tower, TowerLevel, TowerRegistry};
+use crate::gameplay::waves::{WavePhase, Waves};
+use crate::state::global::GameState;
+use crate::ui::player::SelectedBuilding;
+
+/// Oldest changes are forgotten once the history gets longer than this
+const HISTORY_LENGTH: usize = 20;
+
+pub struct BuildHistoryPlugin;
+
+impl Plugin for BuildHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BuildHistory>()
+            .add_systems(
+                (
+                    forget_history_during_waves,
+                    undo_building_changes.after(forget_history_during_waves),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Change the player made to the buildings, refers to the hex since undoing and redoing spawns new entities
+#[derive(Clone, Debug)]
+pub enum BuildCommand {
+    Placed { hex: Hex, tower: String, level: usize },
+    Sold { hex: Hex, tower: String, level: usize },
+}
+
+/// Everything an undo or redo touches
+struct BuildContext<'a, 'w, 's> {
+    commands: &'a mut Commands<'w, 's>,
+    map: &'a mut Map,
+    resources: &'a mut PlayerResources,
+    registry: &'a TowerRegistry,
+    balance: &'a Balance,
+    asset_server: &'a AssetServer,
+    /// levels of the placed towers by their hex
+    levels: HashMap<Hex, usize>,
+}
+
+impl BuildContext<'_, '_, '_> {
+    /// Buys a tower at `level` for everything invested in it, fails if the hex got blocked or the gold spent meanwhile
+    fn place(&mut self, hex: Hex, tower: &str, level: usize, cost: u32) -> bool {
+        let Some(definition) = self.registry.get(tower) else {
+            return false;
+        };
+        if !self.map.is_buildable(hex) || !route_stays_open(self.map, hex) || !self.resources.try_spend(cost) {
+            return false;
+        }
+
+        let entity = self.commands.spawn(SceneBundle::default()).id();
+        insert_tower(self.commands, entity, definition, level, hex, self.map.surface_pos(hex), self.asset_server);
+        self.map.buildings.insert(hex, entity);
+        true
+    }
+
+    /// Level of the building on `hex` it gets removed at, `None` if there is none
+    fn remove(&mut self, hex: Hex) -> Option<usize> {
+        let entity = self.map.buildings.remove(&hex)?;
+        let level = self.levels.remove(&hex).unwrap_or(0);
+        self.commands.entity(entity).despawn_recursive();
+        Some(level)
+    }
+}
+
+impl BuildCommand {
+    /// Does the change again, false if that isn't possible anymore
+    fn apply(&mut self, ctx: &mut BuildContext) -> bool {
+        match self {
+            BuildCommand::Placed { hex, tower, level } => {
+                let cost = ctx.registry.get(tower).map_or(0, |definition| definition.invested(*level));
+                ctx.place(*hex, tower, *level, cost)
+            }
+            BuildCommand::Sold { hex, tower, .. } => {
+                let Some(level) = ctx.remove(*hex) else {
+                    return false;
+                };
+                let refund = ctx.registry.get(tower).map_or(0, |definition| definition.sell_value(level, ctx.balance));
+                ctx.resources.earn(refund);
+                true
+            }
+        }
+    }
+
+    /// Takes the change back, placed buildings are refunded in full, sold ones are bought back for the gold the sale brought
+    fn revert(&mut self, ctx: &mut BuildContext) -> bool {
+        match self {
+            BuildCommand::Placed { hex, tower, level } => {
+                // upgrades bought since are refunded as well and come back with a redo
+                let Some(removed_level) = ctx.remove(*hex) else {
+                    return false;
+                };
+                *level = removed_level;
+                let refund = ctx.registry.get(tower).map_or(0, |definition| definition.invested(*level));
+                ctx.resources.refund(refund);
+                true
+            }
+            BuildCommand::Sold { hex, tower, level } => {
+                let cost = ctx.registry.get(tower).map_or(0, |definition| definition.sell_value(*level, ctx.balance));
+                ctx.place(*hex, tower, *level, cost)
+            }
+        }
+    }
+}
+
+/// Placements and sells of the current wave break which can still be undone
+#[derive(Resource, Default, Debug)]
+pub struct BuildHistory {
+    done: Vec<BuildCommand>,
+    undone: Vec<BuildCommand>,
+}
+
+impl BuildHistory {
+    pub fn record(&mut self, command: BuildCommand) {
+        self.undone.clear();
+        self.done.push(command);
+        if self.done.len() > HISTORY_LENGTH {
+            self.done.remove(0);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
+
+/// Only the changes made since the last wave ended can be taken back
+fn forget_history_during_waves(
+    mut history: ResMut<BuildHistory>,
+    waves: Res<Waves>,
+) {
+    if waves.phase != WavePhase::Break && !(history.done.is_empty() && history.undone.is_empty()) {
+        history.clear();
+    }
+}
+
+fn undo_building_changes(
+    mut commands: Commands,
+    mut history: ResMut<BuildHistory>,
+    mut map: ResMut<Map>,
+    mut resources: ResMut<PlayerResources>,
+    mut grid_changed: EventWriter<GridChanged>,
+    registry: Res<TowerRegistry>,
+    balance: Res<Balance>,
+    asset_server: Res<AssetServer>,
+    towers: Query<(&TowerLevel, &HexLocation), With<Tower>>,
+    actions: Query<&ActionState<Action>>,
+    waves: Res<Waves>,
+) {
+    let Ok(action_state) = actions.get_single() else {
+        return;
+    };
+    let undo = action_state.just_pressed(Action::Undo);
+    let redo = action_state.just_pressed(Action::Redo);
+    if waves.phase != WavePhase::Break || !(undo || redo) {
+        return;
+    }
+
+    let changed = {
+        let mut ctx = BuildContext {
+            commands: &mut commands,
+            map: &mut map,
+            resources: &mut resources,
+            registry: &registry,
+            balance: &balance,
+            asset_server: &asset_server,
+            levels: towers.iter().map(|(level, location)| (location.location, level.0)).collect(),
+        };
+
+        // a change that can't be done anymore stays out of the history
+        if undo {
+            history.done.pop().map_or(false, |mut command| {
+                let reverted = command.revert(&mut ctx);
+                if reverted {
+                    history.undone.push(command);
+                }
+                reverted
+            })
+        } else {
+            history.undone.pop().map_or(false, |mut command| {
+                let applied = command.apply(&mut ctx);
+                if applied {
+                    history.done.push(command);
+                }
+                applied
+            })
+        }
+    };
+
+    if changed {
+        // the selected building might be gone
+        commands.remove_resource::<SelectedBuilding>();
+        grid_changed.send(GridChanged);
+    }
+}
@@ -1,2 +1,29 @@
 pub mod enemy;
-pub mod buildings;
\ No newline at end of file
+pub mod buildings;
+pub mod economy;
+pub mod towers;
+pub mod waves;
+pub mod effects;
+pub mod speed;
+pub mod save;
+pub mod stats;
+pub mod difficulty;
+pub mod vfx;
+pub mod fog;
+pub mod weather;
+pub mod abilities;
+pub mod player_abilities;
+pub mod combat;
+pub mod auras;
+pub mod boss;
+pub mod physics;
+pub mod spatial;
+pub mod movement;
+pub mod history;
+pub mod research;
+pub mod campaign;
+pub mod wave_files;
+pub mod balance;
+pub mod mods;
+#[cfg(feature = "scripting")]
+pub mod scripting;
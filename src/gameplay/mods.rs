@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::gameplay::balance::{check_not_negative, check_positive, EnemyBalance};
+use crate::gameplay::enemy::EnemyKind;
+use crate::gameplay::towers::TowerDefinition;
+use crate::localization::Localization;
+use crate::ui::toasts::Toasts;
+
+/// Directory (relative to the working directory) asset paths start from
+const ASSETS_DIRECTORY: &str = "assets";
+/// Directory (relative to the working directory) searched for mod packs, every pack is a directory of its own
+const MODS_DIRECTORY: &str = "assets/mods";
+/// Asset path of `MODS_DIRECTORY`, the models and icons of the packs are loaded from below it
+const MODS_ASSET_PATH: &str = "mods";
+/// File inside the directory of a pack listing what it adds
+const PACK_FILE: &str = "pack.ron";
+
+/// Looks for mod packs once at startup, the tower registry takes in their towers when it is loaded
+pub struct ModPlugin;
+
+impl Plugin for ModPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(ModPacks::scan(Path::new(MODS_DIRECTORY)))
+            .add_startup_system(report_broken_packs)
+        ;
+    }
+}
+
+/// What a `pack.ron` holds, paths of models and icons are relative to the directory of the pack
+#[derive(Deserialize, Debug)]
+struct PackFile {
+    name: String,
+    #[serde(default)]
+    towers: Vec<TowerDefinition>,
+    /// stats replacing the built-in ones of the enemy kinds, they win over the balance file
+    #[serde(default)]
+    enemies: HashMap<EnemyKind, EnemyBalance>,
+}
+
+#[derive(Debug)]
+pub struct ModPack {
+    pub name: String,
+    /// towers with their paths turned into asset paths
+    pub towers: Vec<TowerDefinition>,
+    pub enemies: HashMap<EnemyKind, EnemyBalance>,
+}
+
+/// Packs found in the mods directory
+#[derive(Resource, Default, Debug)]
+pub struct ModPacks {
+    pub packs: Vec<ModPack>,
+    /// directories of the packs which could not be loaded
+    pub broken: Vec<String>,
+}
+
+impl ModPacks {
+    /// Loads every pack below `directory` in the order of their names, broken packs are left out as a whole
+    fn scan(directory: &Path) -> Self {
+        let mut mods = ModPacks::default();
+        // playing without mods is the usual case
+        let Ok(entries) = fs::read_dir(directory) else {
+            return mods;
+        };
+
+        let mut directories: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        directories.sort();
+
+        for name in directories {
+            match load_pack(directory, &name) {
+                Ok(pack) => {
+                    info!("Loaded the mod {} with {} towers", pack.name, pack.towers.len());
+                    mods.packs.push(pack);
+                }
+                Err(e) => {
+                    warn!("Skipping the mod {}: {}", name, e);
+                    mods.broken.push(name);
+                }
+            }
+        }
+        mods
+    }
+
+    pub fn towers(&self) -> impl Iterator<Item = &TowerDefinition> {
+        self.packs.iter().flat_map(|pack| pack.towers.iter())
+    }
+
+    /// Enemy stats of all packs, later packs win over earlier ones
+    pub fn enemies(&self) -> impl Iterator<Item = (EnemyKind, EnemyBalance)> + '_ {
+        self.packs.iter().flat_map(|pack| pack.enemies.iter().map(|(kind, balance)| (*kind, *balance)))
+    }
+}
+
+fn load_pack(directory: &Path, name: &str) -> Result<ModPack, String> {
+    let path = directory.join(name).join(PACK_FILE);
+    let content = fs::read_to_string(&path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    let file: PackFile = ron::from_str(&content).map_err(|e| format!("{} is malformed: {}", path.display(), e))?;
+
+    let asset_path = |path: &str| format!("{}/{}/{}", MODS_ASSET_PATH, name, path);
+    let mut towers = file.towers;
+    for tower in &mut towers {
+        validate_tower(tower)?;
+
+        if !tower.model.is_empty() {
+            tower.model = asset_path(&tower.model);
+        }
+        tower.icon = tower.icon.as_deref().map(asset_path);
+        for upgrade in &mut tower.upgrades {
+            upgrade.model = upgrade.model.as_deref().map(asset_path);
+        }
+
+        let missing = std::iter::once(tower.model.as_str())
+            .chain(tower.icon.as_deref())
+            .chain(tower.upgrades.iter().filter_map(|upgrade| upgrade.model.as_deref()))
+            .filter(|path| !path.is_empty())
+            .find(|path| !asset_exists(path));
+        if let Some(missing) = missing {
+            return Err(format!("the tower {} uses {} which doesn't exist", tower.id, missing));
+        }
+    }
+
+    for (kind, balance) in &file.enemies {
+        balance.validate(*kind)?;
+    }
+
+    Ok(ModPack {
+        name: file.name,
+        towers,
+        enemies: file.enemies,
+    })
+}
+
+/// Catches the values the game can't work with, like towers firing zero times per second
+fn validate_tower(tower: &TowerDefinition) -> Result<(), String> {
+    if tower.id.is_empty() {
+        return Err(format!("the tower {} has no id", tower.name));
+    }
+    // the duration and the ticks of an effect become timers
+    for effect in &tower.effects {
+        check_not_negative(&format!("the duration of the {:?} of the tower {}", effect.kind, tower.id), effect.duration)?;
+        check_positive(&format!("the tick rate of the {:?} of the tower {}", effect.kind, tower.id), effect.tick_rate)?;
+    }
+    if tower.is_wall() {
+        return Ok(());
+    }
+    if tower.model.is_empty() {
+        return Err(format!("the tower {} has no model", tower.id));
+    }
+    let stats = std::iter::once((tower.range, tower.fire_rate))
+        .chain(tower.upgrades.iter().map(|upgrade| (upgrade.range, upgrade.fire_rate)));
+    for (range, fire_rate) in stats {
+        check_positive(&format!("the range of the tower {}", tower.id), range)?;
+        check_positive(&format!("the fire rate of the tower {}", tower.id), fire_rate)?;
+    }
+    Ok(())
+}
+
+/// Whether the file of an asset path is there, labels like `#Scene0` are left out
+fn asset_exists(path: &str) -> bool {
+    let file = path.split('#').next().unwrap_or(path);
+    Path::new(ASSETS_DIRECTORY).join(file).is_file()
+}
+
+fn report_broken_packs(packs: Res<ModPacks>, localization: Res<Localization>, mut toasts: ResMut<Toasts>) {
+    for name in &packs.broken {
+        toasts.push(localization.t_with("toast.broken_mod", &[("name", name.clone())]));
+    }
+}
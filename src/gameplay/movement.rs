@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{HexLocation, Map};
+use crate::gameplay::enemy::{EnemyKind, EnemyTag, Movement};
+use crate::state::global::GameState;
+
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(
+                (
+                    tick_speed_modifiers,
+                    terrain_speed,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Where a speed modifier comes from, an enemy carries at most one modifier per source
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpeedSource {
+    Slow,
+    Stun,
+    Haste,
+    Boss,
+    Terrain,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpeedChange {
+    /// multiplied with the base speed
+    Multiply(f32),
+}
+
+#[derive(Debug)]
+struct SpeedModifier {
+    source: SpeedSource,
+    change: SpeedChange,
+    /// `None` for modifiers staying until they are removed
+    remaining: Option<Timer>,
+}
+
+/// Walking speed of an enemy, the stats of its kind changed by slows, hastes and the ground it walks on.
+/// The factors of all modifiers multiply, so their order doesn't matter,
+/// the game-wide factors of the difficulty and the weather are applied on top by `enemy_walking`.
+#[derive(Component, Debug)]
+pub struct MovementSpeed {
+    pub base: f32,
+    modifiers: Vec<SpeedModifier>,
+}
+
+impl MovementSpeed {
+    pub fn new(base: f32) -> Self {
+        MovementSpeed {
+            base,
+            modifiers: vec![],
+        }
+    }
+
+    /// Adds a modifier staying until it is removed, replacing the one of the same source
+    pub fn add(&mut self, source: SpeedSource, change: SpeedChange) {
+        self.insert(source, change, None);
+    }
+
+    /// Adds a modifier running out after `duration`, replacing the one of the same source
+    pub fn add_timed(&mut self, source: SpeedSource, change: SpeedChange, duration: Duration) {
+        self.insert(source, change, Some(Timer::new(duration, TimerMode::Once)));
+    }
+
+    fn insert(&mut self, source: SpeedSource, change: SpeedChange, remaining: Option<Timer>) {
+        self.remove(source);
+        self.modifiers.push(SpeedModifier { source, change, remaining });
+    }
+
+    pub fn remove(&mut self, source: SpeedSource) {
+        self.modifiers.retain(|modifier| modifier.source != source);
+    }
+
+    pub fn modifier(&self, source: SpeedSource) -> Option<SpeedChange> {
+        self.modifiers.iter().find(|modifier| modifier.source == source).map(|modifier| modifier.change)
+    }
+
+    /// Speed with all modifiers applied, never below zero
+    pub fn resolved(&self) -> f32 {
+        let mut factor = 1.0;
+        for modifier in &self.modifiers {
+            match modifier.change {
+                SpeedChange::Multiply(by) => factor *= by,
+            }
+        }
+        (self.base * factor).max(0.0)
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        self.modifiers.retain_mut(|modifier| {
+            modifier.remaining.as_mut().map_or(true, |timer| !timer.tick(delta).finished())
+        });
+    }
+}
+
+fn tick_speed_modifiers(
+    mut enemies: Query<&mut MovementSpeed, With<EnemyTag>>,
+    time: Res<Time>,
+) {
+    for mut speed in &mut enemies {
+        speed.tick(time.delta());
+    }
+}
+
+/// Ground enemies slow down on rough terrain, flying ones don't care
+fn terrain_speed(
+    mut enemies: Query<(&mut MovementSpeed, &HexLocation, &EnemyKind), (With<EnemyTag>, Changed<HexLocation>)>,
+    map: Res<Map>,
+) {
+    for (mut speed, location, kind) in &mut enemies {
+        let factor = match kind.stats().movement {
+            Movement::Ground => map.terrain(location.location).map_or(1.0, |terrain| terrain.speed_factor()),
+            Movement::Flying => 1.0,
+        };
+
+        if factor == 1.0 {
+            speed.remove(SpeedSource::Terrain);
+        } else if speed.modifier(SpeedSource::Terrain) != Some(SpeedChange::Multiply(factor)) {
+            speed.add(SpeedSource::Terrain, SpeedChange::Multiply(factor));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+#[cfg(feature = "rapier")]
+use bevy_rapier3d::prelude::{CollisionEvent, CollisionGroups, Group, NoUserData, RapierConfiguration, RapierDebugRenderPlugin, RapierPhysicsPlugin};
+
+use crate::gameplay::buildings::Bullet;
+#[cfg(not(feature = "rapier"))]
+use crate::gameplay::buildings::BULLET_RADIUS;
+#[cfg(not(feature = "rapier"))]
+use crate::gameplay::enemy::{EnemyKind, EnemyTag};
+#[cfg(not(feature = "rapier"))]
+use crate::gameplay::spatial::SpatialIndex;
+use crate::state::global::GameState;
+
+/// Collision group of all enemies
+#[cfg(feature = "rapier")]
+pub const ENEMIES: Group = Group::GROUP_1;
+/// Collision group of the bullets fired by towers
+#[cfg(feature = "rapier")]
+pub const PROJECTILES: Group = Group::GROUP_2;
+
+/// Finds the enemies hit by bullets, through rapier collision events with the `rapier` feature
+/// and through a plain distance check without it
+pub struct PhysicsPlugin {
+    /// draws the colliders, needs the renderer
+    #[cfg_attr(not(feature = "rapier"), allow(dead_code))]
+    pub debug_render: bool,
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BulletTouched>();
+
+        #[cfg(feature = "rapier")]
+        if self.debug_render {
+            app.add_plugin(RapierDebugRenderPlugin::default());
+        }
+        #[cfg(feature = "rapier")]
+        app
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+            .add_system(collision_event_handler.in_set(HitDetection).in_set(OnUpdate(GameState::Playing)))
+            .add_system(pause_physics.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(resume_physics.in_schedule(OnExit(GameState::Paused)))
+        ;
+
+        #[cfg(not(feature = "rapier"))]
+        app.add_system(detect_bullet_hits.in_set(HitDetection).in_set(OnUpdate(GameState::Playing)));
+    }
+}
+
+/// Systems sending `BulletTouched`
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HitDetection;
+
+/// A bullet touched an enemy, the bullet hits it unless it already hit something else
+pub struct BulletTouched {
+    pub bullet: Entity,
+    pub enemy: Entity,
+}
+
+/// Enemies only get hit by projectiles, they follow their paths and don't push each other around
+#[cfg(feature = "rapier")]
+pub fn enemy_groups() -> CollisionGroups {
+    CollisionGroups::new(ENEMIES, PROJECTILES)
+}
+
+/// Projectiles only hit enemies
+#[cfg(feature = "rapier")]
+pub fn projectile_groups() -> CollisionGroups {
+    CollisionGroups::new(PROJECTILES, ENEMIES)
+}
+
+#[cfg(feature = "rapier")]
+fn collision_event_handler(
+    mut event_reader: EventReader<CollisionEvent>,
+    mut touch_writer: EventWriter<BulletTouched>,
+    bullets: Query<(), With<Bullet>>,
+) {
+    for e in event_reader.iter() {
+        let CollisionEvent::Started(e1, e2, _) = *e else {
+            continue;
+        };
+        let (bullet, enemy) = if bullets.contains(e1) { (e1, e2) } else { (e2, e1) };
+        if bullets.contains(bullet) {
+            touch_writer.send(BulletTouched { bullet, enemy });
+        }
+    }
+}
+
+/// The game clock stops while paused, the physics have to as well
+#[cfg(feature = "rapier")]
+fn pause_physics(mut rapier: ResMut<RapierConfiguration>) {
+    rapier.physics_pipeline_active = false;
+}
+
+#[cfg(feature = "rapier")]
+fn resume_physics(mut rapier: ResMut<RapierConfiguration>) {
+    rapier.physics_pipeline_active = true;
+}
+
+/// Sphere overlap of every bullet with the enemies near it, the closest enemy touched counts
+#[cfg(not(feature = "rapier"))]
+fn detect_bullet_hits(
+    mut touch_writer: EventWriter<BulletTouched>,
+    bullets: Query<(Entity, &Transform), With<Bullet>>,
+    enemies: Query<(&Transform, &EnemyKind), With<EnemyTag>>,
+    index: Res<SpatialIndex>,
+) {
+    // the index only knows where enemies stand, not how big they are
+    let reach = BULLET_RADIUS + EnemyKind::ALL.iter().map(|kind| kind.stats().collider_radius).fold(0.0, f32::max);
+
+    for (bullet, transform) in &bullets {
+        let touched = index
+            .within(transform.translation, reach)
+            .filter_map(|(enemy, _)| enemies.get(enemy).ok().map(|enemy_data| (enemy, enemy_data)))
+            .map(|(enemy, (enemy_transform, kind))| {
+                (enemy, enemy_transform.translation.distance(transform.translation) - kind.stats().collider_radius)
+            })
+            .filter(|(_, gap)| *gap <= BULLET_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((enemy, _)) = touched {
+            touch_writer.send(BulletTouched { bullet, enemy });
+        }
+    }
+}
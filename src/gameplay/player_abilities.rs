@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use hexx::Hex;
+
+use crate::state::global::GameState;
+
+/// Mana the player starts with and can't exceed
+const MAX_MANA: f32 = 100.0;
+/// Mana regained per second
+const MANA_REGENERATION: f32 = 2.0;
+
+pub struct PlayerAbilityPlugin;
+
+impl Plugin for PlayerAbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<PlayerAbilities>()
+            .add_event::<CastAbility>()
+            .add_event::<MeteorStrike>()
+            .add_event::<FreezeArea>()
+            .add_event::<RepairBase>()
+            .add_systems(
+                (
+                    regenerate_abilities,
+                    cast_abilities.after(regenerate_abilities),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Ability the player casts from the ability bar, independent of any tower
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlayerAbility {
+    /// heavy damage to all enemies around the target hex
+    MeteorStrike,
+    /// stuns all enemies around the target hex
+    Freeze,
+    /// gives back some of the lost lives
+    Repair,
+}
+
+impl PlayerAbility {
+    pub const ALL: [PlayerAbility; 3] = [
+        PlayerAbility::MeteorStrike,
+        PlayerAbility::Freeze,
+        PlayerAbility::Repair,
+    ];
+
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            PlayerAbility::MeteorStrike => "player_ability.meteor",
+            PlayerAbility::Freeze => "player_ability.freeze",
+            PlayerAbility::Repair => "player_ability.repair",
+        }
+    }
+
+    pub fn mana_cost(&self) -> f32 {
+        match self {
+            PlayerAbility::MeteorStrike => 60.0,
+            PlayerAbility::Freeze => 40.0,
+            PlayerAbility::Repair => 80.0,
+        }
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        match self {
+            PlayerAbility::MeteorStrike => Duration::from_secs(30),
+            PlayerAbility::Freeze => Duration::from_secs(20),
+            PlayerAbility::Repair => Duration::from_secs(60),
+        }
+    }
+
+    /// Rings of hexes around the target hex the ability reaches, `None` for abilities cast without a target
+    pub fn radius(&self) -> Option<u32> {
+        match self {
+            PlayerAbility::MeteorStrike => Some(1),
+            PlayerAbility::Freeze => Some(2),
+            PlayerAbility::Repair => None,
+        }
+    }
+
+    pub fn needs_target(&self) -> bool {
+        self.radius().is_some()
+    }
+}
+
+/// Mana of the player and the cooldowns of the abilities cast recently
+#[derive(Resource, Debug)]
+pub struct PlayerAbilities {
+    pub mana: f32,
+    cooldowns: HashMap<PlayerAbility, Timer>,
+}
+
+impl Default for PlayerAbilities {
+    fn default() -> Self {
+        PlayerAbilities {
+            mana: MAX_MANA,
+            cooldowns: HashMap::new(),
+        }
+    }
+}
+
+impl PlayerAbilities {
+    pub fn max_mana(&self) -> f32 {
+        MAX_MANA
+    }
+
+    pub fn can_cast(&self, ability: PlayerAbility) -> bool {
+        self.mana >= ability.mana_cost() && !self.cooldowns.contains_key(&ability)
+    }
+
+    /// Share of the cooldown of the ability still to go (0..1), 0 once it can be cast again
+    pub fn cooldown_left(&self, ability: PlayerAbility) -> f32 {
+        self.cooldowns.get(&ability).map_or(0.0, |timer| timer.percent_left())
+    }
+}
+
+/// Request to cast the ability, on the hex for abilities that need a target,
+/// ignored without enough mana or while the ability cools down
+pub struct CastAbility {
+    pub ability: PlayerAbility,
+    pub hex: Option<Hex>,
+}
+
+/// Damages all enemies within `radius` rings of hexes around `hex`
+pub struct MeteorStrike {
+    pub hex: Hex,
+    pub radius: u32,
+}
+
+/// Stuns all enemies within `radius` rings of hexes around `hex`
+pub struct FreezeArea {
+    pub hex: Hex,
+    pub radius: u32,
+}
+
+/// Gives back some of the lives lost to enemies reaching the exit
+pub struct RepairBase;
+
+fn regenerate_abilities(mut abilities: ResMut<PlayerAbilities>, time: Res<Time>) {
+    let abilities = abilities.as_mut();
+    abilities.mana = (abilities.mana + MANA_REGENERATION * time.delta_seconds()).min(MAX_MANA);
+    abilities.cooldowns.retain(|_, timer| !timer.tick(time.delta()).finished());
+}
+
+fn cast_abilities(
+    mut cast_reader: EventReader<CastAbility>,
+    mut abilities: ResMut<PlayerAbilities>,
+    mut meteor_writer: EventWriter<MeteorStrike>,
+    mut freeze_writer: EventWriter<FreezeArea>,
+    mut repair_writer: EventWriter<RepairBase>,
+) {
+    for event in cast_reader.iter() {
+        let ability = event.ability;
+        if !abilities.can_cast(ability) {
+            continue;
+        }
+
+        match (ability, event.hex, ability.radius()) {
+            (PlayerAbility::MeteorStrike, Some(hex), Some(radius)) => meteor_writer.send(MeteorStrike { hex, radius }),
+            (PlayerAbility::Freeze, Some(hex), Some(radius)) => freeze_writer.send(FreezeArea { hex, radius }),
+            (PlayerAbility::Repair, _, _) => repair_writer.send(RepairBase),
+            // targeted abilities without a target
+            _ => continue,
+        }
+
+        abilities.mana -= ability.mana_cost();
+        abilities.cooldowns.insert(ability, Timer::new(ability.cooldown(), TimerMode::Once));
+    }
+}
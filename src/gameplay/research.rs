@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::quit_without_file;
+use crate::gameplay::waves::WaveCleared;
+use crate::localization::Localization;
+use crate::state::global::GameState;
+
+/// File (relative to the working directory) containing the tech tree
+const TECH_DEFINITIONS_PATH: &str = "assets/data/research.ron";
+/// Research points awarded for every cleared wave
+const POINTS_PER_WAVE: u32 = 1;
+
+pub struct ResearchPlugin;
+
+impl Plugin for ResearchPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<ResearchTech>()
+            .init_resource::<Research>()
+            .add_startup_system(load_tech_registry.in_base_set(StartupSet::PreStartup))
+            .add_system(earn_research_points.in_set(OnUpdate(GameState::Playing)))
+            // researching happens in the game menu
+            .add_system(research_techs.in_set(OnUpdate(GameState::Paused)))
+        ;
+    }
+}
+
+/// Bonus applied to all towers once researched, bonuses of the same kind add up
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum GlobalModifier {
+    /// share (0..1) of extra damage
+    Damage(f32),
+    /// share (0..1) of extra range
+    Range(f32),
+    /// share (0..1) of extra shots per second
+    FireRate(f32),
+}
+
+impl GlobalModifier {
+    pub fn label(&self, localization: &Localization) -> String {
+        let (key, bonus) = match self {
+            GlobalModifier::Damage(bonus) => ("modifier.damage", bonus),
+            GlobalModifier::Range(bonus) => ("modifier.range", bonus),
+            GlobalModifier::FireRate(bonus) => ("modifier.fire_rate", bonus),
+        };
+        localization.t_with(key, &[("percent", format!("{:.0}", bonus * 100.0))])
+    }
+}
+
+/// What researching a tech gives the player
+#[derive(Deserialize, Clone, Debug)]
+pub enum TechUnlock {
+    /// id of the `TowerDefinition` becoming buildable
+    Tower(String),
+    Modifier(GlobalModifier),
+}
+
+/// Node of the tech tree
+#[derive(Deserialize, Clone, Debug)]
+pub struct TechDefinition {
+    pub id: String,
+    pub name: String,
+    /// research points spent on it
+    pub cost: u32,
+    /// techs which have to be researched first
+    #[serde(default)]
+    pub requires: Vec<String>,
+    pub unlock: TechUnlock,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct TechRegistry {
+    pub techs: Vec<TechDefinition>,
+}
+
+impl TechRegistry {
+    pub fn get(&self, id: &str) -> Option<&TechDefinition> {
+        self.techs.iter().find(|tech| tech.id == id)
+    }
+
+    /// Tech unlocking the tower, towers without one are buildable from the start
+    fn unlocking_tower(&self, tower: &str) -> Option<&TechDefinition> {
+        self.techs.iter().find(|tech| matches!(&tech.unlock, TechUnlock::Tower(id) if id == tower))
+    }
+}
+
+/// Progress through the tech tree during the running game
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Research {
+    /// points not spent yet
+    pub points: u32,
+    /// ids of the researched techs
+    pub researched: HashSet<String>,
+}
+
+impl Research {
+    pub fn is_researched(&self, tech: &str) -> bool {
+        self.researched.contains(tech)
+    }
+
+    /// Whether all requirements are researched, regardless of the points
+    pub fn is_available(&self, tech: &TechDefinition) -> bool {
+        tech.requires.iter().all(|required| self.is_researched(required))
+    }
+
+    pub fn can_research(&self, tech: &TechDefinition) -> bool {
+        !self.is_researched(&tech.id) && self.is_available(tech) && self.points >= tech.cost
+    }
+
+    pub fn is_tower_unlocked(&self, tower: &str, registry: &TechRegistry) -> bool {
+        registry.unlocking_tower(tower).map_or(true, |tech| self.is_researched(&tech.id))
+    }
+
+    fn modifiers<'a>(&'a self, registry: &'a TechRegistry) -> impl Iterator<Item = GlobalModifier> + 'a {
+        registry.techs
+            .iter()
+            .filter(|tech| self.is_researched(&tech.id))
+            .filter_map(|tech| match tech.unlock {
+                TechUnlock::Modifier(modifier) => Some(modifier),
+                TechUnlock::Tower(_) => None,
+            })
+    }
+
+    pub fn damage_factor(&self, registry: &TechRegistry) -> f32 {
+        1.0 + self.modifiers(registry)
+            .map(|modifier| if let GlobalModifier::Damage(bonus) = modifier { bonus } else { 0.0 })
+            .sum::<f32>()
+    }
+
+    pub fn range_factor(&self, registry: &TechRegistry) -> f32 {
+        1.0 + self.modifiers(registry)
+            .map(|modifier| if let GlobalModifier::Range(bonus) = modifier { bonus } else { 0.0 })
+            .sum::<f32>()
+    }
+
+    pub fn fire_rate_factor(&self, registry: &TechRegistry) -> f32 {
+        1.0 + self.modifiers(registry)
+            .map(|modifier| if let GlobalModifier::FireRate(bonus) = modifier { bonus } else { 0.0 })
+            .sum::<f32>()
+    }
+}
+
+/// Request to spend research points on the tech with the given id
+pub struct ResearchTech(pub String);
+
+fn load_tech_registry(mut commands: Commands) {
+    let techs: Vec<TechDefinition> = fs::read_to_string(TECH_DEFINITIONS_PATH)
+        .map_err(|e| e.to_string())
+        .and_then(|content| ron::from_str(&content).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| quit_without_file(TECH_DEFINITIONS_PATH, e));
+
+    commands.insert_resource(TechRegistry { techs });
+}
+
+fn earn_research_points(
+    mut cleared_reader: EventReader<WaveCleared>,
+    mut research: ResMut<Research>,
+) {
+    let cleared = cleared_reader.iter().count() as u32;
+    if cleared > 0 {
+        research.points += cleared * POINTS_PER_WAVE;
+    }
+}
+
+fn research_techs(
+    mut research_reader: EventReader<ResearchTech>,
+    mut research: ResMut<Research>,
+    registry: Res<TechRegistry>,
+) {
+    for event in research_reader.iter() {
+        let Some(tech) = registry.get(&event.0) else {
+            continue;
+        };
+        if !research.can_research(tech) {
+            continue;
+        }
+
+        research.points -= tech.cost;
+        research.researched.insert(tech.id.clone());
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use hexx::Hex;
+use serde::{Deserialize, Serialize};
+
+use crate::{HexLocation, Map};
+use crate::assets::GameAssets;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::boss::{Boss, restore_boss_shield};
+use crate::gameplay::buildings::{BuildingTag, Bullet, LightningArc, MortarShell};
+use crate::gameplay::combat::{CombatRng, CombatRngState};
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::enemy::{Dying, EnemyKind, EnemyProgress, EnemyTag, Health, restore_enemy, Shield, update_enemy_route, WalkingPath};
+use crate::gameplay::history::BuildHistory;
+use crate::gameplay::research::Research;
+use crate::gameplay::towers::{insert_tower, Tower, TowerLevel, TowerRegistry};
+use crate::gameplay::waves::{WavePhase, Waves};
+use crate::gameplay::weather::Weather;
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::confirm_dialog::ConfirmDialog;
+use crate::ui::highlight::HexHighlights;
+use crate::ui::player::SelectedBuilding;
+
+/// Directory (relative to the working directory) the save slots are written to
+const SAVE_DIRECTORY: &str = "saves";
+/// Number of save slots offered in the game menu
+pub const SAVE_SLOTS: usize = 3;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<SaveGame>()
+            .add_event::<LoadGame>()
+            .add_systems(
+                (
+                    on_slot_button_clicked,
+                    save_game.after(on_slot_button_clicked),
+                    load_game.after(on_slot_button_clicked),
+                ).in_set(OnUpdate(GameState::Paused))
+            )
+            // "Continue" in the main menu
+            .add_system(load_game.in_set(OnUpdate(GameState::MainMenu)))
+        ;
+    }
+}
+
+/// Request to write the current game into the given slot
+pub struct SaveGame(pub usize);
+
+/// Request to replace the current game with the one stored in the given slot
+pub struct LoadGame(pub usize);
+
+/// Game menu button saving to or loading from a slot
+#[derive(Component, Clone, Copy)]
+pub enum SlotButton {
+    Save(usize),
+    Load(usize),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedBuilding {
+    /// id of the `TowerDefinition`
+    tower: String,
+    level: usize,
+    hex: [i32; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedEnemy {
+    kind: EnemyKind,
+    health: f32,
+    hex: [i32; 2],
+    /// x and z of the world position
+    position: [f32; 2],
+    waypoints: Vec<[i32; 2]>,
+    /// charges left of the `Shield` of enemies coming with one, missing in older saves
+    #[serde(default)]
+    shield_charges: Option<u32>,
+    /// seconds the shield of a boss phase stays up
+    #[serde(default)]
+    boss_shield: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedWaves {
+    current: usize,
+    phase: WavePhase,
+    queue: Vec<EnemyKind>,
+}
+
+/// Everything written to a save slot
+#[derive(Serialize, Deserialize)]
+struct SaveData {
+    gold: u32,
+    /// missing in saves from before lives existed, those continue with the default lives
+    #[serde(default)]
+    lives: Option<u32>,
+    #[serde(default)]
+    difficulty: Difficulty,
+    #[serde(default)]
+    weather: Weather,
+    /// missing in older saves, those continue with a fresh seed
+    #[serde(default)]
+    rng: Option<CombatRngState>,
+    /// missing in saves from before the tech tree, those start without research
+    #[serde(default)]
+    research: Research,
+    waves: SavedWaves,
+    buildings: Vec<SavedBuilding>,
+    enemies: Vec<SavedEnemy>,
+}
+
+fn slot_path(slot: usize) -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY).join(format!("slot{}.ron", slot))
+}
+
+/// Slot written to most recently, if any
+pub fn latest_slot() -> Option<usize> {
+    (1..=SAVE_SLOTS)
+        .filter_map(|slot| {
+            let modified = fs::metadata(slot_path(slot)).and_then(|metadata| metadata.modified()).ok()?;
+            Some((slot, modified))
+        })
+        .max_by_key(|(_, modified)| *modified)
+        .map(|(slot, _)| slot)
+}
+
+fn to_pair(hex: Hex) -> [i32; 2] {
+    [hex.x, hex.y]
+}
+
+fn from_pair(pair: [i32; 2]) -> Hex {
+    Hex::new(pair[0], pair[1])
+}
+
+fn on_slot_button_clicked(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &SlotButton), Changed<Interaction>>,
+    mut save_writer: EventWriter<SaveGame>,
+    mut load_writer: EventWriter<LoadGame>,
+    localization: Res<Localization>,
+) {
+    for (interaction, button) in &interactions {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *button {
+            // an occupied slot is only written to once the player agrees to lose the game in it
+            SlotButton::Save(slot) if slot_path(slot).exists() => {
+                ConfirmDialog::spawn(
+                    &mut commands,
+                    localization.t_with("dialog.overwrite_save", &[("slot", slot.to_string())]),
+                    SaveGame(slot),
+                );
+            }
+            SlotButton::Save(slot) => save_writer.send(SaveGame(slot)),
+            SlotButton::Load(slot) => load_writer.send(LoadGame(slot)),
+        }
+    }
+}
+
+fn save_game(
+    mut save_reader: EventReader<SaveGame>,
+    resources: Res<PlayerResources>,
+    waves: Res<Waves>,
+    difficulty: Res<Difficulty>,
+    weather: Res<Weather>,
+    rng: Res<CombatRng>,
+    research: Res<Research>,
+    towers: Query<(&Tower, &TowerLevel, &HexLocation)>,
+    enemies: Query<(&EnemyKind, &Health, &HexLocation, &Transform, &WalkingPath, Option<&Shield>, Option<&Boss>), With<EnemyTag>>,
+) {
+    for event in save_reader.iter() {
+        let data = SaveData {
+            gold: resources.gold,
+            lives: Some(resources.lives),
+            difficulty: *difficulty,
+            weather: *weather,
+            rng: Some(rng.state()),
+            research: research.clone(),
+            waves: SavedWaves {
+                current: waves.current,
+                phase: waves.phase,
+                queue: waves.queue().to_vec(),
+            },
+            buildings: towers
+                .iter()
+                .map(|(tower, level, location)| SavedBuilding {
+                    tower: tower.id.clone(),
+                    level: level.0,
+                    hex: to_pair(location.location),
+                })
+                .collect(),
+            enemies: enemies
+                .iter()
+                .map(|(kind, health, location, transform, path, shield, boss)| SavedEnemy {
+                    kind: *kind,
+                    health: health.current,
+                    hex: to_pair(location.location),
+                    position: [transform.translation.x, transform.translation.z],
+                    waypoints: path.waypoints().iter().copied().map(to_pair).collect(),
+                    // a broken shield is gone, it doesn't come back with the load
+                    shield_charges: kind.shield_charges().map(|_| shield.map_or(0, |shield| shield.charges)),
+                    boss_shield: boss.and_then(Boss::shield_left).map(|left| left.as_secs_f32()),
+                })
+                .collect(),
+        };
+
+        let content = match ron::ser::to_string_pretty(&data, ron::ser::PrettyConfig::default()) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Could not serialize the game: {}", e);
+                continue;
+            }
+        };
+
+        let path = slot_path(event.0);
+        let written = fs::create_dir_all(SAVE_DIRECTORY).and_then(|_| fs::write(&path, content));
+        match written {
+            Ok(_) => info!("Saved the game to {}", path.display()),
+            Err(e) => warn!("Could not save the game to {}: {}", path.display(), e),
+        }
+    }
+}
+
+fn load_game(
+    mut commands: Commands,
+    mut load_reader: EventReader<LoadGame>,
+    mut map: ResMut<Map>,
+    mut resources: ResMut<PlayerResources>,
+    mut waves: ResMut<Waves>,
+    mut highlights: ResMut<HexHighlights>,
+    registry: Res<TowerRegistry>,
+    assets: Res<GameAssets>,
+    balance: Res<Balance>,
+    asset_server: Res<AssetServer>,
+    // projectiles still flying would hit the restored enemies
+    existing: Query<Entity, Or<(With<BuildingTag>, With<EnemyTag>, With<Dying>, With<Bullet>, With<MortarShell>, With<LightningArc>)>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // only the last request matters, every load replaces the whole game
+    let Some(slot) = load_reader.iter().last().map(|event| event.0) else {
+        return;
+    };
+
+    let path = slot_path(slot);
+    let data: SaveData = match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| ron::from_str(&content).map_err(|e| e.to_string()))
+    {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Could not load the game from {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    for entity in &existing {
+        commands.entity(entity).despawn_recursive();
+    }
+    map.buildings.clear();
+    commands.remove_resource::<SelectedBuilding>();
+
+    for building in data.buildings {
+        let hex = from_pair(building.hex);
+        let Some(definition) = registry.get(&building.tower) else {
+            warn!("Skipping unknown tower {} in {}", building.tower, path.display());
+            continue;
+        };
+        if !map.is_buildable(hex) {
+            continue;
+        }
+
+        let level = building.level.min(definition.upgrades.len());
+        let entity = commands.spawn(SceneBundle::default()).id();
+        insert_tower(&mut commands, entity, definition, level, hex, map.surface_pos(hex), &asset_server);
+        map.buildings.insert(hex, entity);
+    }
+
+    waves.restore(data.waves.current, data.waves.phase, data.waves.queue, data.difficulty);
+    let health_factor = data.difficulty.health_factor(waves.round());
+    for enemy in data.enemies {
+        let progress = EnemyProgress {
+            location: from_pair(enemy.hex),
+            ground_pos: Vec2::new(enemy.position[0], enemy.position[1]),
+            waypoints: enemy.waypoints.into_iter().map(from_pair).collect(),
+        };
+        let entity = restore_enemy(&mut commands, &map, &assets, &balance, enemy.kind, enemy.health, health_factor, progress);
+        // replaces the full shield the enemy is spawned with, without charges it breaks right away
+        if let Some(charges) = enemy.shield_charges {
+            commands.entity(entity).insert(Shield { charges });
+        }
+        if let Some(left) = enemy.boss_shield.and_then(|seconds| Duration::try_from_secs_f32(seconds).ok()) {
+            restore_boss_shield(&mut commands, &assets, entity, enemy.kind, left);
+        }
+    }
+
+    resources.gold = data.gold;
+    resources.lives = data.lives.unwrap_or_else(|| data.difficulty.lives());
+    commands.insert_resource(data.difficulty);
+    commands.insert_resource(data.weather);
+    commands.insert_resource(data.rng.map_or_else(CombatRng::default, CombatRng::restore));
+    // the changes of the game played before can't be undone in the loaded one
+    commands.insert_resource(BuildHistory::default());
+    commands.insert_resource(data.research);
+
+    // the old route highlight is outdated
+    highlights.clear_all();
+    map.version += 1;
+    update_enemy_route(&mut map);
+
+    info!("Loaded the game from {}", path.display());
+
+    // continuing from the main menu goes straight into the game
+    if state.0 == GameState::MainMenu {
+        next_state.set(GameState::Playing);
+    }
+}
@@ -0,0 +1,264 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope};
+
+use crate::{Map, StartNewGame};
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::enemy::{EnemyKind, SpawnEnemy};
+use crate::gameplay::save::LoadGame;
+use crate::gameplay::waves::{WaveCleared, WavePhase, Waves};
+use crate::state::global::GameState;
+use crate::ui::toasts::Toasts;
+
+/// Operations a single call of a hook may take, stops scripts stuck in a loop
+const MAX_OPERATIONS: u64 = 100_000;
+/// Depth of nested function calls a script may go to
+const MAX_CALL_LEVELS: usize = 32;
+/// Enemies a single call of a hook may spawn
+const MAX_SPAWNS_PER_CALL: usize = 200;
+
+/// Runs the script of the map, needs the `AssetServer` and goes after the `DefaultPlugins`.
+///
+/// Scripts are rhai files defining any of these hooks, waves are counted from 1:
+/// - `on_wave_start(wave)` when the first enemy of a wave is about to spawn
+/// - `on_wave_cleared(wave)` when the last enemy of a wave is gone
+///
+/// Statements outside of the hooks don't run. Hooks can call `spawn_enemy(kind)`, `spawn_enemy(kind, spawn_point)`,
+/// `give_gold(amount)`, `show_message(text)` and `wave_number()`, nothing else of the game or the computer is in reach.
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<ScriptSource>()
+            .init_asset_loader::<ScriptLoader>()
+            .init_resource::<StartedWave>()
+            .add_system(compile_map_script.run_if(resource_exists::<MapScriptFile>()))
+            .add_system(drop_map_script.run_if(resource_removed::<MapScriptFile>()))
+            .add_system(reset_started_wave)
+            .add_systems(
+                (
+                    call_wave_hooks,
+                    apply_script_requests.after(call_wave_hooks),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Text of a `.rhai` file below `assets/`
+#[derive(TypeUuid, Debug)]
+#[uuid = "b7e2c9d4-3f61-4a8e-9c05-6d1e8f2a7b43"]
+pub struct ScriptSource(String);
+
+/// Script of the map being played, missing for maps without one
+#[derive(Resource, Debug)]
+pub struct MapScriptFile(pub Handle<ScriptSource>);
+
+/// Wave `on_wave_start` was called for last, so it is called once per wave
+#[derive(Resource, Default, Debug)]
+struct StartedWave(Option<usize>);
+
+/// Compiled script of the map with the engine running it
+#[derive(Resource)]
+struct MapScript {
+    engine: Engine,
+    ast: AST,
+    context: Arc<Mutex<ScriptContext>>,
+}
+
+/// What the script sees of the game and what it asked for, shared with the functions of the engine
+#[derive(Default)]
+struct ScriptContext {
+    /// number of the wave running or coming up next, counted from 1
+    wave: usize,
+    requests: Vec<ScriptRequest>,
+}
+
+/// Change to the game a script asked for, carried out after the hook returned
+enum ScriptRequest {
+    SpawnEnemy(EnemyKind, usize),
+    GiveGold(u32),
+    ShowMessage(String),
+}
+
+#[derive(Default)]
+struct ScriptLoader;
+
+impl AssetLoader for ScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let source = String::from_utf8(bytes.to_vec())?;
+            load_context.set_default_asset(LoadedAsset::new(ScriptSource(source)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+impl MapScript {
+    fn new() -> Self {
+        let context = Arc::new(Mutex::new(ScriptContext::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        engine.on_print(|text| info!("Map script: {}", text));
+        engine.on_debug(|text, _, position| debug!("Map script at {}: {}", position, text));
+
+        let spawn_context = context.clone();
+        let spawn = move |kind: &str, spawn_point: i64| -> Result<(), Box<EvalAltResult>> {
+            let kind: EnemyKind = ron::from_str(kind).map_err(|_| format!("there is no enemy kind {}", kind))?;
+            let spawn_point = usize::try_from(spawn_point).map_err(|_| "spawn points are counted from 0")?;
+            let mut context = spawn_context.lock().unwrap();
+            let spawns = context.requests.iter().filter(|request| matches!(request, ScriptRequest::SpawnEnemy(..))).count();
+            if spawns >= MAX_SPAWNS_PER_CALL {
+                return Err(format!("a hook can't spawn more than {} enemies", MAX_SPAWNS_PER_CALL).into());
+            }
+            context.requests.push(ScriptRequest::SpawnEnemy(kind, spawn_point));
+            Ok(())
+        };
+        let spawn_first = spawn.clone();
+        engine.register_fn("spawn_enemy", move |kind: &str| spawn_first(kind, 0));
+        engine.register_fn("spawn_enemy", spawn);
+
+        let gold_context = context.clone();
+        engine.register_fn("give_gold", move |amount: i64| -> Result<(), Box<EvalAltResult>> {
+            let amount = u32::try_from(amount).map_err(|_| format!("can't give {} gold", amount))?;
+            gold_context.lock().unwrap().requests.push(ScriptRequest::GiveGold(amount));
+            Ok(())
+        });
+
+        let message_context = context.clone();
+        engine.register_fn("show_message", move |text: &str| {
+            message_context.lock().unwrap().requests.push(ScriptRequest::ShowMessage(text.to_string()));
+        });
+
+        let wave_context = context.clone();
+        engine.register_fn("wave_number", move || wave_context.lock().unwrap().wave as i64);
+
+        MapScript { engine, ast: AST::empty(), context }
+    }
+
+    /// Calls the hook if the script has it, errors of the script are logged and don't stop the game
+    fn call(&self, hook: &str, wave: usize) {
+        if !self.ast.iter_functions().any(|function| function.name == hook && function.params.len() == 1) {
+            return;
+        }
+
+        self.context.lock().unwrap().wave = wave;
+        // only the hooks run, statements outside of them never do
+        let options = CallFnOptions::new().eval_ast(false);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, hook, (wave as i64,));
+        if let Err(e) = result {
+            warn!("The hook {} of the map script failed: {}", hook, e);
+        }
+    }
+}
+
+/// Compiles the script once the file of the map is loaded, and again whenever the file changes on disk
+fn compile_map_script(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<ScriptSource>>,
+    sources: Res<Assets<ScriptSource>>,
+    map_script_file: Res<MapScriptFile>,
+) {
+    let loaded = events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => *handle == map_script_file.0,
+        AssetEvent::Removed { .. } => false,
+    });
+    // starting over on the same map finds the file loaded already
+    if !loaded && !map_script_file.is_changed() {
+        return;
+    }
+    let Some(ScriptSource(source)) = sources.get(&map_script_file.0) else {
+        return;
+    };
+
+    let mut script = MapScript::new();
+    match script.engine.compile(source) {
+        Ok(ast) => {
+            info!("Compiled the map script with {} functions", ast.iter_functions().count());
+            script.ast = ast;
+            commands.insert_resource(script);
+        }
+        Err(e) => {
+            warn!("Could not compile the map script: {}", e);
+            // a broken edit keeps the script compiled before, the one of another map goes
+            if map_script_file.is_changed() {
+                commands.remove_resource::<MapScript>();
+            }
+        }
+    }
+}
+
+fn drop_map_script(mut commands: Commands) {
+    commands.remove_resource::<MapScript>();
+}
+
+/// A new or loaded game starts counting again, the events come in while the game isn't played yet
+fn reset_started_wave(
+    mut new_game_reader: EventReader<StartNewGame>,
+    mut load_reader: EventReader<LoadGame>,
+    mut started: ResMut<StartedWave>,
+) {
+    if new_game_reader.iter().count() + load_reader.iter().count() > 0 {
+        started.0 = None;
+    }
+}
+
+fn call_wave_hooks(
+    script: Option<Res<MapScript>>,
+    waves: Res<Waves>,
+    mut cleared_reader: EventReader<WaveCleared>,
+    mut started: ResMut<StartedWave>,
+) {
+    let Some(script) = script else {
+        cleared_reader.clear();
+        return;
+    };
+
+    if waves.phase == WavePhase::Spawning && started.0 != Some(waves.current) {
+        started.0 = Some(waves.current);
+        script.call("on_wave_start", waves.current + 1);
+    }
+
+    for cleared in cleared_reader.iter() {
+        script.call("on_wave_cleared", cleared.0 + 1);
+    }
+}
+
+/// Carries out what the hooks asked for, spawn points the map doesn't have are skipped
+fn apply_script_requests(
+    script: Option<Res<MapScript>>,
+    map: Res<Map>,
+    mut spawn_writer: EventWriter<SpawnEnemy>,
+    mut resources: ResMut<PlayerResources>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let Some(script) = script else {
+        return;
+    };
+
+    let requests = std::mem::take(&mut script.context.lock().unwrap().requests);
+    for request in requests {
+        match request {
+            ScriptRequest::SpawnEnemy(kind, spawn_point) => match map.spawn_points.get(spawn_point) {
+                Some(hex) => spawn_writer.send(SpawnEnemy(kind, *hex)),
+                None => warn!("The map script spawned an enemy on the spawn point {} the map doesn't have", spawn_point),
+            },
+            ScriptRequest::GiveGold(amount) => resources.earn(amount),
+            ScriptRequest::ShowMessage(text) => toasts.push(text),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::gameplay::enemy::EnemyTag;
+
+/// Side length of the cells of the ground plane enemies are bucketed into
+const CELL_SIZE: f32 = 1.0;
+
+pub struct SpatialIndexPlugin;
+
+impl Plugin for SpatialIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SpatialIndex>()
+            // ready before any gameplay system asks for the enemies around something
+            .add_system(update_spatial_index.in_base_set(CoreSet::PreUpdate))
+        ;
+    }
+}
+
+/// Positions of all enemies at the start of the frame, bucketed by the cell of the ground plane they are in.
+/// Enemies killed during the frame are still listed, callers look them up in their own queries.
+#[derive(Resource, Default, Debug)]
+pub struct SpatialIndex {
+    cells: HashMap<(i32, i32), Vec<(Entity, Vec3)>>,
+}
+
+impl SpatialIndex {
+    fn cell(position: Vec3) -> (i32, i32) {
+        ((position.x / CELL_SIZE).floor() as i32, (position.z / CELL_SIZE).floor() as i32)
+    }
+
+    /// Enemies within `radius` around `center`, measured on the ground so flying enemies count for the spot below them
+    pub fn within(&self, center: Vec3, radius: f32) -> impl Iterator<Item = (Entity, Vec3)> + '_ {
+        let radius = radius.max(0.0);
+        let reach = Vec3::new(radius, 0.0, radius);
+        let (min_x, min_z) = Self::cell(center - reach);
+        let (max_x, max_z) = Self::cell(center + reach);
+
+        (min_x..=max_x)
+            .flat_map(move |x| (min_z..=max_z).map(move |z| (x, z)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, position)| Vec2::new(position.x - center.x, position.z - center.z).length() <= radius)
+    }
+}
+
+fn update_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    enemies: Query<(Entity, &Transform), With<EnemyTag>>,
+) {
+    index.cells.clear();
+    for (e, transform) in &enemies {
+        index.cells.entry(SpatialIndex::cell(transform.translation)).or_default().push((e, transform.translation));
+    }
+}
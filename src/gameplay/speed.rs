@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::Action;
+use crate::state::global::GameState;
+
+/// Selectable simulation speeds, from slow motion to fast forward
+pub const SPEED_STEPS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+
+pub struct GameSpeedPlugin;
+
+impl Plugin for GameSpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameSpeed>()
+            .add_system(change_speed_by_hotkey.in_set(OnUpdate(GameState::Playing)))
+            .add_system(apply_game_speed.run_if(resource_changed::<GameSpeed>()))
+        ;
+    }
+}
+
+/// Multiplier of the gameplay clock, scales walking, shooting and bullets alike
+#[derive(Resource, Debug)]
+pub struct GameSpeed {
+    pub factor: f32,
+}
+
+impl Default for GameSpeed {
+    fn default() -> Self {
+        GameSpeed { factor: 1.0 }
+    }
+}
+
+impl GameSpeed {
+    fn step(&self) -> usize {
+        SPEED_STEPS.iter().position(|s| *s == self.factor).unwrap_or(1)
+    }
+
+    pub fn faster(&mut self) {
+        self.factor = SPEED_STEPS[(self.step() + 1).min(SPEED_STEPS.len() - 1)];
+    }
+
+    pub fn slower(&mut self) {
+        self.factor = SPEED_STEPS[self.step().saturating_sub(1)];
+    }
+}
+
+fn change_speed_by_hotkey(
+    mut speed: ResMut<GameSpeed>,
+    actions: Query<&ActionState<Action>>,
+) {
+    for action_state in &actions {
+        if action_state.just_pressed(Action::SpeedUp) {
+            speed.faster();
+        }
+        if action_state.just_pressed(Action::SpeedDown) {
+            speed.slower();
+        }
+    }
+}
+
+fn apply_game_speed(speed: Res<GameSpeed>, mut time: ResMut<Time>) {
+    time.set_relative_speed(speed.factor);
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::gameplay::buildings::DamageDealt;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::enemy::{EnemyArrivedAtEnd, EnemyDied};
+use crate::gameplay::towers::{SellTower, Tower, TowerBuilt};
+use crate::gameplay::waves::WaveCleared;
+use crate::state::global::GameState;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStats>()
+            .add_systems(
+                (
+                    count_kills,
+                    count_leaks,
+                    count_towers,
+                    count_waves,
+                    sum_tower_damage,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(sync_gold_stats.run_if(resource_changed::<PlayerResources>()))
+        ;
+    }
+}
+
+/// Running totals of the current game, shown in the game menu and after the game
+#[derive(Resource, Default, Debug)]
+pub struct GameStats {
+    pub kills: u32,
+    /// enemies which reached an exit
+    pub leaks: u32,
+    pub gold_earned: u32,
+    pub gold_spent: u32,
+    pub towers_built: u32,
+    pub towers_sold: u32,
+    pub waves_survived: u32,
+    /// damage dealt by all towers of a type, by the id of the `TowerDefinition`
+    pub damage_by_tower: HashMap<String, f32>,
+}
+
+impl GameStats {
+    pub fn total_damage(&self) -> f32 {
+        self.damage_by_tower.values().sum()
+    }
+
+    /// Points for the end screen and the map records, every life kept counts as much as ten kills
+    pub fn score(&self, lives: u32) -> u32 {
+        self.kills * 10 + lives * 100 + self.waves_survived * 50
+    }
+}
+
+fn count_kills(mut died_reader: EventReader<EnemyDied>, mut stats: ResMut<GameStats>) {
+    stats.kills += died_reader.iter().count() as u32;
+}
+
+fn count_leaks(mut arrived_reader: EventReader<EnemyArrivedAtEnd>, mut stats: ResMut<GameStats>) {
+    stats.leaks += arrived_reader.iter().count() as u32;
+}
+
+fn count_towers(
+    mut built_reader: EventReader<TowerBuilt>,
+    mut sell_reader: EventReader<SellTower>,
+    mut stats: ResMut<GameStats>,
+) {
+    stats.towers_built += built_reader.iter().count() as u32;
+    stats.towers_sold += sell_reader.iter().count() as u32;
+}
+
+fn count_waves(mut cleared_reader: EventReader<WaveCleared>, mut stats: ResMut<GameStats>) {
+    stats.waves_survived += cleared_reader.iter().count() as u32;
+}
+
+fn sum_tower_damage(
+    mut damage_reader: EventReader<DamageDealt>,
+    mut stats: ResMut<GameStats>,
+    towers: Query<&Tower>,
+) {
+    for event in damage_reader.iter() {
+        // the tower might have been sold while its bullet was still flying
+        let Ok(tower) = towers.get(event.building) else {
+            continue;
+        };
+        *stats.damage_by_tower.entry(tower.id.clone()).or_default() += event.amount;
+    }
+}
+
+fn sync_gold_stats(resources: Res<PlayerResources>, mut stats: ResMut<GameStats>) {
+    stats.gold_earned = resources.total_earned;
+    stats.gold_spent = resources.total_spent;
+}
@@ -0,0 +1,398 @@
+use std::fs;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use hexx::Hex;
+use serde::Deserialize;
+
+use crate::assets::{GameAssets, quit_without_file};
+use crate::gameplay::abilities::{AbilityState, TowerAbility};
+use crate::gameplay::buildings::{AreaOfEffect, BUILDING_SCALING, BuildingTag, CombatRecord, CurrentTarget, HasAttack, LightningChain, ProjectileKind, TargetingMode, TargetingRange};
+use crate::gameplay::combat::{CriticalHit, DamageType};
+use crate::gameplay::effects::{OnHitEffects, StatusEffect};
+use crate::gameplay::balance::Balance;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::history::{BuildCommand, BuildHistory};
+use crate::gameplay::mods::ModPacks;
+use crate::{GridChanged, HexLocation, Map};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::toasts::{NOT_ENOUGH_GOLD, Toasts};
+
+/// File (relative to the working directory) containing all available tower types
+const TOWER_DEFINITIONS_PATH: &str = "assets/data/towers.ron";
+/// Height of the wall mesh, before the scaling of the buildings
+pub const WALL_HEIGHT: f32 = 2.5;
+
+pub struct TowerRegistryPlugin;
+
+impl Plugin for TowerRegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<UpgradeTower>()
+            .add_event::<SellTower>()
+            .add_event::<TowerBuilt>()
+            .add_startup_system(load_tower_registry.in_base_set(StartupSet::PreStartup))
+            .add_systems(
+                (
+                    upgrade_towers,
+                    sell_towers.after(upgrade_towers),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(spawn_wall_meshes)
+        ;
+    }
+}
+
+/// Stats of a buildable tower type
+#[derive(Deserialize, Clone, Debug)]
+pub struct TowerDefinition {
+    /// unique identifier, used to refer to the tower type from placed buildings
+    pub id: String,
+    pub name: String,
+    /// one line about what the tower is good at, shown in the tooltip of its build button
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub kind: BuildingKind,
+    /// tab of the build menu the tower is listed in
+    #[serde(default)]
+    pub category: BuildCategory,
+    /// asset path of the scene spawned for the tower, walls are built from a mesh of their own
+    #[serde(default)]
+    pub model: String,
+    /// asset path of an image shown on the build button
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub range: f32,
+    /// shots per second
+    #[serde(default)]
+    pub fire_rate: f32,
+    #[serde(default)]
+    pub damage: f32,
+    pub cost: u32,
+    #[serde(default)]
+    pub projectile: ProjectileKind,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    /// share (0..1) of the hits dealing critical damage
+    #[serde(default)]
+    pub crit_chance: f32,
+    /// factor on the damage of critical hits
+    #[serde(default = "default_crit_multiplier")]
+    pub crit_multiplier: f32,
+    /// effects applied to enemies hit by the tower
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    /// makes the projectiles damage every enemy around the impact
+    #[serde(default)]
+    pub area_of_effect: Option<AreaOfEffect>,
+    /// spread of the lightning of `ProjectileKind::ChainLightning` towers
+    #[serde(default)]
+    pub lightning_chain: Option<LightningChain>,
+    /// tiers the tower can be upgraded to, in order
+    #[serde(default)]
+    pub upgrades: Vec<TowerUpgrade>,
+    /// triggered by the player for a short boost
+    #[serde(default)]
+    pub ability: Option<TowerAbility>,
+}
+
+/// What a placed building does on the grid
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BuildingKind {
+    /// shoots at enemies in range
+    #[default]
+    Tower,
+    /// doesn't attack, only blocks the hex so enemies have to walk around it
+    Wall,
+}
+
+/// Tabs of the build menu, in the order they are shown
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BuildCategory {
+    /// towers dealing damage
+    #[default]
+    Attack,
+    /// towers helping the others, by slowing enemies or blocking their way
+    Support,
+    /// buildings producing gold
+    Economy,
+}
+
+impl BuildCategory {
+    pub const ALL: [BuildCategory; 3] = [BuildCategory::Attack, BuildCategory::Support, BuildCategory::Economy];
+
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            BuildCategory::Attack => "build_category.attack",
+            BuildCategory::Support => "build_category.support",
+            BuildCategory::Economy => "build_category.economy",
+        }
+    }
+}
+
+impl TowerDefinition {
+    pub fn is_wall(&self) -> bool {
+        self.kind == BuildingKind::Wall
+    }
+
+    pub fn fire_interval(&self) -> Duration {
+        fire_interval(self.fire_rate)
+    }
+
+    /// Gold spent on a tower of this type upgraded to `level`
+    pub fn invested(&self, level: usize) -> u32 {
+        self.cost + self.upgrades.iter().take(level).map(|upgrade| upgrade.cost).sum::<u32>()
+    }
+
+    pub fn sell_value(&self, level: usize, balance: &Balance) -> u32 {
+        (self.invested(level) as f32 * balance.economy.sell_refund_ratio) as u32
+    }
+
+    /// Range, fire rate, damage and model scale of a tower upgraded to `level`
+    pub fn stats_at(&self, level: usize) -> (f32, f32, f32, f32) {
+        match level.checked_sub(1).and_then(|i| self.upgrades.get(i)) {
+            Some(upgrade) => (upgrade.range, upgrade.fire_rate, upgrade.damage, upgrade.scale),
+            None => (self.range, self.fire_rate, self.damage, 1.0),
+        }
+    }
+
+    /// Model shown for a tower upgraded to `level`
+    pub fn model_at(&self, level: usize) -> &str {
+        self.upgrades
+            .iter()
+            .take(level)
+            .filter_map(|upgrade| upgrade.model.as_deref())
+            .last()
+            .unwrap_or(self.model.as_str())
+    }
+}
+
+/// Stats of a tower after buying one upgrade tier
+#[derive(Deserialize, Clone, Debug)]
+pub struct TowerUpgrade {
+    pub cost: u32,
+    pub range: f32,
+    /// shots per second
+    pub fire_rate: f32,
+    pub damage: f32,
+    /// replaces the model of the previous tier when set
+    #[serde(default)]
+    pub model: Option<String>,
+    /// scale of the model relative to the base tier
+    #[serde(default = "default_upgrade_scale")]
+    pub scale: f32,
+}
+
+fn default_crit_multiplier() -> f32 {
+    2.0
+}
+
+fn default_upgrade_scale() -> f32 {
+    1.0
+}
+
+pub fn fire_interval(fire_rate: f32) -> Duration {
+    Duration::from_secs_f32(1.0 / fire_rate)
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct TowerRegistry {
+    pub towers: Vec<TowerDefinition>,
+    /// towers as loaded, before the balance file changed them
+    pub defaults: Vec<TowerDefinition>,
+}
+
+impl TowerRegistry {
+    pub fn get(&self, id: &str) -> Option<&TowerDefinition> {
+        self.towers.iter().find(|tower| tower.id == id)
+    }
+}
+
+/// Marks a placed building with the id of its `TowerDefinition`
+#[derive(Component, Debug)]
+pub struct Tower {
+    pub id: String,
+}
+
+/// Building made of the wall mesh instead of a tower model, the mesh is added as a child once the marker shows up
+#[derive(Component)]
+pub struct Wall;
+
+/// Number of upgrade tiers bought for a tower, 0 means not upgraded yet
+#[derive(Component, Default, Debug)]
+pub struct TowerLevel(pub usize);
+
+/// Request to buy the next upgrade tier for the tower
+pub struct UpgradeTower(pub Entity);
+
+/// Request to remove the tower from the grid in exchange for part of its cost
+pub struct SellTower(pub Entity);
+
+/// Sent when the player placed a new tower, restored towers of a loaded game don't count
+pub struct TowerBuilt(pub Entity);
+
+/// Turns `entity` into a working tower of the given type upgraded to `level`, standing on `hex`
+pub fn insert_tower(
+    commands: &mut Commands,
+    entity: Entity,
+    definition: &TowerDefinition,
+    level: usize,
+    hex: Hex,
+    translation: Vec3,
+    asset_server: &AssetServer,
+) {
+    if definition.is_wall() {
+        commands.entity(entity)
+            .insert((
+                BuildingTag,
+                Tower { id: definition.id.clone() },
+                Wall,
+                TowerLevel(level),
+                HexLocation { location: hex },
+                Transform::from_translation(translation).with_scale(BUILDING_SCALING),
+            ));
+        return;
+    }
+
+    let (range, fire_rate, damage, scale) = definition.stats_at(level);
+    let scene: Handle<Scene> = asset_server.load(definition.model_at(level));
+
+    commands.entity(entity)
+        .insert((
+            BuildingTag,
+            Tower { id: definition.id.clone() },
+            HasAttack {
+                timer: Timer::new(fire_interval(fire_rate), TimerMode::Repeating),
+                damage,
+            },
+            TargetingRange(range),
+            TargetingMode::default(),
+            CurrentTarget::default(),
+            CombatRecord::default(),
+            definition.projectile,
+            definition.damage_type,
+            OnHitEffects(definition.effects.clone()),
+            TowerLevel(level),
+            HexLocation { location: hex },
+            scene,
+            Transform::from_translation(translation).with_scale(BUILDING_SCALING * scale),
+        ));
+    if let Some(area) = definition.area_of_effect {
+        commands.entity(entity).insert(area);
+    }
+    if let Some(chain) = definition.lightning_chain {
+        commands.entity(entity).insert(chain);
+    }
+    if definition.crit_chance > 0.0 {
+        commands.entity(entity).insert(CriticalHit {
+            chance: definition.crit_chance,
+            multiplier: definition.crit_multiplier,
+        });
+    }
+    if let Some(ability) = definition.ability {
+        commands.entity(entity).insert(AbilityState::new(ability));
+    }
+}
+
+fn load_tower_registry(mut commands: Commands, mods: Res<ModPacks>) {
+    let mut towers: Vec<TowerDefinition> = fs::read_to_string(TOWER_DEFINITIONS_PATH)
+        .map_err(|e| e.to_string())
+        .and_then(|content| ron::from_str(&content).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| quit_without_file(TOWER_DEFINITIONS_PATH, e));
+
+    for tower in mods.towers() {
+        if towers.iter().any(|known| known.id == tower.id) {
+            warn!("Skipping the tower {} of a mod, another tower has the same id", tower.id);
+            continue;
+        }
+        towers.push(tower.clone());
+    }
+
+    commands.insert_resource(TowerRegistry { defaults: towers.clone(), towers });
+}
+
+fn upgrade_towers(
+    mut commands: Commands,
+    mut upgrade_reader: EventReader<UpgradeTower>,
+    registry: Res<TowerRegistry>,
+    mut resources: ResMut<PlayerResources>,
+    asset_server: Res<AssetServer>,
+    mut towers: Query<(&Tower, &mut TowerLevel, &mut HasAttack, &mut TargetingRange, &mut Transform)>,
+    mut toasts: ResMut<Toasts>,
+    localization: Res<Localization>,
+) {
+    for event in upgrade_reader.iter() {
+        let Ok((tower, mut level, mut attack, mut range, mut transform)) = towers.get_mut(event.0) else {
+            continue;
+        };
+
+        let Some(upgrade) = registry.get(&tower.id).and_then(|definition| definition.upgrades.get(level.0)) else {
+            continue;
+        };
+
+        if !resources.try_spend(upgrade.cost) {
+            toasts.push(localization.t(NOT_ENOUGH_GOLD));
+            continue;
+        }
+
+        level.0 += 1;
+        attack.timer.set_duration(fire_interval(upgrade.fire_rate));
+        attack.damage = upgrade.damage;
+        range.0 = upgrade.range;
+        transform.scale = BUILDING_SCALING * upgrade.scale;
+
+        if let Some(model) = &upgrade.model {
+            let scene: Handle<Scene> = asset_server.load(model.as_str());
+            commands.entity(event.0).insert(scene);
+        }
+    }
+}
+
+fn sell_towers(
+    mut commands: Commands,
+    mut sell_reader: EventReader<SellTower>,
+    registry: Res<TowerRegistry>,
+    balance: Res<Balance>,
+    mut resources: ResMut<PlayerResources>,
+    mut map: ResMut<Map>,
+    mut grid_changed: EventWriter<GridChanged>,
+    mut history: ResMut<BuildHistory>,
+    towers: Query<(&Tower, &TowerLevel, &HexLocation)>,
+) {
+    for event in sell_reader.iter() {
+        let Ok((tower, level, location)) = towers.get(event.0) else {
+            continue;
+        };
+
+        if let Some(definition) = registry.get(&tower.id) {
+            resources.earn(definition.sell_value(level.0, &balance));
+        }
+
+        map.buildings.remove(&location.location);
+        commands.entity(event.0).despawn_recursive();
+        grid_changed.send(GridChanged);
+        history.record(BuildCommand::Sold { hex: location.location, tower: tower.id.clone(), level: level.0 });
+    }
+}
+
+fn spawn_wall_meshes(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    walls: Query<Entity, Added<Wall>>,
+) {
+    for wall in &walls {
+        commands.entity(wall).with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: assets.wall_mesh.clone(),
+                material: assets.wall_material.clone(),
+                // resting on the ground of the hex, which is the origin of the building
+                transform: Transform::from_xyz(0.0, WALL_HEIGHT / 2.0, 0.0),
+                ..default()
+            });
+        });
+    }
+}
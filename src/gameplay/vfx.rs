@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::assets::GameAssets;
+use crate::gameplay::enemy::EnemyKind;
+use crate::state::global::GameState;
+
+/// Downwards acceleration of falling particles
+const PARTICLE_GRAVITY: f32 = 3.0;
+
+pub struct VfxPlugin;
+
+impl Plugin for VfxPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<SpawnVfx>()
+            .add_systems(
+                (
+                    spawn_vfx,
+                    animate_particles,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Visual feedback shown for a moment where something happened in combat
+#[derive(Clone, Copy, Debug)]
+pub enum VfxKind {
+    /// a tower fired
+    MuzzleFlash,
+    /// a projectile hit an enemy
+    Impact,
+    /// an enemy of the given kind died, the debris has its color
+    Death(EnemyKind),
+}
+
+/// How the particles of a `VfxKind` look and move
+struct VfxStyle {
+    count: usize,
+    /// initial speed of the particles in world units per second
+    speed: f32,
+    lifetime: Duration,
+    /// scale of the particle mesh at the start, shrinks to zero over the lifetime
+    size: f32,
+    falling: bool,
+}
+
+impl VfxKind {
+    fn style(&self) -> VfxStyle {
+        match self {
+            VfxKind::MuzzleFlash => VfxStyle {
+                count: 1,
+                speed: 0.0,
+                lifetime: Duration::from_millis(80),
+                size: 2.5,
+                falling: false,
+            },
+            VfxKind::Impact => VfxStyle {
+                count: 6,
+                speed: 1.0,
+                lifetime: Duration::from_millis(250),
+                size: 1.0,
+                falling: false,
+            },
+            VfxKind::Death(_) => VfxStyle {
+                count: 14,
+                speed: 1.5,
+                lifetime: Duration::from_millis(600),
+                size: 1.5,
+                falling: true,
+            },
+        }
+    }
+
+    fn material(&self, assets: &GameAssets) -> Handle<StandardMaterial> {
+        match self {
+            VfxKind::MuzzleFlash => assets.muzzle_flash_material.clone(),
+            VfxKind::Impact => assets.impact_material.clone(),
+            VfxKind::Death(kind) => assets.enemy_material(*kind),
+        }
+    }
+}
+
+/// Request to show the given effect at a position in world space
+pub struct SpawnVfx {
+    pub kind: VfxKind,
+    pub position: Vec3,
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec3,
+    size: f32,
+    falling: bool,
+    lifetime: Timer,
+}
+
+fn spawn_vfx(
+    mut commands: Commands,
+    mut vfx_reader: EventReader<SpawnVfx>,
+    assets: Res<GameAssets>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in vfx_reader.iter() {
+        let style = event.kind.style();
+        let material = event.kind.material(&assets);
+
+        for _ in 0..style.count {
+            // scattered upwards, debris shouldn't vanish in the ground right away
+            let direction = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(0.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            ).normalize_or_zero();
+
+            commands.spawn((
+                Particle {
+                    velocity: direction * style.speed * rng.gen_range(0.5..1.0),
+                    size: style.size,
+                    falling: style.falling,
+                    lifetime: Timer::new(style.lifetime, TimerMode::Once),
+                },
+                PbrBundle {
+                    mesh: assets.particle_mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(event.position)
+                        .with_scale(Vec3::splat(style.size)),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+fn animate_particles(
+    mut commands: Commands,
+    mut particles: Query<(&mut Particle, &mut Transform, Entity)>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    for (mut particle, mut transform, e) in &mut particles {
+        particle.lifetime.tick(time.delta());
+        if particle.lifetime.finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+
+        if particle.falling {
+            particle.velocity.y -= PARTICLE_GRAVITY * delta;
+        }
+        transform.translation += particle.velocity * delta;
+        transform.scale = Vec3::splat(particle.size * particle.lifetime.percent_left());
+    }
+}
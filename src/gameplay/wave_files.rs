@@ -0,0 +1,91 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use crate::gameplay::waves::{WaveDefinition, Waves};
+
+/// Loads the waves maps keep in files of their own, needs the `AssetServer` and goes after the `DefaultPlugins`
+pub struct WaveFilePlugin;
+
+impl Plugin for WaveFilePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<WaveFile>()
+            .init_asset_loader::<WaveFileLoader>()
+            .add_system(
+                apply_wave_file
+                    .run_if(resource_exists::<MapWaveFile>().and_then(resource_exists::<Waves>()))
+            )
+        ;
+    }
+}
+
+/// Waves of a map as stored in a `.waves.ron` or `.waves.json` file below `assets/`
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "4a490052-5181-4040-8b29-2dc3730f4176"]
+pub struct WaveFile {
+    pub waves: Vec<WaveDefinition>,
+}
+
+/// Wave file of the map being played, missing for maps with their waves inline
+#[derive(Resource, Debug)]
+pub struct MapWaveFile(pub Handle<WaveFile>);
+
+#[derive(Default)]
+struct WaveFileLoader;
+
+impl AssetLoader for WaveFileLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let is_json = load_context.path().extension().map_or(false, |extension| extension == "json");
+            let file: WaveFile = if is_json {
+                serde_json::from_slice(bytes)?
+            } else {
+                ron::de::from_bytes(bytes)?
+            };
+            // a broken edit keeps the waves loaded before
+            if file.waves.is_empty() {
+                return Err(bevy::asset::Error::msg("a wave file needs at least one wave"));
+            }
+            // the spacing becomes the duration of a repeating timer, which has to be longer than zero
+            let mut spacings = file.waves.iter().flat_map(|wave| &wave.groups).filter_map(|group| group.spacing);
+            if let Some(spacing) = spacings.find(|spacing| !spacing.is_finite() || *spacing <= 0.0) {
+                return Err(bevy::asset::Error::msg(format!("the spacing of a group has to be above zero, it is {}", spacing)));
+            }
+            load_context.set_default_asset(LoadedAsset::new(file));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron", "waves.json"]
+    }
+}
+
+/// Fills in the waves once the file of the map is loaded, and again whenever the file changes on disk
+fn apply_wave_file(
+    mut events: EventReader<AssetEvent<WaveFile>>,
+    files: Res<Assets<WaveFile>>,
+    map_wave_file: Res<MapWaveFile>,
+    mut waves: ResMut<Waves>,
+) {
+    let loaded = events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => *handle == map_wave_file.0,
+        AssetEvent::Removed { .. } => false,
+    });
+    // starting over on the same map finds the file loaded already
+    if !loaded && !map_wave_file.is_changed() {
+        return;
+    }
+
+    if let Some(file) = files.get(&map_wave_file.0) {
+        info!("Using the {} waves of the wave file of the map", file.waves.len());
+        waves.set_definitions(file.waves.clone());
+    }
+}
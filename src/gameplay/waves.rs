@@ -0,0 +1,313 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Map;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::balance::{Balance, TimerBalance};
+use crate::gameplay::combat::CombatRng;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::enemy::{EnemyKind, EnemyTag, SpawnEnemy};
+use crate::gameplay::weather::Weather;
+use crate::state::global::GameState;
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<StartWaveEarly>()
+            .add_event::<WaveCleared>()
+            .add_systems(
+                (
+                    start_wave_early,
+                    run_waves.after(start_wave_early),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// A number of enemies of the same kind
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveGroup {
+    pub kind: EnemyKind,
+    pub count: u32,
+    /// seconds between two enemies of the group
+    #[serde(default)]
+    pub spacing: Option<f32>,
+    /// index of the spawn point all enemies of the group enter through, instead of the `spawn_weights` of the wave
+    #[serde(default)]
+    pub spawn_point: Option<usize>,
+}
+
+impl WaveGroup {
+    /// Number of enemies after applying a factor of the difficulty, groups never vanish completely
+    pub fn scaled_count(&self, factor: f32) -> u32 {
+        ((self.count as f32 * factor).round() as u32).max(1)
+    }
+
+    fn spacing(&self, spawn_interval: Duration) -> Duration {
+        self.spacing.map_or(spawn_interval, Duration::from_secs_f32)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveDefinition {
+    /// spawned one after another
+    pub groups: Vec<WaveGroup>,
+    /// share of the enemies entering through each spawn point of the map,
+    /// the spawn points take turns when left out
+    #[serde(default)]
+    pub spawn_weights: Vec<u32>,
+    /// weather during the wave on maps with `WeatherMode::PerWave`
+    #[serde(default)]
+    pub weather: Option<Weather>,
+}
+
+/// Waves of maps whose wave file can't be loaded, so the game stays playable
+pub fn fallback_waves() -> Vec<WaveDefinition> {
+    let group = |kind, count| WaveGroup { kind, count, spacing: None, spawn_point: None };
+    [
+        vec![group(EnemyKind::Normal, 6)],
+        vec![group(EnemyKind::Normal, 8), group(EnemyKind::Fast, 4)],
+        vec![group(EnemyKind::Fast, 6), group(EnemyKind::Normal, 6), group(EnemyKind::Armored, 3)],
+        vec![group(EnemyKind::Flying, 6), group(EnemyKind::Fast, 6), group(EnemyKind::Armored, 4)],
+        vec![group(EnemyKind::Normal, 8), group(EnemyKind::Armored, 6)],
+    ]
+        .into_iter()
+        .map(|groups| WaveDefinition { groups, spawn_weights: vec![], weather: None })
+        .collect()
+}
+
+impl WaveDefinition {
+    /// Index of the spawn point the next enemy enters through, `spawned` enemies of the wave came before
+    fn choose_spawn(&self, spawned: usize, spawn_count: usize, rng: &mut CombatRng) -> usize {
+        let weights = &self.spawn_weights[..self.spawn_weights.len().min(spawn_count)];
+        rng.choose_weighted(weights).unwrap_or(spawned % spawn_count)
+    }
+
+    /// Group of the enemy at `position` in the spawn order, the boss of a boss wave comes after all groups
+    fn group_at(&self, position: usize, count_factor: f32) -> Option<&WaveGroup> {
+        let mut end = 0;
+        self.groups.iter().find(|group| {
+            end += group.scaled_count(count_factor) as usize;
+            position < end
+        })
+    }
+
+    fn spawn_queue(&self, count_factor: f32) -> Vec<EnemyKind> {
+        // reversed so enemies can be popped off the end in order
+        self.groups
+            .iter()
+            .rev()
+            .flat_map(|group| std::iter::repeat(group.kind).take(group.scaled_count(count_factor) as usize))
+            .collect()
+    }
+}
+
+/// Request to end the wave break right away, the player gets gold for the skipped time
+pub struct StartWaveEarly;
+
+/// Sent when the last enemy of the wave with the given index is gone
+pub struct WaveCleared(pub usize);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WavePhase {
+    /// counting down to the next wave
+    Break,
+    Spawning,
+    /// all enemies spawned, waiting for them to be killed or to reach the end
+    Fighting,
+    /// no waves left
+    Finished,
+}
+
+#[derive(Resource, Debug)]
+pub struct Waves {
+    pub definitions: Vec<WaveDefinition>,
+    /// index of the wave currently running or coming up next
+    pub current: usize,
+    pub phase: WavePhase,
+    pub timer: Timer,
+    queue: Vec<EnemyKind>,
+    /// enemies of the running wave spawned so far
+    spawned: usize,
+    /// every wave with a number divisible by this ends with a boss
+    boss_interval: Option<u32>,
+    /// time between two enemies of groups without a spacing of their own
+    spawn_interval: Duration,
+    /// time between clearing a wave and the next one starting
+    wave_break: Duration,
+}
+
+impl Waves {
+    pub fn new(definitions: Vec<WaveDefinition>, boss_interval: Option<u32>, timers: &TimerBalance) -> Self {
+        Waves {
+            definitions,
+            current: 0,
+            phase: WavePhase::Break,
+            timer: Timer::new(timers.wave_break(), TimerMode::Once),
+            queue: vec![],
+            spawned: 0,
+            boss_interval: boss_interval.filter(|interval| *interval > 0),
+            spawn_interval: timers.spawn_interval(),
+            wave_break: timers.wave_break(),
+        }
+    }
+
+    /// Enemies of the running wave which are still to be spawned, the last one comes first
+    pub fn queue(&self) -> &[EnemyKind] {
+        &self.queue
+    }
+
+    /// Whether there are waves to play, waves kept in a file of their own show up once it is loaded
+    pub fn is_loaded(&self) -> bool {
+        !self.definitions.is_empty()
+    }
+
+    /// Takes over changed timings, a running break gets the new length right away
+    pub fn set_timing(&mut self, timers: &TimerBalance) {
+        self.spawn_interval = timers.spawn_interval();
+        self.wave_break = timers.wave_break();
+        if self.phase == WavePhase::Break {
+            self.timer.set_duration(self.wave_break);
+        }
+    }
+
+    /// Swaps in changed waves, the enemies of a running wave still come as queued
+    pub fn set_definitions(&mut self, definitions: Vec<WaveDefinition>) {
+        self.definitions = definitions;
+    }
+
+    /// Definition of the wave running or coming up next, endless games start over after the last one,
+    /// `None` while the waves of the map aren't loaded yet
+    pub fn definition(&self) -> Option<&WaveDefinition> {
+        self.definitions.get(self.current % self.definitions.len().max(1))
+    }
+
+    /// Whether the wave running or coming up next ends with a boss
+    pub fn is_boss_wave(&self) -> bool {
+        self.boss_interval.map_or(false, |interval| (self.current + 1) % interval as usize == 0)
+    }
+
+    /// Number of times all waves of the map have been played through
+    pub fn round(&self) -> usize {
+        self.current / self.definitions.len().max(1)
+    }
+
+    /// Continues at a saved point, timers start over
+    pub fn restore(&mut self, current: usize, phase: WavePhase, queue: Vec<EnemyKind>, difficulty: Difficulty) {
+        self.current = if difficulty.is_endless() || !self.is_loaded() { current } else { current.min(self.definitions.len()) };
+        self.phase = phase;
+        self.queue = queue;
+        self.spawned = 0;
+        self.timer = match phase {
+            WavePhase::Spawning => Timer::new(self.spawn_interval, TimerMode::Repeating),
+            _ => Timer::new(self.wave_break, TimerMode::Once),
+        };
+    }
+
+    /// Time until the enemy popped off the queue next
+    fn next_spacing(&self, difficulty: Difficulty) -> Duration {
+        self.next_group(difficulty).map_or(self.spawn_interval, |group| group.spacing(self.spawn_interval))
+    }
+
+    /// Group of the enemy popped off the queue next, none for the boss
+    fn next_group(&self, difficulty: Difficulty) -> Option<&WaveGroup> {
+        let count_factor = difficulty.count_factor(self.round());
+        let definition = self.definition()?;
+        let total = definition.groups.iter().map(|group| group.scaled_count(count_factor) as usize).sum::<usize>()
+            + self.is_boss_wave() as usize;
+        definition.group_at(total.saturating_sub(self.queue.len()), count_factor)
+    }
+
+    fn start_wave(&mut self, difficulty: Difficulty) {
+        let Some(definition) = self.definition() else {
+            return;
+        };
+        self.queue = definition.spawn_queue(difficulty.count_factor(self.round()));
+        if self.is_boss_wave() {
+            // popped last
+            self.queue.insert(0, EnemyKind::Boss);
+        }
+        self.spawned = 0;
+        self.phase = WavePhase::Spawning;
+        self.timer = Timer::new(self.next_spacing(difficulty), TimerMode::Repeating);
+    }
+}
+
+fn start_wave_early(
+    mut start_reader: EventReader<StartWaveEarly>,
+    mut waves: ResMut<Waves>,
+    mut resources: ResMut<PlayerResources>,
+    difficulty: Res<Difficulty>,
+    balance: Res<Balance>,
+) {
+    // several requests in one frame still start a single wave
+    if start_reader.iter().count() == 0 || waves.phase != WavePhase::Break {
+        return;
+    }
+
+    resources.earn(balance.early_start_bonus(waves.timer.remaining()));
+    waves.start_wave(*difficulty);
+}
+
+fn run_waves(
+    mut waves: ResMut<Waves>,
+    mut spawn_writer: EventWriter<SpawnEnemy>,
+    mut cleared_writer: EventWriter<WaveCleared>,
+    mut next_state: ResMut<NextState<GameState>>,
+    enemies: Query<(), With<EnemyTag>>,
+    map: Res<Map>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<CombatRng>,
+    time: Res<Time>,
+) {
+    waves.timer.tick(time.delta());
+
+    match waves.phase {
+        WavePhase::Break => {
+            if waves.timer.finished() {
+                waves.start_wave(*difficulty);
+            }
+        }
+        WavePhase::Spawning => {
+            if waves.timer.just_finished() {
+                // switching one interval after the last spawn gives the enemy time to show up in queries
+                let spawn_point = waves
+                    .next_group(*difficulty)
+                    .and_then(|group| group.spawn_point)
+                    .filter(|index| *index < map.spawn_points.len());
+                match waves.queue.pop() {
+                    Some(kind) => {
+                        let spawn = spawn_point
+                            .unwrap_or_else(|| waves.definition().map_or(0, |definition| definition.choose_spawn(waves.spawned, map.spawn_points.len(), &mut rng)));
+                        waves.spawned += 1;
+                        spawn_writer.send(SpawnEnemy(kind, map.spawn_points[spawn]));
+                        // the next enemy follows at the spacing of its own group
+                        let spacing = waves.next_spacing(*difficulty);
+                        waves.timer.set_duration(spacing);
+                    }
+                    None => waves.phase = WavePhase::Fighting,
+                }
+            }
+        }
+        WavePhase::Fighting => {
+            if enemies.is_empty() {
+                cleared_writer.send(WaveCleared(waves.current));
+                waves.current += 1;
+
+                if waves.current >= waves.definitions.len() && !difficulty.is_endless() {
+                    waves.phase = WavePhase::Finished;
+                    next_state.set(GameState::Victory);
+                } else {
+                    waves.phase = WavePhase::Break;
+                    waves.timer = Timer::new(waves.wave_break, TimerMode::Once);
+                }
+            }
+        }
+        WavePhase::Finished => {}
+    }
+}
@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use bevy::pbr::{FogFalloff, FogSettings};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::GameAssets;
+use crate::camera::{CameraController, PlayerCamera};
+use crate::gameplay::balance::TimerBalance;
+use crate::gameplay::combat::CombatRng;
+use crate::gameplay::waves::Waves;
+use crate::state::global::GameState;
+
+/// Half the edge length of the square around the camera focus rain falls in
+const RAIN_AREA: f32 = 10.0;
+/// Height above the ground rain drops start at
+const RAIN_HEIGHT: f32 = 8.0;
+/// Falling speed of rain drops in world units per second
+const RAIN_SPEED: f32 = 12.0;
+/// Illuminance of the sun in clear weather
+const SUN_ILLUMINANCE: f32 = 100000.0;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Weather>()
+            .init_resource::<WeatherForecast>()
+            .add_system(apply_weather_lighting.run_if(resource_changed::<Weather>()))
+            .add_systems(
+                (
+                    advance_weather,
+                    spawn_rain_drops,
+                    animate_rain_drops,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Weather over the map, it changes the light and how enemies and towers fare
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Weather {
+    #[default]
+    Clear,
+    /// slows the enemies down
+    Rain,
+    /// shortens the range of the towers
+    Fog,
+    /// heavy rain and poor sight, slows the enemies and shortens the range of the towers
+    Storm,
+}
+
+impl Weather {
+    pub const ALL: [Weather; 4] = [Weather::Clear, Weather::Rain, Weather::Fog, Weather::Storm];
+
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Weather::Clear => "weather.clear",
+            Weather::Rain => "weather.rain",
+            Weather::Fog => "weather.fog",
+            Weather::Storm => "weather.storm",
+        }
+    }
+
+    /// Factor on the walking speed of the enemies
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            Weather::Clear | Weather::Fog => 1.0,
+            Weather::Rain => 0.85,
+            Weather::Storm => 0.75,
+        }
+    }
+
+    /// Factor on the targeting range of the towers
+    pub fn range_factor(&self) -> f32 {
+        match self {
+            Weather::Clear | Weather::Rain => 1.0,
+            Weather::Fog => 0.7,
+            Weather::Storm => 0.85,
+        }
+    }
+
+    /// Rain drops falling per second
+    fn rain_rate(&self) -> f32 {
+        match self {
+            Weather::Clear | Weather::Fog => 0.0,
+            Weather::Rain => 80.0,
+            Weather::Storm => 200.0,
+        }
+    }
+
+    /// Share of the sunlight reaching the ground
+    fn sunlight(&self) -> f32 {
+        match self {
+            Weather::Clear => 1.0,
+            Weather::Rain => 0.6,
+            Weather::Fog => 0.7,
+            Weather::Storm => 0.35,
+        }
+    }
+
+    fn ambient_light(&self) -> AmbientLight {
+        match self {
+            Weather::Clear => AmbientLight::default(),
+            Weather::Rain => AmbientLight { color: Color::rgb(0.7, 0.75, 0.85), brightness: 0.05 },
+            Weather::Fog => AmbientLight { color: Color::rgb(0.85, 0.85, 0.9), brightness: 0.12 },
+            Weather::Storm => AmbientLight { color: Color::rgb(0.5, 0.55, 0.7), brightness: 0.03 },
+        }
+    }
+
+    /// Distance fog of the camera, `None` for a clear view
+    fn fog(&self) -> Option<FogSettings> {
+        let (color, start, end) = match self {
+            Weather::Clear | Weather::Rain => return None,
+            Weather::Fog => (Color::rgb(0.75, 0.77, 0.8), 6.0, 20.0),
+            Weather::Storm => (Color::rgb(0.3, 0.32, 0.38), 12.0, 35.0),
+        };
+        Some(FogSettings {
+            color,
+            falloff: FogFalloff::Linear { start, end },
+            ..default()
+        })
+    }
+}
+
+/// How the weather of a map changes over the game
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeatherMode {
+    /// always clear
+    #[default]
+    Clear,
+    /// changes to a random weather every now and then
+    Random,
+    /// set by the `weather` of every wave, clear for waves without one
+    PerWave,
+}
+
+/// Decides when the weather changes next
+#[derive(Resource, Debug)]
+pub struct WeatherForecast {
+    mode: WeatherMode,
+    timer: Timer,
+    /// wave the weather was last set for in `WeatherMode::PerWave`
+    wave: Option<usize>,
+}
+
+impl WeatherForecast {
+    /// Random weather changes every `duration`
+    pub fn new(mode: WeatherMode, duration: Duration) -> Self {
+        WeatherForecast {
+            mode,
+            timer: Timer::new(duration, TimerMode::Repeating),
+            wave: None,
+        }
+    }
+
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.timer.set_duration(duration);
+    }
+}
+
+impl Default for WeatherForecast {
+    fn default() -> Self {
+        WeatherForecast::new(WeatherMode::default(), TimerBalance::default().weather_duration())
+    }
+}
+
+#[derive(Component)]
+struct RainDrop;
+
+fn advance_weather(
+    mut forecast: ResMut<WeatherForecast>,
+    mut weather: ResMut<Weather>,
+    waves: Res<Waves>,
+    mut rng: ResMut<CombatRng>,
+    time: Res<Time>,
+) {
+    let next = match forecast.mode {
+        WeatherMode::Clear => Weather::Clear,
+        WeatherMode::Random => {
+            forecast.timer.tick(time.delta());
+            if !forecast.timer.just_finished() {
+                return;
+            }
+            Weather::ALL[rng.index(Weather::ALL.len())]
+        }
+        WeatherMode::PerWave => {
+            // changes as soon as the wave is up next, so the player can prepare during the break
+            if forecast.wave == Some(waves.current) {
+                return;
+            }
+            forecast.wave = Some(waves.current);
+            waves.definition().and_then(|definition| definition.weather).unwrap_or_default()
+        }
+    };
+
+    if *weather != next {
+        *weather = next;
+    }
+}
+
+fn apply_weather_lighting(
+    mut commands: Commands,
+    weather: Res<Weather>,
+    mut ambient: ResMut<AmbientLight>,
+    mut suns: Query<&mut DirectionalLight>,
+    cameras: Query<Entity, With<PlayerCamera>>,
+) {
+    *ambient = weather.ambient_light();
+    for mut sun in &mut suns {
+        sun.illuminance = SUN_ILLUMINANCE * weather.sunlight();
+    }
+
+    for camera in &cameras {
+        match weather.fog() {
+            Some(fog) => commands.entity(camera).insert(fog),
+            None => commands.entity(camera).remove::<FogSettings>(),
+        };
+    }
+}
+
+fn spawn_rain_drops(
+    mut commands: Commands,
+    weather: Res<Weather>,
+    assets: Res<GameAssets>,
+    cameras: Query<&CameraController, With<PlayerCamera>>,
+    time: Res<Time>,
+    // fractions of drops left over from earlier frames
+    mut pending: Local<f32>,
+) {
+    let Ok(controller) = cameras.get_single() else {
+        return;
+    };
+
+    *pending += weather.rain_rate() * time.delta_seconds();
+    // the drops only decorate, drawing them from the `CombatRng` would tie the game to the frame rate
+    let mut rng = rand::thread_rng();
+    while *pending >= 1.0 {
+        *pending -= 1.0;
+
+        let offset = Vec3::new(
+            rng.gen_range(-RAIN_AREA..RAIN_AREA),
+            rng.gen_range(0.0..RAIN_HEIGHT),
+            rng.gen_range(-RAIN_AREA..RAIN_AREA),
+        );
+        commands.spawn((
+            RainDrop,
+            PbrBundle {
+                mesh: assets.particle_mesh.clone(),
+                material: assets.rain_material.clone(),
+                transform: Transform::from_translation(controller.focus * Vec3::new(1.0, 0.0, 1.0) + offset)
+                    .with_scale(Vec3::new(0.6, 8.0, 0.6)),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn animate_rain_drops(
+    mut commands: Commands,
+    mut drops: Query<(&mut Transform, Entity), With<RainDrop>>,
+    time: Res<Time>,
+) {
+    for (mut transform, e) in &mut drops {
+        transform.translation.y -= RAIN_SPEED * time.delta_seconds();
+        if transform.translation.y < 0.0 {
+            commands.entity(e).despawn();
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+use bevy::app::{AppExit, PluginGroupBuilder, ScheduleRunnerPlugin};
+use bevy::prelude::*;
+use bevy::render::RenderPlugin;
+use bevy::render::settings::WgpuSettings;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use serde::Deserialize;
+
+use crate::{GridChanged, Map};
+use crate::gameplay::buildings::CombatRecord;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::enemy::route_stays_open;
+use crate::gameplay::history::{BuildCommand, BuildHistory};
+use crate::gameplay::stats::GameStats;
+use crate::gameplay::towers::{insert_tower, SellTower, Tower, TowerBuilt, TowerLevel, TowerRegistry, UpgradeTower};
+use crate::gameplay::waves::{WaveCleared, Waves};
+use crate::map::format::to_hex;
+use crate::profile::PlayerProfile;
+use crate::state::global::GameState;
+
+/// Waves simulated when `--waves` is left out
+pub const DEFAULT_WAVES: usize = 10;
+/// Game time passing with every frame, independent of how fast the computer runs them
+const SIMULATION_STEP: Duration = Duration::from_millis(1000 / 60);
+/// Name of the profile the simulation plays with, it is never stored
+const HEADLESS_PROFILE: &str = "headless";
+
+/// Options of a run without window, see `LaunchOptions`
+#[derive(Resource, Clone, Debug)]
+pub struct HeadlessOptions {
+    /// waves to simulate before printing the outcome
+    pub waves: usize,
+    /// file (relative to the working directory) listing the towers to build, nothing is built without one
+    pub build_order: Option<String>,
+}
+
+/// Default plugins without window, rendering and event loop, frames run back to back
+pub fn without_window(plugins: PluginGroupBuilder) -> PluginGroupBuilder {
+    plugins
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        })
+        .set(RenderPlugin {
+            wgpu_settings: WgpuSettings {
+                backends: None,
+                ..default()
+            },
+        })
+        .disable::<WinitPlugin>()
+        .add(ScheduleRunnerPlugin::default())
+}
+
+/// Plays the game started by `LaunchPlugin` on its own: builds the towers of the build order,
+/// and prints the outcome once the given number of waves is over or the game ended.
+/// Goes with `without_window`, the profile of the player is left untouched.
+pub struct HeadlessPlugin(pub HeadlessOptions);
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        let build_order = match &self.0.build_order {
+            Some(path) => BuildOrder::load(path),
+            None => BuildOrder::default(),
+        };
+
+        app
+            .insert_resource(self.0.clone())
+            .insert_resource(build_order)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(SIMULATION_STEP))
+            .init_resource::<SimulationLog>()
+            .add_startup_system(use_headless_profile)
+            .add_systems(
+                (
+                    follow_build_order,
+                    record_towers,
+                    record_waves,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(finish_simulation.in_schedule(OnEnter(GameState::GameOver)))
+            .add_system(finish_simulation.in_schedule(OnEnter(GameState::Victory)))
+        ;
+    }
+}
+
+/// Step of a build order, hexes are axial coordinates like in the map files
+#[derive(Deserialize, Clone, Debug)]
+enum BuildStep {
+    Place { tower: String, hex: (i32, i32) },
+    Upgrade { hex: (i32, i32) },
+    Sell { hex: (i32, i32) },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct BuildOrderEntry {
+    /// wave (counted from 1) the step waits for, it is taken during the break before it
+    wave: usize,
+    step: BuildStep,
+}
+
+/// Steps still to take, in order, every step waits for its wave and for the gold it costs
+#[derive(Resource, Default, Debug)]
+struct BuildOrder {
+    steps: Vec<BuildOrderEntry>,
+    /// index of the step taken next
+    next: usize,
+}
+
+impl BuildOrder {
+    fn load(path: &str) -> Self {
+        let steps = match fs::read_to_string(path).map_err(|e| e.to_string())
+            .and_then(|content| ron::from_str(&content).map_err(|e| e.to_string()))
+        {
+            Ok(steps) => steps,
+            Err(e) => {
+                warn!("Could not load the build order {}, building nothing: {}", path, e);
+                Vec::new()
+            }
+        };
+        BuildOrder { steps, next: 0 }
+    }
+}
+
+/// State of the game when a wave was cleared
+#[derive(Debug)]
+struct WaveRecord {
+    wave: usize,
+    gold: u32,
+    gold_earned: u32,
+    lives: u32,
+    kills: u32,
+    leaks: u32,
+}
+
+#[derive(Resource, Default, Debug)]
+struct SimulationLog {
+    waves: Vec<WaveRecord>,
+    /// game time the towers were built at, for their damage per second
+    built_at: HashMap<Entity, f32>,
+    /// the last wave can end the game in the same frame, the outcome is printed once
+    reported: bool,
+}
+
+fn use_headless_profile(mut commands: Commands) {
+    // a fresh profile keeps runs comparable, and the tutorial out of the way
+    commands.insert_resource(PlayerProfile { tutorial_done: true, ..PlayerProfile::new(HEADLESS_PROFILE) });
+}
+
+/// Takes the steps whose wave has come, stops at the first one the player can't afford yet
+fn follow_build_order(
+    mut commands: Commands,
+    mut build_order: ResMut<BuildOrder>,
+    waves: Res<Waves>,
+    registry: Res<TowerRegistry>,
+    mut map: ResMut<Map>,
+    mut resources: ResMut<PlayerResources>,
+    mut grid_changed: EventWriter<GridChanged>,
+    mut built_writer: EventWriter<TowerBuilt>,
+    mut upgrade_writer: EventWriter<UpgradeTower>,
+    mut sell_writer: EventWriter<SellTower>,
+    mut history: ResMut<BuildHistory>,
+    asset_server: Res<AssetServer>,
+    towers: Query<(&Tower, &TowerLevel)>,
+) {
+    while let Some(entry) = build_order.steps.get(build_order.next).cloned() {
+        if entry.wave > waves.current + 1 {
+            return;
+        }
+
+        match entry.step {
+            BuildStep::Place { tower, hex } => {
+                let hex = to_hex(hex);
+                let Some(definition) = registry.get(&tower) else {
+                    warn!("Skipping the build step of the unknown tower {}", tower);
+                    build_order.next += 1;
+                    continue;
+                };
+                if !map.is_buildable(hex) || !route_stays_open(&map, hex) {
+                    warn!("Skipping the {} at {:?}, the hex can't be built on", tower, hex);
+                    build_order.next += 1;
+                    continue;
+                }
+                if !resources.try_spend(definition.cost) {
+                    return;
+                }
+
+                let entity = commands.spawn(SceneBundle::default()).id();
+                insert_tower(&mut commands, entity, definition, 0, hex, map.surface_pos(hex), &asset_server);
+                map.buildings.insert(hex, entity);
+                grid_changed.send(GridChanged);
+                built_writer.send(TowerBuilt(entity));
+                history.record(BuildCommand::Placed { hex, tower: definition.id.clone(), level: 0 });
+            }
+            BuildStep::Upgrade { hex } => {
+                let upgrade = map.buildings.get(&to_hex(hex))
+                    .and_then(|entity| towers.get(*entity).ok().map(|tower| (*entity, tower)))
+                    .and_then(|(entity, (tower, level))| {
+                        let upgrade = registry.get(&tower.id)?.upgrades.get(level.0)?;
+                        Some((entity, upgrade.cost))
+                    });
+                let Some((entity, cost)) = upgrade else {
+                    warn!("Skipping the upgrade at {:?}, there is no tower to upgrade", hex);
+                    build_order.next += 1;
+                    continue;
+                };
+                if !resources.can_afford(cost) {
+                    return;
+                }
+                upgrade_writer.send(UpgradeTower(entity));
+                // the next steps see the tower upgraded
+                build_order.next += 1;
+                return;
+            }
+            BuildStep::Sell { hex } => {
+                let Some(entity) = map.buildings.get(&to_hex(hex)) else {
+                    warn!("Skipping the sale at {:?}, there is no tower", hex);
+                    build_order.next += 1;
+                    continue;
+                };
+                sell_writer.send(SellTower(*entity));
+                // the next steps see the hex free again
+                build_order.next += 1;
+                return;
+            }
+        }
+        build_order.next += 1;
+    }
+}
+
+fn record_towers(time: Res<Time>, mut log: ResMut<SimulationLog>, towers: Query<Entity, Added<Tower>>) {
+    for entity in &towers {
+        log.built_at.insert(entity, time.elapsed_seconds());
+    }
+}
+
+fn record_waves(
+    options: Res<HeadlessOptions>,
+    mut cleared_reader: EventReader<WaveCleared>,
+    resources: Res<PlayerResources>,
+    stats: Res<GameStats>,
+    mut log: ResMut<SimulationLog>,
+    mut exit_writer: EventWriter<AppExit>,
+    time: Res<Time>,
+    towers: Query<(Entity, &Tower, &TowerLevel, &CombatRecord)>,
+) {
+    for cleared in cleared_reader.iter() {
+        log.waves.push(WaveRecord {
+            wave: cleared.0 + 1,
+            gold: resources.gold,
+            gold_earned: resources.total_earned,
+            lives: resources.lives,
+            kills: stats.kills,
+            leaks: stats.leaks,
+        });
+    }
+
+    if !log.reported && log.waves.len() >= options.waves {
+        print_report(&log, &resources, &stats, time.elapsed_seconds(), &towers);
+        log.reported = true;
+        exit_writer.send(AppExit);
+    }
+}
+
+fn finish_simulation(
+    state: Res<State<GameState>>,
+    resources: Res<PlayerResources>,
+    stats: Res<GameStats>,
+    mut log: ResMut<SimulationLog>,
+    mut exit_writer: EventWriter<AppExit>,
+    time: Res<Time>,
+    towers: Query<(Entity, &Tower, &TowerLevel, &CombatRecord)>,
+) {
+    if log.reported {
+        return;
+    }
+    println!("The game ended early: {:?}", state.0);
+    print_report(&log, &resources, &stats, time.elapsed_seconds(), &towers);
+    log.reported = true;
+    exit_writer.send(AppExit);
+}
+
+fn print_report(
+    log: &SimulationLog,
+    resources: &PlayerResources,
+    stats: &GameStats,
+    elapsed: f32,
+    towers: &Query<(Entity, &Tower, &TowerLevel, &CombatRecord)>,
+) {
+    println!();
+    println!("{:>5} {:>7} {:>7} {:>6} {:>6} {:>6}", "wave", "gold", "earned", "lives", "kills", "leaks");
+    for record in &log.waves {
+        println!(
+            "{:>5} {:>7} {:>7} {:>6} {:>6} {:>6}",
+            record.wave, record.gold, record.gold_earned, record.lives, record.kills, record.leaks,
+        );
+    }
+
+    println!();
+    println!("{:<12} {:>5} {:>6} {:>10} {:>8}", "tower", "level", "kills", "damage", "dps");
+    let mut towers: Vec<_> = towers.iter().collect();
+    towers.sort_by_key(|(entity, ..)| log.built_at.get(entity).map(|time| (time * 1000.0) as u64));
+    for (entity, tower, level, record) in towers {
+        let standing = elapsed - log.built_at.get(&entity).copied().unwrap_or(0.0);
+        let dps = if standing > 0.0 { record.damage_dealt / standing } else { 0.0 };
+        println!("{:<12} {:>5} {:>6} {:>10.0} {:>8.1}", tower.id, level.0 + 1, record.kills, record.damage_dealt, dps);
+    }
+
+    println!();
+    println!(
+        "{} waves, {} lives left, {} leaks, {} kills, {} gold earned, {} gold spent, {:.0} damage over {:.0}s",
+        stats.waves_survived, resources.lives, stats.leaks, stats.kills,
+        resources.total_earned, resources.total_spent, stats.total_damage(), elapsed,
+    );
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::settings::GameSettings;
+
+/// Directory (relative to the working directory) of the language files, one map of keys to texts per language
+const LANGUAGE_DIRECTORY: &str = "assets/lang";
+/// Language the others fall back to for the keys they miss
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        // the settings are there already, the first menus come up in the chosen language
+        let language = app.world
+            .get_resource::<GameSettings>()
+            .map_or(DEFAULT_LANGUAGE.to_string(), |settings| settings.language.clone());
+        app
+            .insert_resource(Localization::load(&language))
+            .add_system(switch_language.run_if(resource_changed::<GameSettings>()))
+            .add_system(
+                update_localized_texts
+                    .after(switch_language)
+                    .run_if(resource_changed::<Localization>())
+            )
+        ;
+    }
+}
+
+/// Texts of the UI in the language chosen in the settings
+#[derive(Resource, Debug)]
+pub struct Localization {
+    pub language: String,
+    texts: HashMap<String, String>,
+    /// texts of the default language, for keys the chosen one misses
+    fallback: HashMap<String, String>,
+}
+
+impl Localization {
+    pub fn load(language: &str) -> Self {
+        Localization {
+            language: language.to_string(),
+            texts: read_language_file(language),
+            fallback: if language == DEFAULT_LANGUAGE { HashMap::new() } else { read_language_file(DEFAULT_LANGUAGE) },
+        }
+    }
+
+    /// Text of the key, a key missing in all languages is shown as it is.
+    /// Texts of the data files, like the tower names, are their own keys.
+    pub fn t<'a>(&'a self, key: &'a str) -> &'a str {
+        self.texts
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// Text of the key with every `{name}` in it replaced by the value given for `name`
+    pub fn t_with(&self, key: &str, args: &[(&str, String)]) -> String {
+        args.iter().fold(self.t(key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+/// Text node showing the text of the key, follows along when the language changes
+#[derive(Component, Debug)]
+pub struct LocalizedText(pub &'static str);
+
+/// Languages there is a file for, by the name of the file
+pub fn available_languages() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(LANGUAGE_DIRECTORY) else {
+        return vec![DEFAULT_LANGUAGE.to_string()];
+    };
+
+    let mut languages: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == "ron"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    languages.sort();
+    languages
+}
+
+fn read_language_file(language: &str) -> HashMap<String, String> {
+    let path = Path::new(LANGUAGE_DIRECTORY).join(format!("{}.ron", language));
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("Could not read the language file {}: {}", path.display(), e);
+            return HashMap::new();
+        }
+    };
+
+    match ron::from_str(&content) {
+        Ok(texts) => texts,
+        Err(e) => {
+            warn!("Ignoring malformed {}: {}", path.display(), e);
+            HashMap::new()
+        }
+    }
+}
+
+fn switch_language(settings: Res<GameSettings>, mut localization: ResMut<Localization>) {
+    // most settings have nothing to do with the language, the texts stay untouched then
+    if localization.language != settings.language {
+        *localization = Localization::load(&settings.language);
+    }
+}
+
+fn update_localized_texts(localization: Res<Localization>, mut texts: Query<(&LocalizedText, &mut Text)>) {
+    for (localized, mut text) in &mut texts {
+        text.sections[0].value = localization.t(localized.0).to_string();
+    }
+}
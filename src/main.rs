@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::f32::consts::PI;
 use std::time::Duration;
 
@@ -13,111 +13,327 @@ use bevy::render::render_resource::PrimitiveTopology;
 use bevy::time::common_conditions::on_timer;
 use bevy::window::{PresentMode, WindowMode};
 use bevy_editor_pls::EditorPlugin;
-use bevy_mod_picking::{DefaultPickingPlugins, low_latency_window_plugin, PickableBundle};
+use bevy_mod_picking::{DefaultPickingPlugins, low_latency_window_plugin};
 use bevy_mod_picking::debug::DebugPickingPlugin;
-use bevy_mod_picking::event_listening::{Bubble, ListenedEvent, OnPointer};
-use bevy_mod_picking::events::Click;
 use bevy_mod_picking::highlight::DefaultHighlightingPlugin;
-use bevy_mod_picking::prelude::{RaycastPickCamera, RaycastPickTarget};
-use bevy_rapier3d::prelude::{NoUserData, RapierDebugRenderPlugin, RapierPhysicsPlugin, RigidBody};
+use bevy_mod_picking::prelude::RaycastPickCamera;
 use hexx::*;
-use hexx::algorithms::a_star;
 use hexx::shapes;
 use leafwing_input_manager::buttonlike::MouseMotionDirection;
 use leafwing_input_manager::prelude::*;
 use leafwing_input_manager::user_input::InputKind;
-use rand::Rng;
-
-use crate::gameplay::buildings::BuildingPlugin;
-use crate::gameplay::enemy::EnemyPlugin;
-use crate::ui::menu::{GameMenu, GameMenuPlugin, resource_not_exists};
-use crate::ui::player::PlayerUiPlugin;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::{GameAssets, GameAssetsPlugin, quit_without_file};
+use crate::camera::CameraPlugin;
+use crate::cli::{LaunchOptions, LaunchPlugin};
+use crate::gameplay::abilities::TowerAbilityPlugin;
+use crate::gameplay::auras::AuraPlugin;
+use crate::gameplay::balance::{Balance, BalancePlugin};
+use crate::gameplay::boss::BossPlugin;
+use crate::gameplay::buildings::{Bullet, BuildingPlugin, LightningArc, MortarShell};
+use crate::gameplay::campaign::CampaignPlugin;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::economy::{EconomyPlugin, PlayerResources};
+use crate::gameplay::combat::CombatRng;
+use crate::gameplay::effects::EffectsPlugin;
+use crate::gameplay::history::{BuildHistory, BuildHistoryPlugin};
+use crate::gameplay::mods::ModPlugin;
+use crate::gameplay::movement::MovementPlugin;
+use crate::gameplay::research::{ResearchPlugin, TechRegistry};
+use crate::gameplay::physics::PhysicsPlugin;
+use crate::gameplay::player_abilities::{PlayerAbilities, PlayerAbilityPlugin};
+use crate::gameplay::fog::{FogOfWar, FogOfWarPlugin};
+use crate::gameplay::save::SavePlugin;
+use crate::gameplay::spatial::SpatialIndexPlugin;
+use crate::gameplay::speed::GameSpeedPlugin;
+use crate::gameplay::stats::{GameStats, StatsPlugin};
+use crate::gameplay::towers::TowerRegistryPlugin;
+use crate::gameplay::vfx::VfxPlugin;
+use crate::gameplay::wave_files::{MapWaveFile, WaveFilePlugin};
+use crate::gameplay::waves::WavePlugin;
+use crate::gameplay::weather::{Weather, WeatherForecast, WeatherPlugin};
+use crate::gameplay::enemy::{enemy_route, EnemyPlugin, update_enemy_route};
+use crate::gameplay::waves::Waves;
+use crate::headless::HeadlessPlugin;
+use crate::map::elevation::default_elevation;
+use crate::map::format::{DEFAULT_MAP_PATH, MapFile, to_hex};
+use crate::map::chunks::{GridChunk, GridChunksPlugin, GridSpawner};
+use crate::map::grid_mesh::{GridMesh, GridRendering, MergedGrid};
+use crate::map::picking::HexPickingPlugin;
+use crate::map::flow_field::FlowFields;
+use crate::map::path_cache::PathCache;
+use crate::map::route::Routes;
+use crate::map::terrain::Terrain;
+use crate::profile::{CurrentMap, PlayerProfile, ProfilePlugin};
+use crate::localization::LocalizationPlugin;
+use crate::settings::{GameSettings, SettingsPlugin};
+use crate::tween::TweenPlugin;
+use crate::state::global::GameState;
+use crate::ui::ability_bar::AbilityBarPlugin;
+use crate::ui::accessibility::ScreenReaderPlugin;
+use crate::ui::boss_bar::BossBarPlugin;
+use crate::ui::briefing::BriefingPlugin;
+use crate::ui::build_menu::BuildMenuPlugin;
+use crate::ui::confirm_dialog::ConfirmDialogPlugin;
+use crate::ui::control_groups::ControlGroupsPlugin;
+use crate::ui::controls::ControlsPlugin;
+use crate::ui::end_screen::EndScreenPlugin;
+use crate::ui::damage_numbers::DamageNumbersPlugin;
+use crate::ui::enemy_tooltip::EnemyTooltipPlugin;
+use crate::ui::gamepad::GamepadPlugin;
+use crate::ui::health_bars::HealthBarPlugin;
+use crate::ui::highlight::{HexHighlights, HighlightPlugin};
+use crate::ui::style::UiStylePlugin;
+use crate::ui::loading::LoadingScreenPlugin;
+use crate::ui::main_menu::MainMenuPlugin;
+use crate::ui::menu::GameMenuPlugin;
+use crate::ui::minimap::MinimapPlugin;
+use crate::ui::path_preview::PathPreviewPlugin;
+use crate::ui::player::{PlayerUiPlugin, SelectedBuilding};
+use crate::ui::selection::SelectionPlugin;
+use crate::ui::settings::SettingsScreenPlugin;
+use crate::ui::research::ResearchScreenPlugin;
+use crate::ui::stats::StatsScreenPlugin;
+use crate::ui::toasts::ToastPlugin;
+use crate::ui::tutorial::TutorialPlugin;
+use crate::ui::tooltip::TooltipPlugin;
+use crate::ui::wave_preview::WavePreviewPlugin;
 
 mod ui;
 mod state;
 mod gameplay;
+mod assets;
+mod camera;
+mod map;
+mod settings;
+mod localization;
+mod profile;
+mod headless;
+mod cli;
+#[cfg(feature = "debug-tools")]
+mod debug;
+mod tween;
 
 /// World size of the hexagons (outer radius)
 const HEX_SIZE: Vec2 = Vec2::splat(1.0);
 /// World space height of hex columns
 const COLUMN_HEIGHT: f32 = 1.0;
+/// Height of a hex column at elevation 0, its bottom is hidden below the neighbours
+const COLUMN_BASE_HEIGHT: f32 = 0.1;
 /// Map radius
 const MAP_RADIUS: u32 = 20;
 /// Animation time step
 const TIME_STEP: Duration = Duration::from_millis(100);
 
 // This is the list of "things in the game I want to be able to do based on input"
-#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
 enum Action {
     Jump,
     MoveLeft,
     MoveRight,
     MoveForward,
     MoveBack,
+    RotateLeft,
+    RotateRight,
+    ZoomIn,
+    ZoomOut,
+    FollowTarget,
+    FreeCamera,
+    PanCamera,
+    MoveCursor,
+    SpeedUp,
+    SpeedDown,
+    UseAbility,
+    Undo,
+    Redo,
+    TogglePathPreview,
+    RotateBuilding,
+    /// starts placing the tower on the button of the open build tab with the same number
+    BuildSlot1,
+    BuildSlot2,
+    BuildSlot3,
+    BuildSlot4,
+    BuildSlot5,
+    BuildSlot6,
+    BuildSlot7,
+    BuildSlot8,
+    BuildSlot9,
+    /// stores the selected towers in a control group, see `ControlGroups`
+    AssignGroup1,
+    AssignGroup2,
+    AssignGroup3,
+    AssignGroup4,
+    AssignGroup5,
+    AssignGroup6,
+    AssignGroup7,
+    AssignGroup8,
+    AssignGroup9,
+    /// selects the towers of a control group again
+    RecallGroup1,
+    RecallGroup2,
+    RecallGroup3,
+    RecallGroup4,
+    RecallGroup5,
+    RecallGroup6,
+    RecallGroup7,
+    RecallGroup8,
+    RecallGroup9,
 }
 
 // This is the list of "things in the game I want to be able to do based on input"
-#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
 enum UiAction {
     OpenMenu,
     CloseMenu,
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    Confirm,
+    Cancel,
 }
 
-#[derive(Component)]
-struct PlayerCamera;
-
 #[derive(Component, Debug)]
 struct HexLocation {
     location: Hex,
 }
 
-#[derive(Resource)]
-struct RoutePlanner {
-    obj1: Option<Entity>,
-    obj2: Option<Entity>,
-}
+pub struct HexFieldClicked(Hex, Entity);
 
-struct RouteChosenEvent;
+/// Sent whenever buildings got placed or removed, things walking over the grid need to adapt
+pub struct GridChanged;
 
-pub struct HexFieldClicked(Hex, Entity);
+/// Request to throw away the current game and start over on the map stored at the given path
+#[derive(Clone, Debug)]
+pub struct StartNewGame {
+    pub map: String,
+    pub difficulty: Difficulty,
+    /// index of the campaign level played, `None` for a free game
+    pub level: Option<usize>,
+}
 
 fn main() {
-    App::new()
+    let launch = LaunchOptions::from_args();
+    let default_plugins = DefaultPlugins
+        // wave files are picked up again when they change
+        .set(AssetPlugin {
+            watch_for_changes: true,
+            ..default()
+        });
+    let default_plugins = match launch.headless {
+        Some(_) => headless::without_window(default_plugins),
+        None => default_plugins.set(low_latency_window_plugin()),
+    };
+
+    let mut app = App::new();
+    app
+        .add_state::<GameState>()
+        .add_plugin(LaunchPlugin(launch.clone()))
+        .add_plugin(GameAssetsPlugin)
+        .add_plugin(LoadingScreenPlugin)
+        .add_plugin(CameraPlugin)
+        .add_plugin(TweenPlugin)
+        .add_plugin(MainMenuPlugin)
         .add_plugin(GameMenuPlugin)
+        .add_plugin(ControlsPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(LocalizationPlugin)
+        .add_plugin(UiStylePlugin)
+        .add_plugin(ScreenReaderPlugin)
+        .add_plugin(ProfilePlugin)
+        .add_plugin(SettingsScreenPlugin)
+        .add_plugin(GamepadPlugin)
         .add_plugin(PlayerUiPlugin)
+        .add_plugin(BuildMenuPlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(ControlGroupsPlugin)
+        .add_plugin(HealthBarPlugin)
+        .add_plugin(HighlightPlugin)
+        .add_plugin(PathPreviewPlugin)
+        .add_plugin(HexPickingPlugin)
+        .add_plugin(GridChunksPlugin)
+        .add_plugin(EnemyTooltipPlugin)
+        .add_plugin(TooltipPlugin)
+        .add_plugin(ConfirmDialogPlugin)
+        .add_plugin(ToastPlugin)
+        .add_plugin(BriefingPlugin)
+        .add_plugin(TutorialPlugin)
+        .add_plugin(WavePreviewPlugin)
+        .add_plugin(MinimapPlugin)
+        .add_plugin(StatsScreenPlugin)
+        .add_plugin(EndScreenPlugin)
+        .add_plugin(ResearchScreenPlugin)
+        .add_plugin(AbilityBarPlugin)
+        .add_plugin(DamageNumbersPlugin)
+        .add_plugin(BossBarPlugin)
         .add_plugin(EnemyPlugin)
+        .add_plugin(SpatialIndexPlugin)
+        .add_plugin(MovementPlugin)
+        .add_plugin(BuildHistoryPlugin)
+        .add_plugin(ResearchPlugin)
+        .add_plugin(CampaignPlugin)
         .add_plugin(BuildingPlugin)
-        .add_plugins(DefaultPlugins.set(low_latency_window_plugin()))
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
-        .add_plugin(RapierDebugRenderPlugin::default())
-        // .add_plugin(FrameTimeDiagnosticsPlugin)
-        // .add_plugin(LogDiagnosticsPlugin::default())
+        .add_plugin(EconomyPlugin)
+        .add_plugin(EffectsPlugin)
+        .add_plugin(AuraPlugin)
+        .add_plugin(BossPlugin)
+        .add_plugin(VfxPlugin)
+        .add_plugin(FogOfWarPlugin)
+        .add_plugin(WeatherPlugin)
+        .add_plugin(ModPlugin)
+        .add_plugin(TowerRegistryPlugin)
+        .add_plugin(TowerAbilityPlugin)
+        .add_plugin(PlayerAbilityPlugin)
+        .add_plugin(WavePlugin)
+        .add_plugin(GameSpeedPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugins(default_plugins)
+        .add_plugin(PhysicsPlugin { debug_render: launch.debug.colliders && launch.headless.is_none() })
+        .add_plugin(WaveFilePlugin)
+        .add_plugin(BalancePlugin)
+        // feeds the FPS counter of the bottom bar
+        .add_plugin(FrameTimeDiagnosticsPlugin)
         .add_plugins(
             DefaultPickingPlugins
                 .build()
                 .disable::<DefaultHighlightingPlugin>()
                 .disable::<DebugPickingPlugin>(),
         )
-        .add_plugin(EditorPlugin::default())
         // This plugin maps inputs to an input-type agnostic action-state
         // We need to provide it with an enum which stores the possible actions a player could take
         .add_plugin(InputManagerPlugin::<Action>::default())
-        .add_event::<RouteChosenEvent>()
         .add_event::<HexFieldClicked>()
-        .add_system(
-            listen_for_route_planning
-                .run_if(resource_exists::<RoutePlanner>())
-        )
+        .add_event::<GridChanged>()
+        .add_event::<StartNewGame>()
+        // started from the main menu, the pause menu and the end screen
+        .add_system(start_new_game)
         // setup env
-        .add_startup_system(setup_window)
-        .add_startup_system(setup)
-        .add_startup_system(setup_grid)
-        .run();
+        .add_startup_system(setup_grid);
+
+    match launch.headless {
+        Some(options) => app.add_plugin(HeadlessPlugin(options)),
+        None => app.add_plugin(EditorPlugin::default()),
+    };
+    if launch.debug.diagnostics {
+        app.add_plugin(LogDiagnosticsPlugin::default());
+    }
+    #[cfg(feature = "debug-tools")]
+    app.add_plugin(debug::DebugToolsPlugin);
+    #[cfg(feature = "scripting")]
+    app.add_plugin(gameplay::scripting::ScriptingPlugin);
+
+    app.run();
 }
 
-fn setup_window(mut windows: Query<&mut Window>) {
-    let mut window = windows.single_mut();
-    window.set_maximized(true);
+/// Layout of the hex grid, shared by the grid and the hex mesh
+fn map_layout() -> HexLayout {
+    HexLayout {
+        hex_size: Vec2::new(0.3, 0.3),
+        orientation: HexOrientation::flat(),
+        ..default()
+    }
 }
 
 fn hexagonal_column(hex_layout: &HexLayout) -> Mesh {
@@ -136,9 +352,67 @@ fn hexagonal_column(hex_layout: &HexLayout) -> Mesh {
 pub struct Map {
     layout: HexLayout,
     entities: HashMap<Hex, Entity>,
-    highlighted_material: Handle<StandardMaterial>,
-    selection_material: Handle<StandardMaterial>,
-    default_material: Handle<StandardMaterial>,
+    /// buildings placed on the grid
+    buildings: HashMap<Hex, Entity>,
+    /// hexes enemies are walking over
+    enemy_path: HashSet<Hex>,
+    terrain: HashMap<Hex, Terrain>,
+    /// height of the ground of every hex
+    elevation: HashMap<Hex, f32>,
+    /// hexes enemies enter the map on
+    spawn_points: Vec<Hex>,
+    /// hexes enemies pass after spawning
+    waypoints: Vec<Hex>,
+    /// hexes enemies leave the map through
+    exit_points: Vec<Hex>,
+    /// bumped whenever buildings change the grid, paths computed for older versions are outdated
+    version: u32,
+}
+
+impl Map {
+    pub fn is_occupied(&self, hex: Hex) -> bool {
+        self.buildings.contains_key(&hex)
+    }
+
+    pub fn terrain(&self, hex: Hex) -> Option<Terrain> {
+        self.terrain.get(&hex).copied()
+    }
+
+    /// Waypoints of an enemy entering the map at `spawn`, from the spawn itself to the closest exit
+    pub fn route_from(&self, spawn: Hex) -> Vec<Hex> {
+        let mut route = vec![spawn];
+        route.extend(self.waypoints.iter().copied());
+
+        let last = *route.last().unwrap();
+        if let Some(exit) = self.exit_points.iter().min_by_key(|exit| last.distance_to(**exit)) {
+            route.push(*exit);
+        }
+        route.dedup();
+        route
+    }
+
+    pub fn elevation(&self, hex: Hex) -> f32 {
+        self.elevation.get(&hex).copied().unwrap_or(0.0)
+    }
+
+    /// World position of the center of the ground of the hex, where things standing on it are placed
+    pub fn surface_pos(&self, hex: Hex) -> Vec3 {
+        let pos = self.layout.hex_to_world_pos(hex);
+        Vec3::new(pos.x, self.elevation(hex), pos.y)
+    }
+
+    /// Whether a building may be placed on the hex
+    pub fn is_buildable(&self, hex: Hex) -> bool {
+        self.terrain(hex).map_or(false, |terrain| terrain.is_buildable()) && !self.is_occupied(hex)
+    }
+
+    /// Cost for enemies to walk over the hex, `None` if it can't be walked over
+    pub fn path_cost(&self, hex: Hex) -> Option<u32> {
+        if self.is_occupied(hex) {
+            return None;
+        }
+        self.terrain(hex)?.path_cost()
+    }
 }
 
 #[derive(Debug, Default, Resource)]
@@ -147,11 +421,14 @@ struct HighlightedHexes {
     hexes: Vec<Hex>,
 }
 
-/// Hex grid setup
+/// Hex grid setup from the default map file, shown behind the main menu
 fn setup_grid(
     mut commands: Commands,
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    settings: Res<GameSettings>,
+    balance: Res<Balance>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     commands.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
@@ -166,159 +443,156 @@ fn setup_grid(
         ..default()
     });
 
+    let map_file = MapFile::load(DEFAULT_MAP_PATH)
+        .unwrap_or_else(|e| quit_without_file(DEFAULT_MAP_PATH, e));
+    commands.insert_resource(CurrentMap(DEFAULT_MAP_PATH.to_string()));
+    spawn_grid(&mut commands, &assets, &asset_server, &balance, &mut meshes, map_file, settings.grid_rendering);
+}
 
-    let layout = HexLayout {
-        hex_size: Vec2::new(0.3, 0.3),
-        orientation: HexOrientation::flat(),
-        ..default()
+/// Replaces the grid and everything on it, the player starts over with the starting gold
+fn start_new_game(
+    mut commands: Commands,
+    mut new_game_reader: EventReader<StartNewGame>,
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    settings: Res<GameSettings>,
+    balance: Res<Balance>,
+    profile: Res<PlayerProfile>,
+    techs: Res<TechRegistry>,
+    launch: Res<LaunchOptions>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    // projectiles still flying would hit the enemies of the new game
+    existing: Query<Entity, Or<(With<HexLocation>, With<MergedGrid>, With<GridChunk>, With<Bullet>, With<MortarShell>, With<LightningArc>)>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(StartNewGame { map: path, difficulty, .. }) = new_game_reader.iter().last() else {
+        return;
     };
 
-    // materials
-    let default_material = materials.add(Color::WHITE.into());
-    let highlighted_material = materials.add(Color::YELLOW.into());
-    let selection_material = materials.add(Color::AQUAMARINE.into());
-    // mesh
-    let mesh = hexagonal_column(&layout);
-    let mesh_handle = meshes.add(mesh);
-
-    let entities = shapes::hexagon(Hex::ZERO, 13)
-        .map(|hex| {
-            let pos = layout.hex_to_world_pos(hex);
-            let id = commands
-                .spawn((
-                    PbrBundle {
-                        transform: Transform::from_xyz(pos.x, -0.2, pos.y)
-                            .with_scale(Vec3::new(1.0, 0.1, 1.0)),
-                        mesh: mesh_handle.clone(),
-                        material: default_material.clone(),
-                        ..default()
-                    },
-                    PickableBundle::default(),
-                    RaycastPickTarget::default(),
-                    OnPointer::<Click>::run_callback(on_hex_clicked),
-                    HexLocation {
-                        location: hex,
-                    },
-                    Name::from(format!("Hex ({}/{})", hex.x, hex.y))
-                ))
-                .id();
-            (hex, id)
-        })
-        .collect();
-
-    let map_resource = Map {
-        layout,
-        entities,
-        highlighted_material,
-        selection_material,
-        default_material,
+    let map_file = match MapFile::load(path) {
+        Ok(map_file) => map_file,
+        Err(e) => {
+            warn!("Could not load the map {}: {}", path, e);
+            // nobody is there to pick another map
+            if launch.headless.is_some() {
+                error!("The headless game could not start");
+                std::process::exit(1);
+            }
+            return;
+        }
     };
 
-    spawn_stuff(&map_resource, &mut meshes, &mut materials, &mut commands);
-
-    commands.insert_resource(map_resource);
-    commands.insert_resource(RoutePlanner { obj1: None, obj2: None });
+    // hexes, buildings and enemies all know their hex
+    for entity in &existing {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<SelectedBuilding>();
+    commands.insert_resource(*difficulty);
+    commands.insert_resource(PlayerResources::new(*difficulty));
+    commands.insert_resource(GameStats::default());
+    commands.insert_resource(BuildHistory::default());
+    commands.insert_resource(profile.starting_research(&techs));
+    commands.insert_resource(CurrentMap(path.clone()));
+
+    spawn_grid(&mut commands, &assets, &asset_server, &balance, &mut meshes, map_file, settings.grid_rendering);
+    // replaces the random one of the grid, the same seed plays out the same again
+    if let Some(seed) = launch.seed {
+        commands.insert_resource(CombatRng::new(seed));
+    }
+    // playing starts once all hexes are there
+    next_state.set(GameState::GridLoading);
 }
 
-fn spawn_stuff(map: &Map,
-               meshes: &mut ResMut<Assets<Mesh>>,
-               materials: &mut ResMut<Assets<StandardMaterial>>,
-               commands: &mut Commands,
+/// Spawns the hexes of the map and sets up the resources describing it
+fn spawn_grid(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    asset_server: &AssetServer,
+    balance: &Balance,
+    meshes: &mut Assets<Mesh>,
+    map_file: MapFile,
+    rendering: GridRendering,
 ) {
-    let mut rng = rand::thread_rng();
+    let terrain = shapes::hexagon(Hex::ZERO, map_file.radius)
+        .map(|hex| (hex, map_file.terrain_at(hex)))
+        .collect();
 
-    let keys = map.entities.keys().cloned().collect::<Vec<Hex>>();
+    let mut map_resource = Map {
+        layout: map_layout(),
+        entities: HashMap::new(),
+        buildings: HashMap::new(),
+        enemy_path: HashSet::new(),
+        terrain,
+        elevation: HashMap::new(),
+        spawn_points: map_file.spawn_points.iter().copied().map(to_hex).collect(),
+        waypoints: map_file.waypoints.iter().copied().map(to_hex).collect(),
+        exit_points: map_file.exit_points.iter().copied().map(to_hex).collect(),
+        version: 0,
+    };
 
-    for _ in 1..10 {
-        let key = keys.get(rng.gen_range(0..keys.len() + 1)).unwrap();
-        let entity = map.entities.get(key).unwrap();
-        let pos = map.layout.hex_to_world_pos(*key);
+    // roads are laid along the way enemies take through the untouched terrain
+    if map_file.roads_along_route {
+        let route = enemy_route(&map_resource).expect("the terrain of the map leaves a way to the exit");
+        for hex in route {
+            map_resource.terrain.insert(hex, Terrain::Road);
+        }
+    }
 
-        commands.entity(*entity).insert(map.highlighted_material.clone());
-        commands
-            .spawn((
+    map_resource.elevation = map_resource.terrain
+        .iter()
+        .map(|(hex, terrain)| {
+            let authored = map_file.area_at(*hex).and_then(|area| area.elevation);
+            (*hex, authored.unwrap_or_else(|| default_elevation(*hex, *terrain)))
+        })
+        .collect();
+
+    // the hex entities follow a few chunks per frame
+    commands.insert_resource(GridSpawner::new(&map_resource, rendering));
+
+    match rendering {
+        GridRendering::Tiles => commands.remove_resource::<GridMesh>(),
+        GridRendering::Merged => {
+            let grid_mesh = GridMesh::build(&map_resource, meshes);
+            commands.spawn((
+                Name::from("Merged grid"),
+                MergedGrid,
                 PbrBundle {
-                    mesh: meshes.add(Mesh::from(shape::Capsule {
-                        radius: 0.1,
-                        depth: 0.4,
-                        ..default()
-                    })),
-                    material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
-                    transform: Transform::from_xyz(pos.x, 0.1, pos.y),
+                    mesh: grid_mesh.mesh.clone(),
+                    material: assets.grid_material.clone(),
                     ..default()
                 },
-                HexLocation { location: key.clone() },
-                PickableBundle::default(),
-                RaycastPickTarget::default(),
-                OnPointer::<Click>::run_callback(on_object_clicked),
             ));
+            commands.insert_resource(grid_mesh);
+        }
     }
-}
 
-fn on_hex_clicked(
-    In(event): In<ListenedEvent<Click>>,
-    mut event_writer: EventWriter<HexFieldClicked>,
-    q: Query<&HexLocation>,
-) -> Bubble {
-    let hex_field = q.get_component::<HexLocation>(event.target).unwrap();
-    event_writer.send(HexFieldClicked(hex_field.location, event.target));
-    return Bubble::Burst;
-}
-
-fn on_object_clicked(
-    In(event): In<ListenedEvent<Click>>,
-    mut commands: Commands,
-    map: Res<Map>,
-    mut planner: ResMut<RoutePlanner>,
-    mut planner_event_writer: EventWriter<RouteChosenEvent>,
-) -> Bubble {
-    commands.entity(event.target).insert(map.highlighted_material.clone());
-
-    if planner.obj1.is_none() {
-        planner.obj1 = Some(event.target);
-    } else {
-        planner.obj2 = Some(event.target);
-        planner_event_writer.send(RouteChosenEvent);
+    update_enemy_route(&mut map_resource);
+
+    commands.insert_resource(Routes::from_map(&map_resource));
+    commands.insert_resource(PathCache::default());
+    commands.insert_resource(FlowFields::default());
+    commands.insert_resource(map_file.pathfinding);
+    commands.insert_resource(FogOfWar::new(map_file.fog_of_war));
+    commands.insert_resource(WeatherForecast::new(map_file.weather, balance.timers.weather_duration()));
+    commands.insert_resource(Weather::default());
+    commands.insert_resource(PlayerAbilities::default());
+    commands.insert_resource(CombatRng::default());
+    commands.insert_resource(map_resource);
+    commands.insert_resource(HexHighlights::default());
+    // waves kept in a file of their own come in once it is loaded
+    match &map_file.wave_file {
+        Some(path) => commands.insert_resource(MapWaveFile(asset_server.load(path.as_str()))),
+        None => commands.remove_resource::<MapWaveFile>(),
     }
-
-    return Bubble::Burst;
-}
-
-fn listen_for_route_planning(
-    mut commands: Commands,
-    map: Res<Map>,
-    mut planner: ResMut<RoutePlanner>,
-    mut events: EventReader<RouteChosenEvent>,
-    hex_query: Query<&HexLocation>,
-) {
-    for _ in events.iter() {
-        let start_location = hex_query.get(planner.obj1.unwrap()).unwrap();
-        let end_location = hex_query.get(planner.obj2.unwrap()).unwrap();
-
-        let path = a_star(start_location.location, end_location.location, |h| Some(1));
-        if let Some(hex_fields) = path {
-            hex_fields.iter().for_each(|pos| {
-                commands.entity(*map.entities.get(pos).unwrap()).insert(map.highlighted_material.clone());
-            })
-        }
-
-        planner.obj1 = None;
-        planner.obj2 = None;
+    #[cfg(feature = "scripting")]
+    match &map_file.script {
+        Some(path) => commands.insert_resource(gameplay::scripting::MapScriptFile(asset_server.load(path.as_str()))),
+        None => commands.remove_resource::<gameplay::scripting::MapScriptFile>(),
+    }
+    #[cfg(not(feature = "scripting"))]
+    if let Some(path) = &map_file.script {
+        warn!("Ignoring the script {} of the map, the game is built without the scripting feature", path);
     }
+    commands.insert_resource(Waves::new(map_file.waves, map_file.boss_interval, &balance.timers));
 }
 
-/// set up a simple 3D scene
-fn setup(
-    mut commands: Commands,
-) {
-    commands
-        .spawn((
-            Camera3dBundle {
-                transform: Transform::from_xyz(-4.0, 8.5, 13.0)
-                    .looking_at(Vec3::new(0.0, 0.0, 2.0), Vec3::Y),
-                ..default()
-            },
-            RaycastPickCamera::default(),
-            PlayerCamera,
-        ));
-}
\ No newline at end of file
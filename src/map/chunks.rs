@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use bevy::asset::LoadState;
+use bevy::math::Vec3A;
+use bevy::prelude::*;
+use bevy::render::primitives::{Frustum, Sphere};
+use hexx::Hex;
+
+use crate::{COLUMN_BASE_HEIGHT, COLUMN_HEIGHT, HexLocation, Map};
+use crate::assets::GameAssets;
+use crate::camera::PlayerCamera;
+use crate::gameplay::wave_files::MapWaveFile;
+use crate::gameplay::waves::{fallback_waves, Waves};
+use crate::map::grid_mesh::GridRendering;
+use crate::map::points::{ExitPoint, SpawnPoint};
+use crate::settings::GameSettings;
+use crate::state::global::GameState;
+use crate::ui::highlight::{HexHighlight, HexHighlights};
+
+/// Chunks spawned per frame while the grid builds up, keeps large maps from stalling a single frame
+const CHUNKS_PER_FRAME: usize = 4;
+/// Edge length of the square areas of the ground the hexes are grouped into
+const CHUNK_SIZE: f32 = 3.0;
+
+pub struct GridChunksPlugin;
+
+impl Plugin for GridChunksPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(spawn_grid_chunks.run_if(resource_exists::<GridSpawner>()))
+            .add_system(
+                finish_grid_loading
+                    .after(spawn_grid_chunks)
+                    .in_set(OnUpdate(GameState::GridLoading))
+            )
+            .add_system(cull_grid_chunks)
+        ;
+    }
+}
+
+/// Parent of the hexes in one area of the ground, hidden as a whole when out of view
+#[derive(Component)]
+pub struct GridChunk {
+    center: Vec3,
+    /// radius of a sphere around all columns of the chunk
+    radius: f32,
+}
+
+/// Hexes of a new grid still waiting for their entities, a few chunks are spawned every frame
+#[derive(Resource)]
+pub struct GridSpawner {
+    chunks: Vec<Vec<Hex>>,
+    total: usize,
+    rendering: GridRendering,
+}
+
+impl GridSpawner {
+    pub fn new(map: &Map, rendering: GridRendering) -> Self {
+        let mut chunks: HashMap<IVec2, Vec<Hex>> = HashMap::new();
+        for hex in map.terrain.keys() {
+            let pos = map.layout.hex_to_world_pos(*hex);
+            let key = (pos / CHUNK_SIZE).floor().as_ivec2();
+            chunks.entry(key).or_default().push(*hex);
+        }
+
+        let chunks: Vec<Vec<Hex>> = chunks.into_values().collect();
+        GridSpawner {
+            total: chunks.len(),
+            chunks,
+            rendering,
+        }
+    }
+
+    /// Share of the chunks already spawned (0..1)
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        1.0 - self.chunks.len() as f32 / self.total as f32
+    }
+}
+
+fn spawn_hex(commands: &mut Commands, map: &Map, assets: &GameAssets, hex: Hex, rendering: GridRendering) -> Entity {
+    let terrain = map.terrain(hex).expect("only hexes of the map are spawned");
+    let pos = map.layout.hex_to_world_pos(hex);
+    // columns reach from below the ground up to their elevation
+    let height = COLUMN_BASE_HEIGHT + map.elevation(hex);
+    let transform = Transform::from_xyz(pos.x, -0.2, pos.y)
+        .with_scale(Vec3::new(1.0, height / COLUMN_HEIGHT, 1.0));
+
+    let mut entity = commands.spawn((
+        HexLocation {
+            location: hex,
+        },
+        HexHighlight::default(),
+        terrain,
+        Name::from(format!("Hex ({}/{})", hex.x, hex.y))
+    ));
+    match rendering {
+        GridRendering::Tiles => entity.insert(PbrBundle {
+            transform,
+            mesh: assets.hex_mesh.clone(),
+            material: assets.terrain_material(terrain),
+            ..default()
+        }),
+        // drawn by the merged grid
+        GridRendering::Merged => entity.insert(SpatialBundle::from_transform(transform)),
+    };
+
+    if map.spawn_points.contains(&hex) {
+        entity.insert(SpawnPoint);
+    }
+    if map.exit_points.contains(&hex) {
+        entity.insert(ExitPoint);
+    }
+    entity.id()
+}
+
+fn spawn_grid_chunks(
+    mut commands: Commands,
+    mut spawner: ResMut<GridSpawner>,
+    mut map: ResMut<Map>,
+    mut highlights: ResMut<HexHighlights>,
+    assets: Res<GameAssets>,
+) {
+    for _ in 0..CHUNKS_PER_FRAME {
+        let Some(hexes) = spawner.chunks.pop() else {
+            break;
+        };
+
+        let positions: Vec<Vec3> = hexes.iter().map(|hex| map.surface_pos(*hex)).collect();
+        let center = positions.iter().sum::<Vec3>() / positions.len().max(1) as f32;
+        // generous enough to take in the whole columns, culling a bit late doesn't hurt
+        let radius = positions.iter().map(|pos| pos.distance(center)).fold(0.0, f32::max)
+            + map.layout.hex_size.max_element()
+            + COLUMN_HEIGHT;
+
+        let chunk = commands
+            .spawn((
+                Name::from("Grid chunk"),
+                GridChunk { center, radius },
+                SpatialBundle::default(),
+            ))
+            .id();
+
+        for hex in hexes {
+            let entity = spawn_hex(&mut commands, &map, &assets, hex, spawner.rendering);
+            commands.entity(chunk).add_child(entity);
+            map.entities.insert(hex, entity);
+            // highlights made before the hex existed
+            highlights.refresh(hex);
+        }
+    }
+
+    if spawner.chunks.is_empty() {
+        commands.remove_resource::<GridSpawner>();
+    }
+}
+
+/// Playing starts once all hexes are there and the waves of the map are loaded,
+/// a wave file which can't be loaded is replaced by the built-in waves
+fn finish_grid_loading(
+    spawner: Option<Res<GridSpawner>>,
+    mut waves: Option<ResMut<Waves>>,
+    map_wave_file: Option<Res<MapWaveFile>>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if let (Some(waves), Some(map_wave_file)) = (waves.as_mut(), &map_wave_file) {
+        if !waves.is_loaded() && asset_server.get_load_state(&map_wave_file.0) == LoadState::Failed {
+            warn!("Could not load the wave file of the map, playing the built-in waves");
+            waves.set_definitions(fallback_waves());
+        }
+    }
+    if spawner.is_none() && waves.map_or(false, |waves| waves.is_loaded()) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+/// Hides the chunks outside the view of the player camera, if enabled in the settings
+fn cull_grid_chunks(
+    settings: Res<GameSettings>,
+    cameras: Query<&Frustum, With<PlayerCamera>>,
+    mut chunks: Query<(&GridChunk, &mut Visibility)>,
+) {
+    let frustum = cameras.get_single().ok().filter(|_| settings.chunk_culling);
+
+    for (chunk, mut visibility) in &mut chunks {
+        let visible = frustum.map_or(true, |frustum| {
+            let sphere = Sphere { center: Vec3A::from(chunk.center), radius: chunk.radius };
+            frustum.intersects_sphere(&sphere, true)
+        });
+
+        let wanted = if visible { Visibility::Inherited } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
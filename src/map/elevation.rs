@@ -0,0 +1,25 @@
+use hexx::Hex;
+
+use crate::map::terrain::Terrain;
+
+/// Height of the highest mountain tops
+const MAX_MOUNTAIN_ELEVATION: f32 = 0.35;
+/// Gentle hills of grass land stay below this height
+const MAX_HILL_ELEVATION: f32 = 0.06;
+
+/// Height of the ground of a hex generated from its terrain, 0 is the level roads are built on
+pub fn default_elevation(hex: Hex, terrain: Terrain) -> f32 {
+    match terrain {
+        Terrain::Road => 0.0,
+        Terrain::Water => -0.05,
+        Terrain::Grass => hills(hex) * MAX_HILL_ELEVATION,
+        Terrain::Mountain => MAX_MOUNTAIN_ELEVATION * (0.6 + 0.4 * hills(hex)),
+    }
+}
+
+/// Smooth deterministic noise between 0 and 1
+fn hills(hex: Hex) -> f32 {
+    let (x, y) = (hex.x as f32, hex.y as f32);
+    let wave = (x * 0.7).sin() + (y * 0.5).cos() + ((x + y) * 0.3).sin();
+    (wave / 3.0 + 1.0) / 2.0
+}
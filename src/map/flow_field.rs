@@ -0,0 +1,114 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::prelude::*;
+use hexx::Hex;
+use serde::Deserialize;
+
+use crate::Map;
+use crate::gameplay::enemy::{Movement, step_cost};
+
+/// How enemies find their way to the next waypoint
+#[derive(Resource, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PathfindingMode {
+    /// A* path per enemy, shared through the `PathCache`
+    #[default]
+    AStar,
+    /// every enemy follows the flow field of its next waypoint, cheap with many enemies
+    FlowField,
+}
+
+/// Cost of the cheapest way from every reachable hex to one goal
+#[derive(Debug)]
+pub struct FlowField {
+    costs: HashMap<Hex, u32>,
+}
+
+impl FlowField {
+    /// Integrates the cost of entering each hex outwards from the goal
+    pub fn new(map: &Map, goal: Hex, movement: Movement) -> Self {
+        let mut costs = HashMap::from([(goal, 0)]);
+        let mut open = BinaryHeap::from([Reverse((0, goal.x, goal.y))]);
+
+        while let Some(Reverse((cost, x, y))) = open.pop() {
+            let current = Hex::new(x, y);
+            if costs.get(&current).map_or(false, |known| *known < cost) {
+                continue;
+            }
+            // walking from a neighbor onto the current hex costs entering it
+            let Some(enter_cost) = step_cost(map, current, movement, None) else {
+                continue;
+            };
+
+            for neighbor in current.all_neighbors() {
+                if step_cost(map, neighbor, movement, None).is_none() {
+                    continue;
+                }
+                let neighbor_cost = cost + enter_cost;
+                if costs.get(&neighbor).map_or(true, |known| neighbor_cost < *known) {
+                    costs.insert(neighbor, neighbor_cost);
+                    open.push(Reverse((neighbor_cost, neighbor.x, neighbor.y)));
+                }
+            }
+        }
+
+        FlowField { costs }
+    }
+
+    /// Neighbor of `from` leading towards the goal, `None` at the goal or without a way there
+    pub fn next_hex(&self, from: Hex) -> Option<Hex> {
+        if self.costs.get(&from) == Some(&0) {
+            return None;
+        }
+        from.all_neighbors()
+            .into_iter()
+            .filter_map(|neighbor| self.costs.get(&neighbor).map(|cost| (neighbor, *cost)))
+            .min_by_key(|(_, cost)| *cost)
+            .map(|(neighbor, _)| neighbor)
+    }
+
+    /// Hexes from `from` to the goal when following the field
+    pub fn trace(&self, from: Hex) -> Option<Vec<Hex>> {
+        let mut path = vec![from];
+        let mut current = from;
+        while self.costs.get(&current) != Some(&0) {
+            current = self.next_hex(current)?;
+            path.push(current);
+        }
+        Some(path)
+    }
+}
+
+/// Flow fields of every goal enemies head for, rebuilt when the map version changes
+#[derive(Resource, Debug, Default)]
+pub struct FlowFields {
+    fields: HashMap<(Hex, Movement), FlowField>,
+    version: u32,
+}
+
+impl FlowFields {
+    pub fn field(&mut self, map: &Map, goal: Hex, movement: Movement) -> &FlowField {
+        if self.version != map.version {
+            self.fields.clear();
+            self.version = map.version;
+        }
+        self.fields
+            .entry((goal, movement))
+            .or_insert_with(|| FlowField::new(map, goal, movement))
+    }
+
+    /// Path along all `waypoints` following their fields, `None` if one of them can't be reached
+    pub fn route(&mut self, map: &Map, waypoints: &[Hex], movement: Movement) -> Option<Vec<Hex>> {
+        let mut full_path: Vec<Hex> = vec![];
+
+        for segment in waypoints.windows(2) {
+            let path = self.field(map, segment[1], movement).trace(segment[0])?;
+
+            // the start of a segment is the end of the previous one
+            let skip = if full_path.is_empty() { 0 } else { 1 };
+            full_path.extend(path.into_iter().skip(skip));
+        }
+
+        Some(full_path)
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs;
+
+use hexx::Hex;
+use serde::Deserialize;
+
+use crate::gameplay::waves::WaveDefinition;
+use crate::gameplay::weather::WeatherMode;
+use crate::map::flow_field::PathfindingMode;
+use crate::map::terrain::Terrain;
+
+/// Map played when no other map is chosen
+pub const DEFAULT_MAP_PATH: &str = "assets/maps/default.ron";
+
+/// Directory all maps offered for a new game are read from
+pub const MAP_DIRECTORY: &str = "assets/maps";
+
+/// Paths of the map files in `MAP_DIRECTORY`, sorted by name
+pub fn available_maps() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(MAP_DIRECTORY) else {
+        return vec![DEFAULT_MAP_PATH.to_string()];
+    };
+
+    let mut maps: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == "ron"))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    maps.sort();
+    maps
+}
+
+/// Description of a playable map as stored on disk
+#[derive(Deserialize, Debug)]
+pub struct MapFile {
+    /// number of rings around the center hex
+    pub radius: u32,
+    /// terrain of every hex not covered by `areas`
+    #[serde(default = "default_base_terrain")]
+    pub base_terrain: Terrain,
+    /// terrain painted onto the map, later areas overwrite earlier ones
+    #[serde(default)]
+    pub areas: Vec<TerrainArea>,
+    /// lay roads along the route enemies take through the terrain
+    #[serde(default)]
+    pub roads_along_route: bool,
+    /// hexes enemies enter the map on
+    pub spawn_points: Vec<(i32, i32)>,
+    /// hexes enemies have to pass after spawning
+    #[serde(default)]
+    pub waypoints: Vec<(i32, i32)>,
+    /// hexes enemies leave the map through, each enemy heads for the one closest to the last waypoint
+    pub exit_points: Vec<(i32, i32)>,
+    /// waves played on the map, in order
+    #[serde(default)]
+    pub waves: Vec<WaveDefinition>,
+    /// asset path (below `assets/`) of a `.waves.ron` or `.waves.json` file with the waves, replaces `waves`.
+    /// Changes to the file apply to a running game.
+    #[serde(default)]
+    pub wave_file: Option<String>,
+    /// asset path (below `assets/`) of a `.rhai` script with hooks for scripted events, see `ScriptingPlugin`.
+    /// Needs the `scripting` feature, changes to the file apply to a running game.
+    #[serde(default)]
+    pub script: Option<String>,
+    /// flow fields pay off on maps with many enemies at once
+    #[serde(default)]
+    pub pathfinding: PathfindingMode,
+    /// hexes out of sight of the towers and the base are shrouded, enemies in there can't be seen or shot
+    #[serde(default)]
+    pub fog_of_war: bool,
+    #[serde(default)]
+    pub weather: WeatherMode,
+    /// every wave with a number divisible by this ends with a boss
+    #[serde(default)]
+    pub boss_interval: Option<u32>,
+}
+
+/// All hexes within `radius` around `center`, a radius of 0 is the single hex
+#[derive(Deserialize, Debug)]
+pub struct TerrainArea {
+    pub center: (i32, i32),
+    #[serde(default)]
+    pub radius: u32,
+    pub terrain: Terrain,
+    /// height of the ground, generated from the terrain when left out
+    #[serde(default)]
+    pub elevation: Option<f32>,
+}
+
+fn default_base_terrain() -> Terrain {
+    Terrain::Grass
+}
+
+pub fn to_hex(pair: (i32, i32)) -> Hex {
+    Hex::new(pair.0, pair.1)
+}
+
+impl TerrainArea {
+    pub fn contains(&self, hex: Hex) -> bool {
+        to_hex(self.center).distance_to(hex) <= self.radius as i32
+    }
+}
+
+impl MapFile {
+    pub fn load(path: &str) -> Result<MapFile, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let map: MapFile = ron::from_str(&content).map_err(|e| e.to_string())?;
+
+        let in_bounds = |pair: &(i32, i32)| Hex::ZERO.distance_to(to_hex(*pair)) <= map.radius as i32;
+        if map.spawn_points.is_empty() || map.exit_points.is_empty() {
+            return Err("a map needs at least one spawn point and one exit point".to_string());
+        }
+        if map.waves.is_empty() && map.wave_file.is_none() {
+            return Err("a map needs waves, either listed in the map or in a wave file".to_string());
+        }
+        let mut points = map.spawn_points.iter().chain(map.waypoints.iter()).chain(map.exit_points.iter());
+        if !points.all(in_bounds) {
+            return Err("spawn points, waypoints and exit points have to be on the map".to_string());
+        }
+
+        Ok(map)
+    }
+
+    /// Last area covering the hex, if any
+    pub fn area_at(&self, hex: Hex) -> Option<&TerrainArea> {
+        self.areas.iter().rev().find(|area| area.contains(hex))
+    }
+
+    pub fn terrain_at(&self, hex: Hex) -> Terrain {
+        self.area_at(hex).map_or(self.base_terrain, |area| area.terrain)
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::render_resource::PrimitiveTopology;
+use hexx::{ColumnMeshBuilder, Hex};
+use serde::{Deserialize, Serialize};
+
+use crate::{COLUMN_BASE_HEIGHT, COLUMN_HEIGHT, Map};
+
+/// How the columns of the hex grid are drawn, changes apply to the next game
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridRendering {
+    /// every hex is an entity with its own mesh and material
+    #[default]
+    Tiles,
+    /// all columns are merged into a single mesh colored per vertex, drawn in one call
+    Merged,
+}
+
+impl GridRendering {
+    pub const ALL: [GridRendering; 2] = [GridRendering::Tiles, GridRendering::Merged];
+
+    /// Key of the name shown in the settings, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            GridRendering::Tiles => "grid_rendering.tiles",
+            GridRendering::Merged => "grid_rendering.merged",
+        }
+    }
+}
+
+/// Entity drawing the merged grid
+#[derive(Component)]
+pub struct MergedGrid;
+
+/// Mesh of the merged grid, with the vertices belonging to every hex
+#[derive(Resource)]
+pub struct GridMesh {
+    pub mesh: Handle<Mesh>,
+    vertices: HashMap<Hex, Range<usize>>,
+}
+
+impl GridMesh {
+    /// Builds one mesh out of the columns of all hexes of the map, colored like their terrain
+    pub fn build(map: &Map, meshes: &mut Assets<Mesh>) -> Self {
+        let column = ColumnMeshBuilder::new(&map.layout, COLUMN_HEIGHT)
+            .without_bottom_face()
+            .build();
+
+        let mut positions: Vec<Vec3> = vec![];
+        let mut normals: Vec<Vec3> = vec![];
+        let mut uvs: Vec<Vec2> = vec![];
+        let mut colors: Vec<[f32; 4]> = vec![];
+        let mut indices: Vec<u32> = vec![];
+        let mut vertices = HashMap::new();
+
+        for (hex, terrain) in &map.terrain {
+            let pos = map.layout.hex_to_world_pos(*hex);
+            // same placement as the columns of single tiles
+            let height = COLUMN_BASE_HEIGHT + map.elevation(*hex);
+            let transform = Transform::from_xyz(pos.x, -0.2, pos.y)
+                .with_scale(Vec3::new(1.0, height / COLUMN_HEIGHT, 1.0));
+
+            let start = positions.len();
+            indices.extend(column.indices.iter().map(|i| start as u32 + *i as u32));
+            positions.extend(column.vertices.iter().map(|v| transform.transform_point(*v)));
+            normals.extend(column.normals.iter().copied());
+            uvs.extend(column.uvs.iter().copied());
+            colors.extend(std::iter::repeat(terrain.color().as_linear_rgba_f32()).take(column.vertices.len()));
+            vertices.insert(*hex, start..positions.len());
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        // the merged grid has way more vertices than fit into 16 bit indices
+        mesh.set_indices(Some(Indices::U32(indices)));
+
+        GridMesh {
+            mesh: meshes.add(mesh),
+            vertices,
+        }
+    }
+
+    /// Tints the column of the hex
+    pub fn set_color(&self, meshes: &mut Assets<Mesh>, hex: Hex, color: Color) {
+        let Some(range) = self.vertices.get(&hex) else {
+            return;
+        };
+        let Some(VertexAttributeValues::Float32x4(colors)) = meshes
+            .get_mut(&self.mesh)
+            .and_then(|mesh| mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)) else {
+            return;
+        };
+
+        let color = color.as_linear_rgba_f32();
+        colors[range.clone()].iter_mut().for_each(|c| *c = color);
+    }
+}
@@ -0,0 +1,10 @@
+pub mod terrain;
+pub mod elevation;
+pub mod format;
+pub mod points;
+pub mod flow_field;
+pub mod path_cache;
+pub mod route;
+pub mod grid_mesh;
+pub mod picking;
+pub mod chunks;
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use hexx::Hex;
+
+use crate::Map;
+use crate::gameplay::enemy::{Movement, segment_path};
+
+/// A* paths between two hexes, shared by all enemies walking the same segment
+#[derive(Resource, Debug, Default)]
+pub struct PathCache {
+    /// keyed by start, goal, movement and the map version the path was computed on
+    paths: HashMap<(Hex, Hex, Movement, u32), Option<Vec<Hex>>>,
+}
+
+impl PathCache {
+    /// Path from `start` to `goal`, computed once per map version
+    pub fn segment(&mut self, map: &Map, start: Hex, goal: Hex, movement: Movement) -> Option<&Vec<Hex>> {
+        self.paths
+            .entry((start, goal, movement, map.version))
+            .or_insert_with(|| segment_path(map, start, goal, movement, None))
+            .as_ref()
+    }
+
+    /// Stitches together the cached paths between all `waypoints`, `None` if one of them can't be reached
+    pub fn route(&mut self, map: &Map, waypoints: &[Hex], movement: Movement) -> Option<Vec<Hex>> {
+        let mut full_path: Vec<Hex> = vec![];
+
+        for segment in waypoints.windows(2) {
+            let path = self.segment(map, segment[0], segment[1], movement)?;
+
+            // the start of a segment is the end of the previous one
+            let skip = if full_path.is_empty() { 0 } else { 1 };
+            full_path.extend(path.iter().skip(skip));
+        }
+
+        Some(full_path)
+    }
+
+    /// Drops the paths of older map versions
+    pub fn evict_outdated(&mut self, version: u32) {
+        self.paths.retain(|(_, _, _, path_version), _| *path_version == version);
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use hexx::Hex;
+
+use crate::{HexFieldClicked, Map};
+use crate::camera::PlayerCamera;
+use crate::state::global::GameState;
+use crate::ui::selection::SelectionBox;
+
+/// Refinement steps finding the column the pointer ray hits, one per change of elevation along the ray
+const PICK_REFINEMENTS: usize = 3;
+
+pub struct HexPickingPlugin;
+
+impl Plugin for HexPickingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<PointerHex>()
+            .add_systems(
+                (
+                    update_pointer_hex,
+                    click_pointer_hex.after(update_pointer_hex),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Hex under the mouse pointer, found by intersecting the pointer ray with the grid,
+/// so the hexes don't need to be raycast targets
+#[derive(Resource, Default, Debug)]
+pub struct PointerHex(pub Option<Hex>);
+
+/// Hex whose ground the ray crosses at the given height
+fn hex_at_height(map: &Map, ray: Ray, height: f32) -> Hex {
+    let distance = (height - ray.origin.y) / ray.direction.y;
+    let point = ray.origin + ray.direction * distance;
+    map.layout.world_pos_to_hex(Vec2::new(point.x, point.z))
+}
+
+/// Hex of the grid hit by the ray, starting on the flat ground and moving to the surface of the hexes found on the way
+pub fn pick_hex(map: &Map, ray: Ray) -> Option<Hex> {
+    // rays going upwards never reach the ground
+    if ray.direction.y > -f32::EPSILON {
+        return None;
+    }
+
+    let mut hex = hex_at_height(map, ray, 0.0);
+    for _ in 0..PICK_REFINEMENTS {
+        let refined = hex_at_height(map, ray, map.surface_pos(hex).y);
+        if refined == hex {
+            break;
+        }
+        hex = refined;
+    }
+
+    map.terrain(hex).is_some().then_some(hex)
+}
+
+fn update_pointer_hex(
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    map: Res<Map>,
+    mut pointer: ResMut<PointerHex>,
+) {
+    let hex = windows.get_single().ok()
+        .and_then(|window| window.cursor_position())
+        .zip(cameras.get_single().ok())
+        .and_then(|(cursor, (camera, camera_transform))| camera.viewport_to_world(camera_transform, cursor))
+        .and_then(|ray| pick_hex(&map, ray));
+
+    if pointer.0 != hex {
+        pointer.0 = hex;
+    }
+}
+
+fn click_pointer_hex(
+    mouse: Res<Input<MouseButton>>,
+    pointer: Res<PointerHex>,
+    map: Res<Map>,
+    interactions: Query<&Interaction, With<Node>>,
+    selection_box: Option<Res<SelectionBox>>,
+    mut field_clicks: EventWriter<HexFieldClicked>,
+) {
+    if !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    // releasing a dragged selection box selects what's inside instead
+    if selection_box.map_or(false, |selection_box| selection_box.is_drag()) {
+        return;
+    }
+    // clicks on buttons and panels are meant for the UI
+    if interactions.iter().any(|interaction| *interaction != Interaction::None) {
+        return;
+    }
+
+    let Some((hex, entity)) = pointer.0.and_then(|hex| Some((hex, *map.entities.get(&hex)?))) else {
+        return;
+    };
+    field_clicks.send(HexFieldClicked(hex, entity));
+}
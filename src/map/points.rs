@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+/// Marks a hex enemies enter the map on
+#[derive(Component, Debug)]
+pub struct SpawnPoint;
+
+/// Marks a hex enemies leave the map through
+#[derive(Component, Debug)]
+pub struct ExitPoint;
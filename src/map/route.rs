@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use hexx::Hex;
+
+use crate::Map;
+use crate::gameplay::enemy::Movement;
+use crate::map::path_cache::PathCache;
+
+/// Ordered hexes enemies entering through one spawn point pass until they reach an exit
+#[derive(Debug)]
+pub struct Route {
+    pub waypoints: Vec<Hex>,
+}
+
+impl Route {
+    pub fn spawn(&self) -> Hex {
+        self.waypoints[0]
+    }
+
+    /// Full path along all waypoints, `None` if the grid doesn't leave a way through
+    pub fn path(&self, map: &Map, cache: &mut PathCache, movement: Movement) -> Option<Vec<Hex>> {
+        cache.route(map, &self.waypoints, movement)
+    }
+}
+
+/// Routes of all spawn points of the map
+#[derive(Resource, Debug, Default)]
+pub struct Routes {
+    pub routes: Vec<Route>,
+}
+
+impl Routes {
+    pub fn from_map(map: &Map) -> Self {
+        Routes {
+            routes: map.spawn_points
+                .iter()
+                .map(|spawn| Route { waypoints: map.route_from(*spawn) })
+                .collect(),
+        }
+    }
+
+    pub fn starting_at(&self, spawn: Hex) -> Option<&Route> {
+        self.routes.iter().find(|route| route.spawn() == spawn)
+    }
+}
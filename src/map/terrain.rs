@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Ground type of a hex, decides where buildings may stand and how fast enemies walk over it
+#[derive(Component, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Terrain {
+    Grass,
+    /// can't be built on and ground enemies can't cross it
+    Water,
+    /// can't be built on and is slow to walk over
+    Mountain,
+    /// quickest way for enemies
+    Road,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 4] = [
+        Terrain::Grass,
+        Terrain::Water,
+        Terrain::Mountain,
+        Terrain::Road,
+    ];
+
+    pub fn is_buildable(&self) -> bool {
+        matches!(self, Terrain::Grass | Terrain::Road)
+    }
+
+    /// Cost for ground enemies to walk over the terrain, `None` if they can't
+    pub fn path_cost(&self) -> Option<u32> {
+        match self {
+            Terrain::Road => Some(1),
+            Terrain::Grass => Some(2),
+            Terrain::Mountain => Some(6),
+            Terrain::Water => None,
+        }
+    }
+
+    /// Factor the walking speed of ground enemies gets multiplied with on the terrain
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            Terrain::Mountain => 0.7,
+            _ => 1.0,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Terrain::Grass => Color::rgb(0.35, 0.6, 0.3),
+            Terrain::Water => Color::rgb(0.2, 0.4, 0.8),
+            Terrain::Mountain => Color::rgb(0.5, 0.45, 0.4),
+            Terrain::Road => Color::rgb(0.75, 0.65, 0.45),
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::campaign::ActiveLevel;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::research::{Research, TechRegistry, TechUnlock};
+use crate::gameplay::stats::GameStats;
+use crate::headless::HeadlessOptions;
+use crate::localization::Localization;
+use crate::map::format::available_maps;
+use crate::settings::{config_directory, GameSettings};
+use crate::state::global::GameState;
+use crate::ui::toasts::Toasts;
+
+/// Directory inside the config directory the profiles are stored in, one file per profile
+const PROFILE_DIRECTORY_NAME: &str = "profiles";
+/// Name of the profile created on the very first start
+const DEFAULT_PROFILE_NAME: &str = "Player 1";
+/// Enemies to kill over all games for `Achievement::Exterminator`
+const EXTERMINATOR_KILLS: u32 = 1000;
+/// Towers to build in a single game for `Achievement::Architect`
+const ARCHITECT_TOWERS: u32 = 25;
+/// Stars for winning a map, without losing a life for all of them
+pub const MAX_STARS: u8 = 3;
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<SwitchProfile>()
+            .add_startup_system(load_last_profile.in_base_set(StartupSet::PreStartup))
+            .add_system(switch_profile)
+            // simulated games don't count for the player
+            .add_system(record_finished_game.in_schedule(OnEnter(GameState::Victory)).run_if(not(resource_exists::<HeadlessOptions>())))
+            .add_system(record_finished_game.in_schedule(OnEnter(GameState::GameOver)).run_if(not(resource_exists::<HeadlessOptions>())))
+            // the app closes right after the frame the exit was requested in
+            .add_system(save_profile_on_exit.in_base_set(CoreSet::Last).run_if(not(resource_exists::<HeadlessOptions>())))
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Achievement {
+    /// won a game
+    FirstVictory,
+    /// won a game without a single enemy reaching the exit
+    Flawless,
+    /// killed `EXTERMINATOR_KILLS` enemies over all games
+    Exterminator,
+    /// built `ARCHITECT_TOWERS` towers in one game
+    Architect,
+}
+
+impl Achievement {
+    /// Key of the name shown to the player, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Achievement::FirstVictory => "achievement.first_victory",
+            Achievement::Flawless => "achievement.flawless",
+            Achievement::Exterminator => "achievement.exterminator",
+            Achievement::Architect => "achievement.architect",
+        }
+    }
+}
+
+/// Totals over all finished games of a profile
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub games_played: u32,
+    pub victories: u32,
+    pub kills: u32,
+    pub waves_survived: u32,
+    pub towers_built: u32,
+}
+
+/// Progress of a player carried from one game to the next, stored in the config directory
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PlayerProfile {
+    pub name: String,
+    pub stats: LifetimeStats,
+    /// paths of the maps which can be played, the first map is always open
+    pub unlocked_maps: HashSet<String>,
+    /// ids of the towers buildable without researching them first
+    pub unlocked_towers: HashSet<String>,
+    pub achievements: HashSet<Achievement>,
+    /// best results of the won maps, by the path of the map
+    pub map_records: HashMap<String, MapRecord>,
+    /// levels of the campaign won, the level after them is the next one open
+    pub campaign_progress: usize,
+    /// finished or skipped the tutorial of the first map
+    pub tutorial_done: bool,
+}
+
+/// Best result on a map the profile won at least once
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+#[serde(default)]
+pub struct MapRecord {
+    pub stars: u8,
+    pub best_score: u32,
+    pub victories: u32,
+}
+
+/// Outcome of the game that just ended, shown on the end screen
+#[derive(Resource, Debug)]
+pub struct GameResult {
+    pub won: bool,
+    pub stars: u8,
+    pub score: u32,
+    /// beat the best score of the map stored in the profile
+    pub new_best: bool,
+    /// map following the one just won, if there is one
+    pub next_map: Option<String>,
+    /// map opened for the first time by winning
+    pub unlocked_map: Option<String>,
+    /// ids of the researched towers the profile keeps from now on
+    pub unlocked_towers: Vec<String>,
+    pub achievements: Vec<Achievement>,
+}
+
+impl Default for PlayerProfile {
+    fn default() -> Self {
+        PlayerProfile::new(DEFAULT_PROFILE_NAME)
+    }
+}
+
+/// Map the running game was started on, winning it opens the next one
+#[derive(Resource, Clone, Debug)]
+pub struct CurrentMap(pub String);
+
+/// Request to save the active profile and continue with the one of the given name, created if it doesn't exist
+pub struct SwitchProfile(pub String);
+
+fn profile_directory() -> PathBuf {
+    config_directory().join(PROFILE_DIRECTORY_NAME)
+}
+
+fn profile_path(name: &str) -> PathBuf {
+    profile_directory().join(format!("{}.ron", name))
+}
+
+/// Names of all stored profiles, sorted
+pub fn available_profiles() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(profile_directory()) else {
+        return vec![];
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == "ron"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Name for a new profile not taken by any stored one
+pub fn unused_profile_name() -> String {
+    let taken = available_profiles();
+    (1..)
+        .map(|number| format!("Player {}", number))
+        .find(|name| !taken.contains(name))
+        .unwrap()
+}
+
+impl PlayerProfile {
+    pub fn new(name: &str) -> Self {
+        PlayerProfile {
+            name: name.to_string(),
+            stats: LifetimeStats::default(),
+            unlocked_maps: HashSet::new(),
+            unlocked_towers: HashSet::new(),
+            achievements: HashSet::new(),
+            map_records: HashMap::new(),
+            campaign_progress: 0,
+            tutorial_done: false,
+        }
+    }
+
+    /// Stored profile of the given name, or a fresh one when there is none yet
+    pub fn load(name: &str) -> Self {
+        let path = profile_path(name);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return PlayerProfile::new(name);
+        };
+
+        match ron::from_str::<PlayerProfile>(&content) {
+            // the file name wins over the name inside, copied profiles stay apart
+            Ok(profile) => PlayerProfile { name: name.to_string(), ..profile },
+            Err(e) => {
+                warn!("Ignoring malformed {}: {}", path.display(), e);
+                PlayerProfile::new(name)
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = profile_path(&self.name);
+        let content = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Could not serialize the profile: {}", e);
+                return;
+            }
+        };
+
+        let written = path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, content));
+        if let Err(e) = written {
+            warn!("Could not save the profile to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn map_record(&self, path: &str) -> Option<&MapRecord> {
+        self.map_records.get(path)
+    }
+
+    pub fn is_map_unlocked(&self, path: &str) -> bool {
+        available_maps().first().map_or(true, |first| first == path) || self.unlocked_maps.contains(path)
+    }
+
+    /// Research of a new game, techs of the towers unlocked for good count as researched
+    pub fn starting_research(&self, techs: &TechRegistry) -> Research {
+        let mut research = Research::default();
+        research.researched.extend(
+            techs.techs
+                .iter()
+                .filter(|tech| matches!(&tech.unlock, TechUnlock::Tower(id) if self.unlocked_towers.contains(id)))
+                .map(|tech| tech.id.clone()),
+        );
+        research
+    }
+}
+
+fn load_last_profile(mut commands: Commands, settings: Res<GameSettings>) {
+    let name = settings.profile.clone().unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string());
+    commands.insert_resource(PlayerProfile::load(&name));
+}
+
+fn switch_profile(
+    mut switch_reader: EventReader<SwitchProfile>,
+    mut profile: ResMut<PlayerProfile>,
+    mut settings: ResMut<GameSettings>,
+) {
+    let Some(SwitchProfile(name)) = switch_reader.iter().last() else {
+        return;
+    };
+    if *name == profile.name {
+        return;
+    }
+
+    profile.save();
+    *profile = PlayerProfile::load(name);
+    // a new profile shows up in the list right away
+    profile.save();
+
+    settings.profile = Some(name.clone());
+    settings.save();
+}
+
+/// Stars for winning with `lives` of the `starting_lives` left, one is always earned
+pub fn completion_stars(lives: u32, starting_lives: u32) -> u8 {
+    if lives >= starting_lives {
+        MAX_STARS
+    } else if lives * 2 >= starting_lives {
+        2
+    } else {
+        1
+    }
+}
+
+/// Adds the finished game to the profile, wins open the next map and keep the researched towers
+pub fn record_finished_game(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    stats: Res<GameStats>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+    resources: Res<PlayerResources>,
+    difficulty: Res<Difficulty>,
+    current_map: Option<Res<CurrentMap>>,
+    active_level: Option<Res<ActiveLevel>>,
+    mut profile: ResMut<PlayerProfile>,
+    mut toasts: ResMut<Toasts>,
+    localization: Res<Localization>,
+) {
+    let won = state.0 == GameState::Victory;
+    let earned_before = profile.achievements.clone();
+    let mut result = GameResult {
+        won,
+        stars: 0,
+        score: stats.score(resources.lives),
+        new_best: false,
+        next_map: None,
+        unlocked_map: None,
+        unlocked_towers: vec![],
+        achievements: vec![],
+    };
+
+    profile.stats.games_played += 1;
+    profile.stats.kills += stats.kills;
+    profile.stats.waves_survived += stats.waves_survived;
+    profile.stats.towers_built += stats.towers_built;
+
+    if won {
+        profile.stats.victories += 1;
+        profile.achievements.insert(Achievement::FirstVictory);
+        if stats.leaks == 0 {
+            profile.achievements.insert(Achievement::Flawless);
+        }
+
+        let researched_towers: Vec<String> = techs.techs
+            .iter()
+            .filter(|tech| research.is_researched(&tech.id))
+            .filter_map(|tech| match &tech.unlock {
+                TechUnlock::Tower(id) => Some(id.clone()),
+                TechUnlock::Modifier(_) => None,
+            })
+            .collect();
+        for tower in researched_towers {
+            if profile.unlocked_towers.insert(tower.clone()) {
+                result.unlocked_towers.push(tower);
+            }
+        }
+
+        if let Some(level) = active_level {
+            profile.campaign_progress = profile.campaign_progress.max(level.index + 1);
+        }
+
+        result.stars = completion_stars(resources.lives, difficulty.lives());
+        if let Some(current_map) = current_map {
+            let record = profile.map_records.entry(current_map.0.clone()).or_default();
+            result.new_best = result.score > record.best_score;
+            record.stars = record.stars.max(result.stars);
+            record.best_score = record.best_score.max(result.score);
+            record.victories += 1;
+
+            let maps = available_maps();
+            let next = maps.iter().position(|path| *path == current_map.0).and_then(|index| maps.get(index + 1));
+            if let Some(next) = next {
+                if profile.unlocked_maps.insert(next.clone()) {
+                    result.unlocked_map = Some(next.clone());
+                }
+                result.next_map = Some(next.clone());
+            }
+        }
+    }
+    if profile.stats.kills >= EXTERMINATOR_KILLS {
+        profile.achievements.insert(Achievement::Exterminator);
+    }
+    if stats.towers_built >= ARCHITECT_TOWERS {
+        profile.achievements.insert(Achievement::Architect);
+    }
+
+    result.achievements = profile.achievements.difference(&earned_before).copied().collect();
+    for achievement in &result.achievements {
+        toasts.push(localization.t_with("toast.achievement", &[("name", localization.t(achievement.label_key()).to_string())]));
+    }
+
+    profile.save();
+    commands.insert_resource(result);
+}
+
+fn save_profile_on_exit(mut exit_reader: EventReader<AppExit>, profile: Option<Res<PlayerProfile>>) {
+    if exit_reader.iter().count() == 0 {
+        return;
+    }
+    if let Some(profile) = profile {
+        profile.save();
+    }
+}
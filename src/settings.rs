@@ -0,0 +1,211 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::audio::GlobalVolume;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::CameraSettings;
+use crate::cli::LaunchOptions;
+use crate::localization::DEFAULT_LANGUAGE;
+use crate::map::grid_mesh::GridRendering;
+use crate::ui::highlight::HighlightPalette;
+
+/// Directory inside the user config directory the settings are stored in
+const CONFIG_DIRECTORY_NAME: &str = "game-with-bevy";
+const SETTINGS_FILE_NAME: &str = "settings.ron";
+
+/// Window sizes offered in the settings, a missing resolution keeps the window maximized
+pub const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+pub const VOLUME_STEPS: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+pub const SENSITIVITY_STEPS: [f32; 5] = [0.5, 0.75, 1.0, 1.5, 2.0];
+pub const UI_SCALE_STEPS: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+/// Text sizes on top of the UI scale, for bigger texts without bigger panels
+pub const FONT_SCALE_STEPS: [f32; 5] = [0.85, 1.0, 1.15, 1.3, 1.5];
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(GameSettings::load())
+            .add_system(apply_settings.run_if(resource_changed::<GameSettings>()))
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [DisplayMode::Windowed, DisplayMode::Borderless, DisplayMode::Fullscreen];
+
+    /// Key of the name shown in the settings, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "display_mode.windowed",
+            DisplayMode::Borderless => "display_mode.borderless",
+            DisplayMode::Fullscreen => "display_mode.fullscreen",
+        }
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+/// Options chosen by the player, stored in the user config directory
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GameSettings {
+    pub display_mode: DisplayMode,
+    /// size of the window in windowed mode, maximized when missing
+    pub resolution: Option<(u32, u32)>,
+    pub vsync: bool,
+    /// 0..1, applied on top of the music and sound effect volumes
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// factor applied to the camera panning and rotation speed
+    pub camera_sensitivity: f32,
+    /// size of the whole UI, texts included
+    pub ui_scale: f32,
+    /// size of the texts, see `UiStyle`
+    pub font_scale: f32,
+    /// how the grid of the next game is drawn
+    pub grid_rendering: GridRendering,
+    /// hide the parts of the grid out of view, they also disappear from the minimap
+    pub chunk_culling: bool,
+    /// frames per second shown in the bottom bar
+    pub show_fps: bool,
+    /// name of the player profile used last, picked again on the next start
+    pub profile: Option<String>,
+    /// name of the language file the UI texts come from
+    pub language: String,
+    /// colors of the highlighted hexes
+    pub highlight_palette: HighlightPalette,
+    /// shapes on top of highlighted hexes, so they can be told apart without their colors
+    pub highlight_patterns: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            display_mode: DisplayMode::Windowed,
+            resolution: None,
+            vsync: true,
+            master_volume: 1.0,
+            music_volume: 0.75,
+            sfx_volume: 0.75,
+            camera_sensitivity: 1.0,
+            ui_scale: 1.0,
+            font_scale: 1.0,
+            grid_rendering: GridRendering::Tiles,
+            chunk_culling: false,
+            show_fps: false,
+            profile: None,
+            language: DEFAULT_LANGUAGE.to_string(),
+            highlight_palette: HighlightPalette::Standard,
+            highlight_patterns: false,
+        }
+    }
+}
+
+/// Directory of the game inside the config directory of the platform, falls back to the working directory
+pub fn config_directory() -> PathBuf {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    config_dir.join(CONFIG_DIRECTORY_NAME)
+}
+
+fn settings_path() -> PathBuf {
+    config_directory().join(SETTINGS_FILE_NAME)
+}
+
+impl GameSettings {
+    /// Stored settings, or the defaults when there are none yet
+    fn load() -> Self {
+        let path = settings_path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return GameSettings::default();
+        };
+
+        match ron::from_str(&content) {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Ignoring malformed {}: {}", path.display(), e);
+                GameSettings::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = settings_path();
+        let content = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Could not serialize the settings: {}", e);
+                return;
+            }
+        };
+
+        let written = path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, content));
+        if let Err(e) = written {
+            warn!("Could not save the settings to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Value following `current` in `steps`, wrapping around after the last one, `current` stays without any steps
+pub fn next_step<T: Clone + PartialEq>(steps: &[T], current: T) -> T {
+    let index = steps.iter().position(|step| *step == current);
+    match index {
+        Some(index) => steps[(index + 1) % steps.len()].clone(),
+        None => steps.first().cloned().unwrap_or(current),
+    }
+}
+
+fn apply_settings(
+    settings: Res<GameSettings>,
+    launch: Res<LaunchOptions>,
+    mut windows: Query<&mut Window>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut ui_scale: ResMut<UiScale>,
+    global_volume: Option<ResMut<GlobalVolume>>,
+) {
+    for mut window in &mut windows {
+        // a mode given on the command line wins for this run, without changing the settings
+        window.mode = launch.display_mode.unwrap_or(settings.display_mode).window_mode();
+        window.present_mode = if settings.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync };
+
+        match settings.resolution {
+            Some((width, height)) => {
+                window.set_maximized(false);
+                window.resolution.set(width as f32, height as f32);
+            }
+            None => window.set_maximized(true),
+        }
+    }
+
+    camera_settings.sensitivity = settings.camera_sensitivity;
+    ui_scale.scale = settings.ui_scale as f64;
+    if let Some(mut global_volume) = global_volume {
+        *global_volume = GlobalVolume::new(settings.master_volume);
+    }
+}
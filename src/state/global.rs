@@ -1,5 +1,16 @@
-use bevy::prelude::Resource;
+use bevy::prelude::States;
 
-#[derive(Resource)]
-pub struct GameState {
-}
\ No newline at end of file
+/// Top level state of the game, systems of the different plugins are bound to one of those.
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum GameState {
+    /// preloading models, fonts and textures
+    #[default]
+    AssetLoading,
+    MainMenu,
+    /// spawning the grid of a new game
+    GridLoading,
+    Playing,
+    Paused,
+    GameOver,
+    Victory,
+}
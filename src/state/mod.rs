@@ -1 +1 @@
-pub mod global;
\ No newline at end of file
+pub mod global;
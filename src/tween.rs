@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        // tweens follow the game time, pausing the game holds them where they are
+        app
+            .add_system(animate_tweens::<TransformScaleLens>)
+        ;
+    }
+}
+
+/// Curve mapping the linear progress of a tween to the progress of the animated value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// overshoots the end a bit before settling, for things popping up
+    BackOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::BackOut => {
+                const OVERSHOOT: f32 = 1.70158;
+                let u = t - 1.0;
+                1.0 + (OVERSHOOT + 1.0) * u.powi(3) + OVERSHOOT * u.powi(2)
+            }
+        }
+    }
+}
+
+/// Value of a component which can be animated between two states
+pub trait TweenLens: Send + Sync + 'static {
+    type Target: Component;
+
+    /// Sets the value `progress` (0..1, eased) of the way from the start to the end
+    fn apply(&self, target: &mut Self::Target, progress: f32);
+}
+
+/// Animates the value described by the lens on the same entity, the component is removed once it reached the end
+#[derive(Component)]
+pub struct Tween<L> {
+    pub lens: L,
+    pub easing: Easing,
+    timer: Timer,
+}
+
+impl<L> Tween<L> {
+    pub fn new(lens: L, duration: Duration, easing: Easing) -> Self {
+        Tween {
+            lens,
+            easing,
+            timer: Timer::new(duration, TimerMode::Once),
+        }
+    }
+
+    /// Advances the tween and returns the eased progress
+    fn tick(&mut self, delta: Duration) -> f32 {
+        self.timer.tick(delta);
+        self.easing.apply(self.timer.percent())
+    }
+}
+
+pub struct TransformScaleLens {
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl TweenLens for TransformScaleLens {
+    type Target = Transform;
+
+    fn apply(&self, target: &mut Transform, progress: f32) {
+        target.scale = self.start.lerp(self.end, progress);
+    }
+}
+
+fn animate_tweens<L: TweenLens>(
+    mut commands: Commands,
+    mut tweens: Query<(Entity, &mut Tween<L>, &mut L::Target)>,
+    time: Res<Time>,
+) {
+    for (e, mut tween, mut target) in &mut tweens {
+        let progress = tween.tick(time.delta());
+        tween.lens.apply(&mut target, progress);
+
+        if tween.timer.finished() {
+            commands.entity(e).remove::<Tween<L>>();
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use bevy::a11y::accesskit::Role;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{HexFieldClicked, Map, UiAction};
+use crate::gameplay::player_abilities::{CastAbility, PlayerAbilities, PlayerAbility};
+use crate::localization::Localization;
+use crate::map::picking::PointerHex;
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::gamepad::HexCursor;
+use crate::ui::highlight::{HexHighlights, HighlightKind};
+use crate::ui::player::{BOTTOM_BAR_HEIGHT, BuildingPlacement};
+use crate::ui::style::{TextSize, UiStyle};
+
+const ABILITY_BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const TARGETING_BUTTON_COLOR: Color = Color::rgb(0.2, 0.4, 0.6);
+
+pub struct AbilityBarPlugin;
+
+impl Plugin for AbilityBarPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_ability_bar)
+            .add_systems(
+                (
+                    on_ability_button_clicked,
+                    cast_on_clicked_hex.run_if(resource_exists::<AbilityTargeting>()),
+                    show_ability_target.run_if(resource_exists::<AbilityTargeting>()),
+                    cancel_targeting.run_if(resource_exists::<AbilityTargeting>()),
+                    update_ability_bar,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(stop_targeting.in_schedule(OnExit(GameState::Playing)))
+            .add_system(localize_ability_buttons.run_if(resource_changed::<Localization>()))
+        ;
+    }
+}
+
+/// Ability waiting for the player to click the hex it is cast on
+#[derive(Resource)]
+pub struct AbilityTargeting(pub PlayerAbility);
+
+#[derive(Component)]
+struct AbilityBarButton(PlayerAbility);
+
+/// Part of an ability button filled while the ability cools down
+#[derive(Component)]
+struct AbilityBarCooldown(PlayerAbility);
+
+/// Name and mana cost inside an ability button
+#[derive(Component)]
+struct AbilityBarLabel(PlayerAbility);
+
+#[derive(Component)]
+struct ManaText;
+
+fn setup_ability_bar(mut commands: Commands, ui_style: Res<UiStyle>, localization: Res<Localization>) {
+    let text_style = ui_style.text(TextSize::Body, Color::WHITE);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.0),
+                    // above the bottom bar
+                    bottom: Val::Px(BOTTOM_BAR_HEIGHT + 10.0),
+                    ..default()
+                },
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.15, 0.15, 0.15, 0.8).into(),
+            ..default()
+        })
+        .insert(AccessibleName::new(Role::Toolbar, "a11y.abilities"))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(mana_label(0.0, 0.0, &localization), text_style.clone())
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                ManaText,
+                Label,
+            ));
+
+            for ability in PlayerAbility::ALL {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(110.0), Val::Px(40.0)),
+                                margin: UiRect::all(Val::Px(5.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: ABILITY_BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        AbilityBarButton(ability),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    position: UiRect {
+                                        left: Val::Px(0.0),
+                                        top: Val::Px(0.0),
+                                        ..default()
+                                    },
+                                    size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                    ..default()
+                                },
+                                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                                ..default()
+                            },
+                            AbilityBarCooldown(ability),
+                        ));
+                        parent.spawn((
+                            TextBundle::from_section(ability_button_label(ability, &localization), text_style.clone()),
+                            AbilityBarLabel(ability),
+                        ));
+                    });
+            }
+        });
+}
+
+fn ability_button_label(ability: PlayerAbility, localization: &Localization) -> String {
+    format!("{} ({:.0})", localization.t(ability.label_key()), ability.mana_cost())
+}
+
+fn mana_label(mana: f32, max_mana: f32, localization: &Localization) -> String {
+    localization.t_with("ability_bar.mana", &[("mana", format!("{:.0}", mana)), ("max", format!("{:.0}", max_mana))])
+}
+
+fn localize_ability_buttons(localization: Res<Localization>, mut labels: Query<(&AbilityBarLabel, &mut Text)>) {
+    for (label, mut text) in &mut labels {
+        text.sections[0].value = ability_button_label(label.0, &localization);
+    }
+}
+
+/// Casts abilities without a target right away, the others wait for a click on the grid
+fn on_ability_button_clicked(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &AbilityBarButton), Changed<Interaction>>,
+    abilities: Res<PlayerAbilities>,
+    placement: Option<Res<BuildingPlacement>>,
+    mut highlights: ResMut<HexHighlights>,
+    mut cast_writer: EventWriter<CastAbility>,
+) {
+    for (interaction, button) in &interactions {
+        if *interaction != Interaction::Clicked || !abilities.can_cast(button.0) {
+            continue;
+        }
+
+        if !button.0.needs_target() {
+            cast_writer.send(CastAbility { ability: button.0, hex: None });
+        } else if placement.is_none() {
+            highlights.clear(HighlightKind::AbilityTarget);
+            commands.insert_resource(AbilityTargeting(button.0));
+        }
+    }
+}
+
+fn cast_on_clicked_hex(
+    mut commands: Commands,
+    mut field_click_reader: EventReader<HexFieldClicked>,
+    mut highlights: ResMut<HexHighlights>,
+    targeting: Res<AbilityTargeting>,
+    mut cast_writer: EventWriter<CastAbility>,
+) {
+    let Some(event) = field_click_reader.iter().next() else {
+        return;
+    };
+    field_click_reader.clear();
+
+    cast_writer.send(CastAbility { ability: targeting.0, hex: Some(event.0) });
+    highlights.clear(HighlightKind::AbilityTarget);
+    commands.remove_resource::<AbilityTargeting>();
+}
+
+/// Marks the hexes the ability would reach when cast on the hovered hex
+fn show_ability_target(
+    pointer: Res<PointerHex>,
+    cursor: Option<Res<HexCursor>>,
+    map: Res<Map>,
+    targeting: Res<AbilityTargeting>,
+    mut highlights: ResMut<HexHighlights>,
+) {
+    // the gamepad cursor replaces the mouse while it exists
+    let hovered = cursor.map_or(pointer.0, |cursor| Some(cursor.hex));
+    highlights.clear(HighlightKind::AbilityTarget);
+
+    let (Some(hex), Some(radius)) = (hovered, targeting.0.radius()) else {
+        return;
+    };
+    hex.range(radius)
+        .filter(|hex| map.terrain(*hex).is_some())
+        .for_each(|hex| highlights.highlight(hex, HighlightKind::AbilityTarget));
+}
+
+fn cancel_targeting(
+    mut commands: Commands,
+    mut highlights: ResMut<HexHighlights>,
+    ui_actions: Query<&ActionState<UiAction>>,
+) {
+    let Ok(action_state) = ui_actions.get_single() else {
+        return;
+    };
+    if action_state.just_pressed(UiAction::Cancel) {
+        highlights.clear(HighlightKind::AbilityTarget);
+        commands.remove_resource::<AbilityTargeting>();
+    }
+}
+
+fn stop_targeting(mut commands: Commands, mut highlights: ResMut<HexHighlights>) {
+    highlights.clear(HighlightKind::AbilityTarget);
+    commands.remove_resource::<AbilityTargeting>();
+}
+
+/// Shows the mana, the cooldowns and the ability waiting for its target
+fn update_ability_bar(
+    abilities: Res<PlayerAbilities>,
+    targeting: Option<Res<AbilityTargeting>>,
+    localization: Res<Localization>,
+    mut mana_texts: Query<&mut Text, With<ManaText>>,
+    mut cooldowns: Query<(&AbilityBarCooldown, &mut Style)>,
+    mut buttons: Query<(&AbilityBarButton, &mut BackgroundColor)>,
+) {
+    let mana = mana_label(abilities.mana.floor(), abilities.max_mana(), &localization);
+    for mut text in &mut mana_texts {
+        if text.sections[0].value != mana {
+            text.sections[0].value = mana.clone();
+        }
+    }
+
+    for (cooldown, mut style) in &mut cooldowns {
+        let width = Val::Percent(100.0 * abilities.cooldown_left(cooldown.0));
+        if style.size.width != width {
+            style.size.width = width;
+        }
+    }
+
+    let targeted = targeting.map(|t| t.0);
+    for (button, mut color) in &mut buttons {
+        let wanted: BackgroundColor = if targeted == Some(button.0) {
+            TARGETING_BUTTON_COLOR
+        } else if abilities.can_cast(button.0) {
+            ABILITY_BUTTON_COLOR
+        } else {
+            ABILITY_BUTTON_COLOR * 0.6
+        }.into();
+        if color.0 != wanted.0 {
+            *color = wanted;
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::a11y::{AccessibilityNode, Focus};
+use bevy::a11y::accesskit::{Live, NodeBuilder, Role};
+use bevy::prelude::*;
+
+use crate::localization::Localization;
+use crate::ui::gamepad::MenuFocus;
+use crate::ui::tooltip::Tooltip;
+
+pub struct ScreenReaderPlugin;
+
+impl Plugin for ScreenReaderPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // bevy names buttons and labels after their texts in systems of the update it keeps private,
+            // running after the whole update is the only way to come after them
+            .add_systems(
+                (
+                    apply_accessible_names,
+                    follow_button_texts,
+                    describe_tooltips,
+                    announce_live_regions.after(apply_accessible_names),
+                ).in_base_set(CoreSet::PostUpdate)
+            )
+            .add_system(follow_menu_focus.run_if(resource_changed::<MenuFocus>()))
+        ;
+    }
+}
+
+/// Role and name a screen reader announces the node with, the name is the text of the key in the chosen language.
+/// Texts missing in the language files, like ones already put together, are announced as they are.
+#[derive(Component, Debug)]
+pub struct AccessibleName {
+    role: Role,
+    key: String,
+}
+
+impl AccessibleName {
+    pub fn new(role: Role, key: impl Into<String>) -> Self {
+        AccessibleName { role, key: key.into() }
+    }
+
+    pub fn button(key: impl Into<String>) -> Self {
+        Self::new(Role::Button, key)
+    }
+
+    /// Node holding related widgets, like a menu or a row of the HUD
+    pub fn group(key: impl Into<String>) -> Self {
+        Self::new(Role::Group, key)
+    }
+}
+
+/// Node whose changes get announced without it being focused, like a message popping up
+#[derive(Component, Debug)]
+pub struct LiveRegion;
+
+fn apply_accessible_names(
+    mut commands: Commands,
+    localization: Res<Localization>,
+    mut nodes: Query<(Entity, Ref<AccessibleName>, Option<&mut AccessibilityNode>)>,
+) {
+    for (entity, name, node) in &mut nodes {
+        match node {
+            Some(mut node) => {
+                // bevy replaces the names of buttons when it adds their node, and again whenever it sees them changed
+                if !name.is_changed() && !node.is_changed() && !localization.is_changed() {
+                    continue;
+                }
+                node.set_role(name.role);
+                node.set_name(localization.t(&name.key));
+            }
+            None => {
+                let mut node = NodeBuilder::new(name.role);
+                node.set_name(localization.t(&name.key));
+                commands.entity(entity).insert(AccessibilityNode::from(node));
+            }
+        }
+    }
+}
+
+fn announce_live_regions(mut nodes: Query<&mut AccessibilityNode, (With<LiveRegion>, Added<AccessibilityNode>)>) {
+    for mut node in &mut nodes {
+        node.set_live(Live::Polite);
+    }
+}
+
+/// Buttons are only named after their texts when spawned, texts like the one of a toggle change later on
+fn follow_button_texts(
+    changed_texts: Query<&Parent, Changed<Text>>,
+    mut buttons: Query<(&Children, &mut AccessibilityNode), (With<Button>, Without<AccessibleName>)>,
+    texts: Query<&Text>,
+) {
+    for parent in &changed_texts {
+        let Ok((children, mut node)) = buttons.get_mut(parent.get()) else {
+            continue;
+        };
+        let name = children
+            .iter()
+            .filter_map(|child| texts.get(*child).ok())
+            .flat_map(|text| text.sections.iter().map(|section| section.value.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        node.set_name(name);
+    }
+}
+
+/// Tooltips carry the details of a button, like the stats of a tower, screen readers get them as description
+fn describe_tooltips(mut nodes: Query<(&Tooltip, &mut AccessibilityNode), Or<(Changed<Tooltip>, Added<AccessibilityNode>)>>) {
+    for (tooltip, mut node) in &mut nodes {
+        node.set_description(tooltip.0.clone());
+    }
+}
+
+/// Screen readers announce the button keyboard and gamepad move the focus to
+fn follow_menu_focus(menu_focus: Res<MenuFocus>, mut focus: ResMut<Focus>) {
+    **focus = menu_focus.get();
+}
@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::gameplay::boss::Boss;
+use crate::gameplay::enemy::{EnemyKind, EnemyTag, Health};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::style::{TextSize, UiStyle};
+
+const FILL_COLOR: Color = Color::rgb(0.8, 0.15, 0.15);
+const SHIELDED_FILL_COLOR: Color = Color::rgb(0.4, 0.7, 1.0);
+
+pub struct BossBarPlugin;
+
+impl Plugin for BossBarPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_boss_bar)
+            .add_system(update_boss_bar.in_set(OnUpdate(GameState::Playing)))
+            .add_system(hide_boss_bar.in_schedule(OnExit(GameState::Playing)))
+        ;
+    }
+}
+
+/// Health of the boss across the top of the screen, only visible while a boss is alive
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarFill;
+
+#[derive(Component)]
+struct BossBarText;
+
+fn spawn_boss_bar(mut commands: Commands, ui_style: Res<UiStyle>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(10.0),
+                        left: Val::Percent(30.0),
+                        ..default()
+                    },
+                    size: Size::width(Val::Percent(40.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.15, 0.15, 0.15, 0.8).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            BossBar,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    ui_style.text(TextSize::Body, Color::WHITE),
+                ),
+                BossBarText,
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(14.0)),
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.05, 0.05, 0.05).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: FILL_COLOR.into(),
+                            ..default()
+                        },
+                        BossBarFill,
+                    ));
+                });
+        });
+}
+
+/// Follows the first boss alive, several bosses at once share the bar one after another
+fn update_boss_bar(
+    bosses: Query<(&Health, &Boss, &EnemyKind), With<EnemyTag>>,
+    localization: Res<Localization>,
+    mut bars: Query<&mut Visibility, With<BossBar>>,
+    mut fills: Query<(&mut Style, &mut BackgroundColor), With<BossBarFill>>,
+    mut texts: Query<&mut Text, With<BossBarText>>,
+) {
+    let boss = bosses.iter().find(|(health, _, _)| health.current > 0.0);
+
+    for mut visibility in &mut bars {
+        let wanted = if boss.is_some() { Visibility::Visible } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+
+    let Some((health, boss, kind)) = boss else {
+        return;
+    };
+
+    for (mut style, mut color) in &mut fills {
+        let width = Val::Percent(100.0 * (health.current / health.max).clamp(0.0, 1.0));
+        if style.size.width != width {
+            style.size.width = width;
+        }
+        let wanted = if boss.is_shielded() { SHIELDED_FILL_COLOR } else { FILL_COLOR };
+        if color.0 != wanted {
+            color.0 = wanted;
+        }
+    }
+
+    let key = if boss.is_shielded() { "boss_bar.phase_shielded" } else { "boss_bar.phase" };
+    let label = localization.t_with(
+        key,
+        &[
+            ("name", localization.t(kind.stats().name).to_string()),
+            ("phase", boss.phase().to_string()),
+            ("phases", boss.phase_count().to_string()),
+        ],
+    );
+    for mut text in &mut texts {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+fn hide_boss_bar(mut bars: Query<&mut Visibility, With<BossBar>>) {
+    for mut visibility in &mut bars {
+        *visibility = Visibility::Hidden;
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::gameplay::campaign::LevelMessage;
+use crate::state::global::GameState;
+use crate::ui::accessibility::LiveRegion;
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Time a message of the level stays on screen unless it is clicked away
+const BRIEFING_DURATION: Duration = Duration::from_secs(8);
+
+pub struct BriefingPlugin;
+
+impl Plugin for BriefingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_briefing_panel)
+            .add_systems((show_level_messages, hide_briefing.after(show_level_messages)))
+            // the message belongs to the game left behind, the pause menu keeps it
+            .add_system(close_briefing.in_schedule(OnEnter(GameState::GridLoading)))
+            .add_system(close_briefing.in_schedule(OnEnter(GameState::MainMenu)))
+            .add_system(close_briefing.in_schedule(OnEnter(GameState::Victory)))
+            .add_system(close_briefing.in_schedule(OnEnter(GameState::GameOver)))
+        ;
+    }
+}
+
+/// Panel at the top of the screen showing the intro and the scripted messages of a campaign level
+#[derive(Component, Default)]
+struct BriefingPanel {
+    /// time the current message is shown for, in real time
+    shown: Duration,
+}
+
+#[derive(Component)]
+struct BriefingText;
+
+fn spawn_briefing_panel(mut commands: Commands, ui_style: Res<UiStyle>) {
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Percent(30.0),
+                        top: Val::Percent(12.0),
+                        ..default()
+                    },
+                    size: Size::width(Val::Percent(40.0)),
+                    padding: UiRect::all(Val::Px(12.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.05, 0.05, 0.1, 0.85).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            BriefingPanel::default(),
+            // named after its text like any button, new messages are read out
+            LiveRegion,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    ui_style.text(TextSize::Large, Color::rgb(0.95, 0.95, 0.95)),
+                ),
+                BriefingText,
+            ));
+        });
+}
+
+/// Shows the latest message, replacing the one on screen
+fn show_level_messages(
+    mut message_reader: EventReader<LevelMessage>,
+    mut panels: Query<(&mut BriefingPanel, &mut Visibility)>,
+    mut texts: Query<&mut Text, With<BriefingText>>,
+) {
+    let Some(LevelMessage(message)) = message_reader.iter().last() else {
+        return;
+    };
+
+    for (mut panel, mut visibility) in &mut panels {
+        panel.shown = Duration::ZERO;
+        *visibility = Visibility::Visible;
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = message.clone();
+    }
+}
+
+/// Hides the message once it was shown long enough, measured in real time so it also runs out at high game speed
+fn hide_briefing(
+    time: Res<Time>,
+    mut panels: Query<(&mut BriefingPanel, &mut Visibility, &Interaction)>,
+) {
+    for (mut panel, mut visibility, interaction) in &mut panels {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        panel.shown += time.raw_delta();
+        if panel.shown >= BRIEFING_DURATION || *interaction == Interaction::Clicked {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+fn close_briefing(mut panels: Query<&mut Visibility, With<BriefingPanel>>) {
+    for mut visibility in &mut panels {
+        *visibility = Visibility::Hidden;
+    }
+}
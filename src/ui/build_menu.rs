@@ -0,0 +1,409 @@
+use bevy::a11y::accesskit::Role;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+use crate::gameplay::campaign::{ActiveLevel, allows_tower};
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::research::{Research, TechRegistry};
+use crate::gameplay::towers::{BuildCategory, TowerDefinition, TowerRegistry};
+use crate::localization::{Localization, LocalizedText};
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::style::{TextSize, UiStyle};
+use crate::ui::tooltip::Tooltip;
+
+const TAB_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const ACTIVE_TAB_COLOR: Color = Color::rgb(0.2, 0.5, 0.2);
+/// Tint of the build buttons of towers the player can't pay for
+const UNAFFORDABLE_TINT: Color = Color::rgb(0.4, 0.4, 0.4);
+const UNAFFORDABLE_COST_COLOR: Color = Color::rgb(0.9, 0.3, 0.3);
+const COST_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+/// Pixels the palette moves per line of the mouse wheel
+const SCROLL_LINE_WIDTH: f32 = 40.0;
+
+pub struct BuildMenuPlugin;
+
+impl Plugin for BuildMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BuildMenu>()
+            .add_systems(
+                (
+                    switch_build_category,
+                    scroll_build_palette,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(
+                show_build_buttons
+                    .after(switch_build_category)
+                    .run_if(
+                        resource_changed::<BuildMenu>()
+                            .or_else(resource_changed::<Research>())
+                            // a level of the campaign may forbid some towers
+                            .or_else(resource_added::<ActiveLevel>())
+                            .or_else(resource_removed::<ActiveLevel>())
+                    )
+            )
+            .add_system(shade_unaffordable_build_buttons.run_if(resource_changed::<PlayerResources>()))
+            .add_system(localize_build_buttons.run_if(resource_changed::<Localization>().or_else(resource_changed::<TowerRegistry>())))
+        ;
+    }
+}
+
+/// Tab of the build menu the player looks at
+#[derive(Resource, Default, Debug)]
+pub struct BuildMenu {
+    pub category: BuildCategory,
+}
+
+/// Button starting the placement of the tower with the given id
+#[derive(Component)]
+pub struct BuildButton(pub String);
+
+/// Name label inside a build button
+#[derive(Component)]
+struct BuildNameText;
+
+/// Price label inside a build button
+#[derive(Component)]
+struct BuildCostText;
+
+/// Button opening a tab of the build menu
+#[derive(Component)]
+struct BuildCategoryTab(BuildCategory);
+
+/// Visible part of the build buttons, scrolled with the mouse wheel while hovered
+#[derive(Component)]
+struct BuildPalette;
+
+/// Row of all build buttons inside the palette, moved to the left by `offset` pixels
+#[derive(Component, Default)]
+struct BuildPaletteList {
+    offset: f32,
+}
+
+/// Shown instead of the build buttons when the open tab has nothing to build
+#[derive(Component)]
+struct EmptyCategoryText;
+
+/// Spawns the tabs and the palette of build buttons into the bottom bar
+pub fn spawn_build_menu(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    ui_style: &UiStyle,
+    registry: &TowerRegistry,
+    localization: &Localization,
+) {
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                // takes the space left by the other parts of the bar, the palette scrolls when it needs more
+                flex_grow: 1.0,
+                flex_basis: Val::Px(0.0),
+                overflow: Overflow::Hidden,
+                margin: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle::default(), AccessibleName::new(Role::TabList, "a11y.build_categories")))
+                .with_children(|parent| {
+                    for category in BuildCategory::ALL {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(80.0), Val::Px(26.0)),
+                                        margin: UiRect::horizontal(Val::Px(5.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: TAB_COLOR.into(),
+                                    ..default()
+                                },
+                                BuildCategoryTab(category),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        localization.t(category.label_key()),
+                                        ui_style.text(TextSize::Small, Color::WHITE),
+                                    ),
+                                    LocalizedText(category.label_key()),
+                                ));
+                            });
+                    }
+                });
+
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            size: Size::width(Val::Percent(100.0)),
+                            overflow: Overflow::Hidden,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    // lets the palette notice the hovering mouse, and keeps the wheel from zooming the camera
+                    Interaction::default(),
+                    BuildPalette,
+                ))
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    // as wide as all buttons together, the palette cuts off the rest
+                                    flex_shrink: 0.0,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            BuildPaletteList::default(),
+                            // the build buttons are named after the tower and its price, the stats are in their tooltips
+                            AccessibleName::new(Role::Toolbar, "a11y.build_palette"),
+                        ))
+                        .with_children(|parent| {
+                            for tower in &registry.towers {
+                                spawn_build_button(parent, asset_server, ui_style, localization, tower);
+                            }
+
+                            parent.spawn((
+                                TextBundle {
+                                    style: Style {
+                                        display: Display::None,
+                                        margin: UiRect::all(Val::Px(10.0)),
+                                        ..default()
+                                    },
+                                    ..TextBundle::from_section(
+                                        localization.t("build_menu.empty"),
+                                        ui_style.text(TextSize::Body, Color::GRAY),
+                                    )
+                                },
+                                EmptyCategoryText,
+                                LocalizedText("build_menu.empty"),
+                            ));
+                        });
+                });
+        });
+}
+
+fn spawn_build_button(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    ui_style: &UiStyle,
+    localization: &Localization,
+    tower: &TowerDefinition,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(150.0), Val::Px(65.0)),
+                    margin: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    // center the name and the price
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                image: UiImage {
+                    texture: asset_server.load("images/button-01.png"),
+                    ..default()
+                },
+                ..default()
+            },
+            BuildButton(tower.id.clone()),
+            Tooltip(build_button_tooltip(tower, localization)),
+        ))
+        .with_children(|parent| {
+            if let Some(icon) = &tower.icon {
+                parent.spawn(ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(24.0), Val::Px(24.0)),
+                        ..default()
+                    },
+                    image: UiImage {
+                        texture: asset_server.load(icon.as_str()),
+                        ..default()
+                    },
+                    ..default()
+                });
+            }
+            parent.spawn((
+                TextBundle::from_section(
+                    localization.t(&tower.name),
+                    ui_style.text(TextSize::Body, Color::WHITE),
+                ),
+                BuildNameText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    cost_label(tower, localization),
+                    ui_style.text(TextSize::Small, COST_COLOR),
+                ),
+                BuildCostText,
+            ));
+        });
+}
+
+/// Stats of the tower shown when hovering its build button
+fn build_button_tooltip(definition: &TowerDefinition, localization: &Localization) -> String {
+    let title = format!("{} - {}", localization.t(&definition.name), cost_label(definition, localization));
+    let stats = if definition.is_wall() {
+        title
+    } else {
+        let stats = localization.t_with(
+            "build_menu.tooltip_stats",
+            &[
+                ("damage", format!("{:.0}", definition.damage)),
+                ("fire_rate", format!("{:.1}", definition.fire_rate)),
+                ("range", format!("{:.1}", definition.range)),
+            ],
+        );
+        format!("{}\n{}", title, stats)
+    };
+    if definition.description.is_empty() {
+        stats
+    } else {
+        format!("{}\n{}", stats, localization.t(&definition.description))
+    }
+}
+
+fn cost_label(definition: &TowerDefinition, localization: &Localization) -> String {
+    localization.t_with("build_menu.cost", &[("cost", definition.cost.to_string())])
+}
+
+/// Puts the names, prices and tooltips of the build buttons into the language the player switched to
+fn localize_build_buttons(
+    localization: Res<Localization>,
+    registry: Res<TowerRegistry>,
+    mut buttons: Query<(&BuildButton, &mut Tooltip, &Children)>,
+    mut name_texts: Query<&mut Text, (With<BuildNameText>, Without<BuildCostText>)>,
+    mut cost_texts: Query<&mut Text, (With<BuildCostText>, Without<BuildNameText>)>,
+) {
+    for (button, mut tooltip, children) in &mut buttons {
+        let Some(definition) = registry.get(&button.0) else {
+            continue;
+        };
+
+        tooltip.0 = build_button_tooltip(definition, &localization);
+        let mut names = name_texts.iter_many_mut(children);
+        while let Some(mut text) = names.fetch_next() {
+            text.sections[0].value = localization.t(&definition.name).to_string();
+        }
+        let mut costs = cost_texts.iter_many_mut(children);
+        while let Some(mut text) = costs.fetch_next() {
+            text.sections[0].value = cost_label(definition, &localization);
+        }
+    }
+}
+
+fn switch_build_category(
+    mut menu: ResMut<BuildMenu>,
+    tabs: Query<(&Interaction, &BuildCategoryTab), Changed<Interaction>>,
+) {
+    for (interaction, tab) in &tabs {
+        if *interaction == Interaction::Clicked && menu.category != tab.0 {
+            menu.category = tab.0;
+        }
+    }
+}
+
+/// Shows the researched towers of the open tab the rules of the level allow and scrolls the palette back to its start
+fn show_build_buttons(
+    menu: Res<BuildMenu>,
+    registry: Res<TowerRegistry>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+    active_level: Option<Res<ActiveLevel>>,
+    mut buttons: Query<(&BuildButton, &mut Style), (Without<EmptyCategoryText>, Without<BuildPaletteList>)>,
+    mut empty_texts: Query<&mut Style, (With<EmptyCategoryText>, Without<BuildPaletteList>)>,
+    mut lists: Query<(&mut BuildPaletteList, &mut Style)>,
+    mut tabs: Query<(&BuildCategoryTab, &mut BackgroundColor)>,
+) {
+    let mut shown = 0;
+    for (button, mut style) in &mut buttons {
+        let visible = registry.get(&button.0).map_or(false, |definition| {
+            definition.category == menu.category && allows_tower(active_level.as_deref(), definition)
+        })
+            && research.is_tower_unlocked(&button.0, &techs);
+        style.display = if visible { Display::Flex } else { Display::None };
+        if visible {
+            shown += 1;
+        }
+    }
+
+    for mut style in &mut empty_texts {
+        style.display = if shown == 0 { Display::Flex } else { Display::None };
+    }
+
+    for (mut list, mut style) in &mut lists {
+        list.offset = 0.0;
+        style.position.left = Val::Px(0.0);
+    }
+
+    for (tab, mut color) in &mut tabs {
+        *color = if tab.0 == menu.category { ACTIVE_TAB_COLOR } else { TAB_COLOR }.into();
+    }
+}
+
+/// Moves the build buttons sideways with the mouse wheel, only as far as there are buttons hidden
+fn scroll_build_palette(
+    mut wheel_events: EventReader<MouseWheel>,
+    palettes: Query<(&Interaction, &Node, &Children), With<BuildPalette>>,
+    mut lists: Query<(&mut BuildPaletteList, &mut Style, &Node)>,
+) {
+    let scrolled: f32 = wheel_events
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y * SCROLL_LINE_WIDTH,
+            MouseScrollUnit::Pixel => event.y,
+        })
+        .sum();
+    if scrolled == 0.0 {
+        return;
+    }
+
+    for (interaction, palette, children) in &palettes {
+        if *interaction == Interaction::None {
+            continue;
+        }
+        let mut iter = lists.iter_many_mut(children);
+        while let Some((mut list, mut style, node)) = iter.fetch_next() {
+            let hidden = (node.size().x - palette.size().x).max(0.0);
+            // scrolling the wheel down reveals the buttons further right
+            list.offset = (list.offset - scrolled).clamp(0.0, hidden);
+            style.position.left = Val::Px(-list.offset);
+        }
+    }
+}
+
+/// Greys out the build buttons of towers costing more gold than the player has
+fn shade_unaffordable_build_buttons(
+    resources: Res<PlayerResources>,
+    registry: Res<TowerRegistry>,
+    mut buttons: Query<(&BuildButton, &mut BackgroundColor, &Children)>,
+    mut cost_texts: Query<&mut Text, With<BuildCostText>>,
+) {
+    for (button, mut color, children) in &mut buttons {
+        let Some(definition) = registry.get(&button.0) else {
+            continue;
+        };
+        let affordable = definition.cost <= resources.gold;
+
+        // the background color tints the image of the button
+        *color = if affordable { Color::WHITE } else { UNAFFORDABLE_TINT }.into();
+        let mut texts = cost_texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].style.color = if affordable { COST_COLOR } else { UNAFFORDABLE_COST_COLOR };
+        }
+    }
+}
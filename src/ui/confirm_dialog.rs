@@ -0,0 +1,213 @@
+use bevy::a11y::accesskit::Role;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::UiAction;
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::accessibility::{AccessibleName, LiveRegion};
+use crate::ui::gamepad::MenuFocus;
+use crate::ui::style::{TextSize, UiStyle};
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+
+pub struct ConfirmDialogPlugin;
+
+impl Plugin for ConfirmDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // built before the focus is drawn, so the confirm button is there when it gets the focus
+            .add_system(build_confirm_dialogs.in_base_set(CoreSet::PreUpdate))
+            // dialogs are opened in the game as well as in the game menu
+            .add_systems((switch_dialog_focus, answer_confirm_dialogs))
+            // a question about the game left behind is outdated
+            .add_system(close_confirm_dialogs.in_schedule(OnExit(GameState::Playing)))
+            .add_system(close_confirm_dialogs.in_schedule(OnExit(GameState::Paused)))
+        ;
+    }
+}
+
+/// Modal question with a confirm and a cancel button, sends its event only when confirmed
+#[derive(Component)]
+pub struct ConfirmDialog {
+    message: String,
+    /// sends the event, taken when the dialog is confirmed
+    on_confirm: Option<Box<dyn FnOnce(&mut World) + Send + Sync>>,
+    /// button focused before the dialog opened, focused again once it is closed
+    previous_focus: Option<Entity>,
+}
+
+impl ConfirmDialog {
+    /// Opens a dialog asking `message`, `on_confirm_event` is sent when the player confirms
+    pub fn spawn<E: Event>(commands: &mut Commands, message: impl Into<String>, on_confirm_event: E) -> Entity {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::all(Val::Percent(100.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    // above the menus and the tooltips of the buttons behind it
+                    z_index: ZIndex::Global(15),
+                    // the backdrop catches the clicks meant for the game and the menus behind it
+                    focus_policy: FocusPolicy::Block,
+                    ..default()
+                },
+                Interaction::default(),
+                ConfirmDialog {
+                    message: message.into(),
+                    on_confirm: Some(Box::new(move |world: &mut World| world.send_event(on_confirm_event))),
+                    previous_focus: None,
+                },
+            ))
+            .id()
+    }
+}
+
+/// Answer button of a dialog
+#[derive(Component)]
+pub struct ConfirmDialogButton {
+    dialog: Entity,
+    confirm: bool,
+}
+
+/// Run condition for systems reacting to inputs an open dialog takes for itself, like Escape
+pub fn no_confirm_dialog(dialogs: Query<(), With<ConfirmDialog>>) -> bool {
+    dialogs.is_empty()
+}
+
+fn build_confirm_dialogs(
+    mut commands: Commands,
+    ui_style: Res<UiStyle>,
+    localization: Res<Localization>,
+    mut focus: ResMut<MenuFocus>,
+    mut dialogs: Query<(Entity, &mut ConfirmDialog), Added<ConfirmDialog>>,
+) {
+    for (entity, mut dialog) in &mut dialogs {
+        let text_style = ui_style.text(TextSize::Heading, Color::rgb(0.9, 0.9, 0.9));
+
+        let mut confirm_button = None;
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(dialog.message.clone(), text_style.clone()));
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    margin: UiRect::top(Val::Px(15.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            // the question is read out together with the answers as soon as the dialog opens
+                            AccessibleName::new(Role::Dialog, dialog.message.clone()),
+                            LiveRegion,
+                        ))
+                        .with_children(|parent| {
+                            confirm_button = Some(spawn_dialog_button(parent, localization.t("dialog.yes"), &text_style, entity, true));
+                            spawn_dialog_button(parent, localization.t("dialog.no"), &text_style, entity, false);
+                        });
+                });
+        });
+
+        // keyboard and gamepad answer right away, Enter or A confirms
+        dialog.previous_focus = focus.get();
+        focus.set(confirm_button);
+    }
+}
+
+fn spawn_dialog_button(parent: &mut ChildBuilder, label: &str, text_style: &TextStyle, dialog: Entity, confirm: bool) -> Entity {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(120.0), Val::Px(50.0)),
+                    margin: UiRect::all(Val::Px(5.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            ConfirmDialogButton { dialog, confirm },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        })
+        .id()
+}
+
+/// Tab and the arrow keys move the focus between the two answers, the D-pad does so through the menu navigation
+fn switch_dialog_focus(
+    keys: Res<Input<KeyCode>>,
+    mut focus: ResMut<MenuFocus>,
+    buttons: Query<Entity, With<ConfirmDialogButton>>,
+) {
+    if !keys.any_just_pressed([KeyCode::Tab, KeyCode::Left, KeyCode::Right]) {
+        return;
+    }
+    let current = focus.get();
+    if let Some(other) = buttons.iter().find(|button| Some(*button) != current) {
+        focus.set(Some(other));
+    }
+}
+
+/// Closes the dialog on a clicked answer or on cancel, sending the event if it was confirmed
+fn answer_confirm_dialogs(
+    mut commands: Commands,
+    mut focus: ResMut<MenuFocus>,
+    mut dialogs: Query<(Entity, &mut ConfirmDialog)>,
+    buttons: Query<(&Interaction, &ConfirmDialogButton), Changed<Interaction>>,
+    ui_actions: Query<&ActionState<UiAction>>,
+) {
+    let mut answers: Vec<(Entity, bool)> = buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| (button.dialog, button.confirm))
+        .collect();
+
+    // Escape, right-click or B dismiss all open dialogs
+    if ui_actions.get_single().map_or(false, |state| state.just_pressed(UiAction::Cancel)) {
+        answers.extend(dialogs.iter().map(|(entity, _)| (entity, false)));
+    }
+
+    // a confirmation wins over a cancel in the same frame
+    answers.sort_by_key(|(entity, confirmed)| (*entity, !*confirmed));
+    answers.dedup_by_key(|(entity, _)| *entity);
+
+    for (entity, confirmed) in answers {
+        let Ok((_, mut dialog)) = dialogs.get_mut(entity) else {
+            continue;
+        };
+        if confirmed {
+            if let Some(send_event) = dialog.on_confirm.take() {
+                commands.add(send_event);
+            }
+        }
+        focus.set(dialog.previous_focus);
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn close_confirm_dialogs(mut commands: Commands, dialogs: Query<Entity, With<ConfirmDialog>>) {
+    for entity in &dialogs {
+        commands.entity(entity).despawn_recursive();
+    }
+}
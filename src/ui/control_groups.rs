@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::Action;
+use crate::gameplay::buildings::{CombatRecord, HasAttack};
+use crate::gameplay::towers::{Tower, TowerLevel, TowerRegistry};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::selection::Selection;
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Number keys 1 to 9, the index into the array is the index of the control group
+pub const DIGIT_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Actions storing the selection in a control group, the index into the array is the index of the group
+pub const ASSIGN_ACTIONS: [Action; 9] = [
+    Action::AssignGroup1,
+    Action::AssignGroup2,
+    Action::AssignGroup3,
+    Action::AssignGroup4,
+    Action::AssignGroup5,
+    Action::AssignGroup6,
+    Action::AssignGroup7,
+    Action::AssignGroup8,
+    Action::AssignGroup9,
+];
+
+/// Actions selecting a control group again, in the order of `ASSIGN_ACTIONS`
+pub const RECALL_ACTIONS: [Action; 9] = [
+    Action::RecallGroup1,
+    Action::RecallGroup2,
+    Action::RecallGroup3,
+    Action::RecallGroup4,
+    Action::RecallGroup5,
+    Action::RecallGroup6,
+    Action::RecallGroup7,
+    Action::RecallGroup8,
+    Action::RecallGroup9,
+];
+
+pub struct ControlGroupsPlugin;
+
+impl Plugin for ControlGroupsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ControlGroups>()
+            .add_startup_system(spawn_group_panel)
+            .add_systems(
+                (
+                    assign_and_recall_groups,
+                    update_group_panel.after(assign_and_recall_groups),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(hide_group_panel.in_schedule(OnExit(GameState::Playing)))
+        ;
+    }
+}
+
+/// Towers stored with Ctrl and a number key, selected again with Shift and the same key by default
+#[derive(Resource, Default, Debug)]
+pub struct ControlGroups {
+    pub groups: [Vec<Entity>; ASSIGN_ACTIONS.len()],
+}
+
+/// Stats of all selected towers side by side, shown while more than one is selected
+#[derive(Component)]
+struct GroupPanel;
+
+#[derive(Component)]
+struct GroupPanelText;
+
+fn spawn_group_panel(mut commands: Commands, ui_style: Res<UiStyle>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.0),
+                        top: Val::Percent(35.0),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.15, 0.15, 0.15, 0.8).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            GroupPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    ui_style.text(TextSize::Small, Color::WHITE),
+                ),
+                GroupPanelText,
+            ));
+        });
+}
+
+fn assign_and_recall_groups(
+    actions: Query<&ActionState<Action>>,
+    mut groups: ResMut<ControlGroups>,
+    mut selection: ResMut<Selection>,
+    towers: Query<(), With<Tower>>,
+) {
+    let Ok(action_state) = actions.get_single() else {
+        return;
+    };
+
+    for (index, (assign, recall)) in ASSIGN_ACTIONS.iter().zip(RECALL_ACTIONS.iter()).enumerate() {
+        if action_state.just_pressed(*assign) {
+            // only towers can be grouped, a selected enemy is left out
+            groups.groups[index] = selection.entities
+                .iter()
+                .filter(|entity| towers.contains(**entity))
+                .copied()
+                .collect();
+        } else if action_state.just_pressed(*recall) {
+            // sold towers drop out of the group
+            groups.groups[index].retain(|entity| towers.contains(*entity));
+            selection.entities = groups.groups[index].clone();
+        }
+    }
+}
+
+fn update_group_panel(
+    selection: Res<Selection>,
+    registry: Res<TowerRegistry>,
+    localization: Res<Localization>,
+    towers: Query<(&Tower, &TowerLevel, Option<&HasAttack>, Option<&CombatRecord>)>,
+    mut panels: Query<&mut Visibility, With<GroupPanel>>,
+    mut texts: Query<&mut Text, With<GroupPanelText>>,
+) {
+    let rows: Vec<String> = selection.entities
+        .iter()
+        .filter_map(|entity| towers.get(*entity).ok())
+        .map(|(tower, level, attack, record)| {
+            let name = registry.get(&tower.id).map_or(tower.id.as_str(), |definition| localization.t(&definition.name));
+            let mut args = vec![("name", name.to_string()), ("level", (level.0 + 1).to_string())];
+            match (attack, record) {
+                (Some(attack), Some(record)) => {
+                    args.push(("damage", format!("{:.0}", attack.damage)));
+                    args.push(("fire_rate", format!("{:.1}", 1.0 / attack.timer.duration().as_secs_f32())));
+                    args.push(("kills", record.kills.to_string()));
+                    localization.t_with("control_groups.tower_stats", &args)
+                }
+                _ => localization.t_with("control_groups.tower", &args),
+            }
+        })
+        .collect();
+
+    for mut visibility in &mut panels {
+        let shown = if rows.len() > 1 { Visibility::Visible } else { Visibility::Hidden };
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+    if rows.len() < 2 {
+        return;
+    }
+
+    for mut text in &mut texts {
+        text.sections[0].value = format!(
+            "{}\n{}",
+            localization.t_with("control_groups.selected", &[("count", rows.len().to_string())]),
+            rows.join("\n"),
+        );
+    }
+}
+
+fn hide_group_panel(mut panels: Query<&mut Visibility, With<GroupPanel>>) {
+    for mut visibility in &mut panels {
+        *visibility = Visibility::Hidden;
+    }
+}
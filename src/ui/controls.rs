@@ -0,0 +1,316 @@
+use std::fs;
+
+use bevy::a11y::accesskit::Role;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use leafwing_input_manager::user_input::InputKind;
+use serde::{Deserialize, Serialize};
+
+use crate::{Action, UiAction};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Bindings changed by the player are stored here
+const BINDINGS_PATH: &str = "controls.ron";
+
+const ROW_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const PENDING_ROW_COLOR: Color = Color::rgb(0.35, 0.35, 0.15);
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<BindingsChanged>()
+            .add_startup_system(load_bindings.in_base_set(StartupSet::PostStartup))
+            .add_systems(
+                (
+                    toggle_controls_menu,
+                    start_rebinding,
+                    capture_new_binding.run_if(resource_exists::<PendingRebind>()),
+                    render_controls_menu
+                        .after(toggle_controls_menu)
+                        .after(start_rebinding)
+                        .after(capture_new_binding),
+                ).in_set(OnUpdate(GameState::Paused))
+            )
+            .add_system(close_controls_menu.in_schedule(OnExit(GameState::Paused)))
+        ;
+    }
+}
+
+/// Button in the game menu opening the controls settings
+#[derive(Component)]
+pub struct ControlsButton;
+
+#[derive(Component)]
+struct ControlsMenu;
+
+/// Action that can be rebound by the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingTarget {
+    Gameplay(Action),
+    Ui(UiAction),
+}
+
+/// Row of the controls menu, clicking it starts rebinding its action
+#[derive(Component)]
+struct RebindButton(BindingTarget);
+
+/// Waiting for the player to press the new input of an action
+#[derive(Resource)]
+pub struct PendingRebind(BindingTarget);
+
+/// The controls menu needs to be redrawn
+struct BindingsChanged;
+
+/// Run condition keeping menu hotkeys from firing while a new binding is captured
+pub fn not_rebinding(pending: Option<Res<PendingRebind>>) -> bool {
+    pending.is_none()
+}
+
+/// Format of the persisted bindings
+#[derive(Serialize, Deserialize)]
+struct SavedBindings {
+    gameplay: InputMap<Action>,
+    ui: InputMap<UiAction>,
+}
+
+fn load_bindings(
+    mut gameplay_maps: Query<&mut InputMap<Action>>,
+    mut ui_maps: Query<&mut InputMap<UiAction>>,
+) {
+    // without saved bindings the defaults stay in place
+    let Ok(content) = fs::read_to_string(BINDINGS_PATH) else {
+        return;
+    };
+
+    let saved: SavedBindings = match ron::from_str(&content) {
+        Ok(saved) => saved,
+        Err(e) => {
+            warn!("Ignoring malformed {}: {}", BINDINGS_PATH, e);
+            return;
+        }
+    };
+
+    for mut map in &mut gameplay_maps {
+        *map = saved.gameplay.clone();
+    }
+    for mut map in &mut ui_maps {
+        *map = saved.ui.clone();
+    }
+}
+
+fn save_bindings(gameplay: &InputMap<Action>, ui: &InputMap<UiAction>) {
+    let saved = SavedBindings {
+        gameplay: gameplay.clone(),
+        ui: ui.clone(),
+    };
+
+    let content = match ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::default()) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("Could not serialize the controls: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = fs::write(BINDINGS_PATH, content) {
+        warn!("Could not save the controls to {}: {}", BINDINGS_PATH, e);
+    }
+}
+
+fn toggle_controls_menu(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ControlsButton>)>,
+    menus: Query<Entity, With<ControlsMenu>>,
+    mut changed: EventWriter<BindingsChanged>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        if menus.is_empty() {
+            changed.send(BindingsChanged);
+        } else {
+            for entity in menus.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            commands.remove_resource::<PendingRebind>();
+        }
+    }
+}
+
+fn close_controls_menu(
+    mut commands: Commands,
+    menus: Query<Entity, With<ControlsMenu>>,
+) {
+    for entity in menus.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<PendingRebind>();
+}
+
+fn start_rebinding(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+    mut changed: EventWriter<BindingsChanged>,
+) {
+    for (interaction, button) in interactions.iter() {
+        if *interaction == Interaction::Clicked {
+            commands.insert_resource(PendingRebind(button.0));
+            changed.send(BindingsChanged);
+        }
+    }
+}
+
+/// Replaces the bindings of the pending action with the first key or gamepad button pressed
+fn capture_new_binding(
+    mut commands: Commands,
+    pending: Res<PendingRebind>,
+    keys: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut gameplay_maps: Query<&mut InputMap<Action>>,
+    mut ui_maps: Query<&mut InputMap<UiAction>>,
+    mut changed: EventWriter<BindingsChanged>,
+) {
+    let input = match keys.get_just_pressed().next() {
+        Some(key) => UserInput::from(*key),
+        None => match gamepad_buttons.get_just_pressed().next() {
+            Some(button) => UserInput::from(button.button_type),
+            None => return,
+        },
+    };
+
+    match pending.0 {
+        BindingTarget::Gameplay(action) => {
+            for mut map in &mut gameplay_maps {
+                map.clear_action(action);
+                map.insert(input.clone(), action);
+            }
+        }
+        BindingTarget::Ui(action) => {
+            for mut map in &mut ui_maps {
+                map.clear_action(action);
+                map.insert(input.clone(), action);
+            }
+        }
+    }
+
+    if let (Ok(gameplay), Ok(ui)) = (gameplay_maps.get_single(), ui_maps.get_single()) {
+        save_bindings(gameplay, ui);
+    }
+
+    commands.remove_resource::<PendingRebind>();
+    changed.send(BindingsChanged);
+}
+
+fn describe_input(input: &UserInput) -> String {
+    match input {
+        UserInput::Single(InputKind::Keyboard(key)) => format!("{:?}", key),
+        UserInput::Single(InputKind::GamepadButton(button)) => format!("Pad {:?}", button),
+        UserInput::Single(InputKind::MouseWheel(direction)) => format!("Wheel {:?}", direction),
+        UserInput::Chord(inputs) => inputs
+            .iter()
+            .map(|input| describe_input(&UserInput::Single(input.clone())))
+            .collect::<Vec<_>>()
+            .join(" + "),
+        other => format!("{:?}", other),
+    }
+}
+
+fn describe_bindings<'a>(inputs: impl Iterator<Item=&'a UserInput>, localization: &Localization) -> String {
+    let inputs: Vec<String> = inputs.map(describe_input).collect();
+    if inputs.is_empty() {
+        localization.t("controls.unbound").to_string()
+    } else {
+        inputs.join(", ")
+    }
+}
+
+fn render_controls_menu(
+    mut commands: Commands,
+    mut changed: EventReader<BindingsChanged>,
+    ui_style: Res<UiStyle>,
+    localization: Res<Localization>,
+    pending: Option<Res<PendingRebind>>,
+    menus: Query<Entity, With<ControlsMenu>>,
+    gameplay_maps: Query<&InputMap<Action>>,
+    ui_maps: Query<&InputMap<UiAction>>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    changed.clear();
+
+    for entity in menus.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (Ok(gameplay), Ok(ui)) = (gameplay_maps.get_single(), ui_maps.get_single()) else {
+        return;
+    };
+
+    let mut rows: Vec<(BindingTarget, String)> = Vec::new();
+    for action in Action::variants() {
+        rows.push((BindingTarget::Gameplay(action), describe_bindings(gameplay.get(action).iter(), &localization)));
+    }
+    for action in UiAction::variants() {
+        rows.push((BindingTarget::Ui(action), describe_bindings(ui.get(action).iter(), &localization)));
+    }
+
+    let text_style = ui_style.text(TextSize::Large, Color::rgb(0.9, 0.9, 0.9));
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            z_index: ZIndex::Global(5),
+            ..default()
+        })
+        .insert((ControlsMenu, AccessibleName::new(Role::Menu, "menu.controls")))
+        .with_children(|parent| {
+            for (target, bindings) in rows {
+                let is_pending = pending.as_ref().map_or(false, |p| p.0 == target);
+                let name = match target {
+                    BindingTarget::Gameplay(action) => format!("{:?}", action),
+                    BindingTarget::Ui(action) => format!("{:?}", action),
+                };
+                let label = if is_pending {
+                    localization.t_with("controls.press_key", &[("action", name)])
+                } else {
+                    format!("{}: {}", name, bindings)
+                };
+
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(360.0), Val::Px(28.0)),
+                            align_items: AlignItems::Center,
+                            padding: UiRect::horizontal(Val::Px(8.0)),
+                            margin: UiRect::vertical(Val::Px(2.0)),
+                            ..default()
+                        },
+                        background_color: if is_pending { PENDING_ROW_COLOR } else { ROW_COLOR }.into(),
+                        ..default()
+                    })
+                    .insert(RebindButton(target))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                    });
+            }
+        });
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::camera::PlayerCamera;
+use crate::gameplay::combat::CriticalHitDealt;
+use crate::state::global::GameState;
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Time a damage number stays on screen
+const NUMBER_LIFETIME: Duration = Duration::from_millis(800);
+/// World units a damage number rises over its lifetime
+const NUMBER_RISE: f32 = 0.4;
+
+pub struct DamageNumbersPlugin;
+
+impl Plugin for DamageNumbersPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(
+                (
+                    spawn_crit_numbers,
+                    float_damage_numbers.after(spawn_crit_numbers),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(clear_damage_numbers.in_schedule(OnExit(GameState::Playing)))
+        ;
+    }
+}
+
+/// Damage shown above the spot an enemy got hit, rising and fading out
+#[derive(Component)]
+struct DamageNumber {
+    position: Vec3,
+    timer: Timer,
+}
+
+fn spawn_crit_numbers(
+    mut commands: Commands,
+    mut crit_reader: EventReader<CriticalHitDealt>,
+    ui_style: Res<UiStyle>,
+) {
+    for crit in crit_reader.iter() {
+        commands.spawn((
+            TextBundle::from_section(
+                format!("{:.0}!", crit.amount),
+                ui_style.text(TextSize::Large, Color::rgb(1.0, 0.85, 0.2)),
+            )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                }),
+            DamageNumber {
+                position: crit.position,
+                timer: Timer::new(NUMBER_LIFETIME, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+fn float_damage_numbers(
+    mut commands: Commands,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    mut numbers: Query<(Entity, &mut DamageNumber, &mut Style, &mut Text, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+
+    for (e, mut number, mut style, mut text, mut visibility) in &mut numbers {
+        number.timer.tick(time.delta());
+        if number.timer.finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+
+        let position = number.position + Vec3::Y * NUMBER_RISE * number.timer.percent();
+        // the viewport position starts at the bottom left corner of the window
+        let Some(screen) = camera.world_to_viewport(camera_transform, position) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        style.position = UiRect {
+            left: Val::Px(screen.x),
+            bottom: Val::Px(screen.y),
+            ..default()
+        };
+        text.sections[0].style.color.set_a(number.timer.percent_left());
+    }
+}
+
+fn clear_damage_numbers(mut commands: Commands, numbers: Query<Entity, With<DamageNumber>>) {
+    for e in &numbers {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,230 @@
+use bevy::a11y::accesskit::Role;
+use bevy::prelude::*;
+
+use crate::StartNewGame;
+use crate::gameplay::campaign::{ActiveLevel, Campaign};
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::stats::GameStats;
+use crate::gameplay::towers::TowerRegistry;
+use crate::headless::HeadlessOptions;
+use crate::localization::Localization;
+use crate::profile::{CurrentMap, GameResult, MAX_STARS, record_finished_game};
+use crate::state::global::GameState;
+use crate::ui::accessibility::{AccessibleName, LiveRegion};
+use crate::ui::main_menu::map_name;
+use crate::ui::stats::{spawn_stat_lines, stat_lines};
+use crate::ui::style::{TextSize, UiStyle};
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const STAR_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+const MISSING_STAR_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+
+pub struct EndScreenPlugin;
+
+impl Plugin for EndScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // the screen shows what the profile got out of the game, the result is inserted through commands
+            .add_system(apply_system_buffers.after(record_finished_game).in_schedule(OnEnter(GameState::Victory)))
+            .add_system(apply_system_buffers.after(record_finished_game).in_schedule(OnEnter(GameState::GameOver)))
+            // simulated games record no result and print their own outcome
+            .add_system(spawn_end_screen.after(apply_system_buffers).in_schedule(OnEnter(GameState::Victory)).run_if(not(resource_exists::<HeadlessOptions>())))
+            .add_system(spawn_end_screen.after(apply_system_buffers).in_schedule(OnEnter(GameState::GameOver)).run_if(not(resource_exists::<HeadlessOptions>())))
+            .add_system(on_end_screen_button_clicked.in_set(OnUpdate(GameState::Victory)))
+            .add_system(on_end_screen_button_clicked.in_set(OnUpdate(GameState::GameOver)))
+            .add_system(remove_end_screen.in_schedule(OnExit(GameState::Victory)))
+            .add_system(remove_end_screen.in_schedule(OnExit(GameState::GameOver)))
+        ;
+    }
+}
+
+#[derive(Component)]
+struct EndScreen;
+
+#[derive(Component, Clone, Debug)]
+enum EndScreenButton {
+    /// starts the map or the campaign level following the one just won
+    NextMap(StartNewGame),
+    Replay,
+    MainMenu,
+}
+
+/// Full screen summary once the game is won or lost
+fn spawn_end_screen(
+    mut commands: Commands,
+    ui_style: Res<UiStyle>,
+    stats: Res<GameStats>,
+    registry: Res<TowerRegistry>,
+    result: Res<GameResult>,
+    difficulty: Res<Difficulty>,
+    campaign: Res<Campaign>,
+    active_level: Option<Res<ActiveLevel>>,
+    localization: Res<Localization>,
+) {
+    let title = localization.t(if result.won { "end_screen.victory" } else { "end_screen.game_over" });
+    // the campaign goes on with its own next level instead of the next map
+    let (next_label, next_game) = match &active_level {
+        Some(level) => ("end_screen.next_level", campaign.start_level(level.index + 1).filter(|_| result.won)),
+        None => ("end_screen.next_map", result.next_map.as_ref().map(|path| StartNewGame {
+            map: path.clone(),
+            difficulty: *difficulty,
+            level: None,
+        })),
+    };
+    let text_style = ui_style.text(TextSize::Subtitle, Color::rgb(0.9, 0.9, 0.9));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::all(Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            EndScreen,
+            // the result is announced right away, the score and the rewards follow as labels
+            AccessibleName::new(Role::Dialog, title),
+            LiveRegion,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    title,
+                    ui_style.text(TextSize::Title, Color::WHITE),
+                )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(20.0)),
+                        ..default()
+                    }),
+            );
+
+            if result.won {
+                spawn_stars(parent, result.stars, 40.0);
+            }
+
+            let score_key = if result.new_best { "end_screen.score_new_best" } else { "end_screen.score" };
+            let score = localization.t_with(score_key, &[("score", result.score.to_string())]);
+            spawn_stat_lines(parent, vec![score], &TextStyle { font_size: ui_style.font_size(TextSize::Heading), ..text_style.clone() });
+            spawn_stat_lines(parent, stat_lines(&stats, &registry, &localization), &text_style);
+            spawn_stat_lines(parent, reward_lines(&result, &registry, &localization), &TextStyle { color: STAR_COLOR, ..text_style.clone() });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let button_style = TextStyle { font_size: ui_style.font_size(TextSize::Heading), ..text_style.clone() };
+                    if let Some(next_game) = next_game {
+                        spawn_end_screen_button(parent, localization.t(next_label), &button_style, EndScreenButton::NextMap(next_game));
+                    }
+                    spawn_end_screen_button(parent, localization.t("end_screen.replay"), &button_style, EndScreenButton::Replay);
+                    spawn_end_screen_button(parent, localization.t("end_screen.main_menu"), &button_style, EndScreenButton::MainMenu);
+                });
+        });
+}
+
+/// Row of stars of the given size, the earned ones lit up
+pub fn spawn_stars(parent: &mut ChildBuilder, stars: u8, size: f32) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::vertical(Val::Px(size / 3.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for star in 0..MAX_STARS {
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        size: Size::all(Val::Px(size)),
+                        margin: UiRect::horizontal(Val::Px(size / 5.0)),
+                        ..default()
+                    },
+                    background_color: if star < stars { STAR_COLOR } else { MISSING_STAR_COLOR }.into(),
+                    ..default()
+                });
+            }
+        });
+}
+
+/// What the profile got out of the game, on top of the statistics
+fn reward_lines(result: &GameResult, registry: &TowerRegistry, localization: &Localization) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(map) = &result.unlocked_map {
+        lines.push(localization.t_with("end_screen.map_unlocked", &[("name", map_name(map))]));
+    }
+    for id in &result.unlocked_towers {
+        let name = registry.get(id).map_or(id.as_str(), |definition| localization.t(&definition.name));
+        lines.push(localization.t_with("end_screen.tower_unlocked", &[("name", name.to_string())]));
+    }
+    for achievement in &result.achievements {
+        lines.push(localization.t_with("toast.achievement", &[("name", localization.t(achievement.label_key()).to_string())]));
+    }
+    lines
+}
+
+fn spawn_end_screen_button(parent: &mut ChildBuilder, label: &str, text_style: &TextStyle, button: EndScreenButton) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(220.0), Val::Px(65.0)),
+                    margin: UiRect::all(Val::Px(5.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+fn on_end_screen_button_clicked(
+    interactions: Query<(&Interaction, &EndScreenButton), Changed<Interaction>>,
+    current_map: Res<CurrentMap>,
+    difficulty: Res<Difficulty>,
+    active_level: Option<Res<ActiveLevel>>,
+    mut new_game_writer: EventWriter<StartNewGame>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interactions {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            EndScreenButton::NextMap(new_game) => new_game_writer.send(new_game.clone()),
+            EndScreenButton::Replay => {
+                new_game_writer.send(StartNewGame {
+                    map: current_map.0.clone(),
+                    difficulty: *difficulty,
+                    level: active_level.as_ref().map(|level| level.index),
+                });
+            }
+            EndScreenButton::MainMenu => next_state.set(GameState::MainMenu),
+        }
+    }
+}
+
+fn remove_end_screen(mut commands: Commands, screens: Query<Entity, With<EndScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy_mod_picking::focus::HoverMap;
+
+use crate::gameplay::combat::Armor;
+use crate::gameplay::effects::StatusEffects;
+use crate::gameplay::enemy::{EnemyKind, EnemyTag, Health, Shield};
+use crate::gameplay::movement::MovementSpeed;
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Distance between the mouse cursor and the tooltip
+const CURSOR_OFFSET: f32 = 16.0;
+
+pub struct EnemyTooltipPlugin;
+
+impl Plugin for EnemyTooltipPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_tooltip)
+            .add_system(update_tooltip.in_set(OnUpdate(GameState::Playing)))
+            .add_system(hide_tooltip.in_schedule(OnExit(GameState::Playing)))
+        ;
+    }
+}
+
+/// Small box following the cursor with the details of the hovered enemy
+#[derive(Component)]
+struct EnemyTooltip;
+
+#[derive(Component)]
+struct EnemyTooltipText;
+
+fn spawn_tooltip(mut commands: Commands, ui_style: Res<UiStyle>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.1, 0.1, 0.85).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            EnemyTooltip,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    ui_style.text(TextSize::Small, Color::WHITE),
+                ),
+                EnemyTooltipText,
+            ));
+        });
+}
+
+fn update_tooltip(
+    hover_map: Res<HoverMap>,
+    windows: Query<&Window>,
+    enemies: Query<(&EnemyKind, &Health, &Armor, &StatusEffects, &MovementSpeed, Option<&Shield>), With<EnemyTag>>,
+    localization: Res<Localization>,
+    mut tooltips: Query<(&mut Style, &mut Visibility), With<EnemyTooltip>>,
+    mut texts: Query<&mut Text, With<EnemyTooltipText>>,
+) {
+    let hovered = hover_map.0
+        .values()
+        .flat_map(|hits| hits.keys())
+        .find_map(|entity| enemies.get(*entity).ok());
+    let cursor = windows.get_single().ok().and_then(|window| window.cursor_position());
+
+    let Ok((mut style, mut visibility)) = tooltips.get_single_mut() else {
+        return;
+    };
+    let (Some((kind, health, armor, effects, speed, shield)), Some(cursor)) = (hovered, cursor) else {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+
+    // the cursor position starts at the bottom left corner of the window
+    style.position = UiRect {
+        left: Val::Px(cursor.x + CURSOR_OFFSET),
+        bottom: Val::Px(cursor.y + CURSOR_OFFSET),
+        ..default()
+    };
+    *visibility = Visibility::Visible;
+
+    let lines = enemy_details(kind, health, armor, effects, speed, shield, &localization);
+    for mut text in &mut texts {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+/// Lines describing the enemy, shared by the tooltip and the panel of the selected enemy
+pub fn enemy_details(
+    kind: &EnemyKind,
+    health: &Health,
+    armor: &Armor,
+    effects: &StatusEffects,
+    speed: &MovementSpeed,
+    shield: Option<&Shield>,
+    localization: &Localization,
+) -> Vec<String> {
+    let stats = kind.stats();
+    let mut lines = vec![
+        localization.t(stats.name).to_string(),
+        localization.t_with(
+            "enemy_details.health",
+            &[("current", format!("{:.0}", health.current.max(0.0))), ("max", format!("{:.0}", health.max))],
+        ),
+        localization.t_with("enemy_details.speed", &[("speed", format!("{:.1}", speed.resolved()))]),
+        localization.t_with(
+            "enemy_details.armor",
+            &[
+                ("physical", format!("{:.0}", armor.physical)),
+                ("magic", format!("{:.0}", armor.magic * 100.0)),
+                ("explosive", format!("{:.0}", armor.explosive * 100.0)),
+            ],
+        ),
+    ];
+    if let Some(aura) = kind.aura() {
+        lines.push(localization.t(aura.kind.label_key()).to_string());
+    }
+    if let Some(shield) = shield {
+        lines.push(localization.t_with("enemy_details.shield", &[("hits", shield.charges.to_string())]));
+    }
+    if let Some(splits) = kind.splits() {
+        lines.push(localization.t_with(
+            "enemy_details.splits",
+            &[("count", splits.count.to_string()), ("name", localization.t(splits.child_kind.stats().name).to_string())],
+        ));
+    }
+    if !effects.active.is_empty() {
+        let mut labels: Vec<&str> = effects.active.iter().map(|active| localization.t(active.effect.kind.label_key())).collect();
+        // poison stacks show up once
+        labels.sort();
+        labels.dedup();
+        lines.push(localization.t_with("enemy_details.effects", &[("effects", labels.join(", "))]));
+    }
+    lines
+}
+
+fn hide_tooltip(mut tooltips: Query<&mut Visibility, With<EnemyTooltip>>) {
+    for mut visibility in &mut tooltips {
+        *visibility = Visibility::Hidden;
+    }
+}
@@ -0,0 +1,281 @@
+use std::time::Duration;
+
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use hexx::Hex;
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::*;
+
+use crate::{Action, HexFieldClicked, Map, UiAction};
+use crate::camera::CameraController;
+use crate::ui::confirm_dialog::ConfirmDialogButton;
+use crate::state::global::GameState;
+use crate::ui::player::BuildingPlacement;
+
+/// Tint of the button the gamepad focus is on
+const FOCUS_COLOR: Color = Color::rgb(0.9, 0.75, 0.2);
+/// Delay between two cursor steps while the stick is held
+const CURSOR_REPEAT: Duration = Duration::from_millis(180);
+/// Stick deflection needed to move the hex cursor
+const CURSOR_THRESHOLD: f32 = 0.5;
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MenuFocus>()
+            .add_system(
+                confirm_focused_button
+                    .run_if(not(resource_exists::<HexCursor>()))
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputManagerSystem::Update)
+                    .after(UiSystem::Focus)
+            )
+            .add_system(release_virtual_clicks.in_base_set(CoreSet::Last))
+            .add_systems((navigate_menu, show_menu_focus.after(navigate_menu)))
+            .add_systems(
+                (
+                    drop_hex_cursor,
+                    move_hex_cursor
+                        .run_if(resource_exists::<BuildingPlacement>())
+                        .after(drop_hex_cursor),
+                    confirm_hex_cursor
+                        .run_if(resource_exists::<HexCursor>())
+                        .after(move_hex_cursor),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Button selected with the D-pad
+#[derive(Resource, Default)]
+pub struct MenuFocus(Option<Entity>);
+
+impl MenuFocus {
+    pub fn get(&self) -> Option<Entity> {
+        self.0
+    }
+
+    pub fn set(&mut self, entity: Option<Entity>) {
+        self.0 = entity;
+    }
+}
+
+/// Remembers the color of the focused button
+#[derive(Component)]
+struct FocusIndicator(BackgroundColor);
+
+/// Button clicked with the gamepad, released again at the end of the frame
+#[derive(Component)]
+struct VirtualClick;
+
+/// Hex selected with the stick during building placement, replaces the mouse hover
+#[derive(Resource)]
+pub struct HexCursor {
+    pub hex: Hex,
+    repeat: Timer,
+}
+
+/// Moves the focus to the closest visible button in the pressed direction
+fn navigate_menu(
+    mut focus: ResMut<MenuFocus>,
+    ui_actions: Query<&ActionState<UiAction>>,
+    buttons: Query<(Entity, &GlobalTransform, &ComputedVisibility), With<Button>>,
+    dialog_buttons: Query<(), With<ConfirmDialogButton>>,
+) {
+    let Ok(action_state) = ui_actions.get_single() else {
+        return;
+    };
+
+    // ui coordinates grow downwards
+    let direction = if action_state.just_pressed(UiAction::NavigateUp) {
+        Vec2::NEG_Y
+    } else if action_state.just_pressed(UiAction::NavigateDown) {
+        Vec2::Y
+    } else if action_state.just_pressed(UiAction::NavigateLeft) {
+        Vec2::NEG_X
+    } else if action_state.just_pressed(UiAction::NavigateRight) {
+        Vec2::X
+    } else {
+        return;
+    };
+
+    let visible = buttons
+        .iter()
+        .filter(|(_, _, visibility)| visibility.is_visible())
+        // an open dialog keeps the focus to itself
+        .filter(|(e, _, _)| dialog_buttons.is_empty() || dialog_buttons.contains(*e))
+        .map(|(e, transform, _)| (e, transform.translation().truncate()));
+
+    let current = focus.0.and_then(|e| buttons.get(e).ok());
+    let Some((_, current_transform, _)) = current else {
+        // nothing focused yet, start with the top left button
+        focus.0 = visible
+            .min_by(|(_, a), (_, b)| (a.x + a.y).total_cmp(&(b.x + b.y)))
+            .map(|(e, _)| e);
+        return;
+    };
+    let from = current_transform.translation().truncate();
+
+    // prefer buttons lined up with the direction over closer ones off to the side
+    let next = visible
+        .filter_map(|(e, pos)| {
+            let offset = pos - from;
+            let along = offset.dot(direction);
+            if along <= 1.0 {
+                return None;
+            }
+            let across = (offset - direction * along).length();
+            Some((e, along + across * 2.0))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(e, _)| e);
+
+    if next.is_some() {
+        focus.0 = next;
+    }
+}
+
+fn show_menu_focus(
+    mut commands: Commands,
+    focus: Res<MenuFocus>,
+    mut indicated: Query<(Entity, &mut BackgroundColor, &FocusIndicator)>,
+    mut buttons: Query<&mut BackgroundColor, (With<Button>, Without<FocusIndicator>)>,
+) {
+    if !focus.is_changed() {
+        return;
+    }
+
+    for (entity, mut color, indicator) in &mut indicated {
+        if Some(entity) != focus.0 {
+            *color = indicator.0;
+            commands.entity(entity).remove::<FocusIndicator>();
+        }
+    }
+
+    let Some(entity) = focus.0 else {
+        return;
+    };
+    if let Ok(mut color) = buttons.get_mut(entity) {
+        commands.entity(entity).insert(FocusIndicator(*color));
+        *color = FOCUS_COLOR.into();
+    }
+}
+
+/// Clicks the focused button, before the ui systems check for clicks
+fn confirm_focused_button(
+    mut commands: Commands,
+    focus: Res<MenuFocus>,
+    ui_actions: Query<&ActionState<UiAction>>,
+    mut buttons: Query<&mut Interaction, With<Button>>,
+) {
+    let Ok(action_state) = ui_actions.get_single() else {
+        return;
+    };
+    if !action_state.just_pressed(UiAction::Confirm) {
+        return;
+    }
+
+    let Some(entity) = focus.0 else {
+        return;
+    };
+    if let Ok(mut interaction) = buttons.get_mut(entity) {
+        *interaction = Interaction::Clicked;
+        commands.entity(entity).insert(VirtualClick);
+    }
+}
+
+fn release_virtual_clicks(
+    mut commands: Commands,
+    mut clicked: Query<(Entity, &mut Interaction), With<VirtualClick>>,
+) {
+    for (entity, mut interaction) in &mut clicked {
+        *interaction = Interaction::None;
+        commands.entity(entity).remove::<VirtualClick>();
+    }
+}
+
+/// The mouse takes over again as soon as it moves, the cursor is gone with the placement
+fn drop_hex_cursor(
+    mut commands: Commands,
+    mut mouse_motion: EventReader<MouseMotion>,
+    placement: Option<Res<BuildingPlacement>>,
+    cursor: Option<Res<HexCursor>>,
+) {
+    let moved = mouse_motion.iter().count() > 0;
+    if cursor.is_some() && (moved || placement.is_none()) {
+        commands.remove_resource::<HexCursor>();
+    }
+}
+
+fn move_hex_cursor(
+    mut commands: Commands,
+    map: Res<Map>,
+    cursor: Option<ResMut<HexCursor>>,
+    cameras: Query<(&CameraController, &ActionState<Action>)>,
+    time: Res<Time>,
+) {
+    let Ok((controller, action_state)) = cameras.get_single() else {
+        return;
+    };
+    let stick = action_state
+        .axis_pair(Action::MoveCursor)
+        .map(|axis| axis.xy())
+        .unwrap_or(Vec2::ZERO);
+
+    let Some(mut cursor) = cursor else {
+        if stick.length() > CURSOR_THRESHOLD {
+            // start below the center of the view
+            let start = map.layout.world_pos_to_hex(Vec2::new(controller.focus.x, controller.focus.z));
+            commands.insert_resource(HexCursor {
+                hex: if map.entities.contains_key(&start) { start } else { Hex::ZERO },
+                repeat: Timer::new(CURSOR_REPEAT, TimerMode::Once),
+            });
+        }
+        return;
+    };
+
+    if stick.length() <= CURSOR_THRESHOLD {
+        // the next push moves the cursor immediately
+        let duration = cursor.repeat.duration();
+        cursor.repeat.set_elapsed(duration);
+        return;
+    }
+
+    cursor.repeat.tick(time.delta());
+    if !cursor.repeat.finished() {
+        return;
+    }
+    cursor.repeat.reset();
+
+    // step one hex in the stick direction, relative to the camera orientation
+    let rotation = Quat::from_rotation_y(controller.yaw);
+    let world_direction = rotation * Vec3::X * stick.x + rotation * Vec3::NEG_Z * stick.y;
+    let step = world_direction.normalize_or_zero() * map.layout.hex_size.x * 3f32.sqrt();
+    let next = map.layout.world_pos_to_hex(map.layout.hex_to_world_pos(cursor.hex) + Vec2::new(step.x, step.z));
+
+    if map.entities.contains_key(&next) {
+        cursor.hex = next;
+    }
+}
+
+fn confirm_hex_cursor(
+    cursor: Res<HexCursor>,
+    map: Res<Map>,
+    ui_actions: Query<&ActionState<UiAction>>,
+    mut field_clicks: EventWriter<HexFieldClicked>,
+) {
+    let Ok(action_state) = ui_actions.get_single() else {
+        return;
+    };
+    if !action_state.just_pressed(UiAction::Confirm) {
+        return;
+    }
+
+    if let Some(entity) = map.entities.get(&cursor.hex) {
+        field_clicks.send(HexFieldClicked(cursor.hex, *entity));
+    }
+}
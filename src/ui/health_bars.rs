@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+
+use crate::assets::GameAssets;
+use crate::camera::PlayerCamera;
+use crate::gameplay::enemy::{EnemyKind, EnemyTag, Health};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+
+/// Size of a health bar in world units
+pub const BAR_SIZE: Vec2 = Vec2::new(0.3, 0.04);
+/// Gap between the top of an enemy and its health bar
+const BAR_OFFSET: f32 = 0.08;
+
+pub struct HealthBarPlugin;
+
+impl Plugin for HealthBarPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HealthBarSettings>()
+            .add_systems(
+                (
+                    attach_health_bars,
+                    update_health_bars.after(attach_health_bars),
+                    face_camera.after(attach_health_bars),
+                )
+            )
+            .add_system(toggle_health_bars.in_set(OnUpdate(GameState::Paused)))
+        ;
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct HealthBarSettings {
+    /// show health bars above damaged enemies
+    pub visible: bool,
+}
+
+impl Default for HealthBarSettings {
+    fn default() -> Self {
+        HealthBarSettings { visible: true }
+    }
+}
+
+impl HealthBarSettings {
+    /// Key of the text of the game menu button switching the health bars
+    pub fn label_key(&self) -> &'static str {
+        if self.visible { "menu.health_bars_on" } else { "menu.health_bars_off" }
+    }
+}
+
+/// Game menu button switching the health bars on and off
+#[derive(Component)]
+pub struct HealthBarToggle;
+
+/// Child of an enemy, turned towards the camera
+#[derive(Component)]
+struct HealthBar {
+    /// the part of the bar showing the remaining health
+    fill: Entity,
+}
+
+fn attach_health_bars(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    enemies: Query<(Entity, &EnemyKind), Added<EnemyTag>>,
+) {
+    for (entity, kind) in &enemies {
+        let size = kind.stats().size;
+
+        let fill = commands
+            .spawn(PbrBundle {
+                mesh: assets.health_bar_mesh.clone(),
+                material: assets.health_bar_fill_material.clone(),
+                // in front of the background
+                transform: Transform::from_xyz(0.0, 0.0, 0.001),
+                ..default()
+            })
+            .id();
+
+        let bar = commands
+            .spawn((
+                HealthBar { fill },
+                PbrBundle {
+                    mesh: assets.health_bar_mesh.clone(),
+                    material: assets.health_bar_background_material.clone(),
+                    // the capsule reaches `size * 3` above its center
+                    transform: Transform::from_xyz(0.0, size * 3.0 + BAR_OFFSET, 0.0),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+            ))
+            .add_child(fill)
+            .id();
+
+        commands.entity(entity).add_child(bar);
+    }
+}
+
+fn update_health_bars(
+    settings: Res<HealthBarSettings>,
+    enemies: Query<(Ref<Health>, &Children), With<EnemyTag>>,
+    mut bars: Query<(Ref<HealthBar>, &mut Visibility)>,
+    mut fills: Query<&mut Transform, Without<HealthBar>>,
+) {
+    for (health, children) in &enemies {
+        for child in children.iter() {
+            let Ok((bar, mut visibility)) = bars.get_mut(*child) else {
+                continue;
+            };
+            // new bars and the toggle need an update even without damage
+            if !settings.is_changed() && !health.is_changed() && !bar.is_added() {
+                continue;
+            }
+
+            let share = (health.current / health.max).clamp(0.0, 1.0);
+            *visibility = if settings.visible && share < 1.0 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+
+            // the fill shrinks towards the left end of the bar
+            if let Ok(mut transform) = fills.get_mut(bar.fill) {
+                transform.scale.x = share;
+                transform.translation.x = -(1.0 - share) * BAR_SIZE.x / 2.0;
+            }
+        }
+    }
+}
+
+/// Keeps the bars facing the camera no matter where their enemy turns
+fn face_camera(
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    enemies: Query<&GlobalTransform, With<EnemyTag>>,
+    mut bars: Query<(&Parent, &mut Transform), With<HealthBar>>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let (_, camera_rotation, _) = camera.to_scale_rotation_translation();
+
+    for (parent, mut transform) in &mut bars {
+        let Ok(enemy) = enemies.get(parent.get()) else {
+            continue;
+        };
+        let (_, enemy_rotation, _) = enemy.to_scale_rotation_translation();
+        transform.rotation = enemy_rotation.inverse() * camera_rotation;
+    }
+}
+
+fn toggle_health_bars(
+    mut settings: ResMut<HealthBarSettings>,
+    buttons: Query<(&Interaction, &Children), (Changed<Interaction>, With<HealthBarToggle>)>,
+    mut texts: Query<&mut Text>,
+    localization: Res<Localization>,
+) {
+    for (interaction, children) in &buttons {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        settings.visible = !settings.visible;
+
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = localization.t(settings.label_key()).to_string();
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use bevy::prelude::*;
+use hexx::Hex;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::GameAssets;
+use crate::{COLUMN_HEIGHT, Map};
+use crate::map::grid_mesh::GridMesh;
+use crate::settings::GameSettings;
+
+/// Pulses of the highlighted hexes per second
+const PULSE_FREQUENCY: f32 = 0.8;
+/// Distance of the patterns above the top of the hex, so they don't flicker with it
+const PATTERN_HEIGHT: f32 = 0.01;
+/// Opacity of the ghost of the building to place, tinted like the hex below it
+const GHOST_ALPHA: f32 = 0.45;
+
+pub struct HighlightPlugin;
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HexHighlights>()
+            .add_system(pulse_highlighted_material)
+            .add_system(apply_highlight_palette.run_if(resource_changed::<GameSettings>()))
+            .add_system(
+                apply_hex_highlights
+                    .run_if(resource_changed::<HexHighlights>())
+                    .after(apply_highlight_palette)
+            )
+        ;
+    }
+}
+
+/// Reason a hex stands out from the terrain, later kinds are drawn over earlier ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HighlightKind {
+    /// the way ground enemies take to the exit
+    EnemyPath,
+    /// route chosen with the route planner, only the debug tools plan routes
+    #[cfg_attr(not(feature = "debug-tools"), allow(dead_code))]
+    Route,
+    /// front of the trail of a route preview
+    #[cfg_attr(not(feature = "debug-tools"), allow(dead_code))]
+    RouteHead,
+    /// hexes out of sight of the towers and the base
+    Fog,
+    /// hexes a player ability waiting for its target would reach
+    AbilityTarget,
+    /// surroundings of the hex a building is about to be placed on
+    PlacementArea,
+    /// hex of the selected building
+    Selection,
+    /// hexes the tutorial suggests for the first tower
+    TutorialHint,
+    PlacementValid,
+    PlacementInvalid,
+}
+
+impl HighlightKind {
+    /// Shape drawn on top of the hex when the patterns are switched on, so the kind doesn't rely on its color alone
+    fn pattern(&self) -> Option<HighlightPattern> {
+        match self {
+            // the path pulses, the fog is dark, both stand out without a pattern
+            HighlightKind::EnemyPath | HighlightKind::Route | HighlightKind::RouteHead | HighlightKind::Fog => None,
+            HighlightKind::AbilityTarget | HighlightKind::PlacementArea | HighlightKind::Selection => Some(HighlightPattern::Outline),
+            HighlightKind::TutorialHint | HighlightKind::PlacementValid => Some(HighlightPattern::Dot),
+            HighlightKind::PlacementInvalid => Some(HighlightPattern::Cross),
+        }
+    }
+
+    fn material(&self, assets: &GameAssets) -> Handle<StandardMaterial> {
+        match self {
+            HighlightKind::EnemyPath | HighlightKind::Route => assets.highlighted_material.clone(),
+            HighlightKind::RouteHead => assets.route_head_material.clone(),
+            HighlightKind::Fog => assets.fog_material.clone(),
+            HighlightKind::AbilityTarget | HighlightKind::PlacementArea | HighlightKind::Selection => assets.selection_material.clone(),
+            HighlightKind::TutorialHint | HighlightKind::PlacementValid => assets.valid_material.clone(),
+            HighlightKind::PlacementInvalid => assets.invalid_material.clone(),
+        }
+    }
+}
+
+/// Colors of the highlights, chosen in the settings so players with a color vision deficiency can tell them apart
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HighlightPalette {
+    /// yellow path, aquamarine selection, green and red placement
+    #[default]
+    Standard,
+    /// without red against green, for deuteranopia and protanopia
+    RedGreen,
+    /// without blue against yellow, for tritanopia
+    BlueYellow,
+    HighContrast,
+}
+
+impl HighlightPalette {
+    pub const ALL: [HighlightPalette; 4] = [
+        HighlightPalette::Standard,
+        HighlightPalette::RedGreen,
+        HighlightPalette::BlueYellow,
+        HighlightPalette::HighContrast,
+    ];
+
+    /// Key of the name shown in the settings, see `Localization`
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            HighlightPalette::Standard => "highlight_palette.standard",
+            HighlightPalette::RedGreen => "highlight_palette.red_green",
+            HighlightPalette::BlueYellow => "highlight_palette.blue_yellow",
+            HighlightPalette::HighContrast => "highlight_palette.high_contrast",
+        }
+    }
+
+    fn colors(&self) -> PaletteColors {
+        match self {
+            HighlightPalette::Standard => PaletteColors {
+                path: Color::YELLOW,
+                selection: Color::AQUAMARINE,
+                valid: Color::GREEN,
+                invalid: Color::RED,
+            },
+            HighlightPalette::RedGreen => PaletteColors {
+                path: Color::rgb(0.95, 0.9, 0.25),
+                selection: Color::rgb(0.8, 0.6, 0.7),
+                valid: Color::rgb(0.0, 0.45, 0.7),
+                invalid: Color::rgb(0.9, 0.6, 0.0),
+            },
+            HighlightPalette::BlueYellow => PaletteColors {
+                path: Color::rgb(0.95, 0.45, 0.6),
+                selection: Color::rgb(0.9, 0.9, 0.9),
+                valid: Color::rgb(0.0, 0.6, 0.5),
+                invalid: Color::rgb(0.85, 0.1, 0.1),
+            },
+            HighlightPalette::HighContrast => PaletteColors {
+                path: Color::WHITE,
+                selection: Color::FUCHSIA,
+                valid: Color::CYAN,
+                invalid: Color::rgb(1.0, 0.3, 0.0),
+            },
+        }
+    }
+}
+
+/// Colors of the highlight materials, the fog keeps its color in every palette
+struct PaletteColors {
+    path: Color,
+    selection: Color,
+    valid: Color,
+    invalid: Color,
+}
+
+/// Shape drawn on top of a highlighted hex
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HighlightPattern {
+    Outline,
+    Dot,
+    Cross,
+}
+
+impl HighlightPattern {
+    /// Meshes making up the pattern, placed on the top of the hex
+    fn parts(&self, assets: &GameAssets) -> Vec<(Handle<Mesh>, Transform)> {
+        match self {
+            HighlightPattern::Outline => vec![(assets.highlight_outline_mesh.clone(), Transform::IDENTITY)],
+            // the circle faces the camera looking along -z, it has to lie flat on the hex
+            HighlightPattern::Dot => vec![(assets.highlight_dot_mesh.clone(), Transform::from_rotation(Quat::from_rotation_x(-FRAC_PI_2)))],
+            HighlightPattern::Cross => vec![
+                (assets.highlight_bar_mesh.clone(), Transform::from_rotation(Quat::from_rotation_y(FRAC_PI_4))),
+                (assets.highlight_bar_mesh.clone(), Transform::from_rotation(Quat::from_rotation_y(-FRAC_PI_4))),
+            ],
+        }
+    }
+}
+
+/// Pattern on top of a hex, a child of the hex entity
+#[derive(Component)]
+struct HighlightPatternNode;
+
+/// Kind of highlight a hex entity currently shows, `None` for its terrain material
+#[derive(Component, Default, Debug)]
+pub struct HexHighlight(pub Option<HighlightKind>);
+
+/// All highlights on the grid, the materials of the hexes follow along in a single system
+#[derive(Resource, Default, Debug)]
+pub struct HexHighlights {
+    layers: HashMap<Hex, BTreeSet<HighlightKind>>,
+    /// hexes whose material might have to change
+    changed: HashSet<Hex>,
+    /// all highlighted hexes are drawn again, e.g. after the palette changed
+    repaint_all: bool,
+}
+
+impl HexHighlights {
+    pub fn highlight(&mut self, hex: Hex, kind: HighlightKind) {
+        if self.layers.entry(hex).or_default().insert(kind) {
+            self.changed.insert(hex);
+        }
+    }
+
+    pub fn unhighlight(&mut self, hex: Hex, kind: HighlightKind) {
+        if self.layers.get_mut(&hex).map_or(false, |kinds| kinds.remove(&kind)) {
+            self.changed.insert(hex);
+        }
+    }
+
+    /// Removes the kind from all hexes
+    pub fn clear(&mut self, kind: HighlightKind) {
+        for (hex, kinds) in &mut self.layers {
+            if kinds.remove(&kind) {
+                self.changed.insert(*hex);
+            }
+        }
+    }
+
+    /// Applies the highlights of the hex again, e.g. after its entity got spawned
+    pub fn refresh(&mut self, hex: Hex) {
+        self.changed.insert(hex);
+    }
+
+    /// Draws all highlighted hexes again, with the current colors and patterns
+    pub fn repaint(&mut self) {
+        self.repaint_all = true;
+    }
+
+    /// Gives every hex its terrain material back
+    pub fn clear_all(&mut self) {
+        self.changed.extend(self.layers.drain().map(|(hex, _)| hex));
+    }
+
+    fn shown(&self, hex: Hex) -> Option<HighlightKind> {
+        self.layers.get(&hex).and_then(|kinds| kinds.iter().next_back().copied())
+    }
+}
+
+/// Hexes on the enemy path and planned routes share the material, so they glow in sync,
+/// the merged grid only takes over the base color of the highlight materials
+fn pulse_highlighted_material(
+    assets: Res<GameAssets>,
+    settings: Res<GameSettings>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    let Some(material) = materials.get_mut(&assets.highlighted_material) else {
+        return;
+    };
+
+    let wave = (time.elapsed_seconds() * PULSE_FREQUENCY * std::f32::consts::TAU).sin() * 0.5 + 0.5;
+    material.emissive = settings.highlight_palette.colors().path * (0.1 + 0.5 * wave);
+}
+
+/// Colors the shared highlight materials after the palette, the hexes only need to be drawn again for the merged grid
+/// and the patterns
+fn apply_highlight_palette(
+    settings: Res<GameSettings>,
+    assets: Res<GameAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut highlights: ResMut<HexHighlights>,
+    mut applied: Local<Option<(HighlightPalette, bool)>>,
+) {
+    // most settings have nothing to do with the highlights
+    let wanted = (settings.highlight_palette, settings.highlight_patterns);
+    if *applied == Some(wanted) {
+        return;
+    }
+    *applied = Some(wanted);
+
+    let colors = settings.highlight_palette.colors();
+    let base_colors = [
+        (&assets.highlighted_material, colors.path),
+        (&assets.selection_material, colors.selection),
+        (&assets.selection_ring_material, colors.selection),
+        (&assets.valid_material, colors.valid),
+        (&assets.invalid_material, colors.invalid),
+        (&assets.ghost_valid_material, colors.valid.with_a(GHOST_ALPHA)),
+        (&assets.ghost_invalid_material, colors.invalid.with_a(GHOST_ALPHA)),
+    ];
+    for (handle, color) in base_colors {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = color;
+        }
+    }
+    // the head of the route glows in the color of the path
+    if let Some(material) = materials.get_mut(&assets.route_head_material) {
+        material.emissive = colors.path;
+    }
+
+    highlights.repaint();
+}
+
+/// Swaps the materials of the hexes whose topmost highlight changed, or recolors them in the merged grid,
+/// and puts the pattern of the highlight on top of them
+pub fn apply_hex_highlights(
+    mut commands: Commands,
+    map: Res<Map>,
+    assets: Res<GameAssets>,
+    settings: Res<GameSettings>,
+    grid_mesh: Option<Res<GridMesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut highlights: ResMut<HexHighlights>,
+    mut hexes: Query<(Entity, &mut HexHighlight, Option<&mut Handle<StandardMaterial>>, &Transform, Option<&Children>)>,
+    patterns: Query<(), With<HighlightPatternNode>>,
+) {
+    // draining marks the resource as changed again, the next run must not touch it
+    if highlights.changed.is_empty() && !highlights.repaint_all {
+        return;
+    }
+    let repaint = std::mem::take(&mut highlights.repaint_all);
+    let mut changed: HashSet<Hex> = highlights.changed.drain().collect();
+    // drop hexes without any highlight left, so the layers don't grow with every hex ever touched
+    highlights.layers.retain(|_, kinds| !kinds.is_empty());
+    if repaint {
+        changed.extend(highlights.layers.keys().copied());
+    }
+
+    for hex in changed {
+        let Some((entity, mut shown, mut material, transform, children)) = map.entities.get(&hex).and_then(|e| hexes.get_mut(*e).ok()) else {
+            continue;
+        };
+
+        let kind = highlights.shown(hex);
+        if shown.0 == kind && !repaint {
+            continue;
+        }
+        shown.0 = kind;
+
+        for child in children.iter().flat_map(|children| children.iter()).filter(|child| patterns.contains(**child)) {
+            commands.entity(*child).despawn_recursive();
+        }
+        if let Some(pattern) = kind.and_then(|kind| kind.pattern()).filter(|_| settings.highlight_patterns) {
+            spawn_pattern(&mut commands, &assets, entity, transform, pattern);
+        }
+
+        let Some(terrain) = map.terrain(hex) else {
+            continue;
+        };
+        let highlight_material = kind.map(|kind| kind.material(&assets));
+
+        if let Some(grid_mesh) = &grid_mesh {
+            let color = highlight_material
+                .and_then(|handle| materials.get(&handle))
+                .map_or(terrain.color(), |material| material.base_color);
+            grid_mesh.set_color(&mut meshes, hex, color);
+        } else if let Some(material) = material.as_mut() {
+            **material = highlight_material.unwrap_or_else(|| assets.terrain_material(terrain));
+        }
+    }
+}
+
+fn spawn_pattern(commands: &mut Commands, assets: &GameAssets, hex: Entity, hex_transform: &Transform, pattern: HighlightPattern) {
+    // the column is stretched to its elevation, the pattern on top of it must not be
+    let stretch = hex_transform.scale.y;
+    let transform = Transform::from_xyz(0.0, COLUMN_HEIGHT + PATTERN_HEIGHT / stretch, 0.0)
+        .with_scale(Vec3::new(1.0, 1.0 / stretch, 1.0));
+
+    commands.entity(hex).with_children(|parent| {
+        parent
+            .spawn((HighlightPatternNode, SpatialBundle::from_transform(transform)))
+            .with_children(|parent| {
+                for (mesh, transform) in pattern.parts(assets) {
+                    parent.spawn(PbrBundle {
+                        mesh,
+                        material: assets.highlight_pattern_material.clone(),
+                        transform,
+                        ..default()
+                    });
+                }
+            });
+    });
+}
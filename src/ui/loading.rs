@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::assets::PreloadedAssets;
+use crate::map::chunks::GridSpawner;
+use crate::state::global::GameState;
+
+pub struct LoadingScreenPlugin;
+
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(spawn_loading_screen.in_schedule(OnEnter(GameState::AssetLoading)))
+            .add_system(update_progress_bar.in_set(OnUpdate(GameState::AssetLoading)))
+            .add_system(remove_loading_screen.in_schedule(OnExit(GameState::AssetLoading)))
+            // large maps take a moment to spawn as well
+            .add_system(spawn_loading_screen.in_schedule(OnEnter(GameState::GridLoading)))
+            .add_system(
+                update_grid_progress
+                    .run_if(resource_exists::<GridSpawner>())
+                    .in_set(OnUpdate(GameState::GridLoading))
+            )
+            .add_system(remove_loading_screen.in_schedule(OnExit(GameState::GridLoading)))
+        ;
+    }
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct ProgressBar;
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::all(Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.1, 0.1, 0.1).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            // bar background
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(50.0), Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.9, 0.9, 0.9).into(),
+                            ..default()
+                        },
+                        ProgressBar,
+                    ));
+                });
+        });
+}
+
+fn update_progress_bar(
+    asset_server: Res<AssetServer>,
+    preloaded: Res<PreloadedAssets>,
+    mut q: Query<&mut Style, With<ProgressBar>>,
+) {
+    let progress = preloaded.progress(&asset_server);
+    for mut style in &mut q {
+        style.size.width = Val::Percent(progress * 100.0);
+    }
+}
+
+fn update_grid_progress(spawner: Res<GridSpawner>, mut q: Query<&mut Style, With<ProgressBar>>) {
+    for mut style in &mut q {
+        style.size.width = Val::Percent(spawner.progress() * 100.0);
+    }
+}
+
+fn remove_loading_screen(mut commands: Commands, q: Query<Entity, With<LoadingScreen>>) {
+    for entity in q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use bevy::a11y::accesskit::Role;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use hexx::{Hex, HexLayout};
+
+use crate::{map_layout, StartNewGame};
+use crate::gameplay::campaign::Campaign;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::save::{latest_slot, LoadGame};
+use crate::localization::Localization;
+use crate::map::format::{available_maps, MapFile, to_hex};
+use crate::profile::{available_profiles, PlayerProfile, SwitchProfile, unused_profile_name};
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::end_screen::spawn_stars;
+use crate::ui::settings::SettingsButton;
+use crate::ui::style::{TextSize, UiStyle};
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const LOCKED_CARD_COLOR: Color = Color::rgb(0.08, 0.08, 0.08);
+/// Width and height of the map thumbnails in pixels
+const MAP_THUMBNAIL_SIZE: u32 = 128;
+const MAP_CARD_WIDTH: f32 = 180.0;
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MainMenuPage>()
+            .add_system(reset_main_menu_page.in_schedule(OnEnter(GameState::MainMenu)))
+            .add_systems(
+                (
+                    on_main_menu_button_clicked,
+                    render_main_menu.after(on_main_menu_button_clicked),
+                ).in_set(OnUpdate(GameState::MainMenu))
+            )
+            .add_system(remove_main_menu.in_schedule(OnExit(GameState::MainMenu)))
+        ;
+    }
+}
+
+/// Part of the main menu currently shown
+#[derive(Resource, Default, Clone, Debug, PartialEq, Eq)]
+enum MainMenuPage {
+    #[default]
+    Start,
+    /// levels of the campaign, the won ones and the next one can be played
+    Campaign,
+    /// choosing the map of a new game
+    NewGame,
+    /// choosing the difficulty of a new game on the map at the given path
+    Difficulty(String),
+    /// progress of the active profile and the list of all profiles
+    Profiles,
+}
+
+impl MainMenuPage {
+    /// Name of the page announced by screen readers
+    fn label_key(&self) -> &'static str {
+        match self {
+            MainMenuPage::Start => "a11y.main_menu",
+            MainMenuPage::Campaign => "main_menu.campaign",
+            MainMenuPage::NewGame => "main_menu.new_game",
+            MainMenuPage::Difficulty(_) => "a11y.difficulty",
+            MainMenuPage::Profiles => "a11y.profiles",
+        }
+    }
+}
+
+#[derive(Component)]
+struct MainMenu;
+
+#[derive(Component, Clone, Debug)]
+enum MainMenuButton {
+    Campaign,
+    /// starts the level of the campaign at the given index
+    CampaignLevel(usize),
+    NewGame,
+    Continue(usize),
+    Quit,
+    /// picks the map at the given path for a new game
+    Map(String),
+    /// starts the new game on the map picked before
+    Difficulty(Difficulty),
+    Profiles,
+    /// continues with the profile of the given name
+    Profile(String),
+    NewProfile,
+    Back,
+}
+
+fn reset_main_menu_page(mut page: ResMut<MainMenuPage>) {
+    // also marks the page as changed, which draws the menu
+    *page = MainMenuPage::Start;
+}
+
+fn on_main_menu_button_clicked(
+    interactions: Query<(&Interaction, &MainMenuButton), Changed<Interaction>>,
+    mut page: ResMut<MainMenuPage>,
+    campaign: Res<Campaign>,
+    mut new_game_writer: EventWriter<StartNewGame>,
+    mut load_writer: EventWriter<LoadGame>,
+    mut exit_writer: EventWriter<AppExit>,
+    mut profile_writer: EventWriter<SwitchProfile>,
+) {
+    for (interaction, button) in &interactions {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            MainMenuButton::Campaign => *page = MainMenuPage::Campaign,
+            MainMenuButton::CampaignLevel(index) => {
+                if let Some(new_game) = campaign.start_level(*index) {
+                    new_game_writer.send(new_game);
+                }
+            }
+            MainMenuButton::NewGame => *page = MainMenuPage::NewGame,
+            MainMenuButton::Continue(slot) => load_writer.send(LoadGame(*slot)),
+            MainMenuButton::Quit => exit_writer.send(AppExit),
+            MainMenuButton::Map(path) => *page = MainMenuPage::Difficulty(path.clone()),
+            MainMenuButton::Difficulty(difficulty) => {
+                if let MainMenuPage::Difficulty(path) = &*page {
+                    new_game_writer.send(StartNewGame { map: path.clone(), difficulty: *difficulty, level: None });
+                }
+            }
+            MainMenuButton::Profiles => *page = MainMenuPage::Profiles,
+            MainMenuButton::Profile(name) => {
+                profile_writer.send(SwitchProfile(name.clone()));
+                *page = MainMenuPage::Start;
+            }
+            MainMenuButton::NewProfile => {
+                profile_writer.send(SwitchProfile(unused_profile_name()));
+                *page = MainMenuPage::Start;
+            }
+            MainMenuButton::Back => *page = MainMenuPage::Start,
+        }
+    }
+}
+
+/// Name of a map shown to the player, taken from its file name
+pub fn map_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map_or(path.to_string(), |stem| stem.to_string_lossy().into_owned())
+}
+
+fn render_main_menu(
+    mut commands: Commands,
+    ui_style: Res<UiStyle>,
+    page: Res<MainMenuPage>,
+    profile: Res<PlayerProfile>,
+    campaign: Res<Campaign>,
+    localization: Res<Localization>,
+    menus: Query<Entity, With<MainMenu>>,
+    mut images: ResMut<Assets<Image>>,
+    // thumbnails of the maps by their path, `None` for maps which can't be loaded
+    mut thumbnails: Local<HashMap<String, Option<Handle<Image>>>>,
+) {
+    // the profile name is shown on the start page
+    if !page.is_changed() && !profile.is_changed() && !localization.is_changed() {
+        return;
+    }
+
+    for entity in &menus {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let text_style = ui_style.text(TextSize::Button, Color::rgb(0.9, 0.9, 0.9));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::all(Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.05, 0.05, 0.05, 0.8).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            MainMenu,
+            AccessibleName::new(Role::Menu, page.label_key()),
+        ))
+        .with_children(|parent| {
+            match &*page {
+                MainMenuPage::Start => {
+                    spawn_main_menu_button(parent, localization.t("main_menu.campaign"), &text_style, MainMenuButton::Campaign);
+                    spawn_main_menu_button(parent, localization.t("main_menu.new_game"), &text_style, MainMenuButton::NewGame);
+                    if let Some(slot) = latest_slot() {
+                        spawn_main_menu_button(parent, localization.t("main_menu.continue"), &text_style, MainMenuButton::Continue(slot));
+                    }
+                    spawn_main_menu_button(
+                        parent,
+                        &localization.t_with("main_menu.profile", &[("name", profile.name.clone())]),
+                        &text_style,
+                        MainMenuButton::Profiles,
+                    );
+                    spawn_main_menu_button(parent, localization.t("menu.settings"), &text_style, SettingsButton);
+                    spawn_main_menu_button(parent, localization.t("menu.quit"), &text_style, MainMenuButton::Quit);
+                }
+                MainMenuPage::Campaign => {
+                    // level titles are longer than the other labels
+                    let level_style = TextStyle { font_size: ui_style.font_size(TextSize::Heading), ..text_style.clone() };
+                    let locked_style = TextStyle { color: Color::GRAY, ..level_style.clone() };
+                    for (index, level) in campaign.levels.iter().enumerate() {
+                        let label = format!("{}. {}", index + 1, localization.t(&level.title));
+                        if index <= profile.campaign_progress {
+                            spawn_main_menu_button(parent, &label, &level_style, MainMenuButton::CampaignLevel(index));
+                        } else {
+                            parent.spawn((
+                                TextBundle::from_section(label, locked_style.clone())
+                                    .with_style(Style { margin: UiRect::all(Val::Px(15.0)), ..default() }),
+                                Label,
+                            ));
+                        }
+                    }
+                    spawn_main_menu_button(parent, localization.t("main_menu.back"), &text_style, MainMenuButton::Back);
+                }
+                MainMenuPage::NewGame => {
+                    let card_style = TextStyle { font_size: ui_style.font_size(TextSize::Large), ..text_style.clone() };
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_wrap: FlexWrap::Wrap,
+                                justify_content: JustifyContent::Center,
+                                max_size: Size::width(Val::Percent(90.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for path in available_maps() {
+                                let thumbnail = thumbnails
+                                    .entry(path.clone())
+                                    .or_insert_with(|| MapFile::load(&path).ok().map(|map_file| images.add(map_thumbnail(&map_file))))
+                                    .clone();
+                                spawn_map_card(parent, &path, thumbnail, &profile, &localization, &card_style);
+                            }
+                        });
+                    spawn_main_menu_button(parent, localization.t("main_menu.back"), &text_style, MainMenuButton::Back);
+                }
+                MainMenuPage::Difficulty(_) => {
+                    for difficulty in Difficulty::ALL {
+                        spawn_main_menu_button(parent, localization.t(difficulty.label_key()), &text_style, MainMenuButton::Difficulty(difficulty));
+                    }
+                    spawn_main_menu_button(parent, localization.t("main_menu.back"), &text_style, MainMenuButton::NewGame);
+                }
+                MainMenuPage::Profiles => {
+                    let summary_style = TextStyle { font_size: ui_style.font_size(TextSize::Subtitle), ..text_style.clone() };
+                    for line in profile_summary(&profile, &localization) {
+                        parent.spawn((TextBundle::from_section(line, summary_style.clone()), Label));
+                    }
+                    for name in available_profiles().into_iter().filter(|name| *name != profile.name) {
+                        spawn_main_menu_button(parent, &name, &text_style, MainMenuButton::Profile(name.clone()));
+                    }
+                    spawn_main_menu_button(parent, localization.t("main_menu.new_profile"), &text_style, MainMenuButton::NewProfile);
+                    spawn_main_menu_button(parent, localization.t("main_menu.back"), &text_style, MainMenuButton::Back);
+                }
+            }
+        });
+}
+
+/// Card of a map on the level select page, only unlocked maps can be clicked
+fn spawn_map_card(
+    parent: &mut ChildBuilder,
+    path: &str,
+    thumbnail: Option<Handle<Image>>,
+    profile: &PlayerProfile,
+    localization: &Localization,
+    text_style: &TextStyle,
+) {
+    // winning a map opens the next one, a broken map can't be played at all
+    let playable = thumbnail.is_some() && profile.is_map_unlocked(path);
+    let record = profile.map_record(path);
+    let status = match (&thumbnail, playable, record) {
+        (None, _, _) => localization.t("main_menu.map_broken").to_string(),
+        (_, false, _) => localization.t("main_menu.map_locked").to_string(),
+        (_, true, Some(record)) => localization.t_with("main_menu.best_score", &[("score", record.best_score.to_string())]),
+        (_, true, None) => localization.t("main_menu.map_not_won").to_string(),
+    };
+
+    let style = Style {
+        size: Size::width(Val::Px(MAP_CARD_WIDTH)),
+        margin: UiRect::all(Val::Px(8.0)),
+        padding: UiRect::all(Val::Px(8.0)),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let accessible_name = format!("{} - {}", map_name(path), status);
+    let mut card = if playable {
+        parent.spawn((
+            ButtonBundle {
+                style,
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            MainMenuButton::Map(path.to_string()),
+            AccessibleName::button(accessible_name),
+        ))
+    } else {
+        parent.spawn((
+            NodeBundle {
+                style,
+                background_color: LOCKED_CARD_COLOR.into(),
+                ..default()
+            },
+            AccessibleName::group(accessible_name),
+        ))
+    };
+
+    card.with_children(|parent| {
+        if let Some(thumbnail) = thumbnail {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    size: Size::all(Val::Px(MAP_THUMBNAIL_SIZE as f32)),
+                    ..default()
+                },
+                image: UiImage {
+                    texture: thumbnail,
+                    ..default()
+                },
+                // the color tints the image, locked maps are greyed out
+                background_color: if playable { Color::WHITE } else { Color::DARK_GRAY }.into(),
+                ..default()
+            });
+        }
+        parent.spawn(TextBundle::from_section(map_name(path), text_style.clone()));
+        spawn_stars(parent, record.map_or(0, |record| record.stars), 14.0);
+        parent.spawn(TextBundle::from_section(status, TextStyle { font_size: text_style.font_size * 0.8, ..text_style.clone() }));
+    });
+}
+
+/// Top view of the terrain of a map, with the spawn points in red and the exits in black
+fn map_thumbnail(map_file: &MapFile) -> Image {
+    let layout = HexLayout {
+        hex_size: Vec2::ONE,
+        ..map_layout()
+    };
+    // half the width of the map in world units, with a hex of room around the outer ring
+    let extent = (map_file.radius as f32 + 1.0) * 2.0;
+    let spawn_points: Vec<Hex> = map_file.spawn_points.iter().copied().map(to_hex).collect();
+    let exit_points: Vec<Hex> = map_file.exit_points.iter().copied().map(to_hex).collect();
+
+    let mut data = Vec::with_capacity((MAP_THUMBNAIL_SIZE * MAP_THUMBNAIL_SIZE * 4) as usize);
+    for y in 0..MAP_THUMBNAIL_SIZE {
+        for x in 0..MAP_THUMBNAIL_SIZE {
+            // image rows go downwards
+            let world = Vec2::new(
+                ((x as f32 + 0.5) / MAP_THUMBNAIL_SIZE as f32 * 2.0 - 1.0) * extent,
+                (1.0 - (y as f32 + 0.5) / MAP_THUMBNAIL_SIZE as f32 * 2.0) * extent,
+            );
+            let hex = layout.world_pos_to_hex(world);
+            let color = if Hex::ZERO.distance_to(hex) > map_file.radius as i32 {
+                Color::NONE
+            } else if spawn_points.contains(&hex) {
+                Color::rgb(0.85, 0.2, 0.2)
+            } else if exit_points.contains(&hex) {
+                Color::BLACK
+            } else {
+                map_file.terrain_at(hex).color()
+            };
+            data.extend_from_slice(&color.as_rgba_u8());
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: MAP_THUMBNAIL_SIZE,
+            height: MAP_THUMBNAIL_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// Progress of the profile shown above the list of profiles
+fn profile_summary(profile: &PlayerProfile, localization: &Localization) -> Vec<String> {
+    let stats = &profile.stats;
+    let mut achievements: Vec<&str> = profile.achievements
+        .iter()
+        .map(|achievement| localization.t(achievement.label_key()))
+        .collect();
+    achievements.sort();
+
+    vec![
+        profile.name.clone(),
+        localization.t_with("profile.games", &[
+            ("games", stats.games_played.to_string()),
+            ("victories", stats.victories.to_string()),
+        ]),
+        localization.t_with("profile.totals", &[
+            ("kills", stats.kills.to_string()),
+            ("waves", stats.waves_survived.to_string()),
+            ("towers", stats.towers_built.to_string()),
+        ]),
+        localization.t_with("profile.unlocked", &[
+            ("maps", profile.unlocked_maps.len().to_string()),
+            ("towers", profile.unlocked_towers.len().to_string()),
+        ]),
+        if achievements.is_empty() { localization.t("profile.no_achievements").to_string() } else { achievements.join(", ") },
+    ]
+}
+
+fn spawn_main_menu_button(parent: &mut ChildBuilder, label: &str, text_style: &TextStyle, marker: impl Component) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(300.0), Val::Px(65.0)),
+                margin: UiRect::all(Val::Px(5.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+fn remove_main_menu(mut commands: Commands, menus: Query<Entity, With<MainMenu>>) {
+    for entity in &menus {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,46 +1,88 @@
+use bevy::a11y::accesskit::Role;
 use bevy::app::{App, Plugin};
 use bevy::prelude::*;
 use leafwing_input_manager::InputManagerBundle;
 use leafwing_input_manager::plugin::InputManagerPlugin;
 use leafwing_input_manager::prelude::*;
+use crate::StartNewGame;
+use crate::camera::{CameraFocus, choose_camera_focus};
+use crate::gameplay::campaign::ActiveLevel;
+use crate::gameplay::difficulty::Difficulty;
+use crate::profile::CurrentMap;
+use crate::state::global::GameState;
+use crate::gameplay::save::{SAVE_SLOTS, SlotButton};
+use crate::localization::Localization;
+use crate::ui::ability_bar::AbilityTargeting;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::confirm_dialog::{ConfirmDialog, no_confirm_dialog};
+use crate::ui::controls::{ControlsButton, not_rebinding};
+use crate::ui::health_bars::{HealthBarSettings, HealthBarToggle};
+use crate::ui::player::{BuildingPlacement, cancel_selection};
+use crate::ui::research::ResearchButton;
+use crate::ui::selection::Selection;
+use crate::ui::settings::SettingsButton;
+use crate::ui::stats::StatsButton;
+use crate::ui::style::{TextSize, UiStyle};
 use crate::UiAction;
 
-#[derive(Resource)]
-pub struct GameMenu;
-
 #[derive(Component)]
 struct GameMenuCmp;
 
-pub struct GameMenuPlugin;
-
-pub fn resource_not_exists<T>() -> impl FnMut(Option<Res<T>>) -> bool + Clone
-    where
-        T: Resource,
-{
-    move |res: Option<Res<T>>| res.is_none()
+/// Buttons of the pause menu handled by the menu itself
+#[derive(Component, Clone, Copy, Debug)]
+enum PauseMenuButton {
+    Resume,
+    Restart,
+    Quit,
 }
 
+/// Sent once the player confirmed starting the map over
+struct RestartGame;
+
+/// Sent once the player confirmed leaving the game
+struct QuitToMenu;
+
+pub struct GameMenuPlugin;
+
 impl Plugin for GameMenuPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_plugin(InputManagerPlugin::<UiAction>::default())
             .add_startup_system(setup_menu_keyboard)
+            .add_event::<RestartGame>()
+            .add_event::<QuitToMenu>()
             .add_system(
                 handle_actions
-                    .run_if(resource_not_exists::<GameMenu>())
+                    // Escape answers an open dialog first
+                    .run_if(no_confirm_dialog)
+                    // sees the selection and the focus before the same press clears them
+                    .before(cancel_selection)
+                    .before(choose_camera_focus)
+                    .in_set(OnUpdate(GameState::Playing))
             )
             .add_system(
                 handle_menu_actions
-                    .run_if(resource_exists::<GameMenu>())
+                    .run_if(not_rebinding)
+                    .run_if(no_confirm_dialog)
+                    .in_set(OnUpdate(GameState::Paused))
             )
-            .add_system(
-                render_game_menu
-                    .run_if(resource_added::<GameMenu>())
+            .add_systems(
+                (
+                    on_pause_menu_button_clicked,
+                    restart_game.after(on_pause_menu_button_clicked),
+                    quit_to_menu.after(on_pause_menu_button_clicked),
+                ).in_set(OnUpdate(GameState::Paused))
             )
+            .add_system(render_game_menu.in_schedule(OnEnter(GameState::Paused)))
+            // the language can be switched in the settings opened from the menu
             .add_system(
-                remove_game_menu
-                    .run_if(resource_removed::<GameMenu>())
+                render_game_menu
+                    .run_if(resource_changed::<Localization>())
+                    .in_set(OnUpdate(GameState::Paused))
             )
+            .add_system(remove_game_menu.in_schedule(OnExit(GameState::Paused)))
+            .add_system(pause_simulation.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(resume_simulation.in_schedule(OnExit(GameState::Paused)))
         ;
     }
 }
@@ -52,25 +94,126 @@ fn setup_menu_keyboard(mut commands: Commands) {
         // Describes how to convert from player inputs into those actions
         input_map: InputMap::new(
             [
-                (KeyCode::Space, UiAction::OpenMenu),
+                (KeyCode::Escape, UiAction::OpenMenu),
                 (KeyCode::Escape, UiAction::CloseMenu),
+                (KeyCode::Return, UiAction::Confirm),
             ]
-        ),
+        )
+            .insert_multiple(
+                [
+                    (GamepadButtonType::Start, UiAction::OpenMenu),
+                    (GamepadButtonType::Start, UiAction::CloseMenu),
+                    (GamepadButtonType::DPadUp, UiAction::NavigateUp),
+                    (GamepadButtonType::DPadDown, UiAction::NavigateDown),
+                    (GamepadButtonType::DPadLeft, UiAction::NavigateLeft),
+                    (GamepadButtonType::DPadRight, UiAction::NavigateRight),
+                    (GamepadButtonType::South, UiAction::Confirm),
+                    (GamepadButtonType::East, UiAction::Cancel),
+                ]
+            )
+            .insert(KeyCode::Escape, UiAction::Cancel)
+            .insert(MouseButton::Right, UiAction::Cancel)
+            .build(),
     });
 }
 
-fn handle_actions(mut commands: Commands, query: Query<&ActionState<UiAction>>) {
-    if query.single().pressed(UiAction::OpenMenu) {
-        commands.insert_resource(GameMenu);
+/// Escape pauses the game once there is nothing left for it to cancel, like a placement or the selection
+fn handle_actions(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut query: Query<&mut ActionState<UiAction>>,
+    placement: Option<Res<BuildingPlacement>>,
+    targeting: Option<Res<AbilityTargeting>>,
+    selection: Res<Selection>,
+    focus: Res<CameraFocus>,
+) {
+    let Ok(mut action_state) = query.get_single_mut() else {
+        return;
+    };
+    if !action_state.just_pressed(UiAction::OpenMenu) {
+        return;
+    }
+    let busy = placement.is_some() || targeting.is_some() || !selection.entities.is_empty() || focus.target.is_some();
+    if busy {
+        return;
+    }
+
+    // consumed until released, the same key closes the menu again and must not do so right away
+    action_state.consume(UiAction::OpenMenu);
+    action_state.consume(UiAction::CloseMenu);
+    action_state.consume(UiAction::Cancel);
+    next_state.set(GameState::Paused);
+}
+
+fn handle_menu_actions(mut next_state: ResMut<NextState<GameState>>, mut query: Query<&mut ActionState<UiAction>>) {
+    // just_pressed, the key might have been captured for rebinding in the frame before
+    let Ok(mut action_state) = query.get_single_mut() else {
+        return;
+    };
+    if action_state.just_pressed(UiAction::CloseMenu) || action_state.just_pressed(UiAction::Cancel) {
+        // the held key must neither reopen the menu nor cancel anything in the game
+        action_state.consume(UiAction::OpenMenu);
+        action_state.consume(UiAction::CloseMenu);
+        action_state.consume(UiAction::Cancel);
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn on_pause_menu_button_clicked(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    interactions: Query<(&Interaction, &PauseMenuButton), Changed<Interaction>>,
+    localization: Res<Localization>,
+) {
+    for (interaction, button) in &interactions {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            PauseMenuButton::Resume => next_state.set(GameState::Playing),
+            PauseMenuButton::Restart => {
+                ConfirmDialog::spawn(&mut commands, localization.t("dialog.restart"), RestartGame);
+            }
+            PauseMenuButton::Quit => {
+                ConfirmDialog::spawn(&mut commands, localization.t("dialog.quit_to_menu"), QuitToMenu);
+            }
+        }
+    }
+}
+
+/// Starts a new game on the same map, difficulty and campaign level, the new game clears the old one away
+fn restart_game(
+    mut restart_reader: EventReader<RestartGame>,
+    mut new_game_writer: EventWriter<StartNewGame>,
+    current_map: Res<CurrentMap>,
+    difficulty: Res<Difficulty>,
+    active_level: Option<Res<ActiveLevel>>,
+) {
+    if restart_reader.iter().count() > 0 {
+        new_game_writer.send(StartNewGame {
+            map: current_map.0.clone(),
+            difficulty: *difficulty,
+            level: active_level.map(|level| level.index),
+        });
     }
 }
 
-fn handle_menu_actions(mut commands: Commands, query: Query<&ActionState<UiAction>>) {
-    if query.single().pressed(UiAction::CloseMenu) {
-        commands.remove_resource::<GameMenu>();
+/// The game stays behind the main menu until a new one is started or loaded
+fn quit_to_menu(mut quit_reader: EventReader<QuitToMenu>, mut next_state: ResMut<NextState<GameState>>) {
+    if quit_reader.iter().count() > 0 {
+        next_state.set(GameState::MainMenu);
     }
 }
 
+/// Gameplay systems only run while playing, this stops the clock as well
+fn pause_simulation(mut time: ResMut<Time>) {
+    time.pause();
+}
+
+fn resume_simulation(mut time: ResMut<Time>) {
+    time.unpause();
+}
+
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 
 fn remove_game_menu(mut commands: Commands,
@@ -80,42 +223,111 @@ fn remove_game_menu(mut commands: Commands,
     }
 }
 
-fn render_game_menu(mut commands: Commands, asset_server: Res<AssetServer>, current_state: Res<GameMenu>) {
+fn render_game_menu(
+    mut commands: Commands,
+    ui_style: Res<UiStyle>,
+    health_bars: Res<HealthBarSettings>,
+    localization: Res<Localization>,
+    menus: Query<Entity, With<GameMenuCmp>>,
+) {
+    for entity in &menus {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let text_style = ui_style.text(TextSize::Button, Color::rgb(0.9, 0.9, 0.9));
+
     commands
         .spawn(NodeBundle {
             style: Style {
-                size: Size::width(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                size: Size::all(Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
                 ..default()
             },
+            // dims the paused game behind the menu
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
             ..default()
         })
         .insert(GameMenuCmp)
         .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(localization.t("menu.paused"), TextStyle { font_size: ui_style.font_size(TextSize::Title), ..text_style.clone() }));
+
             parent
-                .spawn(ButtonBundle {
-                    style: Style {
-                        size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                        // horizontally center child text
-                        justify_content: JustifyContent::Center,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                })
+                .spawn(NodeBundle::default())
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Button",
-                        TextStyle {
-                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                            ..default()
-                        },
-                    ));
+                    // leaving the menu, one way or the other
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            AccessibleName::new(Role::Menu, "menu.paused"),
+                        ))
+                        .with_children(|parent| {
+                            spawn_menu_button(parent, localization.t("menu.resume"), &text_style, PauseMenuButton::Resume);
+                            spawn_menu_button(parent, localization.t("menu.restart"), &text_style, PauseMenuButton::Restart);
+                            spawn_menu_button(parent, localization.t("menu.settings"), &text_style, SettingsButton);
+                            spawn_menu_button(parent, localization.t("menu.controls"), &text_style, ControlsButton);
+                            spawn_menu_button(parent, localization.t("menu.quit"), &text_style, PauseMenuButton::Quit);
+                        });
+
+                    // looking at the game without leaving the menu
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            AccessibleName::new(Role::Menu, "a11y.game_menu"),
+                        ))
+                        .with_children(|parent| {
+                            spawn_menu_button(parent, localization.t(health_bars.label_key()), &text_style, HealthBarToggle);
+                            spawn_menu_button(parent, localization.t("menu.stats"), &text_style, StatsButton);
+                            spawn_menu_button(parent, localization.t("menu.research"), &text_style, ResearchButton);
+
+                            for slot in 1..=SAVE_SLOTS {
+                                parent
+                                    .spawn((
+                                        NodeBundle::default(),
+                                        AccessibleName::group(localization.t_with("a11y.save_slot", &[("slot", slot.to_string())])),
+                                    ))
+                                    .with_children(|parent| {
+                                        let slot_arg = [("slot", slot.to_string())];
+                                        spawn_menu_button(parent, &localization.t_with("menu.save_slot", &slot_arg), &text_style, SlotButton::Save(slot));
+                                        spawn_menu_button(parent, &localization.t_with("menu.load_slot", &slot_arg), &text_style, SlotButton::Load(slot));
+                                    });
+                            }
+                        });
                 });
         });
-}
\ No newline at end of file
+}
+
+fn spawn_menu_button(parent: &mut ChildBuilder, label: &str, text_style: &TextStyle, marker: impl Component) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(150.0), Val::Px(65.0)),
+                margin: UiRect::all(Val::Px(5.0)),
+                // horizontally center child text
+                justify_content: JustifyContent::Center,
+                // vertically center child text
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
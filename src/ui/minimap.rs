@@ -0,0 +1,267 @@
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::render_resource::{Extent3d, PrimitiveTopology, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::view::RenderLayers;
+
+use crate::assets::GameAssets;
+use crate::camera::{CameraController, CameraFocus, PlayerCamera};
+use crate::gameplay::enemy::EnemyTag;
+use crate::gameplay::towers::Tower;
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::player::BOTTOM_BAR_HEIGHT;
+
+/// Width and height of the minimap in pixels
+pub const MINIMAP_SIZE: u32 = 200;
+/// World units covered by the minimap, centered on the middle of the map
+const MINIMAP_WORLD_SIZE: f32 = 16.0;
+/// Render layer of everything only shown on the minimap
+const MINIMAP_LAYER: u8 = 1;
+/// Blips float above everything else on the map so nothing covers them
+const BLIP_HEIGHT: f32 = 5.0;
+/// Farthest point of the view outline when the top of the screen shows the sky
+const MAX_VIEW_DISTANCE: f32 = 30.0;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_minimap)
+            .add_systems(
+                (
+                    attach_minimap_blips,
+                    move_minimap_blips.after(attach_minimap_blips),
+                    update_view_outline,
+                )
+            )
+            .add_system(jump_to_clicked_position.in_set(OnUpdate(GameState::Playing)))
+        ;
+    }
+}
+
+/// Image of the minimap, clicking it moves the camera to the clicked spot
+#[derive(Component)]
+struct MinimapImage;
+
+/// Marker of an enemy or tower on the minimap, follows the given entity
+#[derive(Component)]
+struct MinimapBlip(Entity);
+
+/// Outline of the ground area visible to the player camera
+#[derive(Component)]
+struct ViewOutline;
+
+fn spawn_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    assets: Res<GameAssets>,
+) {
+    let size = Extent3d {
+        width: MINIMAP_SIZE,
+        height: MINIMAP_SIZE,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    // fills the image with zeroes
+    image.resize(size);
+    let image = images.add(image);
+
+    commands.spawn((
+        Name::from("Minimap camera"),
+        Camera3dBundle {
+            camera: Camera {
+                // rendered before the player camera, which shows the result
+                order: -1,
+                target: RenderTarget::Image(image.clone()),
+                ..default()
+            },
+            camera_3d: Camera3d {
+                clear_color: ClearColorConfig::Custom(Color::rgb(0.05, 0.05, 0.05)),
+                ..default()
+            },
+            projection: OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: MINIMAP_WORLD_SIZE,
+                    height: MINIMAP_WORLD_SIZE,
+                },
+                ..default()
+            }.into(),
+            // right is +X and up is -Z on the minimap
+            transform: Transform::from_xyz(0.0, 30.0, 0.0).looking_at(Vec3::ZERO, Vec3::NEG_Z),
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+        RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
+    ));
+
+    let mut outline = Mesh::new(PrimitiveTopology::LineStrip);
+    outline.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0, 0.0, 0.0]; 5]);
+    outline.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 5]);
+    commands.spawn((
+        Name::from("Minimap view outline"),
+        ViewOutline,
+        PbrBundle {
+            mesh: meshes.add(outline),
+            material: assets.minimap_frustum_material.clone(),
+            ..default()
+        },
+        RenderLayers::layer(MINIMAP_LAYER),
+    ));
+
+    commands.spawn((
+        ButtonBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(10.0),
+                    // above the bottom bar
+                    bottom: Val::Px(BOTTOM_BAR_HEIGHT + 10.0),
+                    ..default()
+                },
+                size: Size::new(Val::Px(MINIMAP_SIZE as f32), Val::Px(MINIMAP_SIZE as f32)),
+                ..default()
+            },
+            image: UiImage {
+                texture: image,
+                ..default()
+            },
+            ..default()
+        },
+        MinimapImage,
+        // an image without text, bevy has no name to give it
+        AccessibleName::button("a11y.minimap"),
+    ));
+}
+
+fn attach_minimap_blips(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    enemies: Query<Entity, Added<EnemyTag>>,
+    towers: Query<Entity, Added<Tower>>,
+) {
+    let new_blips = enemies
+        .iter()
+        .map(|e| (e, assets.minimap_enemy_material.clone(), 0.12))
+        .chain(towers.iter().map(|e| (e, assets.minimap_tower_material.clone(), 0.18)));
+
+    for (target, material, radius) in new_blips {
+        commands.spawn((
+            Name::from("Minimap blip"),
+            MinimapBlip(target),
+            PbrBundle {
+                mesh: assets.shockwave_mesh.clone(),
+                material,
+                transform: Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2))
+                    .with_scale(Vec3::splat(radius)),
+                ..default()
+            },
+            RenderLayers::layer(MINIMAP_LAYER),
+        ));
+    }
+}
+
+fn move_minimap_blips(
+    mut commands: Commands,
+    mut blips: Query<(Entity, &MinimapBlip, &mut Transform)>,
+    targets: Query<&GlobalTransform>,
+) {
+    for (blip, target, mut transform) in &mut blips {
+        // the enemy died or the tower got sold
+        let Ok(target_transform) = targets.get(target.0) else {
+            commands.entity(blip).despawn();
+            continue;
+        };
+
+        let position = target_transform.translation();
+        transform.translation = Vec3::new(position.x, BLIP_HEIGHT, position.z);
+    }
+}
+
+/// Outlines the ground area between the corners of the player camera's view
+fn update_view_outline(
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    outlines: Query<&Handle<Mesh>, With<ViewOutline>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+
+    let corners = [Vec2::ZERO, Vec2::new(viewport.x, 0.0), viewport, Vec2::new(0.0, viewport.y)];
+    let mut points: Vec<[f32; 3]> = corners
+        .iter()
+        .filter_map(|corner| camera.viewport_to_world(camera_transform, *corner))
+        .map(|ray| {
+            let distance = if ray.direction.y < -f32::EPSILON {
+                (-ray.origin.y / ray.direction.y).min(MAX_VIEW_DISTANCE)
+            } else {
+                MAX_VIEW_DISTANCE
+            };
+            let point = ray.origin + ray.direction * distance;
+            [point.x, BLIP_HEIGHT, point.z]
+        })
+        .collect();
+    if points.len() != corners.len() {
+        return;
+    }
+    // back to the first corner to close the outline
+    points.push(points[0]);
+
+    for handle in &outlines {
+        if let Some(mesh) = meshes.get_mut(handle) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, points.clone());
+        }
+    }
+}
+
+/// Moves the camera to the spot under the cursor while the minimap is pressed
+fn jump_to_clicked_position(
+    minimaps: Query<(&Interaction, &Node, &GlobalTransform), With<MinimapImage>>,
+    windows: Query<&Window>,
+    mut cameras: Query<&mut CameraController>,
+    mut focus: ResMut<CameraFocus>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    // the cursor position starts at the bottom left corner, the UI at the top left one
+    let cursor = Vec2::new(cursor.x, window.height() - cursor.y);
+
+    for (interaction, node, transform) in &minimaps {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        // 0..1 from the top left corner of the minimap
+        let uv = (cursor - transform.translation().truncate()) / node.size() + 0.5;
+        let target = Vec3::new(uv.x - 0.5, 0.0, uv.y - 0.5) * MINIMAP_WORLD_SIZE;
+
+        focus.target = None;
+        for mut controller in &mut cameras {
+            controller.jump_to(target);
+        }
+    }
+}
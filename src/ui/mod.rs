@@ -1,2 +1,29 @@
 pub mod menu;
-pub mod player;
\ No newline at end of file
+pub mod player;
+pub mod loading;
+pub mod controls;
+pub mod gamepad;
+pub mod health_bars;
+pub mod enemy_tooltip;
+pub mod wave_preview;
+pub mod minimap;
+pub mod stats;
+pub mod main_menu;
+pub mod settings;
+pub mod highlight;
+pub mod ability_bar;
+pub mod damage_numbers;
+pub mod boss_bar;
+pub mod research;
+pub mod path_preview;
+pub mod selection;
+pub mod control_groups;
+pub mod tooltip;
+pub mod build_menu;
+pub mod confirm_dialog;
+pub mod toasts;
+pub mod end_screen;
+pub mod briefing;
+pub mod tutorial;
+pub mod style;
+pub mod accessibility;
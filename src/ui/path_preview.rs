@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{Action, Map};
+use crate::gameplay::enemy::{bump_map_version, Movement};
+use crate::map::path_cache::PathCache;
+use crate::map::route::Routes;
+use crate::state::global::GameState;
+use crate::ui::highlight::{HexHighlights, HighlightKind};
+
+pub const PATH_PREVIEW_BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const ACTIVE_PATH_PREVIEW_BUTTON_COLOR: Color = Color::rgb(0.2, 0.5, 0.2);
+
+pub struct PathPreviewPlugin;
+
+impl Plugin for PathPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<PathPreview>()
+            .add_systems(
+                (
+                    toggle_path_preview,
+                    show_enemy_routes
+                        .after(toggle_path_preview)
+                        .after(bump_map_version)
+                        .run_if(resource_exists::<Routes>()),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(highlight_path_preview_button.run_if(resource_changed::<PathPreview>()))
+        ;
+    }
+}
+
+/// Whether the routes ground enemies take from the spawn points to the exit are highlighted
+#[derive(Resource, Default, Debug)]
+pub struct PathPreview {
+    pub shown: bool,
+}
+
+/// Button in the bottom bar switching the path preview on and off
+#[derive(Component)]
+pub struct PathPreviewButton;
+
+fn toggle_path_preview(
+    mut preview: ResMut<PathPreview>,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<PathPreviewButton>)>,
+    actions: Query<&ActionState<Action>>,
+) {
+    let clicked = interactions.iter().any(|interaction| *interaction == Interaction::Clicked);
+    let pressed = actions.get_single().map_or(false, |state| state.just_pressed(Action::TogglePathPreview));
+
+    if clicked || pressed {
+        preview.shown = !preview.shown;
+    }
+}
+
+/// Highlights the routes of all spawn points with the cached paths, again whenever the grid changed
+fn show_enemy_routes(
+    preview: Res<PathPreview>,
+    map: Res<Map>,
+    routes: Res<Routes>,
+    mut path_cache: ResMut<PathCache>,
+    mut highlights: ResMut<HexHighlights>,
+    // map version the highlighted routes were computed on
+    mut shown_version: Local<Option<u32>>,
+) {
+    // a new game brings new routes, a loaded one or a changed grid a new map version
+    if !preview.is_changed() && !routes.is_changed() && *shown_version == Some(map.version) {
+        return;
+    }
+    *shown_version = Some(map.version);
+
+    highlights.clear(HighlightKind::EnemyPath);
+    if !preview.shown {
+        return;
+    }
+
+    for route in &routes.routes {
+        // a cut off route keeps nothing highlighted, the placement would have been refused anyway
+        let Some(path) = route.path(&map, &mut path_cache, Movement::Ground) else {
+            continue;
+        };
+        for hex in path {
+            highlights.highlight(hex, HighlightKind::EnemyPath);
+        }
+    }
+}
+
+fn highlight_path_preview_button(
+    preview: Res<PathPreview>,
+    mut buttons: Query<&mut BackgroundColor, With<PathPreviewButton>>,
+) {
+    for mut color in &mut buttons {
+        *color = if preview.shown {
+            ACTIVE_PATH_PREVIEW_BUTTON_COLOR
+        } else {
+            PATH_PREVIEW_BUTTON_COLOR
+        }.into();
+    }
+}
@@ -1,19 +1,62 @@
 use std::slice::Windows;
 use std::time::Duration;
 
+use bevy::a11y::accesskit::Role;
 use bevy::app::{App, Plugin};
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
 use bevy::input::mouse::MouseMotion;
+use bevy::hierarchy::HierarchyQueryExt;
 use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
 use bevy::utils::petgraph::visit::Walker;
-use bevy::window::WindowResized;
 use bevy_mod_picking::debug::PointerDebug;
-use bevy_mod_picking::focus::HoverMap;
 use bevy_mod_picking::PickableBundle;
 use bevy_mod_picking::prelude::{Bubble, Click, ListenedEvent, OnPointer, PointerLocation, RaycastPickTarget};
 use hexx::Hex;
+use leafwing_input_manager::prelude::ActionState;
 
-use crate::{HexFieldClicked, HexLocation, Map};
-use crate::gameplay::buildings::{BuildingTag, HasAttack};
+use crate::{Action, GridChanged, HexFieldClicked, HexLocation, Map, UiAction};
+use crate::assets::GameAssets;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::abilities::{AbilityState, ActivateAbility};
+use crate::gameplay::campaign::{ActiveLevel, allows_tower, level_has_rule, LevelRule};
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::enemy::{EnemyTag, route_stays_open};
+use crate::gameplay::economy::PlayerResources;
+use crate::gameplay::buildings::{BUILDING_SCALING, CombatRecord, HasAttack, TargetingMode, TargetingRange, VETERANCY_KILLS};
+use crate::gameplay::history::{BuildCommand, BuildHistory};
+use crate::gameplay::research::{Research, TechRegistry};
+use crate::gameplay::speed::{GameSpeed, SPEED_STEPS};
+use crate::gameplay::towers::{insert_tower, SellTower, Tower, TowerBuilt, TowerDefinition, TowerLevel, TowerRegistry, UpgradeTower, Wall};
+use crate::gameplay::waves::Waves;
+use crate::localization::{Localization, LocalizedText};
+use crate::settings::GameSettings;
+use crate::state::global::GameState;
+use crate::tween::{Easing, TransformScaleLens, Tween};
+use crate::map::picking::PointerHex;
+use crate::ui::accessibility::{AccessibleName, LiveRegion};
+use crate::ui::build_menu::{BuildButton, BuildMenu, spawn_build_menu};
+use crate::ui::confirm_dialog::{ConfirmDialog, no_confirm_dialog};
+use crate::ui::gamepad::HexCursor;
+use crate::ui::highlight::{HexHighlights, HighlightKind};
+use crate::ui::path_preview::{PATH_PREVIEW_BUTTON_COLOR, PathPreviewButton};
+use crate::ui::selection::Selection;
+use crate::ui::style::{TextSize, UiStyle};
+use crate::ui::toasts::{NOT_ENOUGH_GOLD, Toasts};
+use crate::ui::tutorial::Tutorial;
+
+/// Actions starting the placement of the towers of the open build tab, in the order of their buttons
+pub const BUILD_SLOT_ACTIONS: [Action; 9] = [
+    Action::BuildSlot1,
+    Action::BuildSlot2,
+    Action::BuildSlot3,
+    Action::BuildSlot4,
+    Action::BuildSlot5,
+    Action::BuildSlot6,
+    Action::BuildSlot7,
+    Action::BuildSlot8,
+    Action::BuildSlot9,
+];
 
 pub struct PlayerUiPlugin;
 
@@ -24,60 +67,178 @@ impl Plugin for PlayerUiPlugin {
         app
             .add_event::<ButtonClickEvent>()
             .add_startup_system(setup_ui)
-            .add_system(on_resize_system)
-            .add_system(on_building_button_clicked)
+            .add_startup_system(spawn_range_indicator)
+            .add_system(update_gold_text.run_if(resource_changed::<PlayerResources>()))
+            .add_system(update_wave_text.run_if(resource_exists::<Waves>().and_then(resource_changed::<Waves>())))
+            .add_system(update_enemies_text.run_if(resource_exists::<Waves>()))
+            .add_system(update_fps_text.run_if(on_timer(FPS_REFRESH)))
+            .add_system(show_fps_widget.run_if(resource_changed::<GameSettings>()))
+            .add_system(on_speed_button_clicked.in_set(OnUpdate(GameState::Playing)))
+            .add_system(highlight_speed_buttons.run_if(resource_changed::<GameSpeed>()))
+            .add_system(on_building_button_clicked.in_set(OnUpdate(GameState::Playing)))
+            .add_system(start_placement_by_hotkey.in_set(OnUpdate(GameState::Playing)))
+            .add_system(
+                rotate_building_to_place
+                    .run_if(resource_exists::<BuildingPlacement>())
+                    .in_set(OnUpdate(GameState::Playing))
+            )
             .add_system(
                 show_building_to_place
                     .run_if(resource_exists::<BuildingPlacement>())
+                    .in_set(OnUpdate(GameState::Playing))
             )
+            .add_system(
+                show_ghost_materials
+                    .run_if(resource_exists::<BuildingPlacement>())
+                    .in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(restore_original_materials.run_if(not(resource_exists::<BuildingPlacement>())))
+            .add_system(
+                show_tower_range
+                    .run_if(not(resource_exists::<BuildingPlacement>()))
+                    .in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(hide_range_indicator.in_schedule(OnExit(GameState::Playing)))
             .add_system(
                 on_hex_field_click
                     .run_if(resource_exists::<BuildingPlacement>())
+                    .in_set(OnUpdate(GameState::Playing))
+            )
+            .add_systems(
+                (
+                    update_building_panel,
+                    update_building_stats.after(update_building_panel),
+                    on_targeting_button_clicked,
+                    on_upgrade_button_clicked,
+                    on_ability_button_clicked,
+                    use_ability_by_hotkey,
+                    update_ability_button.after(update_building_panel),
+                    on_sell_button_clicked,
+                    // Escape answers an open dialog first
+                    cancel_selection.run_if(no_confirm_dialog),
+                ).in_set(OnUpdate(GameState::Playing))
             )
         ;
     }
 }
 
 #[derive(Component)]
-struct ChangingUiPart;
+struct GoldText;
+
+#[derive(Component)]
+struct LivesText;
+
+/// Current and total number of waves
+#[derive(Component)]
+struct WaveText;
+
+#[derive(Component)]
+struct EnemiesText;
+
+#[derive(Component)]
+struct FpsText;
+
+/// Row of the FPS counter, only shown when turned on in the settings
+#[derive(Component)]
+struct FpsWidget;
 
 #[derive(Resource)]
-struct BuildingPlacement {
-    building: Entity,
+pub struct BuildingPlacement {
+    pub building: Entity,
+    pub definition: TowerDefinition,
+    /// whether the building could be placed on the hovered hex
+    pub valid: bool,
+    /// turn around the vertical axis the building gets placed with
+    pub rotation: f32,
 }
 
-const BUILDING_SCALING: Vec3 = Vec3::splat(0.1);
+/// Material of a mesh of the placed building, swapped for the ghost material during placement
+#[derive(Component)]
+struct OriginalMaterial(Handle<StandardMaterial>);
+
+/// Placed building the player clicked on last
+#[derive(Resource)]
+pub struct SelectedBuilding(pub Entity);
+
+/// Translucent disc showing the attack radius of the hovered, selected or placed tower
+#[derive(Component)]
+struct RangeIndicator;
+
+#[derive(Component)]
+struct BuildingPanel;
+
+/// Live stats of the selected building inside the building panel
+#[derive(Component)]
+struct BuildingStatsText;
+
+/// Switches the selected building to the next `TargetingMode`
+#[derive(Component)]
+struct TargetingButton;
+
+#[derive(Component)]
+struct UpgradeButton;
+
+/// Triggers the ability of the selected building
+#[derive(Component)]
+struct AbilityButton;
+
+/// Part of the ability button filled while the ability cools down
+#[derive(Component)]
+struct AbilityCooldownFill;
+
+#[derive(Component)]
+struct AbilityText;
+
+#[derive(Component)]
+struct SellButton;
+
+/// Button switching the game speed to the given factor
+#[derive(Component)]
+struct SpeedButton(f32);
+
+/// Height of the bar along the bottom edge with the gold and the build buttons
+pub const BOTTOM_BAR_HEIGHT: f32 = 150.0;
+
+/// Width of the column of labeled values at the start of the bottom bar
+const HUD_WIDGET_WIDTH: f32 = 120.0;
+/// Interval the FPS counter is updated in, every frame would make it unreadable
+const FPS_REFRESH: Duration = Duration::from_millis(250);
+
+/// Rotation of the building to place per press of the rotate key, a sixth of a turn
+const PLACEMENT_ROTATION_STEP: f32 = std::f32::consts::FRAC_PI_3;
+
+const SPEED_BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const ACTIVE_SPEED_BUTTON_COLOR: Color = Color::rgb(0.2, 0.5, 0.2);
 
 fn setup_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    query: Query<&Window>,
+    ui_style: Res<UiStyle>,
+    registry: Res<TowerRegistry>,
+    localization: Res<Localization>,
 ) {
-    let window = query.single();
-
     commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    position: UiRect {
-                        top: Val::Px(window.height() - 150.0),
-                        ..default()
-                    },
-                    position_type: PositionType::Relative,
-                    size: Size::width(Val::Percent(100.0)),
-                    justify_content: JustifyContent::SpaceBetween,
+        .spawn(NodeBundle {
+            style: Style {
+                // anchored to the bottom edge, follows the window without any resize handling
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
                     ..default()
                 },
+                size: Size::new(Val::Percent(100.0), Val::Px(BOTTOM_BAR_HEIGHT)),
+                justify_content: JustifyContent::SpaceBetween,
                 ..default()
             },
-            ChangingUiPart
-        ))
+            ..default()
+        })
         .with_children(|parent| {
             // left vertical fill (border)
             parent
                 .spawn(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Percent(100.0), Val::Px(150.0)),
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                         ..default()
                     },
                     background_color: Color::rgb(0.65, 0.65, 0.65).into(),
@@ -95,152 +256,950 @@ fn setup_ui(
                             ..default()
                         })
                         .with_children(|parent| {
-                            // text
-                            parent.spawn((
-                                TextBundle::from_section(
-                                    "Text Example",
-                                    TextStyle {
-                                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                        font_size: 17.0,
-                                        color: Color::WHITE,
-                                    },
-                                )
-                                    .with_style(Style {
-                                        margin: UiRect::all(Val::Px(5.0)),
-                                        ..default()
-                                    }),
-                                // Because this is a distinct label widget and
-                                // not button/list item text, this is necessary
-                                // for accessibility to treat the text accordingly.
-                                Label,
-                            ));
-
+                            // labeled widgets bound to the state of the game
                             parent
-                                .spawn(ButtonBundle {
-                                    style: Style {
-                                        size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                                        // horizontally center child text
-                                        justify_content: JustifyContent::Center,
-                                        // vertically center child text
-                                        align_items: AlignItems::Center,
+                                .spawn((
+                                    NodeBundle {
+                                        style: Style {
+                                            flex_direction: FlexDirection::Column,
+                                            size: Size::width(Val::Px(HUD_WIDGET_WIDTH)),
+                                            margin: UiRect::all(Val::Px(5.0)),
+                                            ..default()
+                                        },
                                         ..default()
                                     },
-                                    image: UiImage {
-                                        texture: asset_server.load("images/button-01.png"),
+                                    AccessibleName::new(Role::Status, "a11y.hud"),
+                                ))
+                                .with_children(|parent| {
+                                    spawn_hud_widget(parent, &ui_style, &localization, "hud.gold", (GoldText, ()));
+                                    spawn_hud_widget(parent, &ui_style, &localization, "hud.lives", (LivesText, ()));
+                                    spawn_hud_widget(parent, &ui_style, &localization, "hud.wave", (WaveText, ()));
+                                    spawn_hud_widget(parent, &ui_style, &localization, "hud.enemies", (EnemiesText, ()));
+                                    spawn_hud_widget(parent, &ui_style, &localization, "hud.fps", (FpsText, FpsWidget));
+                                });
+
+                            spawn_build_menu(parent, &asset_server, &ui_style, &registry, &localization);
+
+                            for factor in SPEED_STEPS {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                size: Size::new(Val::Px(50.0), Val::Px(30.0)),
+                                                margin: UiRect::all(Val::Px(5.0)),
+                                                justify_content: JustifyContent::Center,
+                                                align_items: AlignItems::Center,
+                                                ..default()
+                                            },
+                                            background_color: SPEED_BUTTON_COLOR.into(),
+                                            ..default()
+                                        },
+                                        SpeedButton(factor),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(
+                                            format!("{}x", factor),
+                                            ui_style.text(TextSize::Body, Color::WHITE),
+                                        ));
+                                    });
+                            }
+
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            size: Size::new(Val::Px(80.0), Val::Px(30.0)),
+                                            margin: UiRect::all(Val::Px(5.0)),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        background_color: PATH_PREVIEW_BUTTON_COLOR.into(),
                                         ..default()
                                     },
-                                    ..default()
+                                    PathPreviewButton,
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        TextBundle::from_section(
+                                            localization.t("hud.path"),
+                                            ui_style.text(TextSize::Body, Color::WHITE),
+                                        ),
+                                        LocalizedText("hud.path"),
+                                    ));
                                 });
                         });
                 });
         });
 }
 
+/// Row of the HUD with the caption of the key on the left and the value of the text marked with `markers.0` on the right,
+/// `markers.1` goes onto the row itself
+fn spawn_hud_widget(
+    parent: &mut ChildBuilder,
+    ui_style: &UiStyle,
+    localization: &Localization,
+    caption_key: &'static str,
+    markers: (impl Component, impl Bundle),
+) {
+    let text_style = ui_style.text(TextSize::Body, Color::WHITE);
+    let (text_marker, row_marker) = markers;
+
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::SpaceBetween,
+                    margin: UiRect::vertical(Val::Px(2.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            row_marker,
+            // the value is announced under the caption
+            AccessibleName::group(caption_key),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    localization.t(caption_key),
+                    TextStyle { color: Color::rgb(0.7, 0.7, 0.7), ..text_style.clone() },
+                ),
+                LocalizedText(caption_key),
+            ));
+            parent.spawn((
+                TextBundle::from_section("-", text_style),
+                // Because this is a distinct label widget and
+                // not button/list item text, this is necessary
+                // for accessibility to treat the text accordingly.
+                Label,
+                text_marker,
+            ));
+        });
+}
+
+fn on_speed_button_clicked(
+    interactions: Query<(&Interaction, &SpeedButton), Changed<Interaction>>,
+    mut speed: ResMut<GameSpeed>,
+) {
+    for (interaction, button) in &interactions {
+        if *interaction == Interaction::Clicked {
+            speed.factor = button.0;
+        }
+    }
+}
+
+fn highlight_speed_buttons(
+    speed: Res<GameSpeed>,
+    mut buttons: Query<(&SpeedButton, &mut BackgroundColor)>,
+) {
+    for (button, mut color) in &mut buttons {
+        *color = if button.0 == speed.factor {
+            ACTIVE_SPEED_BUTTON_COLOR
+        } else {
+            SPEED_BUTTON_COLOR
+        }.into();
+    }
+}
+
 fn on_hex_field_click(
     mut commands: Commands,
-    map: Res<Map>,
+    mut map: ResMut<Map>,
+    mut highlights: ResMut<HexHighlights>,
     mut field_click_reader: EventReader<HexFieldClicked>,
-    mut placement: ResMut<BuildingPlacement>,
+    placement: Res<BuildingPlacement>,
+    mut resources: ResMut<PlayerResources>,
+    mut grid_changed: EventWriter<GridChanged>,
+    mut built_writer: EventWriter<TowerBuilt>,
+    mut history: ResMut<BuildHistory>,
+    asset_server: Res<AssetServer>,
+    mut toasts: ResMut<Toasts>,
+    localization: Res<Localization>,
+    tutorial: Option<Res<Tutorial>>,
 ) {
-    if field_click_reader.is_empty() {
+    let Some(clicked_hex) = field_click_reader.iter().next().map(|event| event.0) else {
+        return;
+    };
+    field_click_reader.clear();
+
+    if !map.is_buildable(clicked_hex) || !route_stays_open(&map, clicked_hex) {
+        return;
+    }
+    if !tutorial.map_or(true, |tutorial| tutorial.allows_hex(clicked_hex)) {
         return;
     }
 
-    let event = field_click_reader.iter().next().unwrap();
+    if !resources.try_spend(placement.definition.cost) {
+        toasts.push(localization.t(NOT_ENOUGH_GOLD));
+        return;
+    }
 
-    let world_pos = map.layout.hex_to_world_pos(event.0);
     let obj_entity = placement.building;
+    insert_tower(&mut commands, obj_entity, &placement.definition, 0, clicked_hex, map.surface_pos(clicked_hex), &asset_server);
+    // keeps facing the way it was rotated to during placement
+    commands.entity(obj_entity).insert(
+        Transform::from_translation(map.surface_pos(clicked_hex))
+            .with_rotation(Quat::from_rotation_y(placement.rotation))
+            .with_scale(BUILDING_SCALING)
+    );
+    // pops up out of the ground
+    commands.entity(obj_entity).insert(Tween::new(
+        TransformScaleLens { start: Vec3::ZERO, end: BUILDING_SCALING },
+        Duration::from_millis(250),
+        Easing::BackOut,
+    ));
+    map.buildings.insert(clicked_hex, obj_entity);
+    grid_changed.send(GridChanged);
+    built_writer.send(TowerBuilt(obj_entity));
+    history.record(BuildCommand::Placed { hex: clicked_hex, tower: placement.definition.id.clone(), level: 0 });
 
-    commands.entity(obj_entity)
-        .insert((
-            BuildingTag,
-            HasAttack {
-                timer: Timer::new(Duration::from_millis(800), TimerMode::Repeating),
-            },
-            Transform::from_xyz(world_pos.x, 0.0, world_pos.y).with_scale(BUILDING_SCALING),
-        ));
-
-    // clear all fields again
-    map.entities
-        .iter()
-        .for_each(|(hex, e)| {
-            commands.entity(*e).insert(map.default_material.clone());
-        });
+    clear_placement_highlights(&mut highlights);
 
     commands.remove_resource::<BuildingPlacement>();
 }
 
+/// Hex under the gamepad cursor or the mouse, with its column entity
+fn hovered_hex(pointer: &PointerHex, cursor: Option<&HexCursor>, map: &Map) -> Option<(Hex, Entity)> {
+    // the gamepad cursor replaces the mouse while it exists
+    let hex = cursor.map_or(pointer.0, |cursor| Some(cursor.hex))?;
+    map.entities.get(&hex).map(|e| (hex, *e))
+}
+
+fn spawn_range_indicator(mut commands: Commands, assets: Res<GameAssets>) {
+    commands.spawn((
+        RangeIndicator,
+        PbrBundle {
+            mesh: assets.shockwave_mesh.clone(),
+            material: assets.range_material.clone(),
+            transform: Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+/// Moves the range indicator to `center`, hides it without a range
+fn place_range_indicator(
+    indicators: &mut Query<(&mut Transform, &mut Visibility), With<RangeIndicator>>,
+    range: Option<(Vec3, f32)>,
+) {
+    for (mut transform, mut visibility) in indicators.iter_mut() {
+        match range {
+            Some((center, range)) => {
+                // slightly above the ground so the disc doesn't flicker with the hex tops
+                transform.translation = center + Vec3::Y * 0.02;
+                transform.scale = Vec3::new(range, range, 1.0);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
 fn show_building_to_place(
     mut commands: Commands,
-    hover_map: Res<HoverMap>,
+    pointer: Res<PointerHex>,
+    cursor: Option<Res<HexCursor>>,
     map: Res<Map>,
+    mut highlights: ResMut<HexHighlights>,
+    mut placement: ResMut<BuildingPlacement>,
+    mut indicators: Query<(&mut Transform, &mut Visibility), With<RangeIndicator>>,
+) {
+    let Some((hex_field, _)) = hovered_hex(&pointer, cursor.as_deref(), &map) else {
+        return;
+    };
+
+    clear_placement_highlights(&mut highlights);
+
+    // snapped to the center of the hex, exactly where the building is going to land
+    let pos = map.surface_pos(hex_field);
+    commands.entity(placement.building).insert(
+        Transform::from_translation(pos)
+            .with_rotation(Quat::from_rotation_y(placement.rotation))
+            .with_scale(BUILDING_SCALING)
+    );
+    let range = (!placement.definition.is_wall()).then_some((pos, placement.definition.range));
+    place_range_indicator(&mut indicators, range);
+
+    hex_field.ring(1)
+        .for_each(|h| highlights.highlight(h, HighlightKind::PlacementArea));
+
+    let valid = map.is_buildable(hex_field) && route_stays_open(&map, hex_field);
+    if placement.valid != valid {
+        placement.valid = valid;
+    }
+
+    let hovered = if valid {
+        HighlightKind::PlacementValid
+    } else {
+        HighlightKind::PlacementInvalid
+    };
+    highlights.highlight(hex_field, hovered);
+}
+
+fn clear_placement_highlights(highlights: &mut HexHighlights) {
+    highlights.clear(HighlightKind::PlacementArea);
+    highlights.clear(HighlightKind::PlacementValid);
+    highlights.clear(HighlightKind::PlacementInvalid);
+}
+
+/// Tints the meshes of the building being placed, the scene spawns them a few frames after the placement started
+fn show_ghost_materials(
+    mut commands: Commands,
     placement: Res<BuildingPlacement>,
+    assets: Res<GameAssets>,
+    children: Query<&Children>,
+    mut meshes: Query<(&mut Handle<StandardMaterial>, Option<&OriginalMaterial>)>,
 ) {
-    if let Some((_, hit_data)) = hover_map.0.iter().next() {
-        if let Some((entity, hit_value)) = hit_data.iter().next() {
-            let entries = map.entities
-                .iter()
-                .map(|(hex, e)| {
-                    commands.entity(*e).insert(map.default_material.clone());
-                    return (hex, e);
-                })
-                .filter(|(hex, e)| *e == entity)
-                .collect::<Vec<(&Hex, &Entity)>>();
-
-            if let Some((hex_field, field_entity)) = entries.first() {
-                let pos = hit_value.position.unwrap();
-                commands.entity(placement.building).insert(
-                    Transform::from_xyz(pos.x, 0.0, pos.z).with_scale(BUILDING_SCALING)
-                );
-
-                hex_field.ring(1)
-                    .for_each(|h| {
-                        if let Some(e) = map.entities.get(&h) {
-                            commands.entity(*e).insert(map.selection_material.clone());
-                        }
-                    });
-                commands.entity(**field_entity).insert(map.selection_material.clone());
-            }
+    let ghost_material = if placement.valid {
+        &assets.ghost_valid_material
+    } else {
+        &assets.ghost_invalid_material
+    };
+
+    for entity in children.iter_descendants(placement.building) {
+        let Ok((mut material, original)) = meshes.get_mut(entity) else {
+            continue;
+        };
+        if original.is_none() {
+            commands.entity(entity).insert(OriginalMaterial(material.clone()));
+        }
+        if *material != *ghost_material {
+            *material = ghost_material.clone();
         }
     }
 }
 
+/// Gives the confirmed building its own materials back
+fn restore_original_materials(
+    mut commands: Commands,
+    mut meshes: Query<(Entity, &mut Handle<StandardMaterial>, &OriginalMaterial)>,
+) {
+    for (entity, mut material, original) in &mut meshes {
+        *material = original.0.clone();
+        commands.entity(entity).remove::<OriginalMaterial>();
+    }
+}
+
+/// Shows the range of the tower under the pointer, or of the selected one
+fn show_tower_range(
+    pointer: Res<PointerHex>,
+    cursor: Option<Res<HexCursor>>,
+    map: Res<Map>,
+    selected: Option<Res<SelectedBuilding>>,
+    towers: Query<(&HexLocation, &TargetingRange), With<Tower>>,
+    mut indicators: Query<(&mut Transform, &mut Visibility), With<RangeIndicator>>,
+) {
+    let hovered_tower = hovered_hex(&pointer, cursor.as_deref(), &map)
+        .and_then(|(hex, _)| map.buildings.get(&hex).copied());
+    let tower = hovered_tower.or_else(|| selected.map(|s| s.0));
+
+    let range = tower
+        .and_then(|e| towers.get(e).ok())
+        .map(|(location, range)| (map.surface_pos(location.location), range.0));
+    place_range_indicator(&mut indicators, range);
+}
+
+fn hide_range_indicator(mut indicators: Query<(&mut Transform, &mut Visibility), With<RangeIndicator>>) {
+    place_range_indicator(&mut indicators, None);
+}
+
+/// Spawns the ghost of the building to place, replacing the one placed before
+fn start_placement(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    definition: &TowerDefinition,
+    previous: Option<&BuildingPlacement>,
+    selection: &mut Selection,
+) {
+    if let Some(previous) = previous {
+        commands.entity(previous.building).despawn_recursive();
+    }
+
+    let transform = Transform::from_scale(Vec3::splat(0.0));
+    let entity = if definition.is_wall() {
+        commands.spawn((SpatialBundle::from_transform(transform), Wall)).id()
+    } else {
+        commands
+            .spawn((
+                SceneBundle {
+                    scene: asset_server.load(definition.model.as_str()),
+                    transform,
+                    ..default()
+                },
+            )).id()
+    };
+
+    commands.insert_resource(BuildingPlacement {
+        building: entity,
+        definition: definition.clone(),
+        valid: false,
+        // a new building keeps the rotation of the one placed before
+        rotation: previous.map_or(0.0, |previous| previous.rotation),
+    });
+    // placing a building resets the hex materials, including the selection highlight
+    selection.clear();
+}
+
 fn on_building_button_clicked(
     mut commands: Commands,
-    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut interaction_query: Query<(&Interaction, &BuildButton), Changed<Interaction>>,
     asset_server: Res<AssetServer>,
+    registry: Res<TowerRegistry>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+    active_level: Option<Res<ActiveLevel>>,
+    tutorial: Option<Res<Tutorial>>,
+    placement: Option<Res<BuildingPlacement>>,
+    mut selection: ResMut<Selection>,
 ) {
-    for interaction in &mut interaction_query {
+    for (interaction, button) in &mut interaction_query {
         match *interaction {
             Interaction::Clicked => {
-                let entity = commands
+                let Some(definition) = registry.get(&button.0) else {
+                    continue;
+                };
+                if !research.is_tower_unlocked(&definition.id, &techs) || !allows_tower(active_level.as_deref(), definition) {
+                    continue;
+                }
+                if !tutorial.as_ref().map_or(true, |tutorial| tutorial.allows_building(&definition.id)) {
+                    continue;
+                }
+
+                start_placement(&mut commands, &asset_server, definition, placement.as_deref(), &mut selection);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The number keys start placing the buildable towers of the open build tab in the order of their buttons,
+/// held together with Ctrl or Shift they go to the control groups instead
+fn start_placement_by_hotkey(
+    mut commands: Commands,
+    actions: Query<&ActionState<Action>>,
+    asset_server: Res<AssetServer>,
+    registry: Res<TowerRegistry>,
+    menu: Res<BuildMenu>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+    active_level: Option<Res<ActiveLevel>>,
+    tutorial: Option<Res<Tutorial>>,
+    placement: Option<Res<BuildingPlacement>>,
+    mut selection: ResMut<Selection>,
+) {
+    let Some(index) = actions
+        .iter()
+        .find_map(|action_state| BUILD_SLOT_ACTIONS.iter().position(|action| action_state.just_pressed(*action)))
+    else {
+        return;
+    };
+
+    let definition = registry.towers
+        .iter()
+        .filter(|tower| tower.category == menu.category && research.is_tower_unlocked(&tower.id, &techs))
+        .filter(|tower| allows_tower(active_level.as_deref(), tower))
+        .nth(index);
+    // the tutorial keeps the numbers of the buttons, it only refuses the towers it doesn't explain
+    let definition = definition.filter(|definition| tutorial.map_or(true, |tutorial| tutorial.allows_building(&definition.id)));
+    if let Some(definition) = definition {
+        start_placement(&mut commands, &asset_server, definition, placement.as_deref(), &mut selection);
+    }
+}
+
+/// Turns the building to place by a sixth of a turn, so it lines up with the hex edges again
+fn rotate_building_to_place(
+    mut placement: ResMut<BuildingPlacement>,
+    actions: Query<&ActionState<Action>>,
+) {
+    if actions.iter().any(|action_state| action_state.just_pressed(Action::RotateBuilding)) {
+        placement.rotation = (placement.rotation + PLACEMENT_ROTATION_STEP) % std::f32::consts::TAU;
+    }
+}
+
+fn update_building_panel(
+    mut commands: Commands,
+    ui_style: Res<UiStyle>,
+    registry: Res<TowerRegistry>,
+    balance: Res<Balance>,
+    localization: Res<Localization>,
+    selected: Option<Res<SelectedBuilding>>,
+    panels: Query<Entity, With<BuildingPanel>>,
+    towers: Query<(&Tower, &TowerLevel, Option<(&TargetingMode, &HasAttack, &TargetingRange, &CombatRecord)>, Option<&AbilityState>)>,
+    changed_towers: Query<(), Or<(Changed<TowerLevel>, Changed<TargetingMode>)>>,
+) {
+    let selection_changed = selected.as_ref().map_or(false, |s| {
+        s.is_changed() || changed_towers.contains(s.0) || localization.is_changed() || balance.is_changed()
+    });
+    let deselected = selected.is_none() && !panels.is_empty();
+    if !selection_changed && !deselected {
+        return;
+    }
+
+    for panel in &panels {
+        commands.entity(panel).despawn_recursive();
+    }
+
+    let Some((tower, level, combat, ability)) = selected.and_then(|s| towers.get(s.0).ok()) else {
+        return;
+    };
+    let Some(definition) = registry.get(&tower.id) else {
+        return;
+    };
+
+    let text_style = ui_style.text(TextSize::Body, Color::WHITE);
+    let title = localization.t_with(
+        "building.title",
+        &[("name", localization.t(&definition.name).to_string()), ("level", (level.0 + 1).to_string())],
+    );
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(10.0),
+                        right: Val::Px(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                ..default()
+            },
+            BuildingPanel,
+            // selecting a tower reads out its name, the stats and the buttons follow
+            AccessibleName::new(Role::Region, title.clone()),
+            LiveRegion,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(title, text_style.clone())
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                Label,
+            ));
+
+            // walls don't attack, there are no stats or targets to show
+            if let Some((mode, attack, range, record)) = combat {
+                parent.spawn((
+                    TextBundle::from_section(building_stats(attack, range, record, &localization), text_style.clone())
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        }),
+                    BuildingStatsText,
+                    Label,
+                ));
+
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(150.0), Val::Px(40.0)),
+                                margin: UiRect::all(Val::Px(5.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+                            ..default()
+                        },
+                        TargetingButton,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            localization.t_with("building.target", &[("mode", localization.t(mode.label_key()).to_string())]),
+                            text_style.clone(),
+                        ));
+                    });
+            }
+
+            if let Some(ability) = ability {
+                parent
                     .spawn((
-                        SceneBundle {
-                            scene: asset_server.load("models/tower-001.glb#Scene0"),
-                            transform: Transform::from_scale(Vec3::splat(0.0)),
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(150.0), Val::Px(40.0)),
+                                margin: UiRect::all(Val::Px(5.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.3, 0.3, 0.3).into(),
                             ..default()
                         },
-                    )).id();
+                        AbilityButton,
+                    ))
+                    .with_children(|parent| {
+                        // shrinks from the full width back to nothing while the ability cools down
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    position: UiRect {
+                                        left: Val::Px(0.0),
+                                        top: Val::Px(0.0),
+                                        ..default()
+                                    },
+                                    size: Size::new(Val::Percent(100.0 * ability.cooldown_left()), Val::Percent(100.0)),
+                                    ..default()
+                                },
+                                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                                ..default()
+                            },
+                            AbilityCooldownFill,
+                        ));
+                        parent.spawn((
+                            TextBundle::from_section(ability_label(ability, &localization), text_style.clone()),
+                            AbilityText,
+                        ));
+                    });
+            }
 
-                commands.insert_resource(BuildingPlacement {
-                    building: entity
-                });
+            match definition.upgrades.get(level.0) {
+                Some(upgrade) => {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(150.0), Val::Px(40.0)),
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+                                ..default()
+                            },
+                            UpgradeButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                localization.t_with("building.upgrade", &[("cost", upgrade.cost.to_string())]),
+                                text_style.clone(),
+                            ));
+                        });
+                }
+                None => {
+                    parent.spawn((
+                        TextBundle::from_section(localization.t("building.max_level"), text_style.clone())
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(5.0)),
+                                ..default()
+                            }),
+                        Label,
+                    ));
+                }
             }
-            _ => {}
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(150.0), Val::Px(40.0)),
+                            margin: UiRect::all(Val::Px(5.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+                        ..default()
+                    },
+                    SellButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        localization.t_with("building.sell", &[("value", definition.sell_value(level.0, &balance).to_string())]),
+                        text_style.clone(),
+                    ));
+                });
+        });
+}
+
+/// Text listing the attack stats and the record of a building
+fn building_stats(attack: &HasAttack, range: &TargetingRange, record: &CombatRecord, localization: &Localization) -> String {
+    let stats = localization.t_with(
+        "building.stats",
+        &[
+            ("damage", format!("{:.0}", attack.damage)),
+            ("fire_rate", format!("{:.1}", 1.0 / attack.timer.duration().as_secs_f32())),
+            ("range", format!("{:.1}", range.0)),
+            ("kills", record.kills.to_string()),
+            ("damage_dealt", format!("{:.0}", record.damage_dealt)),
+        ],
+    );
+    format!("{}\n{}", stats, veterancy_label(record, localization))
+}
+
+/// Veterancy rank of a tower and the kills missing for the next one
+fn veterancy_label(record: &CombatRecord, localization: &Localization) -> String {
+    let rank = record.rank();
+    let mut args = vec![("rank", rank.to_string()), ("ranks", VETERANCY_KILLS.len().to_string())];
+    match VETERANCY_KILLS.get(rank) {
+        Some(kills) => {
+            args.push(("kills", kills.to_string()));
+            localization.t_with("building.rank", &args)
         }
+        None => localization.t_with("building.rank_veteran", &args),
     }
 }
 
-fn on_resize_system(
-    mut q: Query<&mut Style, With<ChangingUiPart>>,
-    mut resize_reader: EventReader<WindowResized>,
+/// Text of the ability button, telling whether the ability is running or how long it cools down
+fn ability_label(state: &AbilityState, localization: &Localization) -> String {
+    let name = localization.t(state.ability.kind.label_key()).to_string();
+    if state.is_active() {
+        localization.t_with("building.ability_active", &[("name", name)])
+    } else if state.is_ready() {
+        name
+    } else {
+        let seconds = format!("{:.0}", state.cooldown_remaining().as_secs_f32().ceil());
+        localization.t_with("building.ability_cooldown", &[("name", name), ("seconds", seconds)])
+    }
+}
+
+/// Keeps the kills and the damage in the building panel up to date
+fn update_building_stats(
+    selected: Option<Res<SelectedBuilding>>,
+    towers: Query<(&HasAttack, &TargetingRange, Ref<CombatRecord>)>,
+    localization: Res<Localization>,
+    mut texts: Query<&mut Text, With<BuildingStatsText>>,
 ) {
-    let mut text = q.single_mut();
-    for e in resize_reader.iter() {
-        // When resolution is being changed
-        text.position = UiRect {
-            top: Val::Px(e.height - 150.0),
-            ..default()
+    let Some((attack, range, record)) = selected.and_then(|s| towers.get(s.0).ok()) else {
+        return;
+    };
+    if !record.is_changed() {
+        return;
+    }
+
+    for mut text in &mut texts {
+        text.sections[0].value = building_stats(attack, range, &record, &localization);
+    }
+}
+
+/// Marks the hex of the selected building, the previous one loses its mark
+fn on_targeting_button_clicked(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<TargetingButton>)>,
+    selected: Option<Res<SelectedBuilding>>,
+    mut modes: Query<&mut TargetingMode>,
+) {
+    let Some(mut mode) = selected.and_then(|s| modes.get_mut(s.0).ok()) else {
+        return;
+    };
+
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            *mode = mode.next();
+        }
+    }
+}
+
+fn on_ability_button_clicked(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<AbilityButton>)>,
+    selected: Option<Res<SelectedBuilding>>,
+    mut ability_writer: EventWriter<ActivateAbility>,
+) {
+    let Some(selected) = selected else {
+        return;
+    };
+
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            ability_writer.send(ActivateAbility(selected.0));
+        }
+    }
+}
+
+/// Triggers the abilities of all selected towers, e.g. of a recalled control group
+fn use_ability_by_hotkey(
+    selection: Res<Selection>,
+    placement: Option<Res<BuildingPlacement>>,
+    actions: Query<&ActionState<Action>>,
+    mut ability_writer: EventWriter<ActivateAbility>,
+) {
+    // the same key rotates the building to place
+    if placement.is_some() {
+        return;
+    }
+
+    for action_state in &actions {
+        if action_state.just_pressed(Action::UseAbility) {
+            ability_writer.send_batch(selection.entities.iter().map(|entity| ActivateAbility(*entity)));
+        }
+    }
+}
+
+/// Shows the cooldown of the selected building's ability on its button
+fn update_ability_button(
+    selected: Option<Res<SelectedBuilding>>,
+    abilities: Query<&AbilityState>,
+    localization: Res<Localization>,
+    mut fills: Query<&mut Style, With<AbilityCooldownFill>>,
+    mut texts: Query<&mut Text, With<AbilityText>>,
+) {
+    let Some(state) = selected.and_then(|s| abilities.get(s.0).ok()) else {
+        return;
+    };
+
+    let width = Val::Percent(100.0 * state.cooldown_left());
+    for mut style in &mut fills {
+        if style.size.width != width {
+            style.size.width = width;
+        }
+    }
+    let label = ability_label(state, &localization);
+    for mut text in &mut texts {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+fn on_upgrade_button_clicked(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<UpgradeButton>)>,
+    selected: Option<Res<SelectedBuilding>>,
+    mut upgrade_writer: EventWriter<UpgradeTower>,
+) {
+    let Some(selected) = selected else {
+        return;
+    };
+
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            upgrade_writer.send(UpgradeTower(selected.0));
+        }
+    }
+}
+
+fn on_sell_button_clicked(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<SellButton>)>,
+    selected: Option<Res<SelectedBuilding>>,
+    towers: Query<(&Tower, &TowerLevel)>,
+    registry: Res<TowerRegistry>,
+    balance: Res<Balance>,
+    active_level: Option<Res<ActiveLevel>>,
+    localization: Res<Localization>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let Some(selected) = selected else {
+        return;
+    };
+
+    for interaction in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        if level_has_rule(active_level.as_deref(), LevelRule::NoSelling) {
+            toasts.push(localization.t("toast.no_selling"));
+            continue;
+        }
+        let Ok((tower, level)) = towers.get(selected.0) else {
+            continue;
+        };
+        let Some(definition) = registry.get(&tower.id) else {
+            continue;
         };
+
+        // the selection lets go of the tower once it is gone
+        ConfirmDialog::spawn(
+            &mut commands,
+            localization.t_with(
+                "dialog.sell",
+                &[("name", localization.t(&definition.name).to_string()), ("value", definition.sell_value(level.0, &balance).to_string())],
+            ),
+            SellTower(selected.0),
+        );
+    }
+}
+
+/// Replaces the value of the texts only if it differs, so unchanged texts aren't laid out again
+fn set_texts<'a>(texts: impl Iterator<Item = Mut<'a, Text>>, value: String) {
+    for mut text in texts {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+fn update_gold_text(
+    mut texts: Query<&mut Text, (With<GoldText>, Without<LivesText>)>,
+    mut lives_texts: Query<&mut Text, With<LivesText>>,
+    resources: Res<PlayerResources>,
+) {
+    set_texts(texts.iter_mut(), resources.gold.to_string());
+    set_texts(lives_texts.iter_mut(), resources.lives.to_string());
+}
+
+/// Current wave out of all waves of the map, endless games only count up
+fn update_wave_text(
+    mut texts: Query<&mut Text, With<WaveText>>,
+    waves: Res<Waves>,
+    difficulty: Res<Difficulty>,
+) {
+    let total = waves.definitions.len();
+    let value = if difficulty.is_endless() {
+        (waves.current + 1).to_string()
+    } else {
+        format!("{}/{}", (waves.current + 1).min(total), total)
+    };
+    set_texts(texts.iter_mut(), value);
+}
+
+/// Enemies alive plus the ones of the running wave still to be spawned
+fn update_enemies_text(
+    mut texts: Query<&mut Text, With<EnemiesText>>,
+    waves: Res<Waves>,
+    enemies: Query<(), With<EnemyTag>>,
+) {
+    set_texts(texts.iter_mut(), (enemies.iter().count() + waves.queue().len()).to_string());
+}
+
+fn update_fps_text(
+    mut texts: Query<&mut Text, With<FpsText>>,
+    diagnostics: Res<Diagnostics>,
+) {
+    let Some(fps) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS).and_then(|fps| fps.smoothed()) else {
+        return;
+    };
+    set_texts(texts.iter_mut(), format!("{:.0}", fps));
+}
+
+fn show_fps_widget(
+    settings: Res<GameSettings>,
+    mut widgets: Query<&mut Style, With<FpsWidget>>,
+) {
+    for mut style in &mut widgets {
+        style.display = if settings.show_fps { Display::Flex } else { Display::None };
     }
 }
+
+/// Aborts the building placement with Escape, right-click or the gamepad, or clears the selection
+pub fn cancel_selection(
+    mut commands: Commands,
+    mut highlights: ResMut<HexHighlights>,
+    mut selection: ResMut<Selection>,
+    placement: Option<Res<BuildingPlacement>>,
+    ui_actions: Query<&ActionState<UiAction>>,
+) {
+    let Ok(action_state) = ui_actions.get_single() else {
+        return;
+    };
+    if !action_state.just_pressed(UiAction::Cancel) {
+        return;
+    }
+
+    let Some(placement) = placement else {
+        selection.clear();
+        return;
+    };
+
+    commands.entity(placement.building).despawn_recursive();
+    clear_placement_highlights(&mut highlights);
+    commands.remove_resource::<BuildingPlacement>();
+}
@@ -0,0 +1,203 @@
+use bevy::a11y::accesskit::Role;
+use bevy::prelude::*;
+
+use crate::gameplay::research::{Research, ResearchTech, TechDefinition, TechRegistry, TechUnlock};
+use crate::gameplay::towers::TowerRegistry;
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::style::{TextSize, UiStyle};
+
+const RESEARCHED_COLOR: Color = Color::rgb(0.2, 0.45, 0.2);
+const AFFORDABLE_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const LOCKED_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+
+pub struct ResearchScreenPlugin;
+
+impl Plugin for ResearchScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(
+                (
+                    toggle_research_tab,
+                    on_tech_button_clicked,
+                    refresh_research_tab
+                        .after(toggle_research_tab)
+                        .run_if(resource_changed::<Research>().or_else(resource_changed::<Localization>())),
+                ).in_set(OnUpdate(GameState::Paused))
+            )
+            .add_system(close_research_tab.in_schedule(OnExit(GameState::Paused)))
+        ;
+    }
+}
+
+/// Button in the game menu showing the tech tree
+#[derive(Component)]
+pub struct ResearchButton;
+
+#[derive(Component)]
+struct ResearchTab;
+
+/// Row of the tech tree, clicking it researches the tech
+#[derive(Component)]
+struct TechButton(String);
+
+fn tech_label(
+    tech: &TechDefinition,
+    research: &Research,
+    techs: &TechRegistry,
+    towers: &TowerRegistry,
+    localization: &Localization,
+) -> String {
+    let unlock = match &tech.unlock {
+        TechUnlock::Tower(id) => {
+            let name = towers.get(id).map_or(id.as_str(), |tower| localization.t(&tower.name));
+            localization.t_with("research.unlocks", &[("name", name.to_string())])
+        }
+        TechUnlock::Modifier(modifier) => modifier.label(localization),
+    };
+    let state = if research.is_researched(&tech.id) {
+        localization.t("research.researched").to_string()
+    } else if !research.is_available(tech) {
+        let missing: Vec<&str> = tech.requires
+            .iter()
+            .filter(|required| !research.is_researched(required))
+            .map(|required| techs.get(required).map_or(required.as_str(), |required| localization.t(&required.name)))
+            .collect();
+        localization.t_with("research.needs", &[("techs", missing.join(", "))])
+    } else {
+        localization.t_with("research.cost", &[("cost", tech.cost.to_string())])
+    };
+    format!("{} - {} ({})", localization.t(&tech.name), unlock, state)
+}
+
+fn spawn_research_tab(
+    commands: &mut Commands,
+    ui_style: &UiStyle,
+    research: &Research,
+    techs: &TechRegistry,
+    towers: &TowerRegistry,
+    localization: &Localization,
+) {
+    let text_style = ui_style.text(TextSize::Large, Color::rgb(0.9, 0.9, 0.9));
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(30.0),
+                    top: Val::Px(20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            z_index: ZIndex::Global(5),
+            ..default()
+        })
+        .insert((ResearchTab, AccessibleName::new(Role::Menu, "menu.research")))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    localization.t_with("research.points", &[("points", research.points.to_string())]),
+                    text_style.clone(),
+                )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(6.0)),
+                        ..default()
+                    }),
+                Label,
+            ));
+
+            for tech in &techs.techs {
+                let color = if research.is_researched(&tech.id) {
+                    RESEARCHED_COLOR
+                } else if research.can_research(tech) {
+                    AFFORDABLE_COLOR
+                } else {
+                    LOCKED_COLOR
+                };
+
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::height(Val::Px(28.0)),
+                            align_items: AlignItems::Center,
+                            padding: UiRect::horizontal(Val::Px(8.0)),
+                            margin: UiRect::vertical(Val::Px(2.0)),
+                            ..default()
+                        },
+                        background_color: color.into(),
+                        ..default()
+                    })
+                    .insert(TechButton(tech.id.clone()))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(tech_label(tech, research, techs, towers, localization), text_style.clone()));
+                    });
+            }
+        });
+}
+
+fn toggle_research_tab(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ResearchButton>)>,
+    tabs: Query<Entity, With<ResearchTab>>,
+    ui_style: Res<UiStyle>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+    towers: Res<TowerRegistry>,
+    localization: Res<Localization>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        if tabs.is_empty() {
+            spawn_research_tab(&mut commands, &ui_style, &research, &techs, &towers, &localization);
+        } else {
+            for entity in tabs.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn on_tech_button_clicked(
+    interactions: Query<(&Interaction, &TechButton), Changed<Interaction>>,
+    mut research_writer: EventWriter<ResearchTech>,
+) {
+    for (interaction, button) in &interactions {
+        if *interaction == Interaction::Clicked {
+            research_writer.send(ResearchTech(button.0.clone()));
+        }
+    }
+}
+
+/// Shows the new state of the tree after researching something, if the tab is open
+fn refresh_research_tab(
+    mut commands: Commands,
+    tabs: Query<Entity, With<ResearchTab>>,
+    ui_style: Res<UiStyle>,
+    research: Res<Research>,
+    techs: Res<TechRegistry>,
+    towers: Res<TowerRegistry>,
+    localization: Res<Localization>,
+) {
+    if tabs.is_empty() {
+        return;
+    }
+    for entity in tabs.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_research_tab(&mut commands, &ui_style, &research, &techs, &towers, &localization);
+}
+
+fn close_research_tab(mut commands: Commands, tabs: Query<Entity, With<ResearchTab>>) {
+    for entity in tabs.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,388 @@
+use bevy::prelude::*;
+use bevy_mod_picking::focus::HoverMap;
+use hexx::Hex;
+
+use crate::{HexFieldClicked, HexLocation, Map};
+use crate::assets::GameAssets;
+use crate::camera::PlayerCamera;
+use crate::gameplay::combat::Armor;
+use crate::gameplay::effects::StatusEffects;
+use crate::gameplay::enemy::{EnemyKind, EnemyTag, Health, Shield};
+use crate::gameplay::movement::MovementSpeed;
+use crate::gameplay::towers::{Tower, Wall};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::ability_bar::AbilityTargeting;
+use crate::ui::enemy_tooltip::enemy_details;
+use crate::ui::highlight::{HexHighlights, HighlightKind};
+use crate::ui::player::{BuildingPlacement, SelectedBuilding};
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Pixels the mouse has to move while pressed for a selection box instead of a click
+const DRAG_THRESHOLD: f32 = 6.0;
+/// Height of the selection rings above the hexes, so they don't flicker with the hex tops
+const RING_HEIGHT: f32 = 0.03;
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Selection>()
+            .add_startup_system(spawn_selection_ui)
+            .add_systems(
+                (
+                    start_selection_box,
+                    drag_selection_box.after(start_selection_box),
+                    finish_selection_box.after(drag_selection_box),
+                    select_clicked,
+                    sync_selected_building.after(select_clicked).after(finish_selection_box),
+                    highlight_selected_towers.after(sync_selected_building),
+                    place_selection_rings.after(sync_selected_building),
+                    update_enemy_panel.after(sync_selected_building),
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(hide_selection_ui.in_schedule(OnExit(GameState::Playing)))
+        ;
+    }
+}
+
+/// Towers or the enemy the player picked, the first tower is the `SelectedBuilding` shown in the building panel
+#[derive(Resource, Default, Debug)]
+pub struct Selection {
+    pub entities: Vec<Entity>,
+}
+
+impl Selection {
+    pub fn select(&mut self, entity: Entity) {
+        self.entities = vec![entity];
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        self.entities.retain(|selected| *selected != entity);
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+}
+
+/// Screen rectangle spanned while the left mouse button is held, in window coordinates starting at the bottom left
+#[derive(Resource, Debug)]
+pub struct SelectionBox {
+    start: Vec2,
+    end: Vec2,
+}
+
+impl SelectionBox {
+    /// Whether the mouse moved far enough to select everything inside instead of clicking a hex
+    pub fn is_drag(&self) -> bool {
+        self.start.distance(self.end) > DRAG_THRESHOLD
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::from_corners(self.start, self.end)
+    }
+}
+
+/// Translucent rectangle drawn over the area of the selection box
+#[derive(Component)]
+struct SelectionBoxNode;
+
+/// Ring on the ground following the selected entity
+#[derive(Component)]
+struct SelectionRing(Entity);
+
+/// Details of the selected enemy
+#[derive(Component)]
+struct EnemyPanel;
+
+#[derive(Component)]
+struct EnemyPanelText;
+
+fn spawn_selection_ui(mut commands: Commands, ui_style: Res<UiStyle>) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::rgba(0.5, 1.0, 0.85, 0.15).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(2),
+            ..default()
+        },
+        SelectionBoxNode,
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(10.0),
+                        right: Val::Px(10.0),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            EnemyPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    ui_style.text(TextSize::Body, Color::WHITE),
+                ),
+                EnemyPanelText,
+            ));
+        });
+}
+
+fn start_selection_box(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    interactions: Query<&Interaction, With<Node>>,
+    placement: Option<Res<BuildingPlacement>>,
+    targeting: Option<Res<AbilityTargeting>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || placement.is_some() || targeting.is_some() {
+        return;
+    }
+    // presses on buttons and panels are meant for the UI
+    if interactions.iter().any(|interaction| *interaction != Interaction::None) {
+        return;
+    }
+    let Some(cursor) = windows.get_single().ok().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+
+    commands.insert_resource(SelectionBox { start: cursor, end: cursor });
+}
+
+fn drag_selection_box(
+    selection_box: Option<ResMut<SelectionBox>>,
+    windows: Query<&Window>,
+    mut nodes: Query<(&mut Style, &mut Visibility), With<SelectionBoxNode>>,
+) {
+    let Some(mut selection_box) = selection_box else {
+        return;
+    };
+    if let Some(cursor) = windows.get_single().ok().and_then(|window| window.cursor_position()) {
+        selection_box.end = cursor;
+    }
+
+    let rect = selection_box.rect();
+    for (mut style, mut visibility) in &mut nodes {
+        style.position = UiRect {
+            left: Val::Px(rect.min.x),
+            bottom: Val::Px(rect.min.y),
+            ..default()
+        };
+        style.size = Size::new(Val::Px(rect.width()), Val::Px(rect.height()));
+        *visibility = if selection_box.is_drag() { Visibility::Visible } else { Visibility::Hidden };
+    }
+}
+
+/// Selects all towers inside the box once the mouse is released, a release without dragging is a click on the hex
+fn finish_selection_box(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    selection_box: Option<Res<SelectionBox>>,
+    mut selection: ResMut<Selection>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    towers: Query<(Entity, &GlobalTransform), (With<Tower>, Without<Wall>)>,
+    mut nodes: Query<&mut Visibility, With<SelectionBoxNode>>,
+) {
+    if !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    let Some(selection_box) = selection_box else {
+        return;
+    };
+    // removed at the end of the frame, the hex picking still sees the drag and doesn't click
+    commands.remove_resource::<SelectionBox>();
+    for mut visibility in &mut nodes {
+        *visibility = Visibility::Hidden;
+    }
+
+    if !selection_box.is_drag() {
+        return;
+    }
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+
+    let rect = selection_box.rect();
+    selection.entities = towers
+        .iter()
+        .filter(|(_, transform)| {
+            camera.world_to_viewport(camera_transform, transform.translation())
+                .map_or(false, |position| rect.contains(position))
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+}
+
+/// A click selects the enemy under the mouse, else the building on the clicked hex
+fn select_clicked(
+    mut field_click_reader: EventReader<HexFieldClicked>,
+    mut selection: ResMut<Selection>,
+    placement: Option<Res<BuildingPlacement>>,
+    targeting: Option<Res<AbilityTargeting>>,
+    hover_map: Res<HoverMap>,
+    enemies: Query<(), With<EnemyTag>>,
+    map: Res<Map>,
+) {
+    // clicks during placement or while aiming an ability are meant for those
+    if placement.is_some() || targeting.is_some() {
+        field_click_reader.clear();
+        return;
+    }
+
+    for event in field_click_reader.iter() {
+        let enemy = hover_map.0
+            .values()
+            .flat_map(|hits| hits.keys())
+            .find(|entity| enemies.contains(**entity))
+            .copied();
+
+        match enemy.or_else(|| map.buildings.get(&event.0).copied()) {
+            Some(entity) => selection.select(entity),
+            None => selection.clear(),
+        }
+    }
+}
+
+/// Forgets despawned entities and keeps the `SelectedBuilding` on the first selected tower
+fn sync_selected_building(
+    mut commands: Commands,
+    mut selection: ResMut<Selection>,
+    selected: Option<Res<SelectedBuilding>>,
+    towers: Query<(), With<Tower>>,
+    existing: Query<Entity>,
+) {
+    if selection.entities.iter().any(|entity| !existing.contains(*entity)) {
+        selection.entities.retain(|entity| existing.contains(*entity));
+    }
+
+    let building = selection.entities.iter().find(|entity| towers.contains(**entity)).copied();
+    match (building, selected.map(|selected| selected.0)) {
+        (Some(building), Some(selected)) if building == selected => {}
+        (Some(building), _) => commands.insert_resource(SelectedBuilding(building)),
+        (None, Some(_)) => commands.remove_resource::<SelectedBuilding>(),
+        (None, None) => {}
+    }
+}
+
+fn highlight_selected_towers(
+    mut highlights: ResMut<HexHighlights>,
+    selection: Res<Selection>,
+    towers: Query<&HexLocation, With<Tower>>,
+    mut highlighted: Local<Vec<Hex>>,
+) {
+    let hexes: Vec<Hex> = selection.entities
+        .iter()
+        .filter_map(|entity| towers.get(*entity).ok())
+        .map(|location| location.location)
+        .collect();
+    if hexes == *highlighted {
+        return;
+    }
+
+    for hex in highlighted.iter() {
+        highlights.unhighlight(*hex, HighlightKind::Selection);
+    }
+    for hex in &hexes {
+        highlights.highlight(*hex, HighlightKind::Selection);
+    }
+    *highlighted = hexes;
+}
+
+fn place_selection_rings(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    map: Res<Map>,
+    selection: Res<Selection>,
+    targets: Query<(&GlobalTransform, &HexLocation)>,
+    mut rings: Query<(Entity, &SelectionRing, &mut Transform)>,
+) {
+    let ring_position = |target: Entity| {
+        targets.get(target).ok().map(|(transform, location)| {
+            let position = transform.translation();
+            Vec3::new(position.x, map.surface_pos(location.location).y + RING_HEIGHT, position.z)
+        })
+    };
+
+    let mut ringed: Vec<Entity> = vec![];
+    for (entity, ring, mut transform) in &mut rings {
+        match ring_position(ring.0).filter(|_| selection.contains(ring.0)) {
+            Some(position) => {
+                transform.translation = position;
+                ringed.push(ring.0);
+            }
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+
+    for target in selection.entities.iter().filter(|entity| !ringed.contains(*entity)) {
+        let Some(position) = ring_position(*target) else {
+            continue;
+        };
+        commands.spawn((
+            Name::from("Selection ring"),
+            SelectionRing(*target),
+            PbrBundle {
+                mesh: assets.selection_ring_mesh.clone(),
+                material: assets.selection_ring_material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn update_enemy_panel(
+    selection: Res<Selection>,
+    enemies: Query<(&EnemyKind, &Health, &Armor, &StatusEffects, &MovementSpeed, Option<&Shield>), With<EnemyTag>>,
+    localization: Res<Localization>,
+    mut panels: Query<&mut Visibility, With<EnemyPanel>>,
+    mut texts: Query<&mut Text, With<EnemyPanelText>>,
+) {
+    let enemy = selection.entities.first().and_then(|entity| enemies.get(*entity).ok());
+
+    for mut visibility in &mut panels {
+        let shown = if enemy.is_some() { Visibility::Visible } else { Visibility::Hidden };
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+
+    let Some((kind, health, armor, effects, speed, shield)) = enemy else {
+        return;
+    };
+    for mut text in &mut texts {
+        text.sections[0].value = enemy_details(kind, health, armor, effects, speed, shield, &localization).join("\n");
+    }
+}
+
+fn hide_selection_ui(
+    mut commands: Commands,
+    mut nodes: Query<&mut Visibility, Or<(With<SelectionBoxNode>, With<EnemyPanel>)>>,
+) {
+    commands.remove_resource::<SelectionBox>();
+    for mut visibility in &mut nodes {
+        *visibility = Visibility::Hidden;
+    }
+}
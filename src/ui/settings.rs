@@ -0,0 +1,261 @@
+use bevy::a11y::accesskit::Role;
+use bevy::prelude::*;
+
+use crate::localization::{available_languages, Localization};
+use crate::map::grid_mesh::GridRendering;
+use crate::settings::{DisplayMode, FONT_SCALE_STEPS, GameSettings, next_step, RESOLUTIONS, SENSITIVITY_STEPS, UI_SCALE_STEPS, VOLUME_STEPS};
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::highlight::HighlightPalette;
+use crate::ui::style::{TextSize, UiStyle};
+
+const ROW_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+
+pub struct SettingsScreenPlugin;
+
+impl Plugin for SettingsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<SettingsScreenChanged>()
+            // the screen opens from the main menu and from the game menu
+            .configure_set(SettingsSet.run_if(in_state(GameState::Paused).or_else(in_state(GameState::MainMenu))))
+            .add_systems(
+                (
+                    toggle_settings_screen,
+                    change_setting,
+                    render_settings_screen
+                        .after(toggle_settings_screen)
+                        .after(change_setting),
+                ).in_set(SettingsSet)
+            )
+            .add_system(close_settings_screen.in_schedule(OnExit(GameState::Paused)))
+            .add_system(close_settings_screen.in_schedule(OnExit(GameState::MainMenu)))
+        ;
+    }
+}
+
+/// Systems of the settings screen
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SettingsSet;
+
+/// Button in the main and the game menu opening the settings
+#[derive(Component)]
+pub struct SettingsButton;
+
+#[derive(Component)]
+struct SettingsScreen;
+
+/// Option shown in a row of the settings screen, clicking the row switches to the next value
+#[derive(Component, Clone, Copy, Debug)]
+enum SettingsRow {
+    Language,
+    DisplayMode,
+    Resolution,
+    Vsync,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    CameraSensitivity,
+    UiScale,
+    FontScale,
+    GridRendering,
+    ChunkCulling,
+    ShowFps,
+    HighlightPalette,
+    HighlightPatterns,
+}
+
+impl SettingsRow {
+    const ALL: [SettingsRow; 15] = [
+        SettingsRow::Language,
+        SettingsRow::DisplayMode,
+        SettingsRow::Resolution,
+        SettingsRow::Vsync,
+        SettingsRow::MasterVolume,
+        SettingsRow::MusicVolume,
+        SettingsRow::SfxVolume,
+        SettingsRow::CameraSensitivity,
+        SettingsRow::UiScale,
+        SettingsRow::FontScale,
+        SettingsRow::GridRendering,
+        SettingsRow::ChunkCulling,
+        SettingsRow::ShowFps,
+        SettingsRow::HighlightPalette,
+        SettingsRow::HighlightPatterns,
+    ];
+
+    fn label(&self, settings: &GameSettings, localization: &Localization) -> String {
+        let value = |value: String| [("value", value)];
+        let switch = |on: bool| value(localization.t(if on { "settings.on" } else { "settings.off" }).to_string());
+        let percent = |volume: f32| value(format!("{:.0}", volume * 100.0));
+        match self {
+            // the name of the language in the language itself
+            SettingsRow::Language => localization.t_with("settings.language", &value(localization.t("language.name").to_string())),
+            SettingsRow::DisplayMode => {
+                localization.t_with("settings.display_mode", &value(localization.t(settings.display_mode.label_key()).to_string()))
+            }
+            SettingsRow::Resolution => match settings.resolution {
+                Some((width, height)) => localization.t_with("settings.resolution", &value(format!("{}x{}", width, height))),
+                None => localization.t("settings.resolution_maximized").to_string(),
+            },
+            SettingsRow::Vsync => localization.t_with("settings.vsync", &switch(settings.vsync)),
+            SettingsRow::MasterVolume => localization.t_with("settings.master_volume", &percent(settings.master_volume)),
+            SettingsRow::MusicVolume => localization.t_with("settings.music_volume", &percent(settings.music_volume)),
+            SettingsRow::SfxVolume => localization.t_with("settings.sfx_volume", &percent(settings.sfx_volume)),
+            SettingsRow::CameraSensitivity => {
+                localization.t_with("settings.camera_sensitivity", &value(settings.camera_sensitivity.to_string()))
+            }
+            SettingsRow::UiScale => localization.t_with("settings.ui_scale", &value(settings.ui_scale.to_string())),
+            SettingsRow::FontScale => localization.t_with("settings.font_scale", &value(settings.font_scale.to_string())),
+            SettingsRow::GridRendering => {
+                localization.t_with("settings.grid_rendering", &value(localization.t(settings.grid_rendering.label_key()).to_string()))
+            }
+            SettingsRow::ChunkCulling => localization.t_with("settings.chunk_culling", &switch(settings.chunk_culling)),
+            SettingsRow::ShowFps => localization.t_with("settings.show_fps", &switch(settings.show_fps)),
+            SettingsRow::HighlightPalette => localization.t_with(
+                "settings.highlight_palette",
+                &value(localization.t(settings.highlight_palette.label_key()).to_string()),
+            ),
+            SettingsRow::HighlightPatterns => {
+                localization.t_with("settings.highlight_patterns", &switch(settings.highlight_patterns))
+            }
+        }
+    }
+
+    fn advance(&self, settings: &mut GameSettings) {
+        match self {
+            SettingsRow::Language => settings.language = next_step(&available_languages(), settings.language.clone()),
+            SettingsRow::DisplayMode => settings.display_mode = next_step(&DisplayMode::ALL, settings.display_mode),
+            SettingsRow::Resolution => {
+                let mut options = vec![None];
+                options.extend(RESOLUTIONS.iter().copied().map(Some));
+                settings.resolution = next_step(&options, settings.resolution);
+            }
+            SettingsRow::Vsync => settings.vsync = !settings.vsync,
+            SettingsRow::MasterVolume => settings.master_volume = next_step(&VOLUME_STEPS, settings.master_volume),
+            SettingsRow::MusicVolume => settings.music_volume = next_step(&VOLUME_STEPS, settings.music_volume),
+            SettingsRow::SfxVolume => settings.sfx_volume = next_step(&VOLUME_STEPS, settings.sfx_volume),
+            SettingsRow::CameraSensitivity => {
+                settings.camera_sensitivity = next_step(&SENSITIVITY_STEPS, settings.camera_sensitivity)
+            }
+            SettingsRow::UiScale => settings.ui_scale = next_step(&UI_SCALE_STEPS, settings.ui_scale),
+            SettingsRow::FontScale => settings.font_scale = next_step(&FONT_SCALE_STEPS, settings.font_scale),
+            SettingsRow::GridRendering => {
+                settings.grid_rendering = next_step(&GridRendering::ALL, settings.grid_rendering)
+            }
+            SettingsRow::ChunkCulling => settings.chunk_culling = !settings.chunk_culling,
+            SettingsRow::ShowFps => settings.show_fps = !settings.show_fps,
+            SettingsRow::HighlightPalette => {
+                settings.highlight_palette = next_step(&HighlightPalette::ALL, settings.highlight_palette)
+            }
+            SettingsRow::HighlightPatterns => settings.highlight_patterns = !settings.highlight_patterns,
+        }
+    }
+}
+
+/// The settings screen needs to be redrawn
+struct SettingsScreenChanged;
+
+fn toggle_settings_screen(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<SettingsButton>)>,
+    screens: Query<Entity, With<SettingsScreen>>,
+    mut changed: EventWriter<SettingsScreenChanged>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        if screens.is_empty() {
+            changed.send(SettingsScreenChanged);
+        } else {
+            for entity in screens.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn change_setting(
+    interactions: Query<(&Interaction, &SettingsRow), Changed<Interaction>>,
+    mut settings: ResMut<GameSettings>,
+    mut changed: EventWriter<SettingsScreenChanged>,
+) {
+    for (interaction, row) in interactions.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        row.advance(&mut settings);
+        settings.save();
+        changed.send(SettingsScreenChanged);
+    }
+}
+
+fn close_settings_screen(mut commands: Commands, screens: Query<Entity, With<SettingsScreen>>) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn render_settings_screen(
+    mut commands: Commands,
+    mut changed: EventReader<SettingsScreenChanged>,
+    ui_style: Res<UiStyle>,
+    settings: Res<GameSettings>,
+    localization: Res<Localization>,
+    screens: Query<Entity, With<SettingsScreen>>,
+) {
+    // a new language shows up right away, the screen is drawn in it
+    if changed.is_empty() && !(localization.is_changed() && !screens.is_empty()) {
+        return;
+    }
+    changed.clear();
+
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let text_style = ui_style.text(TextSize::Large, Color::rgb(0.9, 0.9, 0.9));
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            // above the main menu
+            z_index: ZIndex::Global(15),
+            ..default()
+        })
+        .insert((SettingsScreen, AccessibleName::new(Role::Menu, "menu.settings")))
+        .with_children(|parent| {
+            for row in SettingsRow::ALL {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(360.0), Val::Px(28.0)),
+                            align_items: AlignItems::Center,
+                            padding: UiRect::horizontal(Val::Px(8.0)),
+                            margin: UiRect::vertical(Val::Px(2.0)),
+                            ..default()
+                        },
+                        background_color: ROW_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(row)
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(row.label(&settings, &localization), text_style.clone()));
+                    });
+            }
+        });
+}
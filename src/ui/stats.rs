@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::gameplay::stats::GameStats;
+use crate::gameplay::towers::TowerRegistry;
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::style::{TextSize, UiStyle};
+
+pub struct StatsScreenPlugin;
+
+impl Plugin for StatsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(toggle_stats_tab.in_set(OnUpdate(GameState::Paused)))
+            .add_system(close_stats_tab.in_schedule(OnExit(GameState::Paused)))
+        ;
+    }
+}
+
+/// Button in the game menu showing the statistics of the running game
+#[derive(Component)]
+pub struct StatsButton;
+
+#[derive(Component)]
+struct StatsTab;
+
+/// Lines describing the statistics, towers dealing the most damage come first
+pub fn stat_lines(stats: &GameStats, registry: &TowerRegistry, localization: &Localization) -> Vec<String> {
+    let line = |key: &str, value: String| localization.t_with(key, &[("value", value)]);
+    let mut lines = vec![
+        line("stats.waves_survived", stats.waves_survived.to_string()),
+        line("stats.kills", stats.kills.to_string()),
+        line("stats.leaks", stats.leaks.to_string()),
+        line("stats.gold_earned", stats.gold_earned.to_string()),
+        line("stats.gold_spent", stats.gold_spent.to_string()),
+        line("stats.towers_built", stats.towers_built.to_string()),
+        line("stats.towers_sold", stats.towers_sold.to_string()),
+        line("stats.damage", format!("{:.0}", stats.total_damage())),
+    ];
+
+    let mut damage: Vec<(&String, &f32)> = stats.damage_by_tower.iter().collect();
+    damage.sort_by(|a, b| b.1.total_cmp(a.1));
+    for (id, amount) in damage {
+        let name = registry.get(id).map_or(id.as_str(), |definition| localization.t(&definition.name));
+        lines.push(format!("  {}: {:.0}", name, amount));
+    }
+
+    lines
+}
+
+pub fn spawn_stat_lines(parent: &mut ChildBuilder, lines: Vec<String>, text_style: &TextStyle) {
+    for line in lines {
+        parent.spawn((
+            TextBundle::from_section(line, text_style.clone())
+                .with_style(Style {
+                    margin: UiRect::vertical(Val::Px(2.0)),
+                    ..default()
+                }),
+            Label,
+        ));
+    }
+}
+
+fn toggle_stats_tab(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<StatsButton>)>,
+    tabs: Query<Entity, With<StatsTab>>,
+    ui_style: Res<UiStyle>,
+    stats: Res<GameStats>,
+    registry: Res<TowerRegistry>,
+    localization: Res<Localization>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        if !tabs.is_empty() {
+            for entity in tabs.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            continue;
+        }
+
+        let text_style = ui_style.text(TextSize::Large, Color::rgb(0.9, 0.9, 0.9));
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(20.0),
+                        top: Val::Px(20.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                z_index: ZIndex::Global(5),
+                ..default()
+            })
+            .insert((StatsTab, AccessibleName::group("menu.stats")))
+            .with_children(|parent| {
+                spawn_stat_lines(parent, stat_lines(&stats, &registry, &localization), &text_style);
+            });
+    }
+}
+
+fn close_stats_tab(mut commands: Commands, tabs: Query<Entity, With<StatsTab>>) {
+    for entity in tabs.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::settings::GameSettings;
+
+const FONT_PATH: &str = "fonts/FiraSans-Bold.ttf";
+
+pub struct UiStylePlugin;
+
+impl Plugin for UiStylePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // the UI spawned on startup already takes its texts from the style
+            .add_startup_system(create_ui_style.in_base_set(StartupSet::PreStartup))
+            .add_system(apply_font_scale.run_if(resource_changed::<GameSettings>()))
+        ;
+    }
+}
+
+/// Role of a text in the UI, deciding its size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextSize {
+    /// tooltips, tabs and prices
+    Small,
+    /// HUD, panels and toasts
+    Body,
+    /// rows of lists like the settings or the stats
+    Large,
+    Subtitle,
+    /// dialogs and the titles of cards
+    Heading,
+    /// buttons of the menus
+    Button,
+    Title,
+}
+
+impl TextSize {
+    /// Size in pixels at a font scale of 1, the UI scale applies on top of it
+    fn px(&self) -> f32 {
+        match self {
+            TextSize::Small => 15.0,
+            TextSize::Body => 17.0,
+            TextSize::Large => 20.0,
+            TextSize::Subtitle => 24.0,
+            TextSize::Heading => 30.0,
+            TextSize::Button => 40.0,
+            TextSize::Title => 60.0,
+        }
+    }
+}
+
+/// Font and text sizes shared by all UI, the sizes follow the text size chosen in the settings
+#[derive(Resource, Debug)]
+pub struct UiStyle {
+    pub font: Handle<Font>,
+    /// factor the texts currently on screen are sized with
+    font_scale: f32,
+}
+
+impl UiStyle {
+    pub fn font_size(&self, size: TextSize) -> f32 {
+        size.px() * self.font_scale
+    }
+
+    pub fn text(&self, size: TextSize, color: Color) -> TextStyle {
+        TextStyle {
+            font: self.font.clone(),
+            font_size: self.font_size(size),
+            color,
+        }
+    }
+}
+
+fn create_ui_style(mut commands: Commands, asset_server: Res<AssetServer>, settings: Res<GameSettings>) {
+    commands.insert_resource(UiStyle {
+        font: asset_server.load(FONT_PATH),
+        font_scale: settings.font_scale,
+    });
+}
+
+/// Resizes the texts on screen when the text size changes, texts spawned later take the new size from the style
+fn apply_font_scale(settings: Res<GameSettings>, mut style: ResMut<UiStyle>, mut texts: Query<&mut Text>) {
+    if style.font_scale == settings.font_scale {
+        return;
+    }
+
+    let factor = settings.font_scale / style.font_scale;
+    for mut text in &mut texts {
+        for section in &mut text.sections {
+            section.style.font_size *= factor;
+        }
+    }
+    style.font_scale = settings.font_scale;
+}
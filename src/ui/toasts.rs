@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::a11y::accesskit::Role;
+use bevy::prelude::*;
+
+use crate::StartNewGame;
+use crate::gameplay::save::LoadGame;
+use crate::gameplay::waves::{WavePhase, Waves};
+use crate::localization::Localization;
+use crate::ui::accessibility::{AccessibleName, LiveRegion};
+use crate::ui::minimap::MINIMAP_SIZE;
+use crate::ui::player::BOTTOM_BAR_HEIGHT;
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Time a toast stays on screen, including the fade out
+const TOAST_DURATION: Duration = Duration::from_millis(3000);
+/// Last part of the duration the toast fades out in
+const TOAST_FADE: Duration = Duration::from_millis(600);
+/// Toasts shown at the same time, further ones wait in the queue
+const MAX_TOASTS: usize = 4;
+
+const TOAST_COLOR: Color = Color::rgba(0.1, 0.1, 0.1, 0.85);
+const TOAST_TEXT_COLOR: Color = Color::rgb(0.95, 0.95, 0.95);
+
+/// Key of the text shown when the player can't pay for a building or an upgrade
+pub const NOT_ENOUGH_GOLD: &str = "toast.not_enough_gold";
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Toasts>()
+            .add_startup_system(spawn_toast_stack)
+            // toasts also show up over the menus, e.g. achievements at the end of the game
+            .add_systems((show_queued_toasts, fade_toasts.after(show_queued_toasts)))
+            .add_system(announce_waves.run_if(resource_exists::<Waves>()))
+        ;
+    }
+}
+
+/// Short messages waiting to be shown in the corner of the screen, already in the language of the player
+#[derive(Resource, Default, Debug)]
+pub struct Toasts {
+    queue: VecDeque<String>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        // repeating the same mistake shouldn't fill the queue
+        if !self.queue.contains(&message) {
+            self.queue.push_back(message);
+        }
+    }
+}
+
+/// Column the toasts are stacked in, the newest at the bottom
+#[derive(Component)]
+struct ToastStack;
+
+#[derive(Component)]
+struct Toast {
+    message: String,
+    age: Duration,
+}
+
+fn spawn_toast_stack(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(10.0),
+                    // above the minimap
+                    bottom: Val::Px(BOTTOM_BAR_HEIGHT + MINIMAP_SIZE as f32 + 20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        ToastStack,
+    ));
+}
+
+fn show_queued_toasts(
+    mut commands: Commands,
+    ui_style: Res<UiStyle>,
+    mut toasts: ResMut<Toasts>,
+    stacks: Query<Entity, With<ToastStack>>,
+    mut shown: Query<&mut Toast>,
+) {
+    let Ok(stack) = stacks.get_single() else {
+        return;
+    };
+    let mut count = shown.iter().count();
+
+    while count < MAX_TOASTS {
+        let Some(message) = toasts.queue.pop_front() else {
+            break;
+        };
+
+        // a message still on screen starts over instead of showing up twice
+        if let Some(mut toast) = shown.iter_mut().find(|toast| toast.message == message) {
+            toast.age = Duration::ZERO;
+            continue;
+        }
+
+        let toast = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        margin: UiRect::top(Val::Px(5.0)),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: TOAST_COLOR.into(),
+                    ..default()
+                },
+                Toast { message: message.clone(), age: Duration::ZERO },
+                AccessibleName::new(Role::Alert, message.clone()),
+                LiveRegion,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    message,
+                    ui_style.text(TextSize::Body, TOAST_TEXT_COLOR),
+                ));
+            })
+            .id();
+        commands.entity(stack).add_child(toast);
+        count += 1;
+    }
+}
+
+/// Ages the toasts in real time, so they also go away while the game is paused, and fades them out at the end
+fn fade_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast, &mut BackgroundColor, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, mut toast, mut color, children) in &mut toasts {
+        toast.age += time.raw_delta();
+        if toast.age >= TOAST_DURATION {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let left = TOAST_DURATION - toast.age;
+        let alpha = (left.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0);
+        color.0 = TOAST_COLOR.with_a(TOAST_COLOR.a() * alpha);
+        let mut children_texts = texts.iter_many_mut(children);
+        while let Some(mut text) = children_texts.fetch_next() {
+            text.sections[0].style.color = TOAST_TEXT_COLOR.with_a(alpha);
+        }
+    }
+}
+
+/// Tells the player about each wave once its enemies start coming
+fn announce_waves(
+    waves: Res<Waves>,
+    localization: Res<Localization>,
+    mut toasts: ResMut<Toasts>,
+    mut new_game_reader: EventReader<StartNewGame>,
+    mut load_reader: EventReader<LoadGame>,
+    mut announced: Local<Option<usize>>,
+) {
+    // a new or loaded game starts counting again
+    if new_game_reader.iter().count() + load_reader.iter().count() > 0 {
+        *announced = None;
+    }
+    if !waves.is_changed() || waves.phase != WavePhase::Spawning || *announced == Some(waves.current) {
+        return;
+    }
+    *announced = Some(waves.current);
+
+    let key = if waves.is_boss_wave() { "toast.boss_wave_incoming" } else { "toast.wave_incoming" };
+    toasts.push(localization.t_with(key, &[("wave", (waves.current + 1).to_string())]));
+}
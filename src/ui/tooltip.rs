@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::ui::style::{TextSize, UiStyle};
+
+/// Time the mouse has to rest on a node before its tooltip shows up
+const TOOLTIP_DELAY: Duration = Duration::from_millis(400);
+/// Distance between the mouse cursor and the tooltip
+const CURSOR_OFFSET: f32 = 16.0;
+
+pub struct TooltipPlugin;
+
+impl Plugin for TooltipPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_tooltip_box)
+            // menus have tooltips as well, the systems run in every state
+            .add_system(show_hovered_tooltip)
+            .add_system(keep_tooltip_on_screen.after(show_hovered_tooltip))
+        ;
+    }
+}
+
+/// Text shown next to the cursor while hovering the UI node, the node needs an `Interaction`, e.g. a button
+#[derive(Component, Clone, Debug)]
+pub struct Tooltip(pub String);
+
+/// Box showing the text of the hovered tooltip, shared by all nodes
+#[derive(Component)]
+struct TooltipBox;
+
+#[derive(Component)]
+struct TooltipText;
+
+fn spawn_tooltip_box(mut commands: Commands, ui_style: Res<UiStyle>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    max_size: Size::width(Val::Px(320.0)),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.05, 0.05, 0.05, 0.9).into(),
+                visibility: Visibility::Hidden,
+                // above the menus
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            TooltipBox,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    ui_style.text(TextSize::Small, Color::WHITE),
+                ),
+                TooltipText,
+            ));
+        });
+}
+
+/// Shows the tooltip of the node the mouse rested on long enough, measured in real time since menus pause the game
+fn show_hovered_tooltip(
+    time: Res<Time>,
+    nodes: Query<(Entity, &Interaction, &Tooltip, &ComputedVisibility)>,
+    mut boxes: Query<&mut Visibility, With<TooltipBox>>,
+    mut texts: Query<&mut Text, With<TooltipText>>,
+    mut hovered: Local<Option<(Entity, Duration)>>,
+) {
+    let node = nodes
+        .iter()
+        .find(|(_, interaction, _, visibility)| **interaction == Interaction::Hovered && visibility.is_visible())
+        .map(|(entity, _, tooltip, _)| (entity, tooltip));
+
+    let shown = match (node, *hovered) {
+        (Some((entity, tooltip)), Some((previous, rested))) if entity == previous => {
+            let rested = rested + time.raw_delta();
+            *hovered = Some((entity, rested));
+            (rested >= TOOLTIP_DELAY).then_some(tooltip)
+        }
+        (Some((entity, _)), _) => {
+            *hovered = Some((entity, Duration::ZERO));
+            None
+        }
+        (None, _) => {
+            *hovered = None;
+            None
+        }
+    };
+
+    for mut visibility in &mut boxes {
+        let wanted = if shown.is_some() { Visibility::Visible } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+    let Some(tooltip) = shown else {
+        return;
+    };
+    for mut text in &mut texts {
+        if text.sections[0].value != tooltip.0 {
+            text.sections[0].value = tooltip.0.clone();
+        }
+    }
+}
+
+/// Puts the tooltip next to the cursor, on the other side of it when it would leave the window
+fn keep_tooltip_on_screen(
+    windows: Query<&Window>,
+    mut boxes: Query<(&mut Style, &Node, &Visibility), With<TooltipBox>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+
+    for (mut style, node, visibility) in &mut boxes {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        // the size is the one laid out in the frame before, good enough while the text stays the same
+        let size = node.size();
+
+        // the cursor position starts at the bottom left corner of the window
+        let left = if cursor.x + CURSOR_OFFSET + size.x > window.width() {
+            cursor.x - CURSOR_OFFSET - size.x
+        } else {
+            cursor.x + CURSOR_OFFSET
+        };
+        let bottom = if cursor.y + CURSOR_OFFSET + size.y > window.height() {
+            cursor.y - CURSOR_OFFSET - size.y
+        } else {
+            cursor.y + CURSOR_OFFSET
+        };
+
+        style.position = UiRect {
+            left: Val::Px(left.max(0.0)),
+            bottom: Val::Px(bottom.max(0.0)),
+            ..default()
+        };
+    }
+}
@@ -0,0 +1,405 @@
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use hexx::Hex;
+
+use crate::{Map, StartNewGame};
+use crate::gameplay::enemy::{enemy_route, route_stays_open};
+use crate::gameplay::save::LoadGame;
+use crate::gameplay::towers::{BuildCategory, TowerBuilt};
+use crate::gameplay::waves::{WavePhase, Waves};
+use crate::localization::{Localization, LocalizedText};
+use crate::map::format::available_maps;
+use crate::profile::PlayerProfile;
+use crate::state::global::GameState;
+use crate::ui::accessibility::LiveRegion;
+use crate::ui::build_menu::{BuildButton, BuildMenu};
+use crate::ui::highlight::{HexHighlights, HighlightKind};
+use crate::ui::player::BuildingPlacement;
+use crate::ui::style::{TextSize, UiStyle};
+use crate::ui::toasts::Toasts;
+use crate::ui::wave_preview::StartEarlyButton;
+
+/// Tower the tutorial walks the player through building
+const TUTORIAL_TOWER: &str = "basic";
+/// Hexes next to the enemy path suggested for the first tower
+const SUGGESTED_HEXES: usize = 3;
+const CALLOUT_WIDTH: f32 = 280.0;
+/// Space between the callout and the node it points at
+const CALLOUT_GAP: f32 = 8.0;
+const SPOTLIGHT_COLOR: Color = Color::rgba(1.0, 0.85, 0.3, 0.35);
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_tutorial_callout)
+            .add_system(start_tutorial)
+            .add_systems(
+                (
+                    advance_tutorial,
+                    hold_first_wave,
+                    on_skip_tutorial_clicked,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(place_tutorial_callout.after(advance_tutorial))
+        ;
+    }
+}
+
+/// Step of the tutorial the player is at, each one waits for the player to do what it asks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TutorialStep {
+    ChooseTower,
+    PlaceTower,
+    StartWave,
+}
+
+impl TutorialStep {
+    /// Key of the text telling the player what to do
+    fn text_key(&self) -> &'static str {
+        match self {
+            TutorialStep::ChooseTower => "tutorial.choose_tower",
+            TutorialStep::PlaceTower => "tutorial.place_tower",
+            TutorialStep::StartWave => "tutorial.start_wave",
+        }
+    }
+}
+
+/// Walks the player through the first game on the first map, missing once finished or skipped
+#[derive(Resource, Debug)]
+pub struct Tutorial {
+    pub step: TutorialStep,
+    /// hexes suggested for the tower while placing it, anywhere goes when the map has none to offer
+    hexes: Vec<Hex>,
+}
+
+impl Tutorial {
+    /// Whether the player may start placing the tower, only the one of the tutorial until it stands
+    pub fn allows_building(&self, id: &str) -> bool {
+        self.step == TutorialStep::StartWave || id == TUTORIAL_TOWER
+    }
+
+    /// Whether the tower may be placed on the hex
+    pub fn allows_hex(&self, hex: Hex) -> bool {
+        self.hexes.is_empty() || self.hexes.contains(&hex)
+    }
+
+    /// Whether the player may end the wave break
+    pub fn allows_wave_start(&self) -> bool {
+        self.step == TutorialStep::StartWave
+    }
+}
+
+/// Box explaining the current step, next to the button the step is about
+#[derive(Component)]
+struct TutorialCallout;
+
+#[derive(Component)]
+struct TutorialText;
+
+/// Arrow of the callout pointing at the button
+#[derive(Component)]
+struct TutorialArrow;
+
+/// Glowing frame over the button the step is about
+#[derive(Component)]
+struct TutorialSpotlight;
+
+#[derive(Component)]
+struct SkipTutorialButton;
+
+fn spawn_tutorial_callout(mut commands: Commands, ui_style: Res<UiStyle>, localization: Res<Localization>) {
+    let text_style = ui_style.text(TextSize::Body, Color::rgb(0.95, 0.95, 0.95));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::width(Val::Px(CALLOUT_WIDTH)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            TutorialCallout,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::width(Val::Percent(100.0)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::FlexEnd,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.1, 0.1, 0.15, 0.9).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section("", text_style.clone())
+                            .with_style(Style { max_size: Size::width(Val::Px(CALLOUT_WIDTH - 20.0)), ..default() }),
+                        TutorialText,
+                        // every new step is read out
+                        Label,
+                        LiveRegion,
+                    ));
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::top(Val::Px(8.0)),
+                                    padding: UiRect::all(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                background_color: Color::rgb(0.25, 0.25, 0.25).into(),
+                                ..default()
+                            },
+                            SkipTutorialButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    localization.t("tutorial.skip"),
+                                    TextStyle { font_size: ui_style.font_size(TextSize::Small), ..text_style.clone() },
+                                ),
+                                LocalizedText("tutorial.skip"),
+                            ));
+                        });
+                });
+            parent.spawn((
+                TextBundle::from_section("", TextStyle { font_size: ui_style.font_size(TextSize::Heading), color: SPOTLIGHT_COLOR.with_a(1.0), ..text_style }),
+                TutorialArrow,
+            ));
+        });
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: SPOTLIGHT_COLOR.into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(5),
+            // only shows the way, the click is meant for the button below
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        },
+        TutorialSpotlight,
+    ));
+}
+
+/// The first game on the first map of a profile comes with the tutorial
+fn start_tutorial(
+    mut commands: Commands,
+    mut new_game_reader: EventReader<StartNewGame>,
+    mut load_reader: EventReader<LoadGame>,
+    profile: Res<PlayerProfile>,
+    mut menu: ResMut<BuildMenu>,
+) {
+    if load_reader.iter().count() > 0 {
+        commands.remove_resource::<Tutorial>();
+    }
+    let Some(new_game) = new_game_reader.iter().last() else {
+        return;
+    };
+
+    let first_map = available_maps().into_iter().next();
+    if profile.tutorial_done || first_map.as_ref() != Some(&new_game.map) {
+        commands.remove_resource::<Tutorial>();
+        return;
+    }
+
+    commands.insert_resource(Tutorial { step: TutorialStep::ChooseTower, hexes: vec![] });
+    // the tab with the tower of the tutorial
+    menu.category = BuildCategory::Attack;
+}
+
+/// Moves on to the next step once the player did what the current one asks for
+fn advance_tutorial(
+    mut commands: Commands,
+    tutorial: Option<ResMut<Tutorial>>,
+    placement: Option<Res<BuildingPlacement>>,
+    mut built_reader: EventReader<TowerBuilt>,
+    waves: Res<Waves>,
+    map: Res<Map>,
+    mut highlights: ResMut<HexHighlights>,
+    mut profile: ResMut<PlayerProfile>,
+    localization: Res<Localization>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let built = built_reader.iter().count() > 0;
+    let Some(mut tutorial) = tutorial else {
+        return;
+    };
+
+    match tutorial.step {
+        TutorialStep::ChooseTower if placement.is_some() => {
+            tutorial.hexes = suggested_hexes(&map);
+            for hex in &tutorial.hexes {
+                highlights.highlight(*hex, HighlightKind::TutorialHint);
+            }
+            tutorial.step = TutorialStep::PlaceTower;
+        }
+        TutorialStep::PlaceTower if built => {
+            highlights.clear(HighlightKind::TutorialHint);
+            tutorial.hexes.clear();
+            tutorial.step = TutorialStep::StartWave;
+        }
+        // cancelled the placement, back to picking the tower
+        TutorialStep::PlaceTower if placement.is_none() => {
+            highlights.clear(HighlightKind::TutorialHint);
+            tutorial.step = TutorialStep::ChooseTower;
+        }
+        TutorialStep::StartWave if waves.phase != WavePhase::Break => {
+            profile.tutorial_done = true;
+            profile.save();
+            toasts.push(localization.t("tutorial.complete"));
+            commands.remove_resource::<Tutorial>();
+        }
+        _ => {}
+    }
+}
+
+/// Buildable hexes right next to the middle of the enemy path which keep the path open
+fn suggested_hexes(map: &Map) -> Vec<Hex> {
+    let Some(route) = enemy_route(map) else {
+        return vec![];
+    };
+
+    let mut hexes = Vec::new();
+    // the middle of the path gives the tower the most time to shoot
+    let (before, after) = route.split_at(route.len() / 2);
+    for hex in after.iter().chain(before.iter().rev()) {
+        for neighbor in hex.all_neighbors() {
+            if hexes.len() == SUGGESTED_HEXES {
+                return hexes;
+            }
+            if !hexes.contains(&neighbor) && map.is_buildable(neighbor) && route_stays_open(map, neighbor) {
+                hexes.push(neighbor);
+            }
+        }
+    }
+    hexes
+}
+
+/// The break before the first wave lasts until the tutorial gets to starting it
+fn hold_first_wave(tutorial: Option<Res<Tutorial>>, mut waves: ResMut<Waves>) {
+    if tutorial.map_or(true, |tutorial| tutorial.allows_wave_start()) || waves.phase != WavePhase::Break {
+        return;
+    }
+    // the countdown doesn't need redrawing, it stays where it starts
+    waves.bypass_change_detection().timer.reset();
+}
+
+fn on_skip_tutorial_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<SkipTutorialButton>)>,
+    mut highlights: ResMut<HexHighlights>,
+    mut profile: ResMut<PlayerProfile>,
+) {
+    if !interactions.iter().any(|interaction| *interaction == Interaction::Clicked) {
+        return;
+    }
+
+    highlights.clear(HighlightKind::TutorialHint);
+    profile.tutorial_done = true;
+    profile.save();
+    commands.remove_resource::<Tutorial>();
+}
+
+/// Shows the text of the step next to the button it is about, the placement step has no button and stays at the top
+fn place_tutorial_callout(
+    tutorial: Option<Res<Tutorial>>,
+    localization: Res<Localization>,
+    state: Res<State<GameState>>,
+    windows: Query<&Window>,
+    build_buttons: Query<(&BuildButton, &Node, &GlobalTransform, &ComputedVisibility)>,
+    start_buttons: Query<(&Node, &GlobalTransform, &ComputedVisibility), With<StartEarlyButton>>,
+    mut callouts: Query<(&mut Style, &mut Visibility, &Node), (With<TutorialCallout>, Without<TutorialSpotlight>)>,
+    mut spotlights: Query<(&mut Style, &mut Visibility), (With<TutorialSpotlight>, Without<TutorialCallout>)>,
+    mut texts: Query<&mut Text, (With<TutorialText>, Without<TutorialArrow>)>,
+    mut arrows: Query<&mut Text, (With<TutorialArrow>, Without<TutorialText>)>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    // the menus cover the game, the tutorial waits behind them
+    let tutorial = tutorial.filter(|_| state.0 == GameState::Playing);
+
+    let target = tutorial.as_ref().and_then(|tutorial| match tutorial.step {
+        TutorialStep::ChooseTower => build_buttons
+            .iter()
+            .find(|(button, _, _, visibility)| button.0 == TUTORIAL_TOWER && visibility.is_visible())
+            .map(|(_, node, transform, _)| (node.size(), transform.translation().truncate())),
+        TutorialStep::StartWave => start_buttons
+            .iter()
+            .find(|(_, _, visibility)| visibility.is_visible())
+            .map(|(node, transform, _)| (node.size(), transform.translation().truncate())),
+        TutorialStep::PlaceTower => None,
+    });
+
+    for (mut style, mut visibility) in &mut spotlights {
+        let wanted = if target.is_some() { Visibility::Visible } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+        // ui nodes are placed by their center, from the top left corner of the window
+        if let Some((size, center)) = target {
+            style.size = Size::new(Val::Px(size.x), Val::Px(size.y));
+            style.position = UiRect {
+                left: Val::Px(center.x - size.x / 2.0),
+                top: Val::Px(center.y - size.y / 2.0),
+                ..default()
+            };
+        }
+    }
+
+    let Some(tutorial) = tutorial else {
+        for (_, mut visibility, _) in &mut callouts {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+
+    for mut text in &mut texts {
+        let step_text = localization.t(tutorial.step.text_key());
+        if text.sections[0].value != step_text {
+            text.sections[0].value = step_text.to_string();
+        }
+    }
+
+    for (mut style, mut visibility, node) in &mut callouts {
+        *visibility = Visibility::Visible;
+        // the height is the one laid out in the frame before, like the tooltips do
+        let height = node.size().y;
+        let (left, top, arrow) = match target {
+            // above buttons in the lower half of the window, below the others
+            Some((size, center)) if center.y > window.height() / 2.0 => {
+                (center.x - CALLOUT_WIDTH / 2.0, center.y - size.y / 2.0 - CALLOUT_GAP - height, "↓")
+            }
+            Some((size, center)) => (center.x - CALLOUT_WIDTH / 2.0, center.y + size.y / 2.0 + CALLOUT_GAP, "↑"),
+            // below the briefing of the level
+            None => ((window.width() - CALLOUT_WIDTH) / 2.0, window.height() * 0.3, ""),
+        };
+
+        style.flex_direction = if arrow == "↑" { FlexDirection::ColumnReverse } else { FlexDirection::Column };
+        style.position = UiRect {
+            left: Val::Px(left.clamp(0.0, (window.width() - CALLOUT_WIDTH).max(0.0))),
+            top: Val::Px(top.max(0.0)),
+            ..default()
+        };
+        for mut text in &mut arrows {
+            if text.sections[0].value != arrow {
+                text.sections[0].value = arrow.to_string();
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+
+use crate::StartNewGame;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::balance::Balance;
+use crate::gameplay::enemy::EnemyKind;
+use crate::gameplay::save::LoadGame;
+use crate::gameplay::waves::{StartWaveEarly, WavePhase, Waves};
+use crate::localization::Localization;
+use crate::state::global::GameState;
+use crate::ui::accessibility::AccessibleName;
+use crate::ui::style::{TextSize, UiStyle};
+use crate::ui::tutorial::Tutorial;
+
+pub struct WavePreviewPlugin;
+
+impl Plugin for WavePreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ShownWave>()
+            .add_startup_system(spawn_wave_preview)
+            .add_system(reset_shown_wave)
+            .add_systems(
+                (
+                    update_wave_preview,
+                    update_wave_countdown.after(update_wave_preview),
+                    on_start_early_clicked,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+        ;
+    }
+}
+
+/// Box listing the enemies of the upcoming wave, only visible during the break before it
+#[derive(Component)]
+struct WavePreview;
+
+#[derive(Component)]
+struct WaveCountdownText;
+
+/// Wave the preview lists the enemies of, none while it is hidden
+#[derive(Resource, Default, Debug)]
+struct ShownWave(Option<usize>);
+
+/// Ends the wave break right away in exchange for bonus gold
+#[derive(Component)]
+pub struct StartEarlyButton;
+
+#[derive(Component)]
+struct StartEarlyText;
+
+fn spawn_wave_preview(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        WavePreview,
+        AccessibleName::group("a11y.wave_preview"),
+    ));
+}
+
+/// Enemy kinds of the wave in order of their first appearance, with the number of enemies of each
+fn wave_composition(waves: &Waves, difficulty: Difficulty) -> Vec<(EnemyKind, u32)> {
+    let Some(definition) = waves.definition() else {
+        return vec![];
+    };
+    let count_factor = difficulty.count_factor(waves.round());
+    let groups = definition.groups.iter().map(|group| (group.kind, group.scaled_count(count_factor)));
+    // the boss of a boss wave comes last
+    let boss = waves.is_boss_wave().then_some((EnemyKind::Boss, 1));
+
+    let mut composition: Vec<(EnemyKind, u32)> = vec![];
+    for (group_kind, group_count) in groups.chain(boss) {
+        match composition.iter_mut().find(|(kind, _)| *kind == group_kind) {
+            Some((_, count)) => *count += group_count,
+            None => composition.push((group_kind, group_count)),
+        }
+    }
+    composition
+}
+
+/// A new or loaded game replaces the waves, even with the same wave coming up.
+/// The events come in while the game isn't played yet.
+fn reset_shown_wave(
+    mut new_game_reader: EventReader<StartNewGame>,
+    mut load_reader: EventReader<LoadGame>,
+    mut shown_wave: ResMut<ShownWave>,
+) {
+    if new_game_reader.iter().count() + load_reader.iter().count() > 0 {
+        shown_wave.0 = None;
+    }
+}
+
+/// Fills the preview with the upcoming wave once its break starts, hides it again when the wave starts
+fn update_wave_preview(
+    mut commands: Commands,
+    ui_style: Res<UiStyle>,
+    waves: Option<Res<Waves>>,
+    difficulty: Res<Difficulty>,
+    localization: Res<Localization>,
+    mut previews: Query<(Entity, &mut Visibility), With<WavePreview>>,
+    mut shown_wave: ResMut<ShownWave>,
+) {
+    let Ok((preview, mut visibility)) = previews.get_single_mut() else {
+        return;
+    };
+    let Some(waves) = waves.filter(|waves| waves.phase == WavePhase::Break && waves.is_loaded()) else {
+        // after a reset the shown wave is gone while the preview of the game before is still up
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        shown_wave.0 = None;
+        return;
+    };
+    if shown_wave.0 == Some(waves.current) && !localization.is_changed() {
+        return;
+    }
+
+    let text_style = ui_style.text(TextSize::Body, Color::WHITE);
+    let margin = UiRect::all(Val::Px(5.0));
+
+    commands.entity(preview).despawn_descendants();
+    commands.entity(preview).with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section(
+                localization.t_with("wave_preview.title", &[("wave", (waves.current + 1).to_string())]),
+                text_style.clone(),
+            )
+                .with_style(Style { margin, ..default() }),
+            Label,
+        ));
+
+        for (kind, count) in wave_composition(&waves, *difficulty) {
+            let stats = kind.stats();
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        margin,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // colored like the enemy itself
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(14.0), Val::Px(14.0)),
+                            margin: UiRect::right(Val::Px(5.0)),
+                            ..default()
+                        },
+                        background_color: stats.color.into(),
+                        ..default()
+                    });
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("{} x{}", localization.t(stats.name), count),
+                            text_style.clone(),
+                        ),
+                        Label,
+                    ));
+                });
+        }
+
+        parent.spawn((
+            TextBundle::from_section("", text_style.clone())
+                .with_style(Style { margin, ..default() }),
+            WaveCountdownText,
+            Label,
+        ));
+
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(150.0), Val::Px(40.0)),
+                        margin,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+                    ..default()
+                },
+                StartEarlyButton,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section("", text_style.clone()),
+                    StartEarlyText,
+                ));
+            });
+    });
+
+    *visibility = Visibility::Visible;
+    shown_wave.0 = Some(waves.current);
+}
+
+fn update_wave_countdown(
+    waves: Option<Res<Waves>>,
+    balance: Res<Balance>,
+    localization: Res<Localization>,
+    mut countdowns: Query<&mut Text, (With<WaveCountdownText>, Without<StartEarlyText>)>,
+    mut start_texts: Query<&mut Text, (With<StartEarlyText>, Without<WaveCountdownText>)>,
+) {
+    let Some(waves) = waves.filter(|waves| waves.phase == WavePhase::Break) else {
+        return;
+    };
+    let remaining = waves.timer.remaining();
+
+    for mut text in &mut countdowns {
+        text.sections[0].value = localization.t_with(
+            "wave_preview.starts_in",
+            &[("seconds", remaining.as_secs_f32().ceil().to_string())],
+        );
+    }
+    for mut text in &mut start_texts {
+        text.sections[0].value = localization.t_with(
+            "wave_preview.start_now",
+            &[("bonus", balance.early_start_bonus(remaining).to_string())],
+        );
+    }
+}
+
+fn on_start_early_clicked(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<StartEarlyButton>)>,
+    tutorial: Option<Res<Tutorial>>,
+    mut start_writer: EventWriter<StartWaveEarly>,
+) {
+    // the tutorial explains the towers first
+    if !tutorial.map_or(true, |tutorial| tutorial.allows_wave_start()) {
+        return;
+    }
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            start_writer.send(StartWaveEarly);
+        }
+    }
+}